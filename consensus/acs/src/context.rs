@@ -8,7 +8,7 @@ use config::Node;
 
 use fnv::FnvHashMap;
 use network::{
-    plaintcp::{CancelHandler, TcpReceiver, TcpReliableSender},
+    plaintcp::CancelHandler,
     Acknowledgement,
 };
 //use signal_hook::{iterator::Signals, consts::{SIGINT, SIGTERM}};
@@ -19,14 +19,14 @@ use tokio::{sync::{
 // use tokio_util::time::DelayQueue;
 use types::{Replica, WrapperMsg};
 
-use consensus::{LargeFieldSSS, LargeFieldSer};
+use consensus::{HashKeys, LargeFieldSSS, LargeFieldSer, SecretBytes, ReplicaSender};
 use crypto::{aes_hash::HashState, LargeField, hash::Hash};
 
-use crate::{msg::ProtMsg, Handler, protocol::ACSState};
+use crate::{msg::ProtMsg, protocol::{ACSState, split_sub_protocol_instance}};
 
 pub struct Context {
     /// Networking context
-    pub net_send: TcpReliableSender<Replica, WrapperMsg<ProtMsg>, Acknowledgement>,
+    pub net_send: ReplicaSender<WrapperMsg<ProtMsg>>,
     pub net_recv: UnboundedReceiver<WrapperMsg<ProtMsg>>,
     //pub sync_send: TcpReliableSender<Replica, SyncMsg, Acknowledgement>,
     //pub sync_recv: UnboundedReceiver<SyncMsg>,
@@ -38,7 +38,7 @@ pub struct Context {
 
     pub large_field_shamir_ss: LargeFieldSSS,
     /// Secret Key map
-    pub sec_key_map: HashMap<Replica, Vec<u8>>,
+    pub sec_key_map: HashMap<Replica, SecretBytes>,
 
     /// Hardware acceleration context
     pub hash_context: HashState,
@@ -58,8 +58,12 @@ pub struct Context {
     /// Constants for PRF seeding
     pub nonce_seed: usize,
 
-    ///// State for GatherState and ACS
-    pub acs_state: ACSState,
+    ///// State for GatherState and ACS, one per ACS instance
+    pub acs_states: HashMap<usize, ACSState>,
+    // ACS instances that output, their state is dropped and late messages are ignored
+    pub finished_acs: HashSet<usize>,
+    // Highest ACS instance this node started or finished, the base of the window of instances accepted from other parties
+    pub max_acs_inst: usize,
     
     pub acss_map: HashMap<Replica, 
         HashMap<usize, 
@@ -75,8 +79,12 @@ pub struct Context {
     //pub acss_req: Sender<(usize, Vec<LargeFieldSer>)>,
     //pub acss_out_recv: Receiver<(usize, usize, Hash, Vec<LargeFieldSer>)>,
 
-    pub event_recv_channel: Receiver<(usize,usize, Vec<LargeFieldSer>)>,
-    pub acs_out_channel: Sender<(usize,Vec<usize>)>,
+    pub event_recv_channel: Option<Receiver<(usize,usize, Vec<LargeFieldSer>)>>,
+    pub acs_out_channel: Option<Sender<(usize,Vec<usize>)>>,
+
+    // Value-based ACS: (ACS instance, proposal) in, (ACS instance, [(party, proposal)]) out
+    pub proposal_recv_channel: Option<Receiver<(usize, Vec<u8>)>>,
+    pub value_out_channel: Option<Sender<(usize, Vec<(Replica, Vec<u8>)>)>>,
 
     pub asks_req: Sender<(usize, usize, bool, bool, Option<Vec<LargeField>>,Option<usize>)>,
     pub asks_out_recv: Receiver<(usize, Replica, Option<Vec<LargeField>>)>,
@@ -98,9 +106,47 @@ pub struct Context {
 impl Context {
     pub fn spawn(
         config: Node,
+        hash_keys: HashKeys,
         term_event_channel: Receiver<(usize,usize, Vec<LargeFieldSer>)>,
         acs_out_channel: Sender<(usize,Vec<usize>)>,
         byz: bool) -> anyhow::Result<(oneshot::Sender<()>, Vec<Result<oneshot::Sender<()>>>)> {
+        Self::spawn_inner(
+            config,
+            hash_keys, 
+            Some(term_event_channel), 
+            Some(acs_out_channel), 
+            None, 
+            None, 
+            byz
+        )
+    }
+
+    // ACS over arbitrary byte proposals. Each party submits (acs_instance, proposal) and receives the proposals of the agreed subset ordered by party index.
+    pub fn spawn_with_values(
+        config: Node,
+        hash_keys: HashKeys,
+        proposal_channel: Receiver<(usize, Vec<u8>)>,
+        value_out_channel: Sender<(usize, Vec<(Replica, Vec<u8>)>)>,
+        byz: bool) -> anyhow::Result<(oneshot::Sender<()>, Vec<Result<oneshot::Sender<()>>>)> {
+        Self::spawn_inner(
+            config,
+            hash_keys, 
+            None, 
+            None, 
+            Some(proposal_channel), 
+            Some(value_out_channel), 
+            byz
+        )
+    }
+
+    fn spawn_inner(
+        config: Node,
+        hash_keys: HashKeys,
+        term_event_channel: Option<Receiver<(usize,usize, Vec<LargeFieldSer>)>>,
+        acs_out_channel: Option<Sender<(usize,Vec<usize>)>>,
+        proposal_channel: Option<Receiver<(usize, Vec<u8>)>>,
+        value_out_channel: Option<Sender<(usize, Vec<(Replica, Vec<u8>)>)>>,
+        byz: bool) -> anyhow::Result<(oneshot::Sender<()>, Vec<Result<oneshot::Sender<()>>>)> {
         // Add a separate configuration for RBC service. 
 
         let mut consensus_addrs: FnvHashMap<Replica, SocketAddr> = FnvHashMap::default();
@@ -138,10 +184,6 @@ impl Context {
 
         // Setup networking
         let (tx_net_to_consensus, rx_net_to_consensus) = unbounded_channel();
-        TcpReceiver::<Acknowledgement, WrapperMsg<ProtMsg>, _>::spawn(
-            my_address,
-            Handler::new(tx_net_to_consensus),
-        );
 
         //let syncer_listen_port = config.client_port;
         //let syncer_l_address = to_socket_address("0.0.0.0", syncer_listen_port);
@@ -153,18 +195,13 @@ impl Context {
         //    SyncHandler::new(tx_net_to_client),
         //);
 
-        let consensus_net = TcpReliableSender::<Replica, WrapperMsg<ProtMsg>, Acknowledgement>::with_peers(
-            consensus_addrs.clone(),
-        );
+        let consensus_net = consensus::spawn_replica_network(my_address, consensus_addrs.clone(), tx_net_to_consensus);
         //let sync_net =
         //    TcpReliableSender::<Replica, SyncMsg, Acknowledgement>::with_peers(syncer_map);
         let (exit_tx, exit_rx) = oneshot::channel();
 
         // Keyed AES ciphers
-        let key0 = [5u8; 16];
-        let key1 = [29u8; 16];
-        let key2 = [23u8; 16];
-        let hashstate = HashState::new(key0, key1, key2);
+        let hashstate = hash_keys.hash_state();
 
         let rbc_start_id = 1;
                 
@@ -207,7 +244,9 @@ impl Context {
                 threshold: 10000,
 
                 max_id: rbc_start_id, 
-                acs_state: ACSState::new(),
+                acs_states: HashMap::default(),
+                finished_acs: HashSet::default(),
+                max_acs_inst: 0,
 
                 //num_batches: num_batches,
                 //per_batch: per_batch, 
@@ -224,6 +263,9 @@ impl Context {
                 event_recv_channel: term_event_channel,
                 acs_out_channel: acs_out_channel,
 
+                proposal_recv_channel: proposal_channel,
+                value_out_channel: value_out_channel,
+
                 ctrbc_req: ctrbc_req_send_channel,
                 ctrbc_out_recv: ctrbc_out_recv_channel,
 
@@ -235,8 +277,8 @@ impl Context {
             };
 
             // Populate secret keys from config
-            for (id, sk_data) in config.sk_map.clone() {
-                c.sec_key_map.insert(id, sk_data.clone());
+            for (id, sk_data) in config.sk_map.iter() {
+                c.sec_key_map.insert(*id, SecretBytes::new(sk_data.clone()));
             }
 
             // Run the consensus context
@@ -274,7 +316,8 @@ impl Context {
         statuses.push(_rbc_serv_status);
 
         let _asks_serv_status = asks::Context::spawn(
-            asks_config, 
+            asks_config,
+            hash_keys.clone(), 
             asks_req_recv_channel, 
             asks_out_send_channel,
             false
@@ -284,6 +327,7 @@ impl Context {
 
         let _ra_serv_status = ra::Context::spawn(
             ra_config,
+            hash_keys.clone(),
             ra_req_recv_channel,
             ra_out_send_channel,
             false
@@ -298,9 +342,11 @@ impl Context {
     }
 
     pub async fn broadcast(&mut self, protmsg: ProtMsg) {
-        let sec_key_map = self.sec_key_map.clone();
-        for (replica, sec_key) in sec_key_map.into_iter() {
+        let replicas: Vec<Replica> = self.sec_key_map.keys().cloned().collect();
+        for replica in replicas {
+            let sec_key = self.sec_key_map.get(&replica).unwrap();
             let wrapper_msg = WrapperMsg::new(protmsg.clone(), self.myid, &sec_key.as_slice());
+            consensus::count_sent_message(&wrapper_msg);
             let cancel_handler: CancelHandler<Acknowledgement> = self.net_send.send(replica, wrapper_msg).await;
             self.add_cancel_handler(cancel_handler);
         }
@@ -311,6 +357,7 @@ impl Context {
     }
 
     pub async fn send(&mut self, replica: Replica, wrapper_msg: WrapperMsg<ProtMsg>) {
+        consensus::count_sent_message(&wrapper_msg);
         let cancel_handler: CancelHandler<Acknowledgement> =
             self.net_send.send(replica, wrapper_msg).await;
         self.add_cancel_handler(cancel_handler);
@@ -372,13 +419,20 @@ impl Context {
                 //     log::debug!("Received message from CTRBC channel {:?}", acss_msg);
                 //     self.process_acss_event(acss_msg.0, acss_msg.1, acss_msg.2, acss_msg.3).await;
                 // },
-                term_event = self.event_recv_channel.recv() => {
+                term_event = recv_if_present(&mut self.event_recv_channel) => {
                     let term_event = term_event.ok_or_else(||
                         anyhow!("Networking layer has closed")
                     )?;
                     log::debug!("Received termination event: {:?}", term_event);
                     // Process the termination event
-                    self.process_termination_event(term_event.1).await;
+                    self.process_termination_event(term_event.0, term_event.1).await;
+                },
+                proposal = recv_if_present(&mut self.proposal_recv_channel) => {
+                    let proposal = proposal.ok_or_else(||
+                        anyhow!("Networking layer has closed")
+                    )?;
+                    log::debug!("Received proposal for ACS instance {}", proposal.0);
+                    self.init_value_proposal(proposal.0, proposal.1).await;
                 },
                 ctrbc_msg = self.ctrbc_out_recv.recv() => {
                     let ctrbc_msg = ctrbc_msg.ok_or_else(||
@@ -394,11 +448,16 @@ impl Context {
                     )?;
 
                     log::debug!("Received message from ASKS channel {:?}", asks_msg);
+                    let (acs_inst, vaba_inst) = split_sub_protocol_instance(asks_msg.0);
+                    // ASKS instances of other parties carry ids they picked
+                    if self.finished_acs.contains(&acs_inst) || !self.acs_inst_in_window(acs_inst){
+                        continue;
+                    }
                     if asks_msg.2.is_none(){
-                        self.process_asks_termination(asks_msg.0, asks_msg.1, asks_msg.2).await;
+                        self.process_asks_termination(acs_inst, vaba_inst, asks_msg.1, asks_msg.2).await;
                     }
                     else{
-                        self.process_asks_reconstruction_result(asks_msg.0, asks_msg.1, asks_msg.2.unwrap()).await;
+                        self.process_asks_reconstruction_result(acs_inst, vaba_inst, asks_msg.1, asks_msg.2.unwrap()).await;
                     }
                 },
                 ra_msg = self.ra_out_recv.recv() => {
@@ -407,7 +466,11 @@ impl Context {
                     )?;
 
                     log::debug!("Received message from RA channel {:?}", ra_msg);
-                    self.process_ra_termination(ra_msg.1, ra_msg.0, ra_msg.2).await;
+                    let (acs_inst, vaba_inst) = split_sub_protocol_instance(ra_msg.1);
+                    if self.finished_acs.contains(&acs_inst) || !self.acs_inst_in_window(acs_inst){
+                        continue;
+                    }
+                    self.process_ra_termination(acs_inst, vaba_inst, ra_msg.0, ra_msg.2).await;
                 }
             };
         }
@@ -415,6 +478,14 @@ impl Context {
     }
 }
 
+// Receives from an optional channel. Channels that are not configured never yield a value. 
+async fn recv_if_present<T>(channel: &mut Option<Receiver<T>>) -> Option<T> {
+    match channel {
+        Some(recv) => recv.recv().await,
+        None => futures::future::pending().await,
+    }
+}
+
 pub fn to_socket_address(ip_str: &str, port: u16) -> SocketAddr {
     let addr = SocketAddrV4::new(ip_str.parse().unwrap(), port);
     addr.into()
@@ -4,9 +4,6 @@ pub use context::*;
 pub mod protocol;
 pub use protocol::*;
 
-mod handlers;
-pub use handlers::*;
-
 mod msg;
 
 mod process;
\ No newline at end of file
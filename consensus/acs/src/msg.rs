@@ -5,9 +5,10 @@ use types::Replica;
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub enum ProtMsg{
     // Gather Echo
-    GatherEcho(usize, Vec<Replica>),
+    // acs_instance, vaba_instance, witnesses
+    GatherEcho(usize, usize, Vec<Replica>),
     // Gather Echo2
-    GatherEcho2(usize, Vec<Replica>),
+    GatherEcho2(usize, usize, Vec<Replica>),
     // // SecEq
     // // instance_id, secret_origin, c1_c2, aggregated_challenge_point
     // SecEq(usize, Replica, u8, LargeFieldSer),
@@ -18,6 +19,7 @@ pub enum ProtMsg{
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct CTRBCInterface{
+    pub acs_inst: usize,
     pub id: usize,
     pub msg: Vec<u8>
 }
\ No newline at end of file
@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use crate::{context::Context, msg::ProtMsg};
+use crate::{context::Context, msg::ProtMsg, protocol::vaba_inst_in_range};
 use consensus::delay_message_processing;
 use crypto::hash::verf_mac;
 //use network::{plaintcp::CancelHandler, Acknowledgement};
@@ -34,15 +34,21 @@ impl Context {
         // Verify the message's authenticity before proceeding
         if self.check_proposal(msg) {
             match wrapper_msg.clone().protmsg {
-                ProtMsg::GatherEcho(instance, witnesses) => {
+                ProtMsg::GatherEcho(acs_inst, _, _) | ProtMsg::GatherEcho2(acs_inst, _, _) if self.finished_acs.contains(&acs_inst) => {
+                    log::debug!("Ignoring Gather message for finished ACS instance {} from node : {}", acs_inst, wrapper_msg.sender);
+                },
+                ProtMsg::GatherEcho(acs_inst, instance, _) | ProtMsg::GatherEcho2(acs_inst, instance, _) if !self.acs_inst_in_window(acs_inst) || !vaba_inst_in_range(instance) => {
+                    log::warn!("Dropping Gather message for out of range ACS instance {} and instance id {} from node : {}", acs_inst, instance, wrapper_msg.sender);
+                },
+                ProtMsg::GatherEcho(acs_inst, instance, witnesses) => {
                     // RBC initialized
-                    log::debug!("Received Gather Echo for instance id {} from node : {}", instance, wrapper_msg.sender);
-                    self.process_gather_echo(witnesses, wrapper_msg.sender, instance).await;
+                    log::debug!("Received Gather Echo for ACS instance {} and instance id {} from node : {}", acs_inst, instance, wrapper_msg.sender);
+                    self.process_gather_echo(acs_inst, witnesses, wrapper_msg.sender, instance).await;
                 },
-                ProtMsg::GatherEcho2(instance, witnesses) => {
+                ProtMsg::GatherEcho2(acs_inst, instance, witnesses) => {
                     // RBC initialized
-                    log::debug!("Received Gather Echo2 for instance id {} from node : {}", instance, wrapper_msg.sender);
-                    self.process_gather_echo2(witnesses, wrapper_msg.sender, instance).await;
+                    log::debug!("Received Gather Echo2 for ACS instance {} and instance id {} from node : {}", acs_inst, instance, wrapper_msg.sender);
+                    self.process_gather_echo2(acs_inst, witnesses, wrapper_msg.sender, instance).await;
                 },
             }
         } else {
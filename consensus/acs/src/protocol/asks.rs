@@ -5,26 +5,27 @@ use types::{Replica};
 
 use crate::Context;
 
-use super::VABAState;
+use super::{VABAState, ACSState, sub_protocol_instance};
 
 impl Context{
-    pub async fn process_asks_termination(&mut self, instance: usize, sender: Replica, value: Option<Vec<LargeField>>){
-        log::info!("Processing ASKS termination for instance {} from sender {}", instance, sender);
-        if !self.acs_state.vaba_states.contains_key(&instance){
+    pub async fn process_asks_termination(&mut self, acs_inst: usize, instance: usize, sender: Replica, value: Option<Vec<LargeField>>){
+        log::info!("Processing ASKS termination for ACS instance {} and instance {} from sender {}", acs_inst, instance, sender);
+        let acs_state = self.acs_states.entry(acs_inst).or_insert_with(ACSState::new);
+        if !acs_state.vaba_states.contains_key(&instance){
             let vaba_context = VABAState::new_without_pre_justify();
-            self.acs_state.vaba_states.insert(instance, vaba_context);
+            acs_state.vaba_states.insert(instance, vaba_context);
         }
-        
-        let vaba_context = self.acs_state.vaba_states.get_mut(&instance).unwrap();
-        
+
+        let vaba_context = acs_state.vaba_states.get_mut(&instance).unwrap();
+
         if value.is_none(){
             vaba_context.term_asks_instances.insert(sender);
             // Remove pending asks instances from the map of unvalidated pre_justify_votes
             for (_rep, map) in vaba_context.unvalidated_pre_justify_votes.iter_mut(){
                 map.1.remove(&sender);
             }
-            self.broadcast_pre(instance).await;
-            self.check_witness_pre_broadcast(instance).await;
+            self.broadcast_pre(acs_inst, instance).await;
+            self.check_witness_pre_broadcast(acs_inst, instance).await;
         }
         else{
             let value = value.unwrap()[0].clone();
@@ -32,14 +33,14 @@ impl Context{
         }
     }
 
-    pub async fn init_asks_reconstruction(&mut self, instance: usize){
+    pub async fn init_asks_reconstruction(&mut self, acs_inst: usize, instance: usize){
         // Generate list of all ASKS instances to be reconstructed
         // Reconstruct all received ASKS shares
-        let vaba_context = self.acs_state.vaba_states.get_mut(&instance).unwrap();
+        let vaba_context = self.acs_states.get_mut(&acs_inst).unwrap().vaba_states.get_mut(&instance).unwrap();
         if vaba_context.asks_reconstruction_started{
             return;
         }
-        log::info!("Secret reconstruction started for instance {}", instance);
+        log::info!("Secret reconstruction started for ACS instance {} and instance {}", acs_inst, instance);
         for rep in 0..self.num_nodes{
             vaba_context.ranks_parties.insert(rep, LargeField::from(0));
         }
@@ -71,45 +72,50 @@ impl Context{
             }
         }
 
+        let asks_inst = match sub_protocol_instance(acs_inst, instance) {
+            Some(asks_inst) => asks_inst,
+            None => return,
+        };
         for rep in vaba_context.term_asks_instances.iter(){
-            let _status = self.asks_req.send((instance, 1, true, true, None, Some(*rep))).await;
+            let _status = self.asks_req.send((asks_inst, 1, true, true, None, Some(*rep))).await;
         }
         // Reconstruction true
         vaba_context.asks_reconstruction_started = true;
         // Wait until receiving all results for ranks
-        self.check_reconstruction_phase_terminated(instance).await;
+        self.check_reconstruction_phase_terminated(acs_inst, instance).await;
     }
 
-    pub async fn process_asks_reconstruction_result(&mut self, instance: usize, secret_preparer_rep: usize, recon_result: Vec<LargeField>){
-        log::info!("Received reconstruction result from ASKS for instance {} and Replica {}", instance, secret_preparer_rep);
-        
+    pub async fn process_asks_reconstruction_result(&mut self, acs_inst: usize, instance: usize, secret_preparer_rep: usize, recon_result: Vec<LargeField>){
+        log::info!("Received reconstruction result from ASKS for ACS instance {}, instance {} and Replica {}", acs_inst, instance, secret_preparer_rep);
+
         let recon_result = recon_result[0].clone();
         // Compute Rank of reconstruction
-        if !self.acs_state.vaba_states.contains_key(&instance){
+        let acs_state = self.acs_states.entry(acs_inst).or_insert_with(ACSState::new);
+        if !acs_state.vaba_states.contains_key(&instance){
             let vaba_context = VABAState::new_without_pre_justify();
-            self.acs_state.vaba_states.insert(instance, vaba_context);
+            acs_state.vaba_states.insert(instance, vaba_context);
         }
-        
-        let vaba_context = self.acs_state.vaba_states.get_mut(&instance).unwrap();
+
+        let vaba_context = acs_state.vaba_states.get_mut(&instance).unwrap();
 
         vaba_context.asks_reconstructed_values.insert(secret_preparer_rep, recon_result.clone());
-        self.check_reconstruction_phase_terminated(instance).await;
+        self.check_reconstruction_phase_terminated(acs_inst, instance).await;
     }
 
-    pub async fn check_reconstruction_phase_terminated(&mut self, instance: usize){
-        let vaba_context = self.acs_state.vaba_states.get_mut(&instance).unwrap();
-        
+    pub async fn check_reconstruction_phase_terminated(&mut self, acs_inst: usize, instance: usize){
+        let vaba_context = self.acs_states.get_mut(&acs_inst).unwrap().vaba_states.get_mut(&instance).unwrap();
+
         if vaba_context.asks_reconstruction_started{
             let mut new_ranks_reconstructed_parties = Vec::new();
             for (secret_preparer_rep, recon_result) in vaba_context.asks_reconstructed_values.iter(){
                 for (rep, set_indices) in vaba_context.asks_reconstruction_list.iter_mut(){
                     if set_indices.contains(&secret_preparer_rep){
                         let mut agg_secret_old = vaba_context.ranks_parties.get(rep).unwrap().clone();
-                        
+
                         agg_secret_old += recon_result.clone();
                         vaba_context.ranks_parties.insert(*rep, agg_secret_old);
                         set_indices.remove(&secret_preparer_rep);
-    
+
                         if set_indices.is_empty(){
                             new_ranks_reconstructed_parties.push(*rep);
                         }
@@ -119,7 +125,7 @@ impl Context{
             for rep in new_ranks_reconstructed_parties.into_iter(){
                 vaba_context.asks_reconstruction_list.remove(&rep);
             }
-        }    
+        }
         if vaba_context.asks_reconstruction_list.is_empty() && vaba_context.asks_reconstruction_started && vaba_context.elected_leader.is_none(){
             // Compute party with maximum rank
             let mut max_rank = LargeField::from(0);
@@ -139,8 +145,8 @@ impl Context{
             log::info!("Party with maximum rank {}, maximum rank {}", party_with_max_rank, max_rank);
             vaba_context.elected_leader = Some(party_with_max_rank.clone());
             // Start voting phase
-            self.start_vote_phase(instance, party_with_max_rank).await;
-            //self.terminate("Terminate".to_string()).await;   
+            self.start_vote_phase(acs_inst, instance, party_with_max_rank).await;
+            //self.terminate("Terminate".to_string()).await;
         }
     }
-}
\ No newline at end of file
+}
@@ -4,23 +4,48 @@ use types::Replica;
 
 use crate::{Context, msg::CTRBCInterface};
 
+use super::{ACSState, vaba_inst_in_range};
+
 impl Context{
     pub async fn process_ctrbc_event(&mut self, broadcaster: usize, _instance: usize, value: Vec<u8>){
-        let deser_msg: CTRBCInterface = bincode::deserialize(value.as_slice()).unwrap();
+        let deser_msg: CTRBCInterface = match bincode::deserialize(value.as_slice()) {
+            Ok(deser_msg) => deser_msg,
+            Err(e) => {
+                log::error!("Malformed CTRBC broadcast from party {}: {}", broadcaster, e);
+                return;
+            }
+        };
+        let acs_inst = deser_msg.acs_inst;
         let instance = deser_msg.id;
         let value = deser_msg.msg;
+        if self.finished_acs.contains(&acs_inst){
+            log::debug!("Ignoring CTRBC broadcast from party {} for finished ACS instance {}", broadcaster, acs_inst);
+            return;
+        }
+        // Broadcasters pick both ids, so instances this node cannot run are dropped before any state is created
+        let vaba_index = if instance > 2 {(instance - 1)/2} else {1};
+        if instance == 0 || !self.acs_inst_in_window(acs_inst) || !vaba_inst_in_range(vaba_index){
+            log::warn!("Dropping CTRBC broadcast from party {} for out of range ACS instance {} and instance {}", broadcaster, acs_inst, instance);
+            return;
+        }
+        if !self.acs_states.contains_key(&acs_inst){
+            self.acs_states.insert(acs_inst, ACSState::new());
+        }
         if instance == 1 {
             // First instance is for the RBC of the core ACS instance
             //let replicas_list: Vec<Replica> = bincode::deserialize(value.as_slice()).unwrap();
-            log::info!("Received L1 CTRBC broadcast from party {}", broadcaster);
-            self.acs_state.broadcast_messages.insert(broadcaster , Vec::new());
-            
-            if self.acs_state.broadcast_messages.len() == self.num_nodes - self.num_faults{
+            log::info!("Received L1 CTRBC broadcast from party {} for ACS instance {}", broadcaster, acs_inst);
+            let acs_state = self.acs_states.get_mut(&acs_inst).unwrap();
+            acs_state.broadcast_messages.insert(broadcaster , Vec::new());
+            acs_state.proposals.insert(broadcaster, value);
+
+            if acs_state.broadcast_messages.len() == self.num_nodes - self.num_faults{
                 // Invoke CTRBC to broadcast list of indices
-                let key_set:Vec<Replica> = self.acs_state.broadcast_messages.keys().map(|key | key.clone()).collect();
+                let key_set:Vec<Replica> = acs_state.broadcast_messages.keys().map(|key | key.clone()).collect();
                 let ser_value = bincode::serialize(&key_set).unwrap();
 
                 let ctrbc_msg = CTRBCInterface{
+                    acs_inst: acs_inst,
                     id: 2,
                     msg: ser_value
                 };
@@ -30,14 +55,21 @@ impl Context{
                 log::info!("Received n-f broadcasts of the initial value, broadcasting the list of broadcasts");
                 let _status = self.ctrbc_req.send(ser_inst_id_val).await;
             }
-            self.check_witnesses_rbc_inst(broadcaster).await;
+            self.check_witnesses_rbc_inst(acs_inst, broadcaster).await;
+            self.check_value_output(acs_inst).await;
         }
         else if instance == 2 {
-            log::info!("Received L2 CTRBC broadcast from party {}", broadcaster);
+            log::info!("Received L2 CTRBC broadcast from party {} for ACS instance {}", broadcaster, acs_inst);
             // Second RBC instance is for list of broadcasts
-            let replicas_list: Vec<Replica> = bincode::deserialize(value.as_slice()).unwrap();
-            self.acs_state.re_broadcast_messages.insert(broadcaster, replicas_list.clone());
-            self.check_witnesses_rbc_inst_single_party(broadcaster).await;
+            let replicas_list: Vec<Replica> = match bincode::deserialize(value.as_slice()) {
+                Ok(replicas_list) => replicas_list,
+                Err(e) => {
+                    log::error!("Malformed list of broadcasts from party {} for ACS instance {}: {}", broadcaster, acs_inst, e);
+                    return;
+                }
+            };
+            self.acs_states.get_mut(&acs_inst).unwrap().re_broadcast_messages.insert(broadcaster, replicas_list.clone());
+            self.check_witnesses_rbc_inst_single_party(acs_inst, broadcaster).await;
         }
         else{
             // Second instance RBC is for VABA instance
@@ -48,85 +80,91 @@ impl Context{
             if true_inst_mod % tot_rbcs_per_vaba == 1{
                 let vaba_index = (true_inst_mod/tot_rbcs_per_vaba) + 1;
                 // This broadcast corresponds to Broadcast termination of (pre_v, asks_v, justify_v)
-                self.process_pre_broadcast(vaba_index, broadcaster, value).await;
+                self.process_pre_broadcast(acs_inst, vaba_index, broadcaster, value).await;
             }
             else if true_inst_mod % tot_rbcs_per_vaba == 0{
                 let vaba_index = true_inst_mod/tot_rbcs_per_vaba;
                 // This broadcast corresponds to a Vote instance
                 // This case has not been handled
-                self.process_vote(vaba_index, value, broadcaster).await;
+                self.process_vote(acs_inst, vaba_index, value, broadcaster).await;
             }
         }
     }
 
-    pub async fn check_witnesses_rbc_inst(&mut self, broadcaster: usize){
+    pub async fn check_witnesses_rbc_inst(&mut self, acs_inst: usize, broadcaster: usize){
+        let acs_state = self.acs_states.get_mut(&acs_inst).unwrap();
         let mut added_witnesses = Vec::new();
         // Check for witnesses after each accepted broadcast
-        for (rep_key, broadcast_list) in self.acs_state.broadcasts_left_to_be_accepted.iter_mut(){
+        for (rep_key, broadcast_list) in acs_state.broadcasts_left_to_be_accepted.iter_mut(){
             broadcast_list.remove(&broadcaster);
             if broadcast_list.len() == 0{
                 // Add party to witness list
-                log::info!("Added party {} to list of first witnesses self.acs_state.accepted_witnesses", *rep_key);
+                log::info!("Added party {} to list of first witnesses acs_state.accepted_witnesses", *rep_key);
                 added_witnesses.push(*rep_key);
             }
         }
         for witness in added_witnesses.iter(){
-            self.acs_state.broadcasts_left_to_be_accepted.remove(&witness);
-            self.acs_state.accepted_witnesses.insert(*witness);
+            acs_state.broadcasts_left_to_be_accepted.remove(&witness);
+            acs_state.accepted_witnesses.insert(*witness);
         }
-        
+
         // Check if any new witnesses were added after this broadcast terminated
         if !added_witnesses.is_empty(){
-            self.check_witness_pre_broadcast(1).await;
+            self.check_witness_pre_broadcast(acs_inst, 1).await;
         }
 
         // If this is the first witness accepted for the first time ever
-        if self.acs_state.accepted_witnesses.len() >= 1 && !self.acs_state.vaba_started{
+        let acs_state = self.acs_states.get_mut(&acs_inst).unwrap();
+        if acs_state.accepted_witnesses.len() >= 1 && !acs_state.vaba_started{
             // Start first phase of VABA
             // Start ASKS first
             let pre_i = broadcaster;
-            self.start_vaba(pre_i, Vec::new(), 1).await;
-            self.acs_state.vaba_started = true;
+            acs_state.vaba_started = true;
+            self.start_vaba(acs_inst, pre_i, Vec::new(), 1).await;
         }
     }
 
-    pub async fn check_witnesses_rbc_inst_single_party(&mut self, broadcaster: usize){
+    pub async fn check_witnesses_rbc_inst_single_party(&mut self, acs_inst: usize, broadcaster: usize){
+        let acs_state = self.acs_states.get_mut(&acs_inst).unwrap();
         // Check for witnesses
-        let replicas_list = self.acs_state.re_broadcast_messages.get(&broadcaster).unwrap();
+        let replicas_list = acs_state.re_broadcast_messages.get(&broadcaster).unwrap();
         let mut hashset_replicas: HashSet<usize> = HashSet::default();
         for rep in replicas_list.into_iter(){
-            if !self.acs_state.broadcast_messages.contains_key(&rep){
+            if !acs_state.broadcast_messages.contains_key(&rep){
                 hashset_replicas.insert(*rep);
             }
         }
-        
+
         if hashset_replicas.is_empty(){
             // Add witness to witness list
-            self.acs_state.accepted_witnesses.insert(broadcaster);
-            self.check_witness_pre_broadcast(1).await;
+            acs_state.accepted_witnesses.insert(broadcaster);
+            self.check_witness_pre_broadcast(acs_inst, 1).await;
         }
         else {
-            self.acs_state.broadcasts_left_to_be_accepted.insert(broadcaster, hashset_replicas.clone());
+            acs_state.broadcasts_left_to_be_accepted.insert(broadcaster, hashset_replicas.clone());
         }
 
         // If this is the first witness accepted for the first time ever
-        if self.acs_state.accepted_witnesses.len() >= 1 && !self.acs_state.vaba_started{
+        let acs_state = self.acs_states.get_mut(&acs_inst).unwrap();
+        if acs_state.accepted_witnesses.len() >= 1 && !acs_state.vaba_started{
             // Start first phase of VABA
             // Start ASKS first
             let pre_i = broadcaster;
-            self.start_vaba( pre_i, Vec::new(), 1).await;
-            self.acs_state.vaba_started = true;
+            acs_state.vaba_started = true;
+            self.start_vaba(acs_inst, pre_i, Vec::new(), 1).await;
         }
     }
 
-    pub async fn process_termination_event(&mut self, replica: usize){
+    pub async fn process_termination_event(&mut self, acs_inst: usize, replica: usize){
+        self.max_acs_inst = self.max_acs_inst.max(acs_inst);
         self.acs_input_set.insert(replica);
-        log::info!("Completed sharing process for secrets originated by {}, adding to acs_set", replica);
+        log::info!("Completed sharing process for secrets originated by {}, adding to acs_set of instance {}", replica, acs_inst);
         let ctrbc_msg = CTRBCInterface{
+            acs_inst: acs_inst,
             id: 1,
             msg: Vec::new()
         };
         let ser_msg = bincode::serialize(&ctrbc_msg).unwrap();
         self.process_ctrbc_event(replica, 1, ser_msg).await;
     }
-}
\ No newline at end of file
+}
@@ -4,29 +4,30 @@ use types::Replica;
 
 use crate::{Context, msg::ProtMsg};
 
-use super::VABAState;
+use super::{VABAState, ACSState};
 
 impl Context{
-    pub async fn process_gather_echo(&mut self, gather_indices: Vec<Replica>, broadcaster: usize, inst: usize){
-        if !self.acs_state.vaba_states.contains_key(&inst){
+    pub async fn process_gather_echo(&mut self, acs_inst: usize, gather_indices: Vec<Replica>, broadcaster: usize, inst: usize){
+        let acs_state = self.acs_states.entry(acs_inst).or_insert_with(ACSState::new);
+        if !acs_state.vaba_states.contains_key(&inst){
             let vaba_context = VABAState::new_without_pre_justify();
-            self.acs_state.vaba_states.insert(inst, vaba_context);
+            acs_state.vaba_states.insert(inst, vaba_context);
         }
 
-        let vaba_context = self.acs_state.vaba_states.get_mut(&inst).unwrap();
+        let vaba_context = acs_state.vaba_states.get_mut(&inst).unwrap();
         vaba_context.gather_state.received_gather_echos.insert(broadcaster , gather_indices.clone());
         let mut set_indices = HashSet::default();
         set_indices.extend(gather_indices);
         vaba_context.gather_state.unvalidated_gather_echos.insert(broadcaster, set_indices);
 
         // Check gather termination
-        self.check_gather_echo_new_party(inst, broadcaster).await;
+        self.check_gather_echo_new_party(acs_inst, inst, broadcaster).await;
     }
 
-    pub async fn check_gather_echo_termination(&mut self, inst: usize, terminated_rbcs: Vec<Replica>){
-        let vaba_context = self.acs_state.vaba_states.get_mut(&inst).unwrap();
+    pub async fn check_gather_echo_termination(&mut self, acs_inst: usize, inst: usize, terminated_rbcs: Vec<Replica>){
+        let vaba_context = self.acs_states.get_mut(&acs_inst).unwrap().vaba_states.get_mut(&inst).unwrap();
         let mut new_witnesses = Vec::new();
-        
+
         for (rep, map) in vaba_context.gather_state.unvalidated_gather_echos.iter_mut(){
             for terminated_rbc in terminated_rbcs.clone().into_iter(){
                 // First, the broadcasts of parties in map must terminate and be validated
@@ -44,16 +45,16 @@ impl Context{
             vaba_context.gather_state.unvalidated_gather_echos.remove(&witness);
         }
         vaba_context.gather_state.validated_gather_echos.extend(new_witnesses);
-        
-        // Upon collecting n-f ECHOs, broadcast this list again as ECHO2s. 
+
+        // Upon collecting n-f ECHOs, broadcast this list again as ECHO2s.
         if vaba_context.gather_state.validated_gather_echos.len() == self.num_nodes - self.num_faults{
-            self.init_gather_echo2(inst).await;
+            self.init_gather_echo2(acs_inst, inst).await;
         }
-        self.check_gather_echo2_termination(inst, terminated_rbcs).await;
+        self.check_gather_echo2_termination(acs_inst, inst, terminated_rbcs).await;
     }
 
-    pub async fn check_gather_echo_new_party(&mut self, inst: usize, sender: Replica){
-        let vaba_context = self.acs_state.vaba_states.get_mut(&inst).unwrap();
+    pub async fn check_gather_echo_new_party(&mut self, acs_inst: usize, inst: usize, sender: Replica){
+        let vaba_context = self.acs_states.get_mut(&acs_inst).unwrap().vaba_states.get_mut(&inst).unwrap();
         let gather_indices = vaba_context.gather_state.unvalidated_gather_echos.get_mut(&sender).unwrap();
         for index in gather_indices.clone().into_iter(){
             if vaba_context.validated_pre_justify_votes.contains(&index) && vaba_context.reliable_agreement.contains(&index){
@@ -66,15 +67,15 @@ impl Context{
             vaba_context.gather_state.validated_gather_echos.insert(sender);
             vaba_context.gather_state.unvalidated_gather_echos.remove(&sender);
         }
-        // Upon collecting n-f ECHOs, broadcast this list again as ECHO2s. 
+        // Upon collecting n-f ECHOs, broadcast this list again as ECHO2s.
         if vaba_context.gather_state.validated_gather_echos.len() >= self.num_nodes - self.num_faults{
-            self.init_gather_echo2(inst).await;
+            self.init_gather_echo2(acs_inst, inst).await;
         }
     }
 
-    pub async fn init_gather_echo2(&mut self, inst: usize){
-        let vaba_context = self.acs_state.vaba_states.get_mut(&inst).unwrap();
-        
+    pub async fn init_gather_echo2(&mut self, acs_inst: usize, inst: usize){
+        let vaba_context = self.acs_states.get_mut(&acs_inst).unwrap().vaba_states.get_mut(&inst).unwrap();
+
         // Union witnesses
         if !vaba_context.gather_state.gather2_started{
             let mut union_witness_list: HashSet<Replica> = HashSet::default();
@@ -88,33 +89,34 @@ impl Context{
                     vec_witnesses.push(rep);
                 }
             }
-            
+
             log::info!("Starting Gather Echo2 with witnesses {:?}", vec_witnesses);
-            let prot_msg = ProtMsg::GatherEcho2(inst , vec_witnesses);
+            let prot_msg = ProtMsg::GatherEcho2(acs_inst, inst , vec_witnesses);
             vaba_context.gather_state.gather2_started = true;
             self.broadcast(prot_msg).await;
         }
     }
 
-    pub async fn process_gather_echo2(&mut self, gather_indices: Vec<Replica>, broadcaster: usize, inst: usize){
+    pub async fn process_gather_echo2(&mut self, acs_inst: usize, gather_indices: Vec<Replica>, broadcaster: usize, inst: usize){
         log::info!("Processing Gather Echo2 from {} with indices {:?}", broadcaster, gather_indices);
-        if !self.acs_state.vaba_states.contains_key(&inst){
+        let acs_state = self.acs_states.entry(acs_inst).or_insert_with(ACSState::new);
+        if !acs_state.vaba_states.contains_key(&inst){
             let vaba_context = VABAState::new_without_pre_justify();
-            self.acs_state.vaba_states.insert(inst, vaba_context);
+            acs_state.vaba_states.insert(inst, vaba_context);
         }
 
-        let vaba_context = self.acs_state.vaba_states.get_mut(&inst).unwrap();
+        let vaba_context = acs_state.vaba_states.get_mut(&inst).unwrap();
         vaba_context.gather_state.received_gather_echo2s.insert(broadcaster , gather_indices.clone());
         let mut set_indices = HashSet::default();
         set_indices.extend(gather_indices);
         vaba_context.gather_state.unvalidated_gather_echo2s.insert(broadcaster, set_indices);
 
         // Check gather termination
-        self.check_gather_echo2_new_party(inst, broadcaster).await;
+        self.check_gather_echo2_new_party(acs_inst, inst, broadcaster).await;
     }
 
-    pub async fn check_gather_echo2_termination(&mut self, inst: usize, terminated_rbcs: Vec<Replica>){
-        let vaba_context = self.acs_state.vaba_states.get_mut(&inst).unwrap();
+    pub async fn check_gather_echo2_termination(&mut self, acs_inst: usize, inst: usize, terminated_rbcs: Vec<Replica>){
+        let vaba_context = self.acs_states.get_mut(&acs_inst).unwrap().vaba_states.get_mut(&inst).unwrap();
         let mut new_witnesses = Vec::new();
         for (rep, map) in vaba_context.gather_state.unvalidated_gather_echo2s.iter_mut(){
             // First, the broadcasts of parties in map must terminate and be validated
@@ -133,15 +135,15 @@ impl Context{
             vaba_context.gather_state.unvalidated_gather_echo2s.remove(&witness);
         }
         vaba_context.gather_state.validated_gather_echo2s.extend(new_witnesses);
-        
-        // Upon collecting n-f ECHOs, broadcast this list again as ECHO2s. 
+
+        // Upon collecting n-f ECHOs, broadcast this list again as ECHO2s.
         if vaba_context.gather_state.validated_gather_echo2s.len() >= self.num_nodes - self.num_faults{
-            self.init_asks_reconstruction(inst).await;
+            self.init_asks_reconstruction(acs_inst, inst).await;
         }
     }
 
-    pub async fn check_gather_echo2_new_party(&mut self, inst: usize, sender: Replica){
-        let vaba_context = self.acs_state.vaba_states.get_mut(&inst).unwrap();
+    pub async fn check_gather_echo2_new_party(&mut self, acs_inst: usize, inst: usize, sender: Replica){
+        let vaba_context = self.acs_states.get_mut(&acs_inst).unwrap().vaba_states.get_mut(&inst).unwrap();
         let gather_indices = vaba_context.gather_state.unvalidated_gather_echo2s.get_mut(&sender).unwrap();
         for index in gather_indices.clone().into_iter(){
             if vaba_context.validated_pre_justify_votes.contains(&index) && vaba_context.reliable_agreement.contains(&index){
@@ -154,10 +156,10 @@ impl Context{
             vaba_context.gather_state.validated_gather_echo2s.insert(sender);
             vaba_context.gather_state.unvalidated_gather_echo2s.remove(&sender);
         }
-        // Upon collecting n-f ECHOs, broadcast this list again as ECHO2s. 
+        // Upon collecting n-f ECHOs, broadcast this list again as ECHO2s.
         if vaba_context.gather_state.validated_gather_echo2s.len() >= self.num_nodes - self.num_faults{
-            // Start next phase of the protocol. Reconstruct ASKS instances. 
-            self.init_asks_reconstruction(inst).await;
+            // Start next phase of the protocol. Reconstruct ASKS instances.
+            self.init_asks_reconstruction(acs_inst, inst).await;
         }
-    }    
-}
\ No newline at end of file
+    }
+}
@@ -7,4 +7,6 @@ mod vaba;
 
 mod asks;
 
-mod gather;
\ No newline at end of file
+mod gather;
+
+mod proposal;
\ No newline at end of file
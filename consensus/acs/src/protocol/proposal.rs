@@ -0,0 +1,63 @@
+use types::Replica;
+
+use crate::{Context, msg::CTRBCInterface};
+
+use super::ACSState;
+
+impl Context{
+    // Disseminates this party's proposal for an ACS instance. The first CTRBC instance doubles as the L1 broadcast of the ACS protocol.
+    pub async fn init_value_proposal(&mut self, acs_inst: usize, value: Vec<u8>){
+        log::info!("Broadcasting proposal of {} bytes for ACS instance {}", value.len(), acs_inst);
+        if self.finished_acs.contains(&acs_inst){
+            log::error!("ACS instance {} already output, dropping the proposal", acs_inst);
+            return;
+        }
+        if !self.acs_states.contains_key(&acs_inst){
+            self.acs_states.insert(acs_inst, ACSState::new());
+        }
+        self.max_acs_inst = self.max_acs_inst.max(acs_inst);
+        let ctrbc_msg = CTRBCInterface{
+            acs_inst: acs_inst,
+            id: 1,
+            msg: value
+        };
+        let ser_msg = bincode::serialize(&ctrbc_msg).unwrap();
+        let status = self.ctrbc_req.send(ser_msg).await;
+        if status.is_err(){
+            log::error!("Error sending proposal to the CTRBC queue, abandoning ACS instance {}", acs_inst);
+        }
+    }
+
+    // Outputs the agreed proposals once the ACS output set is fixed and every proposal in it has been delivered.
+    pub async fn check_value_output(&mut self, acs_inst: usize){
+        if self.value_out_channel.is_none(){
+            return;
+        }
+        let acs_state = self.acs_states.get_mut(&acs_inst).unwrap();
+        if acs_state.value_output_sent || acs_state.output_set.is_none(){
+            return;
+        }
+
+        let mut output_set = acs_state.output_set.clone().unwrap();
+        output_set.sort();
+        let mut agreed_values: Vec<(Replica, Vec<u8>)> = Vec::new();
+        for rep in output_set.into_iter(){
+            match acs_state.proposals.get(&rep){
+                Some(value) => {
+                    agreed_values.push((rep, value.clone()));
+                },
+                None => {
+                    log::info!("Proposal of party {} in ACS instance {} not delivered yet, waiting for CTRBC", rep, acs_inst);
+                    return;
+                }
+            }
+        }
+        acs_state.value_output_sent = true;
+        log::info!("ACS instance {} output values of parties {:?}", acs_inst, agreed_values.iter().map(|(rep,_)| *rep).collect::<Vec<Replica>>());
+        let status = self.value_out_channel.as_ref().unwrap().send((acs_inst, agreed_values)).await;
+        if status.is_err(){
+            log::error!("Error sending ACS output for instance {} to the output channel", acs_inst);
+        }
+        self.finish_acs_instance(acs_inst);
+    }
+}
@@ -3,6 +3,35 @@ use std::collections::{HashSet, HashMap};
 use crypto::{LargeField};
 use types::Replica;
 
+/// Maximum number of VABA instances run within a single ACS instance.
+/// ASKS and RA instance ids are derived as acs_inst*VABA_INSTANCES + vaba_inst so that concurrent ACS instances do not collide.
+/// Every VABA instance ends the ACS with constant probability, so an ACS that needs more is abandoned rather than colliding.
+pub const VABA_INSTANCES: usize = 50;
+
+/// Highest ACS instance id whose sub-protocol ids fit below the ASKS instances of the next party
+pub const MAX_ACS_INSTANCE: usize = asks::INSTANCE_THRESHOLD/VABA_INSTANCES - 1;
+
+/// ACS instances a party may be ahead of the highest instance this node started or finished.
+/// Messages for later instances are dropped so Byzantine parties cannot grow the state without limit.
+pub const MAX_ACS_INSTANCES_AHEAD: usize = 10;
+
+/// ASKS and RA instance id of a VABA instance, or None if the ACS or VABA instance is out of range
+pub fn sub_protocol_instance(acs_inst: usize, vaba_inst: usize) -> Option<usize>{
+    if acs_inst > MAX_ACS_INSTANCE || vaba_inst >= VABA_INSTANCES{
+        return None;
+    }
+    Some(acs_inst*VABA_INSTANCES + vaba_inst)
+}
+
+/// Whether a VABA instance id received from another party can be a VABA instance of an ACS
+pub fn vaba_inst_in_range(vaba_inst: usize) -> bool{
+    vaba_inst >= 1 && vaba_inst < VABA_INSTANCES
+}
+
+pub fn split_sub_protocol_instance(instance: usize) -> (usize, usize){
+    (instance/VABA_INSTANCES, instance%VABA_INSTANCES)
+}
+
 pub struct ACSState{
     pub broadcast_messages: HashMap<Replica, Vec<Replica>>,
     
@@ -15,6 +44,11 @@ pub struct ACSState{
     pub ra_value: Option<Replica>,
 
     pub acs_output: HashSet<Replica>,
+
+    // Proposals delivered through the first CTRBC instance. Empty when ACS only agrees on indices. 
+    pub proposals: HashMap<Replica, Vec<u8>>,
+    pub output_set: Option<Vec<Replica>>,
+    pub value_output_sent: bool,
 }
 
 impl ACSState{
@@ -31,6 +65,10 @@ impl ACSState{
             ra_value: None,
 
             acs_output: HashSet::default(),
+
+            proposals: HashMap::default(),
+            output_set: None,
+            value_output_sent: false,
         }
     }
 }
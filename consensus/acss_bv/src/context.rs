@@ -7,28 +7,27 @@ use anyhow::{anyhow, Result};
 use config::Node;
 
 use fnv::FnvHashMap;
-use lambdaworks_math::traits::ByteConversion;
 use network::{
-    plaintcp::{CancelHandler, TcpReceiver, TcpReliableSender},
+    plaintcp::CancelHandler,
     Acknowledgement,
 };
 use num_bigint_dig::{BigInt};
 use tokio::sync::{
-    mpsc::{unbounded_channel, UnboundedReceiver, Receiver, Sender},
+    mpsc::{channel, unbounded_channel, UnboundedReceiver, Receiver, Sender},
     oneshot,
 };
 // use tokio_util::time::DelayQueue;
 use types::{Replica,WrapperMsg};
 
-use consensus::{LargeFieldSSS, FoldingDZKContext};
+use consensus::{AcssOutput, Acss, AcssParams, AcssScheme, AcssService, HashKeys, LargeFieldSSS, FoldingDZKContext, PubRecOutput, SecretBytes, ReplicaSender};
 
-use ha_crypto::{aes_hash::HashState, LargeField, LargeFieldSer, hash::Hash};
+use ha_crypto::{aes_hash::HashState, LargeField};
 
-use crate::{msg::ProtMsg, handlers::Handler, protocol::BatchACSSState};
+use crate::{msg::ProtMsg, protocol::BatchACSSState};
 
 pub struct Context {
     /// Networking context
-    pub net_send: TcpReliableSender<Replica, WrapperMsg<ProtMsg>, Acknowledgement>,
+    pub net_send: ReplicaSender<WrapperMsg<ProtMsg>>,
     pub net_recv: UnboundedReceiver<WrapperMsg<ProtMsg>>,
     
     /// Data context
@@ -42,7 +41,7 @@ pub struct Context {
     pub large_field_prime: BigInt,
 
     /// Secret Key map
-    pub sec_key_map: HashMap<Replica, Vec<u8>>,
+    pub sec_key_map: HashMap<Replica, SecretBytes>,
 
     /// Hardware acceleration context
     pub hash_context: HashState,
@@ -69,21 +68,24 @@ pub struct Context {
     pub folding_dzk_context: FoldingDZKContext,
 
 
-    /// Constants for PRF seeding
-    pub nonce_seed: usize,
-
     /// State for ACSS
     pub acss_state: HashMap<usize, BatchACSSState>,
 
     /// Input and output request channels
-    pub inp_acss_requests: Receiver<(usize, Vec<LargeFieldSer>)>,
-    pub out_acss_shares: Sender<(usize, usize, Hash, Vec<LargeFieldSer>)>
+    pub inp_acss_requests: Receiver<(usize, Vec<LargeField>)>,
+    pub out_acss_shares: Sender<AcssOutput>,
+
+    pub inp_pub_rec: Receiver<(usize, Replica)>,
+    pub out_pub_rec: Sender<PubRecOutput>,
 }
 
 impl Context {
-    pub fn spawn(config: Node, 
-        inp_req_channel: Receiver<(usize, Vec<LargeFieldSer>)>,
-        out_shares_channel: Sender<(usize, usize, Hash, Vec<LargeFieldSer>)>,
+    pub fn spawn(config: Node,
+        hash_keys: HashKeys,
+        inp_req_channel: Receiver<(usize, Vec<LargeField>)>,
+        out_shares_channel: Sender<AcssOutput>,
+        inp_pub_rec_channel: Receiver<(usize, Replica)>,
+        out_pub_rec_channel: Sender<PubRecOutput>,
         byz: bool) -> anyhow::Result<oneshot::Sender<()>> {
         // Add a separate configuration for RBC service. 
         // Constants for RBC service as a channel
@@ -101,22 +103,12 @@ impl Context {
         
         // Setup networking
         let (tx_net_to_consensus, rx_net_to_consensus) = unbounded_channel();
-        TcpReceiver::<Acknowledgement, WrapperMsg<ProtMsg>, _>::spawn(
-            my_address,
-            Handler::new(tx_net_to_consensus),
-        );
-
-        let consensus_net = TcpReliableSender::<Replica, WrapperMsg<ProtMsg>, Acknowledgement>::with_peers(
-            consensus_addrs.clone(),
-        );
+        let consensus_net = consensus::spawn_replica_network(my_address, consensus_addrs.clone(), tx_net_to_consensus);
         let (exit_tx, exit_rx) = oneshot::channel();
 
         // Keyed AES ciphers
-        let key0 = [5u8; 16];
-        let key1 = [29u8; 16];
-        let key2 = [23u8; 16];
-        let hashstate = HashState::new(key0, key1, key2);
-        let hashstate2 = HashState::new(key0, key1, key2);
+        let hashstate = hash_keys.hash_state();
+        let hashstate2 = hash_keys.hash_state();
 
         let threshold:usize = 10000;
         let rbc_start_id = threshold*config.id;
@@ -197,15 +189,17 @@ impl Context {
                 folding_dzk_context:folding_context,
 
                 acss_state: HashMap::default(),
-                nonce_seed: 1,
 
                 inp_acss_requests: inp_req_channel,
-                out_acss_shares: out_shares_channel
+                out_acss_shares: out_shares_channel,
+
+                inp_pub_rec: inp_pub_rec_channel,
+                out_pub_rec: out_pub_rec_channel,
             };
 
             // Populate secret keys from config
-            for (id, sk_data) in config.sk_map.clone() {
-                c.sec_key_map.insert(id, sk_data.clone());
+            for (id, sk_data) in config.sk_map.iter() {
+                c.sec_key_map.insert(*id, SecretBytes::new(sk_data.clone()));
             }
 
             // Run the consensus context
@@ -216,15 +210,46 @@ impl Context {
         Ok(exit_tx)
     }
 
+    /// Spawns the service behind the common `Acss` interface, the way DPSS drives it
+    pub fn spawn_acss(config: Node, hash_keys: HashKeys, params: AcssParams) -> Result<Box<dyn Acss>>{
+        let (acss_req_send, acss_req_recv) = channel(10000);
+        let (acss_out_send, acss_out_recv) = channel(10000);
+        let (pub_rec_req_send, pub_rec_req_recv) = channel(10000);
+        let (pub_rec_out_send, pub_rec_out_recv) = channel(10000);
+        let num_faults = config.num_faults;
+
+        let exit_tx = Self::spawn(
+            config,
+            hash_keys,
+            acss_req_recv,
+            acss_out_send,
+            pub_rec_req_recv,
+            pub_rec_out_send,
+            params.byz
+        )?;
+        Ok(Box::new(AcssService {
+            scheme: AcssScheme::Bv,
+            num_faults: num_faults,
+            acss_req: acss_req_send,
+            acss_out: acss_out_recv,
+            pub_rec_req: pub_rec_req_send,
+            pub_rec_out: pub_rec_out_recv,
+            pub_rec_selected: None,
+            exit_txs: vec![exit_tx],
+        }))
+    }
+
     pub async fn broadcast(&mut self, protmsg: ProtMsg) {
-        let sec_key_map = self.sec_key_map.clone();
-        for (replica, sec_key) in sec_key_map.into_iter() {
+        let replicas: Vec<Replica> = self.sec_key_map.keys().cloned().collect();
+        for replica in replicas {
+            let sec_key = self.sec_key_map.get(&replica).unwrap();
             if self.byz && replica % 2 == 0 {
                 // Simulates a crash fault
                 continue;
             }
             if replica != self.myid {
                 let wrapper_msg = WrapperMsg::new(protmsg.clone(), self.myid, &sec_key.as_slice());
+                consensus::count_sent_message(&wrapper_msg);
                 let cancel_handler: CancelHandler<Acknowledgement> =
                     self.net_send.send(replica, wrapper_msg).await;
                 self.add_cancel_handler(cancel_handler);
@@ -237,6 +262,7 @@ impl Context {
     }
 
     pub async fn send(&mut self, replica: Replica, wrapper_msg: WrapperMsg<ProtMsg>) {
+        consensus::count_sent_message(&wrapper_msg);
         let cancel_handler: CancelHandler<Acknowledgement> =
             self.net_send.send(replica, wrapper_msg).await;
         self.add_cancel_handler(cancel_handler);
@@ -255,7 +281,7 @@ impl Context {
                 },
                 msg = self.net_recv.recv() => {
                     // Received messages are processed here
-                    log::trace!("Got a consensus message from the network: {:?}", msg);
+                    log::trace!("Got a consensus message from the network from {:?}", msg.as_ref().map(|msg| msg.sender));
                     let msg = msg.ok_or_else(||
                         anyhow!("Networking layer has closed")
                     )?;
@@ -266,9 +292,13 @@ impl Context {
                         anyhow!("Networking layer has closed")
                     )?;
                     let acss_inst_id = self.myid*self.threshold + acss_msg.0;
-                    let acss_share_msgs_ser = acss_msg.1;
-                    let acss_lf_secrets: Vec<LargeField> = acss_share_msgs_ser.into_iter().map(|x| LargeField::from_bytes_be(x.as_slice()).unwrap()).collect();
-                    self.init_batch_acss_va(acss_lf_secrets, acss_inst_id).await;
+                    self.init_batch_acss_va(acss_msg.1, acss_inst_id).await;
+                },
+                pub_rec_req = self.inp_pub_rec.recv() => {
+                    let (instance_id, dealer) = pub_rec_req.ok_or_else(||
+                        anyhow!("Networking layer has closed")
+                    )?;
+                    self.init_pub_rec(instance_id, dealer).await;
                 }
             };
         }
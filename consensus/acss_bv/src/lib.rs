@@ -3,8 +3,6 @@ pub use context::*;
 
 mod protocol;
 
-mod handlers;
-
 mod msg;
 
 mod process;
\ No newline at end of file
@@ -271,7 +271,9 @@ pub struct Commitment{
     pub blinding_roots: Vec<Vec<Hash>>,
     pub blinding_nonces: Vec<Vec<LargeFieldSer>>,
     pub dzk_poly: Vec<Vec<LargeFieldSer>>,
-    pub batch_count: usize
+    pub batch_count: usize,
+    // Number of secrets dealt, without the padding of the last bivariate polynomial
+    pub num_secrets: usize,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -291,5 +293,9 @@ pub enum ProtMsg{
         CTRBCMsg,
         Vec<u8>, // Encrypted shares on row and column
         usize // ACSS Instance ID
-    )
+    ),
+    PubRec(
+        usize, // ACSS Instance ID
+        Vec<(Replica, PointsBVSer)> // Points on the sender's rows, by evaluation point
+    ),
 }
\ No newline at end of file
@@ -27,7 +27,8 @@ impl Context {
     }
 
     pub(crate) async fn process_msg(&mut self, wrapper_msg: WrapperMsg<ProtMsg>) {
-        log::trace!("Received protocol msg: {:?}", wrapper_msg);
+        // Payloads can carry shares, so only the sender is logged
+        log::trace!("Received protocol msg from {}", wrapper_msg.sender);
         delay_message_processing().await;
         let msg = Arc::new(wrapper_msg.clone());
 
@@ -54,6 +55,11 @@ impl Context {
                     log::debug!("Received Ready for instance id {} from node : {}", instance_id, main_msg.origin);
                     self.process_ready(main_msg,encrypted_share, wrapper_msg.sender,instance_id).await;
                 },
+                ProtMsg::PubRec(instance_id, points) => {
+                    // Public reconstruction of the dealer's secrets
+                    log::debug!("Received PubRec for instance id {} from node : {}", instance_id, wrapper_msg.sender);
+                    self.process_pub_rec(instance_id, points, wrapper_msg.sender).await;
+                },
                 // ProtMsg::Deliver(avid_shard, origin, instance_id) => {
                     
                 //     log::debug!("Received Deliver for instance id {} from node : {}", instance_id, origin);
@@ -1,7 +1,7 @@
 use std::collections::{HashSet, HashMap};
 
-use consensus::LargeFieldSSS;
-use ha_crypto::{LargeField, pseudorandom_lf, hash::Hash, rand_field_element};
+use consensus::{LargeFieldSSS, PrfDomain, SecretBytes};
+use ha_crypto::{LargeField, hash::Hash, rand_field_element};
 use types::Replica;
 
 use crate::{Context, msg::{Commitment, PointsBV}};
@@ -11,15 +11,14 @@ impl Context{
     // Returns (a,b,c) - a is the set of evaluations on row polynomials on the given evaluation points, 
     // b is the set of evaluations on the column polynomials on the given evaluation points
     // c is the set of coefficients of row polynomials. 
-    // instance_id acts the prf seed for generating shares randomly
+    // prf_rows holds the coefficients of the rows of the first t parties, see Self::prf_rows
     pub fn sample_bivariate_polynomial_with_prf(
         num_faults: usize,
         num_nodes:usize, 
-        sec_key_map: HashMap<Replica, Vec<u8>>,
         large_field_uv_sss: LargeFieldSSS,
         secret_poly_coeffs: Option<Vec<LargeField>>, 
         evaluation_pts: Vec<LargeField>, 
-        prf_seed: Vec<u8>
+        prf_rows: Vec<Vec<LargeField>>
     )->(Vec<Vec<LargeField>>, Vec<Vec<LargeField>>,Vec<Vec<LargeField>>){
         let eval_pts_len = evaluation_pts.len();
         let mut row_coefficients = Vec::new();
@@ -28,18 +27,7 @@ impl Context{
         if secret_encoded{
             row_coefficients.push(secret_poly_coeffs.unwrap());
         }
-        for rep in 0..num_faults{
-            let mut sec_key = sec_key_map.get(&rep).unwrap().clone();
-            sec_key.extend(prf_seed.clone());
-            
-            let sampled_coefficients: Vec<LargeField> = pseudorandom_lf(sec_key.as_slice(), 2*num_faults+1).into_iter().map(
-                |elem|{
-                    let mod_elem = elem;
-                    mod_elem
-                }
-            ).collect();
-            row_coefficients.push(sampled_coefficients);
-        }
+        row_coefficients.extend(prf_rows);
         if !secret_encoded{
             // First polynomial must be randomly sampled
             let mut first_poly = Vec::new();
@@ -66,6 +54,27 @@ impl Context{
         (row_evals,col_evals, row_coefficients)
     }
 
+    // Coefficients of the rows of the first t parties in num_polys bivariate polynomials. 
+    // Party i's rows come from the PRF stream between the dealer and i, entry [p][i] is its row in polynomial p.
+    pub fn prf_rows(
+        num_faults: usize,
+        sec_key_map: &HashMap<Replica, SecretBytes>,
+        domain: &PrfDomain,
+        purpose: u8,
+        num_polys: usize
+    )-> Vec<Vec<Vec<LargeField>>>{
+        let row_len = 2*num_faults+1;
+        let mut rows: Vec<Vec<Vec<LargeField>>> = (0..num_polys).into_iter().map(|_| Vec::new()).collect();
+        for rep in 0..num_faults{
+            let sec_key = sec_key_map.get(&rep).unwrap();
+            let stream = domain.stream(sec_key.as_slice(), rep, purpose, num_polys*row_len);
+            for (poly_rows, row) in rows.iter_mut().zip(stream.chunks(row_len)){
+                poly_rows.push(row.to_vec());
+            }
+        }
+        rows
+    }
+
     pub fn generate_row_column_evaluations(coefficients: &Vec<Vec<LargeField>>, 
             eval_points: Vec<LargeField>, 
             large_field_shamir_context: &LargeFieldSSS,
@@ -207,4 +216,24 @@ impl Context{
         }
         Some(share_map)
     }
+
+    /// Flattens interpolated shares, indexed by evaluation point, into the order the dealer packed the secrets:
+    /// batch, then bivariate polynomial, then evaluation point. Drops the padding of the last polynomial.
+    pub fn shares_in_dealing_order(share_map: Vec<(Vec<Vec<LargeField>>, Vec<LargeField>)>, num_secrets: usize)-> Vec<LargeField>{
+        let mut shares = Vec::new();
+        if share_map.is_empty(){
+            return shares;
+        }
+        let tot_batches = share_map[0].0.len();
+        for batch in 0..tot_batches{
+            let polys_in_batch = share_map[0].0[batch].len();
+            for poly in 0..polys_in_batch{
+                for (evals, _) in share_map.iter(){
+                    shares.push(evals[batch][poly].clone());
+                }
+            }
+        }
+        shares.truncate(num_secrets);
+        shares
+    }
 }
\ No newline at end of file
@@ -187,6 +187,7 @@ impl Context{
 
                     let ready_msg = ProtMsg::Ready(rbc_msg.clone(), enc_share, instance_id);
                     let wrapper_msg = WrapperMsg::new(ready_msg, self.myid, &secret_key);
+                    consensus::count_sent_message(&wrapper_msg);
                     let cancel_handler: CancelHandler<Acknowledgement> = self.net_send.send(recipient, wrapper_msg).await;
                     self.add_cancel_handler(cancel_handler);
                 }
@@ -199,7 +200,7 @@ impl Context{
                 log::info!("Received n ECHO messages for ACSS Instance ID {}, terminating",instance_id);
                 acss_va_state.terminated = true;
                 let comm_root = acss_va_state.verified_hash.clone().unwrap();
-                let shares = acss_va_state.shares.clone().unwrap();
+                let shares = acss_va_state.shares.as_ref().map(|shares| shares.to_vec());
                 self.terminate(shares, comm_root, instance_id).await;
             }
         } 
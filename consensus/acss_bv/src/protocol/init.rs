@@ -1,19 +1,20 @@
-use std::{time::{SystemTime, UNIX_EPOCH}, collections::HashMap};
+use std::time::{SystemTime, UNIX_EPOCH};
 
-use consensus::{get_shards, LargeFieldSSS};
-use ha_crypto::{LargeField, aes_hash::{HashState, MerkleTree}, decrypt, encrypt, hash::Hash, pseudorandom_lf, rand_field_element};
+use consensus::{get_shards, sharing_batch, LargeFieldSSS, PrfDomain, SecretShares, PRF_NONCE, PRF_SHARES};
+use ha_crypto::{LargeField, aes_hash::{HashState, MerkleTree}, decrypt, encrypt, hash::Hash, rand_field_element};
 use ctrbc::CTRBCMsg;
 use lambdaworks_math::traits::ByteConversion;
 use network::{plaintcp::CancelHandler, Acknowledgement};
 use rayon::prelude::IntoParallelRefIterator;
 use types::{WrapperMsg, Replica};
-use crate::{Context, msg::{RowPolynomialsBatch, Commitment, RowPolynomialsBatchSer, ProtMsg, PointsBV}, protocol::BatchACSSState};
+use crate::{Context, msg::{RowPolynomialsBatch, Commitment, RowPolynomialsBatchSer, ProtMsg, PointsBV}, protocol::{BatchACSSState, PRF_PROTOCOL}};
 use rayon::prelude::{ParallelIterator};
 
 impl Context{
     pub async fn init_batch_acss_va(self: &mut Context, secrets: Vec<LargeField>, instance_id: usize){
 
         let tot_batches = 1;
+        let num_secrets = secrets.len();
         
         // Sample bivariate polynomials
         // Pack t+1 degree-t sharings in each bivariate polynomial
@@ -27,23 +28,31 @@ impl Context{
 
         let ht_indices: Vec<LargeField> = (1..2*self.num_faults+2).into_iter().map(|el| LargeField::from(el as u64)).collect();
         
+        // The secrets sit on F(x,0) at the share evaluation points 0,-1,...,-t.
+        // F(x,0) takes random values at points 1..t, which fills it up to degree 2t.
         let eval_point_start: isize = ((self.num_faults) as isize) * (-1);
         let mut eval_point_indices_lf: Vec<LargeField> = (eval_point_start..1).into_iter().map(|index| LargeField::from(index as u64)).collect();
+        eval_point_indices_lf.reverse();
         for val in 1..self.num_faults+1{
             eval_point_indices_lf.push(LargeField::from(val as u64));
         }
 
-        //let vandermonde_matrix_shares_ht = self.large_field_uv_sss.vandermonde_matrix(&eval_point_indices_lf);
-        //let inverse_vandermonde_shares = self.large_field_uv_sss.inverse_vandermonde(vandermonde_matrix_shares_ht);
+        let vandermonde_matrix_secrets = LargeFieldSSS::vandermonde_matrix(eval_point_indices_lf);
+        let inverse_vandermonde_secrets = LargeFieldSSS::inverse_vandermonde(vandermonde_matrix_secrets);
 
         let vandermonde_matrix_ht =  LargeFieldSSS::vandermonde_matrix(ht_indices);
         let inverse_vandermonde = LargeFieldSSS::inverse_vandermonde(vandermonde_matrix_ht);
 
         // Parallelize this part using tokio
         let num_cores = 4;
-        let len_each_chunk = batched_secrets.len()/num_cores;
+        // Round up, so that fewer bivariate polynomials than cores still make a non-empty chunk
+        let len_each_chunk = std::cmp::max(1, (batched_secrets.len()+num_cores-1)/num_cores);
         // Divide everything into num_cores batches
         let batched_parallel_secrets: Vec<Vec<Vec<LargeField>>> = batched_secrets.chunks(len_each_chunk).into_iter().map(|el| el.to_vec()).collect();
+        // Rows of the first t parties in each bivariate polynomial, from their PRF streams
+        let prf_domain = PrfDomain::new(PRF_PROTOCOL, instance_id, sharing_batch(instance_id), self.myid);
+        let prf_rows = Self::prf_rows(self.num_faults, &self.sec_key_map, &prf_domain, PRF_SHARES, batched_secrets.len());
+        let parallel_prf_rows: Vec<Vec<Vec<Vec<LargeField>>>> = prf_rows.chunks(len_each_chunk).into_iter().map(|el| el.to_vec()).collect();
         
         let mut handles: Vec<(usize, 
         tokio::task::JoinHandle<
@@ -51,18 +60,15 @@ impl Context{
             Vec<Vec<Vec<LargeField>>>, 
             Vec<Vec<Vec<LargeField>>>, 
             Vec<Vec<Vec<LargeField>>>)>)> = Vec::new();
-        for (b_i,batch) in (0..num_cores).into_iter().zip(batched_parallel_secrets.into_iter()){
-            let batch_index = b_i*len_each_chunk;
+        for (b_i,(batch, batch_prf_rows)) in (0..num_cores).into_iter().zip(batched_parallel_secrets.into_iter().zip(parallel_prf_rows.into_iter())){
             let job = tokio::spawn(
                 Self::generate_shares(
-                        self.sec_key_map.clone(),
                         batch, 
-                        instance_id, 
-                        batch_index, 
+                        batch_prf_rows, 
                         inverse_vandermonde.clone(),
+                        inverse_vandermonde_secrets.clone(),
                         self.num_faults,
                         self.num_nodes,
-                        self.large_field_bv_sss.clone(),
                         self.large_field_uv_sss.clone()
                     )
                 );
@@ -91,8 +97,6 @@ impl Context{
         let col_poly_evals_batches: Vec<Vec<Vec<Vec<LargeField>>>> = col_evaluations_batch.chunks(each_batch).map(|el| el.to_vec()).collect();
         let dzk_poly_evals_batches: Vec<Vec<Vec<Vec<LargeField>>>> = col_dzk_proof_evaluations_batch.chunks(each_batch).map(|el| el.to_vec()).collect();
 
-        let mut index: usize = 0;
-        
         let mut share_messages_party = Vec::new();
         for _ in 0..self.num_nodes{
             share_messages_party.push(Vec::new());
@@ -103,15 +107,12 @@ impl Context{
         let mut dzk_polynomials = Vec::new();
 
         let mut blinding_nonces = Vec::new();
+        // One nonce polynomial per batch
+        let mut nonce_prf_rows = Self::prf_rows(self.num_faults, &self.sec_key_map, &prf_domain, PRF_NONCE, row_coeffs_batches.len()).into_iter();
         for ((row_coeffs_batch, _row_evals_batch),(col_evals_batch, dzk_polys_batch)) in 
                 (row_coeffs_batches.into_iter().zip(row_poly_evals_batches.into_iter())).zip(col_poly_evals_batches.into_iter().zip(dzk_poly_evals_batches).into_iter()){
 
             // Sample nonce polynomial
-            let mut prf_seed = Vec::new();
-            prf_seed.extend(instance_id.to_be_bytes());
-            prf_seed.extend(self.nonce_seed.to_be_bytes());
-            prf_seed.extend(index.clone().to_be_bytes());
-            
             let evaluation_points = (0..self.num_nodes+1).into_iter().map(|el| LargeField::from(el as u64)).collect();
             
             // First polynomial must be randomly sampled
@@ -124,11 +125,10 @@ impl Context{
                     = Self::sample_bivariate_polynomial_with_prf(
                         self.num_faults,
                         self.num_nodes,
-                        self.sec_key_map.clone(),
                         self.large_field_uv_sss.clone(),
                         Some(first_poly), 
                         evaluation_points, 
-                        prf_seed
+                        nonce_prf_rows.next().unwrap()
                     );
             
             // Secrets are no longer needed. Remove the first row and column evaluation. They are not needed because they do not correspond to any node's shares
@@ -189,7 +189,7 @@ impl Context{
             // Generate Distributed ZK polynomial
             let mut agg_poly = blinding_eval_points_dzk.clone();
             // Divide DZK poly generation into batches
-            let per_batch = dzk_polys_batch.len()/num_cores;
+            let per_batch = std::cmp::max(1, (dzk_polys_batch.len()+num_cores-1)/num_cores);
             let batches_chunked: Vec<Vec<Vec<Vec<LargeField>>>> = dzk_polys_batch.chunks(per_batch).into_iter().map(|el| el.to_vec()).collect();
             let mut handles = Vec::new();
             for batch in batches_chunked{
@@ -287,7 +287,6 @@ impl Context{
             }
             merkle_roots_batches.push(mrs);
             blinding_commitments_batches.push(blinding_commitment_vec);
-            index+=1;
         }
 
         let commitment = Commitment{
@@ -295,7 +294,8 @@ impl Context{
             blinding_roots: blinding_commitments_batches,
             blinding_nonces: blinding_nonces,
             dzk_poly: dzk_polynomials,
-            batch_count: each_batch
+            batch_count: each_batch,
+            num_secrets: num_secrets,
         };
 
         for (rep,row_polys) in (0..self.num_nodes).into_iter().zip(share_messages_party.into_iter()){
@@ -309,6 +309,7 @@ impl Context{
             let init_msg = ProtMsg::Init(enc_share, commitment.clone(), self.myid, instance_id);
             let wrapper_msg = WrapperMsg::new(init_msg,self.myid, secret_key.as_slice());
 
+            consensus::count_sent_message(&wrapper_msg);
             let cancel_handler: CancelHandler<Acknowledgement> = self.net_send.send(rep, wrapper_msg).await;
             self.add_cancel_handler(cancel_handler);
         }
@@ -334,14 +335,12 @@ impl Context{
     }
 
     async fn generate_shares(
-        sec_key_map: HashMap<Replica, Vec<u8>>,
         batched_secrets: Vec<Vec<LargeField>>, 
-        instance_id: usize, 
-        batch_index: usize, 
+        prf_rows: Vec<Vec<Vec<LargeField>>>, 
         inverse_vandermonde: Vec<Vec<LargeField>>,
+        inverse_vandermonde_secrets: Vec<Vec<LargeField>>,
         num_faults: usize,
         num_nodes: usize,
-        large_field_bv_sss: LargeFieldSSS,
         large_field_uv_sss: LargeFieldSSS
     )-> 
     (Vec<Vec<Vec<LargeField>>>,Vec<Vec<Vec<LargeField>>>,Vec<Vec<Vec<LargeField>>>,Vec<Vec<Vec<LargeField>>>)
@@ -350,7 +349,7 @@ impl Context{
         let mut row_evaluations_batch = Vec::new();
         let mut col_evaluations_batch = Vec::new();
         let mut col_dzk_proof_evaluations_batch = Vec::new();
-        for (index,batch) in (batch_index..batch_index+batched_secrets.len()).into_iter().zip(batched_secrets.into_iter()){
+        for (batch, poly_prf_rows) in batched_secrets.into_iter().zip(prf_rows.into_iter()){
             // Sample F(x,0) polynomial next
             let eval_point_start: isize = ((num_faults) as isize) * (-1);
             let mut eval_point_indices_lf: Vec<LargeField> = (eval_point_start..1).into_iter().map(|index| LargeField::from(index as u64)).collect();
@@ -363,22 +362,17 @@ impl Context{
             }
 
             // Generate coefficients of this polynomial
-            let coeffs_f_x0 = large_field_bv_sss.polynomial_coefficients_with_precomputed_vandermonde_matrix(&points_f_x0);
-            
-            let mut prf_seed = Vec::new();
-            prf_seed.extend(instance_id.to_be_bytes());
-            prf_seed.extend(index.to_be_bytes());
+            let coeffs_f_x0 = large_field_uv_sss.polynomial_coefficients_with_vandermonde_matrix(&inverse_vandermonde_secrets, &points_f_x0);
 
             let (mut row_evaluations,mut col_evaluations, bv_coefficients) 
                         = Self::sample_bivariate_polynomial_with_prf(
                             num_faults,
                             num_nodes,
-                            sec_key_map.clone(),
                             large_field_uv_sss.clone(),
 
                 Some(coeffs_f_x0), 
                 (0..num_nodes+1).into_iter().map(|el| LargeField::from(el as u64)).collect(), 
-                prf_seed.clone()
+                poly_prf_rows
             );
             
             // Secrets are no longer needed. Remove the first row and column evaluation. They are not needed because they do not correspond to any node's shares
@@ -432,9 +426,21 @@ impl Context{
         // Deserialize the encrypted message
         let shares_msg: Vec<RowPolynomialsBatchSer> = bincode::deserialize(dec_msg.as_slice()).unwrap();
         
-        // Verify shares
-        let mut index_batch: usize = 0;
+        // The first t parties derive their rows from the PRF. The dealer drew the rows of all polynomials from one stream
+        // and the nonce rows of all batches from another.
+        let row_len = 2*self.num_faults+1;
+        let mut prf_rows = Vec::new();
+        let mut nonce_prf_rows = Vec::new();
+        if self.myid < self.num_faults{
+            let prf_domain = PrfDomain::new(PRF_PROTOCOL, instance_id, sharing_batch(instance_id), sender);
+            let num_polys: usize = shares_msg.iter().map(|batch| batch.num_bv_polys).sum();
+            prf_rows = prf_domain.stream(secret_key.as_slice(), self.myid, PRF_SHARES, num_polys*row_len);
+            nonce_prf_rows = prf_domain.stream(secret_key.as_slice(), self.myid, PRF_NONCE, shares_msg.len()*row_len);
+        }
+        let mut prf_rows = prf_rows.chunks(row_len);
+        let mut nonce_prf_rows = nonce_prf_rows.chunks(row_len);
 
+        // Verify shares
         let mut row_evaluations = Vec::new();
         let mut nonce_evaluations = Vec::new();
         let mut proofs = Vec::new();
@@ -447,38 +453,11 @@ impl Context{
             let num_polys = shares_batch.num_bv_polys;
             if self.myid < self.num_faults{
                 // Generate share coefficients locally using PRF
-                for index in 0..num_polys{
-                    let mut sk_coeff = secret_key.clone();
-                    let mut prf_seed = Vec::new();
-                    prf_seed.extend(instance_id.to_be_bytes());
-                    prf_seed.extend(index.to_be_bytes());
-
-                    sk_coeff.extend(prf_seed.clone());
-            
-                    let sampled_coefficients: Vec<LargeField> = pseudorandom_lf(sk_coeff.as_slice(), 2*self.num_faults+1).into_iter().map(
-                        |elem|{
-                            let mod_elem = elem;
-                            mod_elem
-                        }
-                    ).collect();
-                    shares_batch.coefficients.push(sampled_coefficients);
+                for _ in 0..num_polys{
+                    shares_batch.coefficients.push(prf_rows.next().unwrap().to_vec());
                 }
-                let mut sk_nonce = secret_key.clone();
-                let mut prf_seed = Vec::new();
-                prf_seed.extend(instance_id.to_be_bytes());
-                prf_seed.extend(self.nonce_seed.to_be_bytes());
-                prf_seed.extend(index_batch.to_be_bytes());
-
-                sk_nonce.extend(prf_seed);
                 // Generate nonce polynomial through PRF
-                let sampled_coefficients: Vec<LargeField> = pseudorandom_lf(sk_nonce.as_slice(), 2*self.num_faults+1).into_iter().map(
-                    |elem|{
-                        let mod_elem = elem;
-                        mod_elem
-                    }
-                ).collect();
-                shares_batch.nonce_coefficients.extend(sampled_coefficients);
-                index_batch+=1;
+                shares_batch.nonce_coefficients.extend(nonce_prf_rows.next().unwrap().to_vec());
             }
             // generate Merkle tree on commitments
             let shares_mt = MerkleTree::new(merkle_roots.clone(), &self.hash_context);
@@ -543,7 +522,9 @@ impl Context{
         }
         log::info!("Successfully verified all shares for ACSS instance ID {}", instance_id);
         acss_state.rows_reconstructed = true;
-        acss_state.shares = Some(shares);
+        // Drop the shares of the padding in the last bivariate polynomial
+        shares.truncate(commitment_copy.num_secrets);
+        acss_state.shares = Some(SecretShares::new(shares));
         // Send ECHOs to all parties
         let mut points_vec = Vec::new();
         for _ in 0..self.num_nodes{
@@ -576,8 +557,12 @@ impl Context{
             let roots_party = commitment_copy.roots.clone().into_iter().map(|comm_vec| comm_vec[rep].clone()).collect();
             assert!(points.verify_points(roots_party, &self.hash_context).is_some());
         }
+        // Keep the points on our rows, they are revealed in public reconstruction
+        acss_state.row_points = points_vec.iter().enumerate().map(|(rep, points)| (rep+1, points.clone())).collect();
+
         // Broadcast commitment
         let comm_ser = bincode::serialize(&commitment_copy).unwrap();
+        acss_state.rbc_state.message = Some(comm_ser.clone());
         let shards = get_shards(comm_ser, self.num_faults+1, 2*self.num_faults);
         let shard_hashes = shards.iter().map(|shard| self.hash_context.do_hash_aes(shard.as_slice())).collect();
 
@@ -603,6 +588,7 @@ impl Context{
             let echo = ProtMsg::Echo(rbc_msg, enc_msg, instance_id);
             let wrapper_msg = WrapperMsg::new(echo,self.myid, secret_key_party.as_slice());
 
+            consensus::count_sent_message(&wrapper_msg);
             let cancel_handler: CancelHandler<Acknowledgement> = self.net_send.send(rep, wrapper_msg).await;
             self.add_cancel_handler(cancel_handler);
 
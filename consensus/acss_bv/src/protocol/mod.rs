@@ -8,4 +8,9 @@ mod bivariate;
 mod state;
 pub use state::*;
 
-mod ready;
\ No newline at end of file
+mod ready;
+
+mod pub_rec;
+
+// Protocol name bound into the PRF seeds of this crate
+pub const PRF_PROTOCOL: &str = "acss_bv";
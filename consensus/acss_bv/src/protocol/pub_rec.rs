@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+
+use consensus::{vandermonde_matrix, inverse_vandermonde, matrix_vector_multiply};
+use ha_crypto::LargeField;
+use lambdaworks_math::polynomial::Polynomial;
+use types::{Replica, WrapperMsg};
+
+use crate::{Context, msg::{Commitment, PointsBV, PointsBVSer, ProtMsg}};
+
+impl Context{
+    /// Reveals the verified points on this node's rows of `dealer`'s sharing in `instance_id`.
+    /// Every node interpolates this node's shares from them, and the secrets from t+1 such shares.
+    pub async fn init_pub_rec(&mut self, instance_id: usize, dealer: Replica){
+        let acss_inst_id = dealer*self.threshold + instance_id;
+        log::info!("Received request to publicly reconstruct secrets of dealer {} in instance {}", dealer, instance_id);
+        if !self.acss_state.contains_key(&acss_inst_id){
+            log::error!("No ACSS state found for instance {} of dealer {}", instance_id, dealer);
+            return;
+        }
+        let acss_state = self.acss_state.get(&acss_inst_id).unwrap();
+        if !acss_state.terminated || acss_state.row_points.is_empty(){
+            log::error!("No verified row points for instance {} of dealer {}, cannot reveal shares", instance_id, dealer);
+            return;
+        }
+
+        let mut row_points_ser: Vec<(Replica, PointsBVSer)> = acss_state.row_points.iter().map(|(point, points_bv)| {
+            (*point, points_bv.to_ser())
+        }).collect();
+        row_points_ser.sort_by_key(|(point, _)| *point);
+
+        let pub_rec_msg = ProtMsg::PubRec(acss_inst_id, row_points_ser);
+        for rep in 0..self.num_nodes{
+            let secret_key = self.sec_key_map.get(&rep).unwrap().clone();
+            let wrapper_msg = WrapperMsg::new(pub_rec_msg.clone(), self.myid, &secret_key);
+            self.send(rep, wrapper_msg).await;
+        }
+    }
+
+    pub async fn process_pub_rec(&mut self, instance_id: usize, points_ser: Vec<(Replica, PointsBVSer)>, share_sender: Replica){
+        if !self.acss_state.contains_key(&instance_id){
+            log::error!("No ACSS state found for instance {}, ignoring points of node {}", instance_id, share_sender);
+            return;
+        }
+        let acss_state = self.acss_state.get(&instance_id).unwrap();
+        if acss_state.pub_rec_terminated || acss_state.pub_rec_shares.contains_key(&share_sender){
+            return;
+        }
+        if acss_state.rbc_state.message.is_none(){
+            log::error!("No commitment found for ACSS instance {}, ignoring points of node {}", instance_id, share_sender);
+            return;
+        }
+        let commitment: Commitment = bincode::deserialize(acss_state.rbc_state.message.as_ref().unwrap()).unwrap();
+        let num_secrets = commitment.num_secrets;
+
+        let points: HashMap<Replica, PointsBV> = points_ser.into_iter().map(|(point, points_bv)| {
+            (point, PointsBV::from_ser(points_bv))
+        }).collect();
+
+        // Shares of the sender are on its rows at the evaluation points 0,-1,...,-t
+        let eval_point_start: isize = ((self.num_faults) as isize) * (-1);
+        let mut eval_point_indices_lf: Vec<LargeField> = (eval_point_start..1).into_iter().map(|index| LargeField::from(index as u64)).collect();
+        eval_point_indices_lf.reverse();
+
+        let verf_status = self.interpolate_points_on_share_poly(
+            commitment,
+            points,
+            false,
+            eval_point_indices_lf
+        );
+        if verf_status.is_none(){
+            log::error!("Error verifying row points of node {} in public reconstruction of ACSS instance {}", share_sender, instance_id);
+            return;
+        }
+        let shares = Self::shares_in_dealing_order(verf_status.unwrap(), num_secrets);
+        log::info!("Verified shares of node {} for public reconstruction of ACSS instance {}", share_sender, instance_id);
+
+        let acss_state = self.acss_state.get_mut(&instance_id).unwrap();
+        acss_state.pub_rec_shares.insert(share_sender, shares);
+
+        if acss_state.pub_rec_shares.len() == self.num_faults+1{
+            log::info!("t+1 shares received for public reconstruction of ACSS instance {}", instance_id);
+            let mut eval_points = Vec::new();
+            let mut shares_indexed: Vec<Vec<LargeField>> = vec![Vec::new(); num_secrets];
+            for party in 0..self.num_nodes{
+                if let Some(shares_party) = acss_state.pub_rec_shares.get(&party){
+                    eval_points.push(LargeField::from((party+1) as u64));
+                    for (index, share) in shares_party.iter().enumerate(){
+                        shares_indexed[index].push(share.clone());
+                    }
+                }
+            }
+
+            let vandermonde = vandermonde_matrix(eval_points);
+            let inverse_vandermonde = inverse_vandermonde(vandermonde);
+            let secret_evaluation_point = LargeField::from(0 as u64);
+
+            let secrets: Vec<LargeField> = shares_indexed.into_iter().map(|evals| {
+                let coefficients = matrix_vector_multiply(&inverse_vandermonde, &evals);
+                Polynomial::new(&coefficients).evaluate(&secret_evaluation_point)
+            }).collect();
+
+            acss_state.pub_rec_terminated = true;
+            acss_state.pub_rec_shares.clear();
+
+            let true_inst_id = instance_id%self.threshold;
+            let dealer = instance_id/self.threshold;
+            log::info!("Publicly reconstructed {} secrets of dealer {} in instance {}", secrets.len(), dealer, true_inst_id);
+            let _status = self.out_pub_rec.send((true_inst_id, dealer, secrets)).await;
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use consensus::reconstruct_data;
+use consensus::{reconstruct_data, SecretShares};
 use ha_crypto::{decrypt, hash::{Hash}, aes_hash::{MerkleTree, Proof}, encrypt, LargeField};
 use ctrbc::CTRBCMsg;
 use lambdaworks_math::traits::ByteConversion;
@@ -124,6 +124,7 @@ impl Context{
 
                             let ready_msg = ProtMsg::Ready(rbc_msg.clone(), enc_share, instance_id);
                             let wrapper_msg = WrapperMsg::new(ready_msg, self.myid, &secret_key);
+                            consensus::count_sent_message(&wrapper_msg);
                             let cancel_handler: CancelHandler<Acknowledgement> = self.net_send.send(recipient, wrapper_msg).await;
                             self.add_cancel_handler(cancel_handler);
                         }
@@ -145,6 +146,7 @@ impl Context{
                 let ser_commitment = acss_va_context.rbc_state.message.clone().unwrap();
                 let commitment: Commitment = bincode::deserialize(&ser_commitment).unwrap();
                 let bv_ready_points = acss_va_context.bv_ready_points.clone();
+                let bv_ready_points_rows = bv_ready_points.clone();
 
                 // Sample F(x,0) polynomial next
                 let eval_point_start: isize = ((self.num_faults) as isize) * (-1);
@@ -166,6 +168,7 @@ impl Context{
                 
                 let shares = verf_status.unwrap();
                 let tot_batches = commitment.roots.len();
+                let num_secrets = commitment.num_secrets;
                 for (batch, 
                     (batch_roots, 
                         (batch_blinding_commitments,
@@ -209,14 +212,9 @@ impl Context{
                 log::info!("Verified dzk proofs for ACSS instance {}", instance_id);
                 let acss_va_context: &mut BatchACSSState = self.acss_state.get_mut(&instance_id).unwrap();
                 if acss_va_context.shares.is_none(){
-                    // Interpolate and set shares. 
-                    let mut shares_concatenated = Vec::new();
-                    for shares_poly in shares{
-                        for shares_each_row in shares_poly.0{
-                            shares_concatenated.extend(shares_each_row);
-                        }
-                    }
-                    acss_va_context.shares = Some(shares_concatenated);
+                    // Interpolate and set shares in the order the dealer packed the secrets
+                    acss_va_context.shares = Some(SecretShares::new(Self::shares_in_dealing_order(shares, num_secrets)));
+                    acss_va_context.row_points = bv_ready_points_rows;
                     acss_va_context.rows_reconstructed = true;
                 }
 
@@ -226,21 +224,19 @@ impl Context{
             // Interpolate rows and verify distributed ZK proof
             let acss_va_context: &mut BatchACSSState = self.acss_state.get_mut(&instance_id).unwrap();
             acss_va_context.terminated = true;
-            let _term_msg = "Terminated";
-            let shares = acss_va_context.shares.clone().unwrap();
+            let shares = acss_va_context.shares.as_ref().map(|shares| shares.to_vec());
             let root_commitment = acss_va_context.verified_hash.clone().unwrap();
             // Get shares and then terminate
             self.terminate(shares, root_commitment, instance_id).await;
         }
     }
     // Invoke this function once you terminate the protocol
-    pub async fn terminate(&mut self, shares: Vec<LargeField>, root_comm: Hash, instance_id: usize) {
+    pub async fn terminate(&mut self, shares: Option<Vec<LargeField>>, root_comm: Hash, instance_id: usize) {
         
         let true_inst_id = instance_id%self.threshold;
         let sender_party = instance_id/self.threshold;
         log::info!("Terminating ACSS for instance id {}, true_inst_id: {}, sender_party: {}",instance_id, true_inst_id, sender_party);
 
-        let shares_ser = shares.into_iter().map(|share| share.to_bytes_be()).collect();
-        let _status = self.out_acss_shares.send((true_inst_id, sender_party, root_comm, shares_ser)).await;
+        let _status = self.out_acss_shares.send((true_inst_id, sender_party, root_comm, shares, None)).await;
     }
 }
\ No newline at end of file
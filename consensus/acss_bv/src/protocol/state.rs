@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 
 use ha_crypto::{LargeField, hash::Hash, aes_hash::MerkleTree};
+use consensus::{SecretShares, wipe_field_elements};
 use ctrbc::RBCState;
 use types::Replica;
 
@@ -24,7 +25,7 @@ pub struct BatchACSSState{
     // DZK polynomials
     pub dzk_polynomials: Vec<Vec<LargeField>>,
 
-    pub shares: Option<Vec<LargeField>>,
+    pub shares: Option<SecretShares>,
 
     pub verified_hash: Option<Hash>,
 
@@ -43,6 +44,12 @@ pub struct BatchACSSState{
 
     // Encrypted row polynomial shares
     pub encrypted_shares: Vec<(Replica, Vec<u8>)>,
+
+    // Verified points on this node's rows, by evaluation point
+    pub row_points: HashMap<Replica, PointsBV>,
+    // Shares of other nodes verified during public reconstruction
+    pub pub_rec_shares: HashMap<Replica, Vec<LargeField>>,
+    pub pub_rec_terminated: bool,
     
     pub rbc_state: RBCState,
     pub terminated: bool,
@@ -80,6 +87,10 @@ impl BatchACSSState{
 
             encrypted_shares: Vec::new(),
 
+            row_points: HashMap::default(),
+            pub_rec_shares: HashMap::default(),
+            pub_rec_terminated: false,
+
             shares: None,
             verified_hash: None,
 
@@ -88,4 +99,31 @@ impl BatchACSSState{
             terminated: false,
         }
     }
-}
\ No newline at end of file
+}
+
+// Row polynomials and column points of this node are shares of the dealer's secrets
+impl Drop for BatchACSSState{
+    fn drop(&mut self) {
+        for rows in self.row_coefficients.iter_mut(){
+            for row in rows.iter_mut(){
+                wipe_field_elements(row);
+            }
+        }
+        for coefficients in self.nonce_coefficients.iter_mut(){
+            wipe_field_elements(coefficients);
+        }
+        wipe_field_elements(&mut self.blinding_row_shares);
+        wipe_field_elements(&mut self.blinding_nonce_shares);
+        for columns in self.column_shares.values_mut(){
+            for column in columns.iter_mut(){
+                wipe_field_elements(column);
+            }
+        }
+        for (columns, nonces) in self.col_share_map.iter_mut(){
+            for column in columns.iter_mut(){
+                wipe_field_elements(column);
+            }
+            wipe_field_elements(nonces);
+        }
+    }
+}
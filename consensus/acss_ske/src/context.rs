@@ -11,10 +11,10 @@ use fnv::FnvHashMap;
 use ha_crypto::aes_hash::HashState;
 use lambdaworks_math::{ fft::cpu::roots_of_unity::get_powers_of_primitive_root, field::traits::RootsConfig};
 use network::{
-    plaintcp::{CancelHandler, TcpReceiver, TcpReliableSender},
+    plaintcp::CancelHandler,
     Acknowledgement,
 };
-use consensus::{LargeField, LargeFieldSSS, FoldingDZKContext};
+use consensus::{AcssOutput, HashKeys, LargeField, LargeFieldSSS, FoldingDZKContext, SecretBytes, ReplicaSender, Acss, AcssParams, AcssScheme, AcssService, SelectedPubRecOutput};
 
 use tokio::{sync::{
     mpsc::{Receiver, Sender, channel, unbounded_channel, UnboundedReceiver},
@@ -23,15 +23,12 @@ use tokio::{sync::{
 // use tokio_util::time::DelayQueue;
 use types::{Replica, WrapperMsg};
 
-use ha_crypto::hash::Hash;
-
 use crate::{protocol::{ACSSABState, SymmetricKeyState}, msg::ProtMsg};
 
-use crate::Handler;
 
 pub struct Context {
     /// Networking context
-    pub net_send: TcpReliableSender<Replica, WrapperMsg<ProtMsg>, Acknowledgement>,
+    pub net_send: ReplicaSender<WrapperMsg<ProtMsg>>,
     pub net_recv: UnboundedReceiver<WrapperMsg<ProtMsg>>,
     
     /// Data context
@@ -40,7 +37,7 @@ pub struct Context {
     pub num_faults: usize,
 
     /// Secret Key map
-    pub sec_key_map: HashMap<Replica, Vec<u8>>,
+    pub sec_key_map: HashMap<Replica, SecretBytes>,
 
     /// Hardware acceleration context
     pub hash_context: HashState,
@@ -65,11 +62,15 @@ pub struct Context {
     
     // Input queue for receiving acss requests with bool field indicating ACSS or AVSS.
     pub inp_acss: Receiver<(usize, Vec<LargeField>)>,
-    pub out_acss: Sender<(usize, Replica, Hash, Option<Vec<LargeField>>)>,
+    pub out_acss: Sender<AcssOutput>,
 
     pub inp_pub_rec_in: Receiver<(usize, Replica)>,
     pub out_pub_rec_out: Sender<(usize, Replica, Vec<LargeField>)>,
 
+    // Public reconstruction of selected secrets of a sharing
+    pub inp_pub_rec_selected: Receiver<(usize, Replica, Vec<usize>)>,
+    pub out_pub_rec_selected: Sender<SelectedPubRecOutput>,
+
     /// ASKS input and output channels
     pub asks_inp_channel: Sender<(usize, usize, bool, bool, Option<Vec<LargeField>>, Option<usize>)>,
     pub asks_recv_out: Receiver<(usize, Replica, Option<Vec<LargeField>>)>,
@@ -98,10 +99,13 @@ pub struct Context {
 impl Context {
     pub fn spawn(
         config: Node,
+        hash_keys: HashKeys,
         input_acss: Receiver<(usize,Vec<LargeField>)>, 
-        output_acss: Sender<(usize,Replica,Hash,Option<Vec<LargeField>>)>,
+        output_acss: Sender<AcssOutput>,
         input_pubrec: Receiver<(usize, Replica)>,
         output_pubrec: Sender<(usize, Replica, Vec<LargeField>)>, 
+        input_pubrec_selected: Receiver<(usize, Replica, Vec<usize>)>,
+        output_pubrec_selected: Sender<SelectedPubRecOutput>,
         use_fft: bool,
         lin_or_quad: bool,
         _byz: bool
@@ -141,10 +145,6 @@ impl Context {
 
         // Setup networking
         let (tx_net_to_consensus, rx_net_to_consensus) = unbounded_channel();
-        TcpReceiver::<Acknowledgement, WrapperMsg<ProtMsg>, _>::spawn(
-            my_address,
-            Handler::new(tx_net_to_consensus),
-        );
 
         // let syncer_listen_port = config.client_port;
         // let syncer_l_address = to_socket_address("0.0.0.0", syncer_listen_port);
@@ -156,55 +156,21 @@ impl Context {
         //     SyncHandler::new(tx_net_to_client),
         // );
 
-        let consensus_net = TcpReliableSender::<Replica, WrapperMsg<ProtMsg>, Acknowledgement>::with_peers(
-            consensus_addrs.clone(),
-        );
+        let consensus_net = consensus::spawn_replica_network(my_address, consensus_addrs.clone(), tx_net_to_consensus);
         // let sync_net =
         //     TcpReliableSender::<Replica, SyncMsg, Acknowledgement>::with_peers(syncer_map);
         
         let (exit_tx, exit_rx) = oneshot::channel();
 
         // Hardware accelerated Hash functions - Keyed AES ciphers
-        let key0 = [5u8; 16];
-        let key1 = [29u8; 16];
-        let key2 = [23u8; 16];
-        let hashstate = HashState::new(key0, key1, key2);
-        let hashstate2 =  HashState::new(key0, key1, key2);
+        let hashstate = hash_keys.hash_state();
+        let hashstate2 =  hash_keys.hash_state();
 
         let threshold:usize = 10000;
         let rbc_start_id = threshold*config.id;
 
-        let lf_uv_sss = LargeFieldSSS::new_with_vandermonde(
-            config.num_faults +1,
-            config.num_nodes
-        );
-
-        // Prepare dZK context for halving degrees
-        let mut start_degree = config.num_faults as isize;
-        let end_degree = 3 as usize;
-        let mut ss_contexts = HashMap::default();
-        while start_degree > 0 {
-            let split_point;
-            if start_degree % 2 == 0{
-                split_point = start_degree/2;
-            }
-            else{
-                split_point = (start_degree+1)/2;
-            }
-            start_degree = start_degree - split_point;
-            ss_contexts.insert(start_degree,split_point);
-        }
-        //ss_contexts.insert(start_degree, lf_dzk_sss);
-
         // Folding context
-        let folding_context = FoldingDZKContext{
-            large_field_uv_sss: lf_uv_sss.clone(),
-            hash_context: hashstate2,
-            poly_split_evaluation_map: ss_contexts,
-            evaluation_points: (1..config.num_nodes+1).into_iter().collect(),
-            recon_threshold: config.num_faults+1,
-            end_degree_threshold: end_degree,
-        };
+        let folding_context = Self::folding_dzk_context(config.num_nodes, config.num_faults, hashstate2);
         
         let (asks_req_send_channel, asks_req_recv_channel) = channel(10000);
         let (asks_out_send_channel, asks_out_recv_channel) = channel(10000);
@@ -249,6 +215,9 @@ impl Context {
                 inp_pub_rec_in: input_pubrec,
                 out_pub_rec_out: output_pubrec,
 
+                inp_pub_rec_selected: input_pubrec_selected,
+                out_pub_rec_selected: output_pubrec_selected,
+
                 roots_of_unity: Self::gen_roots_of_unity(config.num_nodes),
 
                 asks_inp_channel: asks_req_send_channel,
@@ -274,8 +243,8 @@ impl Context {
             };
 
             // Populate secret keys from config
-            for (id, sk_data) in config.sk_map.clone() {
-                c.sec_key_map.insert(id, sk_data.clone());
+            for (id, sk_data) in config.sk_map.iter() {
+                c.sec_key_map.insert(*id, SecretBytes::new(sk_data.clone()));
             }
 
             // Run the consensus context
@@ -286,7 +255,8 @@ impl Context {
         let mut vector_statuses = Vec::new();
         
         let _status =  asks::Context::spawn(
-            asks_config, 
+            asks_config,
+            hash_keys.clone(), 
             asks_req_recv_channel, 
             asks_out_send_channel, 
             false
@@ -302,7 +272,8 @@ impl Context {
 
         vector_statuses.push(_status);
         let _status =  avid::Context::spawn(
-            avid_config, 
+            avid_config,
+            hash_keys.clone(), 
             avid_req_recv_channel, 
             avid_out_send_channel, 
             false
@@ -310,6 +281,7 @@ impl Context {
         vector_statuses.push(_status);
         let _status = ra::Context::spawn(
             ra_config,
+            hash_keys.clone(),
             ra_req_recv_channel,
             ra_out_send_channel,
             false
@@ -318,10 +290,51 @@ impl Context {
         Ok((exit_tx, vector_statuses))
     }
 
+    /// Spawns the service behind the common `Acss` interface, the way DPSS drives it
+    pub fn spawn_acss(config: Node, hash_keys: HashKeys, params: AcssParams) -> Result<Box<dyn Acss>>{
+        let (acss_req_send, acss_req_recv) = channel(10000);
+        let (acss_out_send, acss_out_recv) = channel(10000);
+        let (pub_rec_req_send, pub_rec_req_recv) = channel(10000);
+        let (pub_rec_out_send, pub_rec_out_recv) = channel(10000);
+        let (pub_rec_selected_req_send, pub_rec_selected_req_recv) = channel(10000);
+        let (pub_rec_selected_out_send, pub_rec_selected_out_recv) = channel(10000);
+        let num_faults = config.num_faults;
+
+        let (exit_tx, statuses) = Self::spawn(
+            config,
+            hash_keys,
+            acss_req_recv,
+            acss_out_send,
+            pub_rec_req_recv,
+            pub_rec_out_send,
+            pub_rec_selected_req_recv,
+            pub_rec_selected_out_send,
+            params.use_fft,
+            params.lin_or_quad,
+            params.byz
+        )?;
+        let mut exit_txs = vec![exit_tx];
+        for status in statuses{
+            exit_txs.push(status?);
+        }
+        Ok(Box::new(AcssService {
+            scheme: AcssScheme::Ske,
+            num_faults: num_faults,
+            acss_req: acss_req_send,
+            acss_out: acss_out_recv,
+            pub_rec_req: pub_rec_req_send,
+            pub_rec_out: pub_rec_out_recv,
+            pub_rec_selected: Some((pub_rec_selected_req_send, pub_rec_selected_out_recv)),
+            exit_txs: exit_txs,
+        }))
+    }
+
     pub async fn broadcast(&mut self, protmsg: ProtMsg) {
-        let sec_key_map = self.sec_key_map.clone();
-        for (replica, sec_key) in sec_key_map.into_iter() {
+        let replicas: Vec<Replica> = self.sec_key_map.keys().cloned().collect();
+        for replica in replicas {
+            let sec_key = self.sec_key_map.get(&replica).unwrap();
             let wrapper_msg = WrapperMsg::new(protmsg.clone(), self.myid, &sec_key.as_slice());
+            consensus::count_sent_message(&wrapper_msg);
             let cancel_handler: CancelHandler<Acknowledgement> = self.net_send.send(replica, wrapper_msg).await;
             self.add_cancel_handler(cancel_handler);
         }
@@ -339,7 +352,7 @@ impl Context {
                 // Receive exit handlers
                 msg = self.net_recv.recv() => {
                     // Received messages are processed here
-                    log::trace!("Got a consensus message from the network: {:?}", msg);
+                    log::trace!("Got a consensus message from the network from {:?}", msg.as_ref().map(|msg| msg.sender));
                     if msg.is_none(){
                         log::error!("Got none from the consensus layer, most likely it closed");
                         break;
@@ -387,6 +400,12 @@ impl Context {
                     //     self.share_validity_oracle(recon_request.0, recon_request.1, recon_request.2).await;
                     // }
                 },
+                pub_rec_selected_msg = self.inp_pub_rec_selected.recv() =>{
+                    let (instance_id, dealer, indices) = pub_rec_selected_msg.ok_or_else(||
+                        anyhow!("Networking layer has closed")
+                    )?;
+                    self.init_pubrec_selected(instance_id, dealer, indices).await;
+                },
                 asks_msg = self.asks_recv_out.recv() => {
                     let asks_msg = asks_msg.ok_or_else(||
                         anyhow!("Networking layer has closed")
@@ -486,6 +505,39 @@ impl Context {
         let order = len.trailing_zeros();
         get_powers_of_primitive_root(order.into(), len, RootsConfig::Natural).unwrap()
     }
+
+    /// DZK context that folds the degree-t share polynomials down to degree 3
+    pub fn folding_dzk_context(num_nodes: usize, num_faults: usize, hash_context: HashState) -> FoldingDZKContext{
+        let lf_uv_sss = LargeFieldSSS::new_with_vandermonde(
+            num_faults +1,
+            num_nodes
+        );
+
+        // Prepare dZK context for halving degrees
+        let mut start_degree = num_faults as isize;
+        let end_degree = 3 as usize;
+        let mut ss_contexts = HashMap::default();
+        while start_degree > 0 {
+            let split_point;
+            if start_degree % 2 == 0{
+                split_point = start_degree/2;
+            }
+            else{
+                split_point = (start_degree+1)/2;
+            }
+            start_degree = start_degree - split_point;
+            ss_contexts.insert(start_degree,split_point);
+        }
+
+        FoldingDZKContext{
+            large_field_uv_sss: lf_uv_sss,
+            hash_context: hash_context,
+            poly_split_evaluation_map: ss_contexts,
+            evaluation_points: (1..num_nodes+1).into_iter().collect(),
+            recon_threshold: num_faults+1,
+            end_degree_threshold: end_degree,
+        }
+    }
 }
 
 pub fn to_socket_address(ip_str: &str, port: u16) -> SocketAddr {
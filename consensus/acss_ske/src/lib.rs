@@ -6,4 +6,7 @@ mod protocol;
 mod msg;
 pub use msg::*;
 
-mod process;
\ No newline at end of file
+mod process;
+
+mod verifier;
+pub use verifier::*;
\ No newline at end of file
@@ -25,7 +25,8 @@ impl Context {
     }
 
     pub(crate) async fn process_msg(&mut self, wrapper_msg: WrapperMsg<ProtMsg>) {
-        log::trace!("Received protocol msg: {:?}", wrapper_msg);
+        // Payloads can carry shares, so only the sender is logged
+        log::trace!("Received protocol msg from {}", wrapper_msg.sender);
         let msg = Arc::new(wrapper_msg.clone());
 
         // Verify the message's authenticity before proceeding
@@ -46,6 +47,10 @@ impl Context {
                     log::debug!("Received PubRecL2 message for instance id {} from node : {}", instance_id, wrapper_msg.sender);
                     self.process_pub_rec_l2_msg(instance_id, source_party, shares, wrapper_msg.sender).await;
                 }
+                ProtMsg::PubRecSelected(instance_id, dealer, indices, shares) => {
+                    log::debug!("Received PubRecSelected message for instance id {} from node : {}", instance_id, wrapper_msg.sender);
+                    self.process_pub_rec_selected_msg(instance_id, dealer, indices, shares, wrapper_msg.sender).await;
+                }
             }
         } else {
             log::warn!(
@@ -2,7 +2,7 @@ use std::collections::{HashMap, HashSet};
 
 use ha_crypto::hash::Hash;
 use lambdaworks_math::polynomial::Polynomial;
-use consensus::{LargeField, VACommitment};
+use consensus::{LargeField, VACommitment, SecretBytes};
 use types::Replica;
 
 use crate::msg::AcssSKEShares;
@@ -27,6 +27,9 @@ pub struct ACSSABState{
     pub public_reconstruction_l1_status: HashSet<Replica>,
     pub public_reconstruction_l2_shares: HashMap<Replica, HashMap<Replica, Vec<LargeField>>>,
     pub public_reconstruction_l2_status: HashSet<Replica>,
+    // Shares of selected secrets, by dealer and secret indices
+    pub public_reconstruction_selected_shares: HashMap<(Replica, Vec<usize>), HashMap<Replica, Vec<LargeField>>>,
+    pub public_reconstruction_selected_status: HashSet<(Replica, Vec<usize>)>,
 
     pub dzk_poly: HashMap<Replica,Polynomial<LargeField>>,
     pub commitment_root_fe: HashMap<Replica, Hash>,
@@ -48,6 +51,8 @@ impl ACSSABState{
             public_reconstruction_l1_status: HashSet::default(),
             public_reconstruction_l2_shares: HashMap::default(),
             public_reconstruction_l2_status: HashSet::default(),
+            public_reconstruction_selected_shares: HashMap::default(),
+            public_reconstruction_selected_status: HashSet::default(),
 
             dzk_poly: HashMap::default(),
             commitment_root_fe: HashMap::default(),
@@ -56,8 +61,8 @@ impl ACSSABState{
 }
 
 pub struct SymmetricKeyState{
-    pub keys_from_me: HashMap<Replica, Vec<u8>>,
-    pub keys_to_me: HashMap<Replica, Vec<u8>>,
+    pub keys_from_me: HashMap<Replica, SecretBytes>,
+    pub keys_to_me: HashMap<Replica, SecretBytes>,
     pub term_asks_sharing: HashSet<Replica>,
     pub term_asks_recon: HashSet<Replica>,
 }
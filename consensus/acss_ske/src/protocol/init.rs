@@ -1,13 +1,13 @@
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use crate::{Context, msg::AcssSKEShares};
+use crate::{Context, ShareEvidence, msg::AcssSKEShares};
 use ha_crypto::{hash::{Hash}, aes_hash::{MerkleTree, Proof}, encrypt};
 use lambdaworks_math::{traits::ByteConversion};
-use consensus::{LargeField, LargeFieldSer, generate_evaluation_points_fft, expand_sharing_to_n_evaluation_points, expand_sharing_to_n_evaluation_points_opt, sample_polynomials_from_prf, rand_field_element, VACommitment};
+use consensus::{LargeField, LargeFieldSer, generate_evaluation_points_fft, expand_sharing_to_n_evaluation_points, expand_sharing_to_n_evaluation_points_opt, sample_polynomials_from_prf, PrfDomain, sharing_batch, SecretBytes, PRF_SHARES, PRF_NONCE, PRF_BLINDING, PRF_BLINDING_NONCE, rand_field_element, VACommitment, AcssScheme, share_export_dir};
 use rayon::prelude::{ParallelIterator, IndexedParallelIterator, IntoParallelIterator};
 use types::Replica;
 
-use super::ACSSABState;
+use super::{ACSSABState, PRF_PROTOCOL};
 
 impl Context{
     pub async fn init_symmetric_key_setup(&mut self){
@@ -18,9 +18,9 @@ impl Context{
             for i in 0..self.num_nodes{
                 let key = rand_field_element();
                 symm_keys.push(key.clone());
-                self.symmetric_keys_avid.keys_from_me.insert(i, key.to_bytes_be().to_vec());
+                self.symmetric_keys_avid.keys_from_me.insert(i, SecretBytes::new(key.to_bytes_be().to_vec()));
             }
-            log::info!("Symmetric keys generated: {:?}", symm_keys);
+            log::info!("Generated {} symmetric keys", symm_keys.len());
             // Now share these keys through ASKS
             let _status = self.asks_inp_channel.send((
                 1,
@@ -60,10 +60,10 @@ impl Context{
     }
 
     pub async fn process_symmetric_key_reconstruction(&mut self, party: Replica, secret: Vec<LargeField>){
-        log::info!("Received reconstructed symmetric keys from party {} {:?}", party, secret);
+        log::info!("Received reconstructed symmetric keys from party {}", party);
         if !self.symmetric_keys_avid.keys_to_me.contains_key(&party){
             let secret = secret[0].clone().to_bytes_be();
-            self.symmetric_keys_avid.keys_to_me.insert(party, secret.to_vec());
+            self.symmetric_keys_avid.keys_to_me.insert(party, SecretBytes::new(secret.to_vec()));
             // Now that the key is available, we can use it to decrypt the secrets initialized by the party {party}
             self.decrypt_shares_all_instances(party).await;
         } else {
@@ -89,13 +89,14 @@ impl Context{
             let acss_ab_state = ACSSABState::new();
             self.acss_ab_state.insert(instance_id, acss_ab_state);
         }
-        // Number of secrets must be a multiple of self.num_faults+1
+        // Number of secrets must be a multiple of self.num_faults+1, the same rule `SharingReceipt`s report
         let mut secrets = secrets;
-        if secrets.len() % (self.num_faults + 1) != 0 {
-            let rem_secrets = (self.num_faults+1) - (secrets.len()%(self.num_faults+1));
-            for _ in 0..rem_secrets {
-                secrets.push(rand_field_element());
-            }
+        let padded_len = AcssScheme::Ske.padded_len(secrets.len(), self.num_faults);
+        if padded_len > secrets.len(){
+            log::info!("Padding {} secrets with {} random sharings in instance {}", secrets.len(), padded_len - secrets.len(), instance_id);
+        }
+        while secrets.len() < padded_len {
+            secrets.push(rand_field_element());
         }
 
         let consensus_start_time = SystemTime::now()
@@ -117,12 +118,14 @@ impl Context{
         
         if !self.use_fft{
             // Generate evaluations right here
+            // All streams of the instance are bound to the batch it belongs to
+            let prf_domain = PrfDomain::new(PRF_PROTOCOL, instance_id, sharing_batch(instance_id), self.myid);
             let evaluations_prf = sample_polynomials_from_prf(
                 secrets, 
-                self.symmetric_keys_avid.keys_from_me.clone(), 
+                &self.symmetric_keys_avid.keys_from_me, 
                 self.num_faults, 
-                false, 
-                1u8
+                &prf_domain, 
+                PRF_SHARES
             );
             (evaluations, coefficients) = expand_sharing_to_n_evaluation_points_opt(
                 evaluations_prf,
@@ -157,10 +160,10 @@ impl Context{
             let nonce_secrets:Vec<LargeField> = (0..self.num_nodes).into_iter().map(|_| rand_field_element()).collect();
             let evaluations_nonce_prf = sample_polynomials_from_prf(
                 nonce_secrets,
-                self.symmetric_keys_avid.keys_from_me.clone(), 
+                &self.symmetric_keys_avid.keys_from_me, 
                 self.num_faults, 
-                true, 
-                1u8
+                &prf_domain, 
+                PRF_NONCE
             );
             let (nonce_evaluations_ret,_nonce_coefficients) = expand_sharing_to_n_evaluation_points(
                 evaluations_nonce_prf,
@@ -175,10 +178,10 @@ impl Context{
             let blinding_secrets: Vec<LargeField> = (0..self.num_nodes).into_iter().map(|_| rand_field_element()).collect();
             let blinding_prf = sample_polynomials_from_prf(
                 blinding_secrets, 
-                self.symmetric_keys_avid.keys_from_me.clone(), 
+                &self.symmetric_keys_avid.keys_from_me, 
                 self.num_faults, 
-                true, 
-                2u8
+                &prf_domain, 
+                PRF_BLINDING
             );
             let (blinding_poly_evaluations_vec, blinding_poly_coefficients_vec) = expand_sharing_to_n_evaluation_points(
                 blinding_prf,
@@ -192,10 +195,10 @@ impl Context{
             let blinding_nonce_secrets: Vec<LargeField> = (0..self.num_nodes).into_iter().map(|_| rand_field_element()).collect();
             let blinding_nonce_prf = sample_polynomials_from_prf(
                 blinding_nonce_secrets, 
-                self.symmetric_keys_avid.keys_from_me.clone(), 
+                &self.symmetric_keys_avid.keys_from_me, 
                 self.num_faults, 
-                true, 
-                3u8
+                &prf_domain, 
+                PRF_BLINDING_NONCE
             );
 
             let (nonce_blinding_poly_evaluations_vec, _nonce_blinding_poly_coefficients_vec) = expand_sharing_to_n_evaluation_points(
@@ -425,7 +428,7 @@ impl Context{
 
             let shares_ser = bincode::serialize(&shares_struct).unwrap();
             
-            let sec_key = self.symmetric_keys_avid.keys_from_me.get(&rep).unwrap().clone();
+            let sec_key = self.symmetric_keys_avid.keys_from_me.get(&rep).unwrap();
             let enc_shares = encrypt(sec_key.as_slice(), shares_ser);
             
             let ser_enc_msg = bincode::serialize(&(instance_id,enc_shares)).unwrap();
@@ -462,101 +465,41 @@ impl Context{
         // Share verification first
         let shares_full = acss_ab_state.shares.get(&sender).unwrap().clone();
         let va_commitment = acss_ab_state.commitments.get(&sender).unwrap().clone();
-        
-        let shares: Vec<LargeField> = shares_full.evaluations.0.into_iter().map(|el| 
-            LargeField::from_bytes_be(el.as_slice()).unwrap()
-        ).collect();
-        let nonce_shares = shares_full.evaluations.1.into_iter().map(|el| 
-            LargeField::from_bytes_be(el.as_slice()).unwrap()
-        ).collect();
-        let merkle_proofs = shares_full.evaluations.2;
-        
-        let evaluation_points;
-        if !self.use_fft{
-            evaluation_points = (1..self.num_nodes+1).into_iter().map(|x| LargeField::from(x as u64)).collect();
-        }
-        else{
-            evaluation_points = self.roots_of_unity.clone();
-        }
-
-        let roots_from_proofs: Vec<Hash> = merkle_proofs.iter().map(|proof| proof.root()).collect();
-        
-        if roots_from_proofs != va_commitment.column_roots {
-            log::error!("Share commitment roots mismatch for instance {} from sender {}", instance_id, sender);
-            return;
-        }
-        if !Self::verify_commitments(
-            self.num_faults+1, 
-            evaluation_points.clone(), 
-            shares.clone(), 
-            nonce_shares, 
-            merkle_proofs, 
-            &self.hash_context
-        ){
-            log::error!("Share commitment verification failed for instance {} from sender {}", instance_id, sender);
-            return;
-        }
-
-        log::info!("Successfully verified commitments of shares sent by sender {} in instance_id {}", sender, instance_id);
-        // Blinding share verification next
-        let blinding_shares: Vec<LargeField> = shares_full.blinding_evaluations.0.into_iter().map(|el| 
-            LargeField::from_bytes_be(el.as_slice()).unwrap()
-        ).collect();
-        let blinding_nonce_shares = shares_full.blinding_evaluations.1.into_iter().map(|el| 
-            LargeField::from_bytes_be(el.as_slice()).unwrap()
-        ).collect();
-        let blinding_merkle_proofs = shares_full.blinding_evaluations.2;
-
-        let blinding_merkle_roots: Vec<Hash> = blinding_merkle_proofs.iter().map(|proof| proof.root()).collect();
-        if blinding_merkle_roots != va_commitment.blinding_column_roots {
-            log::error!("Blinding share commitment roots mismatch for instance {} from sender {}", instance_id, sender);
-            return;
-        }
-
-        if !Self::verify_blinding_commitments(
-            blinding_shares.clone(), 
-            blinding_nonce_shares,
-            blinding_merkle_proofs, 
-            &self.hash_context
-        ){
-            log::error!("Blinding share commitment verification failed for instance {} from sender {}", instance_id, sender);
-            return;
-        }
-
-        log::info!("Successfully verified blinding commitments of shares sent by sender {} in instance_id {}", sender, instance_id);
-        // Finally, verify DZK proofs
-        let grouped_points = Self::group_points_for_public_reconstruction(
-            shares, 
-            evaluation_points.clone(), 
-            self.num_faults+1
-        );
-
-        let root_comm_fe: Vec<LargeField> = roots_from_proofs.iter().zip(blinding_merkle_roots.iter()).map(|(root, b_root)|{
-            //let root = mt.root();
-            let root_combined = self.hash_context.hash_two(root.clone(), b_root.clone());
-            return LargeField::from_bytes_be(root_combined.as_slice()).unwrap();
-        }).collect();
-
-        let dzk_aggregated_points: Vec<LargeField> = grouped_points.into_iter().zip(
-            root_comm_fe.clone().into_iter()).map(|(shares, root)|{
-                return self.folding_dzk_context.gen_agg_poly_dzk(shares, root.to_bytes_be());
-            }).collect();
+        let evaluation_points = self.gen_evaluation_points();
 
-        let status = self.folding_dzk_context.verify_dzk_proof_row(
-            shares_full.dzk_iters.clone(), 
-            va_commitment.dzk_roots.clone(), 
-            va_commitment.polys.clone(), 
-            root_comm_fe.into_iter().map(|el| el.to_bytes_be()).collect(), 
-            dzk_aggregated_points, 
-            blinding_shares.clone(), 
-            self.myid+1
+        let status = Self::check_shares(
+            &self.folding_dzk_context,
+            &self.hash_context,
+            evaluation_points,
+            self.num_faults,
+            &va_commitment,
+            &shares_full,
+            self.myid
         );
-
-        if !status{
-            log::error!("DZK proof verification failed for instance {} from sender {}", instance_id, sender);
+        if let Err(failed_check) = status{
+            log::error!("Share verification failed at check {} for instance {} from sender {}", failed_check, instance_id, sender);
+            // Keep the rejected shares for the offline verifier
+            if let Some(export_dir) = share_export_dir(){
+                let evidence = ShareEvidence {
+                    num_nodes: self.num_nodes,
+                    num_faults: self.num_faults,
+                    use_fft: self.use_fft,
+                    instance_id: instance_id,
+                    dealer: sender,
+                    party: self.myid,
+                    failed_check: failed_check.name().to_string(),
+                    commitment: va_commitment,
+                    shares: shares_full,
+                };
+                match evidence.write_to_dir(export_dir) {
+                    Ok(path) => log::info!("Wrote rejected shares of sender {} to {}", sender, path.display()),
+                    Err(e) => log::error!("Unable to keep rejected shares of sender {}: {}", sender, e),
+                }
+            }
             return;
         }
         
+        let acss_ab_state = self.acss_ab_state.get_mut(&instance_id).unwrap();
         log::info!("Share from {} verified", sender);
         acss_ab_state.verification_status.insert(sender,true);
         // Start reliable agreement
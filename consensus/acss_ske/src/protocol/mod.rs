@@ -11,9 +11,14 @@ mod ra;
 
 mod ske;
 
+// Protocol name bound into the PRF seeds of this crate
+pub const PRF_PROTOCOL: &str = "acss_ske";
+
 mod pub_rec;
 
 mod pub_rec_quad;
+
+mod pub_rec_selected;
 // mod echo;
 // pub use echo::*;
 
@@ -355,6 +355,7 @@ impl Context{
             let secret_key = self.sec_key_map.get(&rep).unwrap().clone();
             let wrapper_msg = WrapperMsg::new(prot_msg, self.myid, secret_key.as_slice());
 
+            consensus::count_sent_message(&wrapper_msg);
             let cancel_handler = self.net_send.send(rep, wrapper_msg).await;
             self.add_cancel_handler(cancel_handler);
         }
@@ -32,18 +32,18 @@ impl Context{
         let va_commitment = acss_ab_state.commitments.get(&acss_msg.rep).unwrap();
 
         let sender = acss_msg.rep.clone();
-        let shares_full = acss_msg;
+        let mut shares_full = acss_msg;
         // let shares = acss_msg.evaluations.0.clone();
         // let nonce_share = acss_msg.evaluations.1[0].clone();
         // let share_mp = acss_msg.evaluations.2[0].clone();
         
-        let shares: Vec<LargeField> = shares_full.evaluations.0.into_iter().map(|el| 
+        let shares: Vec<LargeField> = shares_full.evaluations.0.iter().map(|el| 
             LargeField::from_bytes_be(el.as_slice()).unwrap()
         ).collect();
-        let nonce_shares = shares_full.evaluations.1.into_iter().map(|el| 
+        let nonce_shares = shares_full.evaluations.1.iter().map(|el| 
             LargeField::from_bytes_be(el.as_slice()).unwrap()
         ).collect();
-        let merkle_proofs = shares_full.evaluations.2;
+        let merkle_proofs = std::mem::take(&mut shares_full.evaluations.2);
         
         let evaluation_points;
         if !self.use_fft{
@@ -73,13 +73,13 @@ impl Context{
 
         log::info!("Successfully verified commitments of shares sent by sender {} in instance_id {}", sender, instance_id);
         // Blinding share verification next
-        let blinding_shares: Vec<LargeField> = shares_full.blinding_evaluations.0.into_iter().map(|el| 
+        let blinding_shares: Vec<LargeField> = shares_full.blinding_evaluations.0.iter().map(|el| 
             LargeField::from_bytes_be(el.as_slice()).unwrap()
         ).collect();
-        let blinding_nonce_shares = shares_full.blinding_evaluations.1.into_iter().map(|el| 
+        let blinding_nonce_shares = shares_full.blinding_evaluations.1.iter().map(|el| 
             LargeField::from_bytes_be(el.as_slice()).unwrap()
         ).collect();
-        let blinding_merkle_proofs = shares_full.blinding_evaluations.2;
+        let blinding_merkle_proofs = std::mem::take(&mut shares_full.blinding_evaluations.2);
 
         let blinding_merkle_roots: Vec<Hash> = blinding_merkle_proofs.iter().map(|proof| proof.root()).collect();
         if blinding_merkle_roots != va_commitment.blinding_column_roots {
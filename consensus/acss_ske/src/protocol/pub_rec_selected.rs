@@ -0,0 +1,111 @@
+use consensus::{LargeField, LargeFieldSer, robust_interpolate};
+use lambdaworks_math::traits::ByteConversion;
+use types::Replica;
+
+use crate::{Context, msg::ProtMsg, protocol::ACSSABState};
+
+impl Context{
+    /// Reveals this node's shares of only the secrets at `indices` of `dealer`'s sharing in `instance_id`.
+    /// Every secret lies on its own degree-t polynomial, so the other secrets of the sharing stay hidden.
+    pub async fn init_pubrec_selected(&mut self, instance_id: usize, dealer: Replica, indices: Vec<usize>){
+        log::info!("Received request to publicly reconstruct {} secrets of dealer {} in instance {}", indices.len(), dealer, instance_id);
+        if !self.acss_ab_state.contains_key(&instance_id){
+            log::error!("No ACSS AB state found for instance {}", instance_id);
+            return;
+        }
+        let acss_ab_state = self.acss_ab_state.get(&instance_id).unwrap();
+        if !acss_ab_state.shares.contains_key(&dealer) || acss_ab_state.verification_status.get(&dealer) != Some(&true){
+            log::error!("No verified shares found for dealer {} in instance {}", dealer, instance_id);
+            return;
+        }
+        let shares = &acss_ab_state.shares.get(&dealer).unwrap().evaluations.0;
+        if indices.is_empty() || indices.iter().any(|index| *index >= shares.len()){
+            log::error!("Secret indices {:?} out of range for the {} secrets of dealer {} in instance {}", indices, shares.len(), dealer, instance_id);
+            return;
+        }
+        let selected_shares: Vec<LargeFieldSer> = indices.iter().map(|index| shares[*index]).collect();
+
+        self.broadcast(ProtMsg::PubRecSelected(instance_id, dealer, indices, selected_shares)).await;
+    }
+
+    /// Shares of selected secrets carry no proofs of their own, the commitments only bind a node's shares as a whole.
+    /// Nodes instead decode every secret from the shares with online error correction and accept a polynomial only
+    /// once 2t+1 shares lie on it. At least t+1 of them come from honest nodes, which fixes the polynomial.
+    pub async fn process_pub_rec_selected_msg(&mut self,
+        instance_id: usize,
+        dealer: Replica,
+        indices: Vec<usize>,
+        shares_ser: Vec<LargeFieldSer>,
+        share_sender: Replica
+    ){
+        log::info!("Received PubRecSelected message for {} secrets of dealer {} in instance {} from party {}", indices.len(), dealer, instance_id, share_sender);
+        if shares_ser.len() != indices.len(){
+            log::error!("Party {} sent {} shares for {} secret indices of dealer {} in instance {}", share_sender, shares_ser.len(), indices.len(), dealer, instance_id);
+            return;
+        }
+        let mut shares = Vec::with_capacity(shares_ser.len());
+        for share in shares_ser.iter(){
+            match LargeField::from_bytes_be(share.as_slice()) {
+                Ok(share) => shares.push(share),
+                Err(_) => {
+                    log::error!("Party {} sent a malformed share of dealer {} in instance {}", share_sender, dealer, instance_id);
+                    return;
+                }
+            }
+        }
+
+        let evaluation_points = self.gen_evaluation_points();
+        let secret_evaluation_point = if !self.use_fft{
+            LargeField::zero()
+        }
+        else{
+            self.roots_of_unity[0]
+        };
+
+        if !self.acss_ab_state.contains_key(&instance_id){
+            let acss_ab_state = ACSSABState::new();
+            self.acss_ab_state.insert(instance_id, acss_ab_state);
+        }
+        let acss_ab_state = self.acss_ab_state.get_mut(&instance_id).unwrap();
+
+        let request = (dealer, indices);
+        if acss_ab_state.public_reconstruction_selected_status.contains(&request){
+            return;
+        }
+        let selected_shares = acss_ab_state.public_reconstruction_selected_shares.entry(request.clone()).or_default();
+        if selected_shares.contains_key(&share_sender){
+            return;
+        }
+        selected_shares.insert(share_sender, shares);
+        if selected_shares.len() < 2*self.num_faults+1{
+            return;
+        }
+
+        let mut eval_points = Vec::new();
+        let mut shares_indexed: Vec<Vec<LargeField>> = vec![Vec::new(); request.1.len()];
+        for party in 0..self.num_nodes{
+            if let Some(shares_party) = selected_shares.get(&party){
+                eval_points.push(evaluation_points[party]);
+                for (index, share) in shares_party.iter().enumerate(){
+                    shares_indexed[index].push(*share);
+                }
+            }
+        }
+
+        let mut secrets = Vec::with_capacity(shares_indexed.len());
+        for evals in shares_indexed.iter(){
+            match robust_interpolate(&eval_points, evals, self.num_faults, 2*self.num_faults+1) {
+                Some(polynomial) => secrets.push(polynomial.evaluate(&secret_evaluation_point)),
+                None => {
+                    log::info!("Shares of {} parties for dealer {} in instance {} do not agree yet, waiting for more", eval_points.len(), dealer, instance_id);
+                    return;
+                }
+            }
+        }
+
+        acss_ab_state.public_reconstruction_selected_shares.remove(&request);
+        acss_ab_state.public_reconstruction_selected_status.insert(request.clone());
+        log::info!("Publicly reconstructed {} selected secrets of dealer {} in instance {}", secrets.len(), dealer, instance_id);
+        let _status = self.out_pub_rec_selected.send((instance_id, dealer, request.1, secrets)).await;
+    }
+}
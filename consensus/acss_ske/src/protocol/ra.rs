@@ -1,4 +1,4 @@
-use consensus::LargeField;
+use consensus::{LargeField, ShareOpening};
 use lambdaworks_math::traits::ByteConversion;
 
 use crate::{Context, protocol::ACSSABState};
@@ -52,14 +52,22 @@ impl Context{
                 //else{
                 let root_comm = acss_state.commitment_root_fe.get(&sender).unwrap().clone();
 
-                let shares: Vec<LargeField> = acss_state.shares.get(&sender).unwrap().clone().evaluations.0.into_iter().map(|el| LargeField::from_bytes_be(el.as_slice()).unwrap()).collect();
-                let _status = self.out_acss.send((instance_id,sender, root_comm,Some(shares))).await;
+                let shares_full = acss_state.shares.get(&sender).unwrap();
+                let shares: Vec<LargeField> = shares_full.evaluations.0.iter().map(|el| LargeField::from_bytes_be(el.as_slice()).unwrap()).collect();
+                // Nonces and proofs let anyone holding the shares check them against the root later, like in an offline reconstruction
+                let opening = ShareOpening {
+                    use_fft: self.use_fft,
+                    nonces: shares_full.evaluations.1.clone(),
+                    proofs: shares_full.evaluations.2.clone(),
+                    blinding_roots: acss_state.commitments.get(&sender).unwrap().blinding_column_roots.clone(),
+                };
+                let _status = self.out_acss.send((instance_id,sender, root_comm,Some(shares), Some(opening))).await;
                 acss_state.acss_status.insert(sender);
                 //}
                 //self.terminate("Hello".to_string()).await;
             }
             else{
-                let _status = self.out_acss.send((instance_id, sender, [0;32],None)).await;
+                let _status = self.out_acss.send((instance_id, sender, [0;32],None, None)).await;
                 acss_state.acss_status.insert(sender);
             }
         }
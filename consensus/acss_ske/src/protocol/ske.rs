@@ -1,8 +1,8 @@
-use consensus::{interpolate_shares};
+use consensus::{interpolate_shares, PrfDomain, sharing_batch, PRF_SHARES, PRF_NONCE, PRF_BLINDING, PRF_BLINDING_NONCE};
 use ha_crypto::{decrypt};
 use types::Replica;
 
-use crate::{Context, msg::AcssSKEShares};
+use crate::{Context, msg::AcssSKEShares, protocol::PRF_PROTOCOL};
 
 impl Context{
     pub async fn interpolate_shares(&mut self, sender_rep: Replica, instance_id: usize){
@@ -29,11 +29,12 @@ impl Context{
         // Interpolate shares here for first t parties
         if !self.use_fft && self.myid < self.num_faults{
             // Interpolate your shares in this case
-            let secret_key = self.symmetric_keys_avid.keys_to_me.get(&sender_rep).clone().unwrap().clone();
-            let shares = interpolate_shares(secret_key.clone(), comm_dzk_vals.tot_shares, false, 1).into_iter().map(|el| el.to_bytes_be()).collect();
-            let nonce_shares = interpolate_shares(secret_key.clone(),self.num_nodes, true, 1u8).into_iter().map(|el| el.to_bytes_be()).collect();
-            let blinding_shares = interpolate_shares(secret_key.clone(), self.num_nodes, true, 2u8).into_iter().map(|el| el.to_bytes_be()).collect();
-            let blinding_nonce_shares = interpolate_shares(secret_key, self.num_nodes, true, 3u8).into_iter().map(|el| el.to_bytes_be()).collect();
+            let secret_key = self.symmetric_keys_avid.keys_to_me.get(&sender_rep).unwrap();
+            let prf_domain = PrfDomain::new(PRF_PROTOCOL, instance_id, sharing_batch(instance_id), sender_rep);
+            let shares = interpolate_shares(secret_key, comm_dzk_vals.tot_shares, &prf_domain, self.myid, PRF_SHARES).into_iter().map(|el| el.to_bytes_be()).collect();
+            let nonce_shares = interpolate_shares(secret_key, self.num_nodes, &prf_domain, self.myid, PRF_NONCE).into_iter().map(|el| el.to_bytes_be()).collect();
+            let blinding_shares = interpolate_shares(secret_key, self.num_nodes, &prf_domain, self.myid, PRF_BLINDING).into_iter().map(|el| el.to_bytes_be()).collect();
+            let blinding_nonce_shares = interpolate_shares(secret_key, self.num_nodes, &prf_domain, self.myid, PRF_BLINDING_NONCE).into_iter().map(|el| el.to_bytes_be()).collect();
             
             let acss_shares = acss_ab_state.shares.get_mut(&sender_rep).unwrap();
             acss_shares.evaluations = (shares, nonce_shares, acss_shares.evaluations.2.clone());
@@ -55,7 +56,7 @@ impl Context{
             return;
         }
         
-        let sec_key = self.symmetric_keys_avid.keys_to_me.get(&sender_rep).unwrap().clone();
+        let sec_key = self.symmetric_keys_avid.keys_to_me.get(&sender_rep).unwrap();
         let shares = acss_ab_state.enc_shares.get(&sender_rep).unwrap().clone();
         
         let dec_shares = decrypt(sec_key.as_slice(), shares);
@@ -0,0 +1,227 @@
+use std::{fmt, fs, path::{Path, PathBuf}};
+
+use consensus::{FoldingDZKContext, LargeField, LargeFieldSer, ShareOpening, VACommitment};
+use ha_crypto::{aes_hash::HashState, hash::Hash};
+use lambdaworks_math::traits::ByteConversion;
+use serde::{Deserialize, Serialize};
+use types::Replica;
+
+use crate::{Context, msg::AcssSKEShares};
+
+const SHARE_EVIDENCE_MAGIC: &[u8; 8] = b"ACSSEVID";
+/// Version of the share evidence layout. Readers reject files of other versions.
+pub const SHARE_EVIDENCE_VERSION: u32 = 1;
+
+/// Checks of a party's shares against the dealer's commitment, in the order they run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShareCheck{
+    /// Shares, nonces or the commitment's polynomials are not field elements
+    Malformed,
+    /// Roots of the share proofs against the commitment's column roots
+    ShareRoots,
+    /// Shares and nonces against their Merkle proofs
+    ShareCommitments,
+    /// Roots of the blinding proofs against the commitment's blinding column roots
+    BlindingRoots,
+    /// Blinding shares and nonces against their Merkle proofs
+    BlindingCommitments,
+    /// Folding DZK proof that the shares lie on degree-t polynomials
+    DzkProof,
+}
+
+impl ShareCheck{
+    pub const ALL: [ShareCheck; 6] = [
+        ShareCheck::Malformed,
+        ShareCheck::ShareRoots,
+        ShareCheck::ShareCommitments,
+        ShareCheck::BlindingRoots,
+        ShareCheck::BlindingCommitments,
+        ShareCheck::DzkProof,
+    ];
+
+    pub fn name(&self) -> &'static str{
+        match self {
+            ShareCheck::Malformed => "field_elements",
+            ShareCheck::ShareRoots => "share_roots",
+            ShareCheck::ShareCommitments => "share_commitments",
+            ShareCheck::BlindingRoots => "blinding_roots",
+            ShareCheck::BlindingCommitments => "blinding_commitments",
+            ShareCheck::DzkProof => "dzk_proof",
+        }
+    }
+}
+
+impl fmt::Display for ShareCheck{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result{
+        f.write_str(self.name())
+    }
+}
+
+/// Shares a node rejected, with the dealer's commitment and what is needed to check them again offline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShareEvidence{
+    pub num_nodes: usize,
+    pub num_faults: usize,
+    pub use_fft: bool,
+    pub instance_id: usize,
+    pub dealer: Replica,
+    /// Node the shares were meant for. Its DZK evaluation point is party+1.
+    pub party: Replica,
+    /// Check the node saw fail
+    pub failed_check: String,
+    pub commitment: VACommitment,
+    pub shares: AcssSKEShares,
+}
+
+impl ShareEvidence{
+    pub fn file_name(instance_id: usize, dealer: Replica, party: Replica) -> String{
+        format!("acss_ske-rejected-instance{}-dealer{}-node{}.bin", instance_id, dealer, party)
+    }
+
+    pub fn write_to_dir(&self, dir: &Path) -> Result<PathBuf, String>{
+        let path = dir.join(Self::file_name(self.instance_id, self.dealer, self.party));
+        let body = bincode::serialize(self)
+            .map_err(|e| format!("unable to serialize share evidence: {}", e))?;
+        let mut contents = Vec::with_capacity(SHARE_EVIDENCE_MAGIC.len() + 4 + body.len());
+        contents.extend_from_slice(SHARE_EVIDENCE_MAGIC);
+        contents.extend_from_slice(&SHARE_EVIDENCE_VERSION.to_le_bytes());
+        contents.extend(body);
+        fs::write(&path, contents)
+            .map_err(|e| format!("unable to write share evidence {}: {}", path.display(), e))?;
+        Ok(path)
+    }
+
+    pub fn from_file(path: &Path) -> Result<ShareEvidence, String>{
+        let contents = fs::read(path)
+            .map_err(|e| format!("unable to read share evidence {}: {}", path.display(), e))?;
+        let prefix_len = SHARE_EVIDENCE_MAGIC.len() + 4;
+        if contents.len() < prefix_len || &contents[..SHARE_EVIDENCE_MAGIC.len()] != SHARE_EVIDENCE_MAGIC{
+            return Err(format!("{} is not a share evidence file", path.display()));
+        }
+        let mut version = [0u8; 4];
+        version.copy_from_slice(&contents[SHARE_EVIDENCE_MAGIC.len()..prefix_len]);
+        let version = u32::from_le_bytes(version);
+        if version != SHARE_EVIDENCE_VERSION{
+            return Err(format!("share evidence {} has version {}, expected {}", path.display(), version, SHARE_EVIDENCE_VERSION));
+        }
+        bincode::deserialize(&contents[prefix_len..])
+            .map_err(|e| format!("unable to parse share evidence {}: {}", path.display(), e))
+    }
+}
+
+fn to_field_elements(elements: &Vec<LargeFieldSer>) -> Result<Vec<LargeField>, ShareCheck>{
+    elements.iter()
+        .map(|element| LargeField::from_bytes_be(element.as_slice()).map_err(|_| ShareCheck::Malformed))
+        .collect()
+}
+
+impl Context{
+    /// Checks a node's shares against the dealer's root with the opening the node kept, like the node checked them when they arrived.
+    /// Recomputes the leaves from the shares and nonces, checks them against the Merkle proofs, and hashes the column roots
+    /// of the proofs and the blinding column roots into the root.
+    pub fn verify_share_opening(
+        hc: &HashState,
+        num_nodes: usize,
+        num_faults: usize,
+        shares: Vec<LargeField>,
+        opening: &ShareOpening,
+        root: &Hash
+    ) -> bool{
+        let nonce_shares = match to_field_elements(&opening.nonces) {
+            Ok(nonce_shares) => nonce_shares,
+            Err(_) => return false
+        };
+        let evaluation_points: Vec<LargeField> = if opening.use_fft {
+            Self::gen_roots_of_unity(num_nodes)
+        } else {
+            (1..num_nodes+1).map(|x| LargeField::from(x as u64)).collect()
+        };
+        if nonce_shares.len() != opening.proofs.len() || opening.proofs.is_empty(){
+            return false;
+        }
+        let column_roots: Vec<Hash> = opening.proofs.iter().map(|proof| proof.root()).collect();
+        Self::verify_commitments(num_faults+1, evaluation_points, shares, nonce_shares, opening.proofs.clone(), hc)
+            && Self::compute_root_commitment(column_roots, opening.blinding_roots.clone(), hc) == *root
+    }
+
+    /// Runs the checks a node runs on the shares `dealer` sent to `party`, outside of the protocol.
+    /// Returns the first check that fails.
+    pub fn check_shares(
+        folding_dzk_context: &FoldingDZKContext,
+        hc: &HashState,
+        evaluation_points: Vec<LargeField>,
+        num_faults: usize,
+        commitment: &VACommitment,
+        shares_full: &AcssSKEShares,
+        party: Replica
+    ) -> Result<(), ShareCheck>{
+        let shares = to_field_elements(&shares_full.evaluations.0)?;
+        let nonce_shares = to_field_elements(&shares_full.evaluations.1)?;
+        let blinding_shares = to_field_elements(&shares_full.blinding_evaluations.0)?;
+        let blinding_nonce_shares = to_field_elements(&shares_full.blinding_evaluations.1)?;
+        for poly in commitment.polys.iter(){
+            to_field_elements(poly)?;
+        }
+
+        let merkle_proofs = shares_full.evaluations.2.clone();
+
+        let roots_from_proofs: Vec<Hash> = merkle_proofs.iter().map(|proof| proof.root()).collect();
+        if roots_from_proofs != commitment.column_roots {
+            return Err(ShareCheck::ShareRoots);
+        }
+        if !Self::verify_commitments(
+            num_faults+1,
+            evaluation_points.clone(),
+            shares.clone(),
+            nonce_shares,
+            merkle_proofs,
+            hc
+        ){
+            return Err(ShareCheck::ShareCommitments);
+        }
+
+        let blinding_merkle_proofs = shares_full.blinding_evaluations.2.clone();
+
+        let blinding_merkle_roots: Vec<Hash> = blinding_merkle_proofs.iter().map(|proof| proof.root()).collect();
+        if blinding_merkle_roots != commitment.blinding_column_roots {
+            return Err(ShareCheck::BlindingRoots);
+        }
+        if !Self::verify_blinding_commitments(
+            blinding_shares.clone(),
+            blinding_nonce_shares,
+            blinding_merkle_proofs,
+            hc
+        ){
+            return Err(ShareCheck::BlindingCommitments);
+        }
+
+        let grouped_points = Self::group_points_for_public_reconstruction(
+            shares,
+            evaluation_points,
+            num_faults+1
+        );
+
+        let root_comm_fe: Vec<LargeField> = roots_from_proofs.iter().zip(blinding_merkle_roots.iter()).map(|(root, b_root)|{
+            let root_combined = hc.hash_two(root.clone(), b_root.clone());
+            LargeField::from_bytes_be(root_combined.as_slice()).unwrap()
+        }).collect();
+
+        let dzk_aggregated_points: Vec<LargeField> = grouped_points.into_iter().zip(
+            root_comm_fe.clone().into_iter()).map(|(shares, root)|{
+                folding_dzk_context.gen_agg_poly_dzk(shares, root.to_bytes_be())
+            }).collect();
+
+        if !folding_dzk_context.verify_dzk_proof_row(
+            shares_full.dzk_iters.clone(),
+            commitment.dzk_roots.clone(),
+            commitment.polys.clone(),
+            root_comm_fe.into_iter().map(|el| el.to_bytes_be()).collect(),
+            dzk_aggregated_points,
+            blinding_shares,
+            party+1
+        ){
+            return Err(ShareCheck::DzkProof);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,198 @@
+use std::{
+    collections::HashMap,
+    net::{SocketAddr, SocketAddrV4},
+};
+
+use anyhow::{anyhow, Result};
+use config::Node;
+
+use fnv::FnvHashMap;
+use network::{
+    plaintcp::CancelHandler,
+    Acknowledgement,
+};
+use tokio::sync::{
+    mpsc::{UnboundedReceiver, Sender, Receiver, channel, unbounded_channel},
+    oneshot,
+};
+use tsig::TsigKey;
+use types::{Replica, WrapperMsg};
+
+use consensus::{HashKeys, LargeFieldSer, SecretBytes, ReplicaSender};
+
+use crate::{msg::ProtMsg, protocol::ADKGState};
+
+pub struct Context {
+    /// Networking context
+    pub net_send: ReplicaSender<WrapperMsg<ProtMsg>>,
+    pub net_recv: UnboundedReceiver<WrapperMsg<ProtMsg>>,
+
+    /// Data context
+    pub num_nodes: usize,
+    pub myid: usize,
+    pub num_faults: usize,
+    _byz: bool,
+
+    /// Secret Key map
+    pub sec_key_map: HashMap<Replica, SecretBytes>,
+
+    /// Cancel Handlers
+    pub cancel_handlers: HashMap<u64, Vec<CancelHandler<Acknowledgement>>>,
+    exit_rx: oneshot::Receiver<()>,
+    // Exit handler of the ACS service, dropping it terminates the service
+    service_exit_txs: Vec<oneshot::Sender<()>>,
+
+    pub adkg_state: ADKGState,
+
+    /// Output: the generated key, optionally also written to this path as a `tsig` key file
+    pub key_out: Sender<TsigKey>,
+    pub export_path: Option<String>,
+    // Session of this key generation, bound into the keystreams of rows and points
+    pub session: Vec<u8>,
+
+    /// Channels to interact with other services
+    pub acs_term_event: Sender<(usize, usize, Vec<LargeFieldSer>)>,
+    pub acs_out_recv: Receiver<(usize, Vec<usize>)>,
+}
+
+impl Context {
+    pub fn spawn(
+        config: Node,
+        hash_keys: HashKeys,
+        key_out: Sender<TsigKey>,
+        export_path: Option<String>,
+        session: Vec<u8>,
+        byz: bool
+    ) -> anyhow::Result<(oneshot::Sender<()>, Vec<Result<oneshot::Sender<()>>>)> {
+        // Pairwise keys outlive a key generation, so every run needs its own session to get fresh keystreams
+        if session.is_empty(){
+            return Err(anyhow!("ADKG session must not be empty"));
+        }
+        let mut consensus_addrs: FnvHashMap<Replica, SocketAddr> = FnvHashMap::default();
+
+        let mut acs_config = config.clone();
+
+        let port_acs: u16 = 900;
+
+        for (replica, address) in config.net_map.iter() {
+            let address: SocketAddr = address.parse().expect("Unable to parse address");
+
+            let acs_address: SocketAddr = SocketAddr::new(address.ip(), address.port() + port_acs);
+
+            acs_config.net_map.insert(*replica, acs_address.to_string());
+
+            consensus_addrs.insert(*replica, SocketAddr::from(address.clone()));
+        }
+        log::info!("Consensus addresses: {:?}", consensus_addrs);
+        let my_port = consensus_addrs.get(&config.id).unwrap();
+        let my_address = to_socket_address("0.0.0.0", my_port.port());
+
+        // Setup networking
+        let (tx_net_to_consensus, rx_net_to_consensus) = unbounded_channel();
+        let consensus_net = consensus::spawn_replica_network(my_address, consensus_addrs.clone(), tx_net_to_consensus);
+        let (exit_tx, exit_rx) = oneshot::channel();
+
+        let (acs_req_send_channel, acs_req_recv_channel) = channel(10000);
+        let (acs_out_send_channel, acs_out_recv_channel) = channel(10000);
+
+        let (acs_exit_tx, statuses) = acs::Context::spawn(
+            acs_config,
+            hash_keys.clone(),
+            acs_req_recv_channel,
+            acs_out_send_channel,
+            false
+        )?;
+
+        tokio::spawn(async move {
+            let mut c = Context {
+                net_send: consensus_net,
+                net_recv: rx_net_to_consensus,
+
+                num_nodes: config.num_nodes,
+                myid: config.id,
+                num_faults: config.num_faults,
+                _byz: byz,
+
+                sec_key_map: HashMap::default(),
+                cancel_handlers: HashMap::default(),
+                exit_rx: exit_rx,
+                service_exit_txs: vec![acs_exit_tx],
+
+                adkg_state: ADKGState::new(),
+
+                key_out: key_out,
+                export_path: export_path,
+                session: session,
+
+                acs_term_event: acs_req_send_channel,
+                acs_out_recv: acs_out_recv_channel,
+            };
+
+            // Populate secret keys from config
+            for (id, sk_data) in config.sk_map.iter() {
+                c.sec_key_map.insert(*id, SecretBytes::new(sk_data.clone()));
+            }
+
+            // Run the ADKG context
+            if let Err(e) = c.run().await {
+                log::error!("ADKG error: {}", e);
+            }
+        });
+        Ok((exit_tx, statuses))
+    }
+
+    pub async fn send_to(&mut self, replicas: Vec<Replica>, protmsg: ProtMsg) {
+        for replica in replicas {
+            let sec_key = match self.sec_key_map.get(&replica) {
+                Some(sec_key) => sec_key,
+                None => continue,
+            };
+            let wrapper_msg = WrapperMsg::new(protmsg.clone(), self.myid, &sec_key.as_slice());
+            consensus::count_sent_message(&wrapper_msg);
+            let cancel_handler: CancelHandler<Acknowledgement> = self.net_send.send(replica, wrapper_msg).await;
+            self.add_cancel_handler(cancel_handler);
+        }
+    }
+
+    pub fn add_cancel_handler(&mut self, canc: CancelHandler<Acknowledgement>) {
+        self.cancel_handlers.entry(0).or_default().push(canc);
+    }
+
+    pub async fn run(&mut self) -> Result<()>{
+        self.start_adkg().await;
+        loop {
+            tokio::select! {
+                // Receive exit handlers
+                exit_val = &mut self.exit_rx => {
+                    exit_val.map_err(anyhow::Error::new)?;
+                    log::info!("Termination signal received by the server. Exiting.");
+                    for service_exit_tx in self.service_exit_txs.drain(..){
+                        let _status = service_exit_tx.send(());
+                    }
+                    break
+                },
+                msg = self.net_recv.recv() => {
+                    // Received messages are processed here
+                    log::trace!("Got a consensus message from the network from {:?}", msg.as_ref().map(|msg| msg.sender));
+                    let msg = msg.ok_or_else(||
+                        anyhow!("Networking layer has closed")
+                    )?;
+                    self.process_msg(msg).await;
+                },
+                acs_output = self.acs_out_recv.recv() => {
+                    let acs_output = acs_output.ok_or_else(||
+                        anyhow!("Networking layer has closed")
+                    )?;
+                    log::debug!("Received ACS output {:?}", acs_output);
+                    self.process_acs_output(acs_output.1).await;
+                },
+            };
+        }
+        Ok(())
+    }
+}
+
+pub fn to_socket_address(ip_str: &str, port: u16) -> SocketAddr {
+    let addr = SocketAddrV4::new(ip_str.parse().unwrap(), port);
+    addr.into()
+}
@@ -0,0 +1,13 @@
+// Asynchronous distributed key generation: every party deals a random secret through an AVSS with Feldman commitments,
+// ACS agrees on a set of dealers, and the key is the sum of their secrets. The AVSS is its own, not acss_ske, because the
+// dealings need commitments in the exponent. Keys live in G1 of BLS12-381 from the bls12_381 crate and are `tsig` signing keys,
+// so the output feeds threshold signing and is exported in `tsig`'s key file format.
+mod context;
+pub use context::*;
+
+pub mod protocol;
+pub use protocol::*;
+
+mod msg;
+
+mod process;
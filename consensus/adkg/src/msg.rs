@@ -0,0 +1,17 @@
+use crypto::hash::Hash;
+use serde::{Serialize, Deserialize};
+use types::Replica;
+
+/// Messages of the AVSS run by every dealer. Rows and points are encrypted for their recipient, see `protocol::avss`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum ProtMsg{
+    // Dealer to party
+    // compressed commitments to the dealer's bivariate polynomial, recipient's row
+    Send(Vec<Vec<u8>>, Vec<u8>),
+    // Party to party, once its row matched the commitments
+    // dealer, compressed commitments, point on the recipient's row
+    Echo(Replica, Vec<Vec<u8>>, Vec<u8>),
+    // Party to party, once 2t+1 parties echoed or t+1 parties sent Ready for the same commitments
+    // dealer, digest of the commitments, point on the recipient's row
+    Ready(Replica, Hash, Vec<u8>),
+}
@@ -0,0 +1,53 @@
+use std::sync::Arc;
+
+use crate::{context::Context, msg::ProtMsg};
+use crypto::hash::verf_mac;
+use types::WrapperMsg;
+
+impl Context {
+    // This function verifies the Message Authentication Code (MAC) of a sent message
+    // A node cannot impersonate as another node because of MACs
+    pub fn check_proposal(&self, wrapper_msg: Arc<WrapperMsg<ProtMsg>>) -> bool {
+        // validate MAC
+        let byte_val =
+            bincode::serialize(&wrapper_msg.protmsg).expect("Failed to serialize object");
+        let sec_key = match self.sec_key_map.get(&wrapper_msg.clone().sender) {
+            Some(val) => val,
+            None => {
+                panic!("Secret key not available, this shouldn't happen")
+            }
+        };
+        if !verf_mac(&byte_val, &sec_key.as_slice(), &wrapper_msg.mac) {
+            log::warn!("MAC Verification failed.");
+            return false;
+        }
+        true
+    }
+
+    pub(crate) async fn process_msg(&mut self, wrapper_msg: WrapperMsg<ProtMsg>) {
+        // Payloads can carry shares, so only the sender is logged
+        log::trace!("Received protocol msg from {}", wrapper_msg.sender);
+        let msg = Arc::new(wrapper_msg.clone());
+
+        // Verify the message's authenticity before proceeding
+        if self.check_proposal(msg) {
+            let sender = wrapper_msg.sender;
+            match wrapper_msg.protmsg {
+                ProtMsg::Send(commitments, row) => {
+                    log::debug!("Received Send from node : {}", sender);
+                    self.process_send(sender, commitments, row).await;
+                }
+                ProtMsg::Echo(dealer, commitments, point) => {
+                    log::debug!("Received Echo for dealer {} from node : {}", dealer, sender);
+                    self.process_echo(dealer, commitments, point, sender).await;
+                }
+                ProtMsg::Ready(dealer, digest, point) => {
+                    log::debug!("Received Ready for dealer {} from node : {}", dealer, sender);
+                    self.process_ready(dealer, digest, point, sender).await;
+                }
+            }
+        } else {
+            log::warn!("MAC Verification failed for a message from node {}", wrapper_msg.sender);
+        }
+    }
+}
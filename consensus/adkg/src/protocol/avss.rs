@@ -0,0 +1,313 @@
+use bls12_381::Scalar;
+use consensus::SecretBytes;
+use crypto::hash::{Hash, do_hash};
+use rand_chacha::ChaCha20Rng;
+use rand_core::{RngCore, SeedableRng};
+use tsig::rand_scalar;
+use types::Replica;
+use zeroize::Zeroizing;
+
+use crate::{Context, msg::ProtMsg, protocol::{AVSSState, BivariatePoly, CheckedCommitment, Commitment, commitment_digest, evaluate, interpolate_at, verify_point, verify_row}};
+
+/*
+    AVSS of Cachin, Kursawe, Lysyanskaya and Strobl with Feldman commitments. The dealer sends every party its row and the commitments.
+    A party whose row matches the commitments echoes the commitments to everyone, with a point on the recipient's row.
+    After 2t+1 echoes or t+1 Readys for the same commitments, a party recovers its row from t+1 points that match the commitments
+    if it did not receive a valid one, and sends Ready with a point on the recipient's row. After 2t+1 Readys the sharing completes.
+    If one honest party completes, every honest party completes with a share on the committed polynomial.
+*/
+
+// Kinds of encrypted payloads, bound into the keystream
+const SEND_PAYLOAD: u8 = 0;
+const ECHO_PAYLOAD: u8 = 1;
+const READY_PAYLOAD: u8 = 2;
+
+impl Context{
+    /// Shares a random secret with every party
+    pub async fn deal(&mut self){
+        if self.adkg_state.dealt{
+            return;
+        }
+        self.adkg_state.dealt = true;
+        let poly = BivariatePoly::random(rand_scalar(), self.num_faults);
+        let commitments = poly.commit().to_bytes();
+        for rep in 0..self.num_nodes{
+            let row = Zeroizing::new(poly.row(rep));
+            let row_ser = Zeroizing::new(bincode::serialize(&row.iter().map(|coefficient| coefficient.to_bytes()).collect::<Vec<[u8; 32]>>()).unwrap());
+            let sealed_row = self.seal(SEND_PAYLOAD, self.myid, self.myid, rep, row_ser.as_slice());
+            self.send_to(vec![rep], ProtMsg::Send(commitments.clone(), sealed_row)).await;
+        }
+    }
+
+    pub async fn process_send(&mut self, dealer: Replica, commitments: Vec<Vec<u8>>, sealed_row: Vec<u8>){
+        if self.avss_state(dealer).map(|state| state.send_received).unwrap_or(true){
+            return;
+        }
+        self.avss_state(dealer).unwrap().send_received = true;
+
+        let digest = commitment_digest(&commitments);
+        let row_ser = Zeroizing::new(self.unseal(SEND_PAYLOAD, dealer, dealer, self.myid, sealed_row.as_slice()));
+        let row: Option<Vec<Scalar>> = bincode::deserialize::<Vec<[u8; 32]>>(row_ser.as_slice()).ok()
+            .and_then(|row| row.iter().map(|coefficient| Option::<Scalar>::from(Scalar::from_bytes(coefficient))).collect());
+        let row = match row {
+            Some(row) => Zeroizing::new(row),
+            None => {
+                log::error!("Dealer {} sent a malformed row", dealer);
+                return;
+            }
+        };
+        let state = self.avss_state(dealer).unwrap();
+        state.commitments.entry(digest).or_insert(commitments.clone());
+        let valid = match self.check_commitment(dealer, digest) {
+            Some(checked) => verify_row(&checked.row_commitment, &row),
+            None => false
+        };
+        if !valid{
+            log::error!("Row of dealer {} does not match its commitments", dealer);
+            return;
+        }
+        let evaluations: Vec<Scalar> = (0..self.num_nodes+1).map(|x| evaluate(&row, &Scalar::from(x as u64))).collect();
+        let state = self.avss_state(dealer).unwrap();
+        if state.row.is_none(){
+            state.row = Some((digest, evaluations));
+        }
+
+        log::info!("Row of dealer {} matches its commitments, echoing", dealer);
+        for rep in 0..self.num_nodes{
+            let point = match self.row_point(dealer, &digest, rep) {
+                Some(point) => point,
+                None => return,
+            };
+            let sealed_point = self.seal(ECHO_PAYLOAD, dealer, self.myid, rep, &point);
+            self.send_to(vec![rep], ProtMsg::Echo(dealer, commitments.clone(), sealed_point)).await;
+        }
+        // Echoes or Readys may have arrived before the row
+        self.check_avss(dealer, digest).await;
+    }
+
+    pub async fn process_echo(&mut self, dealer: Replica, commitments: Vec<Vec<u8>>, sealed_point: Vec<u8>, sender: Replica){
+        let state = match self.avss_state(dealer) {
+            Some(state) => state,
+            None => return,
+        };
+        if !state.echo_senders.insert(sender){
+            return;
+        }
+        let digest = commitment_digest(&commitments);
+        state.commitments.entry(digest).or_insert(commitments);
+        state.echoes.entry(digest).or_default().insert(sender);
+        self.store_point(ECHO_PAYLOAD, dealer, digest, sealed_point, sender);
+        self.check_avss(dealer, digest).await;
+    }
+
+    pub async fn process_ready(&mut self, dealer: Replica, digest: Hash, sealed_point: Vec<u8>, sender: Replica){
+        let state = match self.avss_state(dealer) {
+            Some(state) => state,
+            None => return,
+        };
+        if !state.ready_senders.insert(sender){
+            return;
+        }
+        state.readys.entry(digest).or_default().insert(sender);
+        self.store_point(READY_PAYLOAD, dealer, digest, sealed_point, sender);
+        self.check_avss(dealer, digest).await;
+    }
+
+    // Keeps a point on this party's row for commitments it has no row for yet
+    fn store_point(&mut self, kind: u8, dealer: Replica, digest: Hash, sealed_point: Vec<u8>, sender: Replica){
+        let point_ser = Zeroizing::new(self.unseal(kind, dealer, sender, self.myid, sealed_point.as_slice()));
+        let state = self.avss_state(dealer).unwrap();
+        if state.row.as_ref().map(|(row_digest, _)| *row_digest == digest).unwrap_or(false){
+            return;
+        }
+        let point_bytes: Option<[u8; 32]> = point_ser.as_slice().try_into().ok();
+        let point: Option<Scalar> = point_bytes.and_then(|bytes| Option::from(Scalar::from_bytes(&bytes)));
+        match point {
+            Some(point) => {
+                state.points.entry(digest).or_default().entry(sender).or_insert(point);
+            },
+            None => {
+                log::warn!("Node {} sent a malformed point for dealer {}", sender, dealer);
+            }
+        }
+    }
+
+    async fn check_avss(&mut self, dealer: Replica, digest: Hash){
+        let state = self.avss_state(dealer).unwrap();
+        if state.completed.is_some(){
+            return;
+        }
+        let num_echoes = state.echoes.get(&digest).map(|echoes| echoes.len()).unwrap_or(0);
+        let num_readys = state.readys.get(&digest).map(|readys| readys.len()).unwrap_or(0);
+        // Echo and Ready quorums of n-t parties intersect in an honest party for any n > 3t
+        if num_echoes < self.num_nodes-self.num_faults && num_readys < self.num_faults+1{
+            return;
+        }
+        if !self.recover_row(dealer, digest){
+            log::info!("Waiting for points to recover the row of dealer {}", dealer);
+            return;
+        }
+
+        let state = self.avss_state(dealer).unwrap();
+        if !state.ready_sent{
+            state.ready_sent = true;
+            log::info!("Sending Ready for dealer {}", dealer);
+            for rep in 0..self.num_nodes{
+                let point = match self.row_point(dealer, &digest, rep) {
+                    Some(point) => point,
+                    None => return,
+                };
+                let sealed_point = self.seal(READY_PAYLOAD, dealer, self.myid, rep, &point);
+                self.send_to(vec![rep], ProtMsg::Ready(dealer, digest, sealed_point)).await;
+            }
+        }
+
+        if num_readys >= self.num_nodes-self.num_faults{
+            self.avss_state(dealer).unwrap().completed = Some(digest);
+            log::info!("Sharing of dealer {} completed", dealer);
+            self.process_avss_termination(dealer).await;
+        }
+    }
+
+    // Makes sure this party holds its row for the given commitments, recovering it from t+1 valid points if needed
+    fn recover_row(&mut self, dealer: Replica, digest: Hash) -> bool{
+        let num_nodes = self.num_nodes;
+        let num_faults = self.num_faults;
+        let state = self.avss_state(dealer).unwrap();
+        if state.row.as_ref().map(|(row_digest, _)| *row_digest == digest).unwrap_or(false){
+            return true;
+        }
+        if state.points.get(&digest).map(|points| points.is_empty()).unwrap_or(true){
+            return false;
+        }
+        if self.check_commitment(dealer, digest).is_none(){
+            return false;
+        }
+        let state = self.avss_state(dealer).unwrap();
+        let row_commitment = &state.checked.get(&digest).unwrap().as_ref().unwrap().row_commitment;
+
+        // Every point is checked once
+        let pending: Vec<(Replica, Scalar)> = state.points.get_mut(&digest).unwrap().drain().collect();
+        let mut valid = Vec::new();
+        for (sender, point) in pending.into_iter(){
+            if verify_point(row_commitment, sender, &point){
+                valid.push((sender, point));
+            }
+            else{
+                log::warn!("Node {} sent a point for dealer {} that does not match the commitments", sender, dealer);
+            }
+        }
+        let valid_points = state.valid_points.entry(digest).or_default();
+        valid_points.extend(valid);
+        if valid_points.len() < num_faults+1{
+            return false;
+        }
+
+        let basis: Vec<(Replica, Scalar)> = valid_points[..num_faults+1].to_vec();
+        let evaluations: Vec<Scalar> = (0..num_nodes+1).map(|x| interpolate_at(&basis, &Scalar::from(x as u64))).collect();
+        log::info!("Recovered the row of dealer {} from the points of parties {:?}", dealer, basis.iter().map(|(rep, _)| *rep).collect::<Vec<Replica>>());
+        state.row = Some((digest, evaluations));
+        state.points.clear();
+        state.valid_points.clear();
+        true
+    }
+
+    // Parses the commitments with the given digest once and computes the commitments to this party's row
+    fn check_commitment(&mut self, dealer: Replica, digest: Hash) -> Option<&CheckedCommitment>{
+        let num_faults = self.num_faults;
+        let myid = self.myid;
+        let state = self.avss_state(dealer)?;
+        if !state.checked.contains_key(&digest){
+            let commitments = state.commitments.get(&digest)?;
+            let checked = Commitment::from_bytes(commitments, num_faults).map(|commitment| {
+                let row_commitment = commitment.row_commitment(myid);
+                CheckedCommitment {
+                    commitment: commitment,
+                    row_commitment: row_commitment
+                }
+            });
+            if checked.is_none(){
+                log::warn!("Commitments of dealer {} are malformed", dealer);
+            }
+            state.checked.insert(digest, checked);
+        }
+        state.checked.get(&digest).unwrap().as_ref()
+    }
+
+    // Point of this party's row for party rep, row(rep+1), which by symmetry lies on rep's row
+    fn row_point(&mut self, dealer: Replica, digest: &Hash, rep: Replica) -> Option<[u8; 32]>{
+        let state = self.avss_state(dealer)?;
+        match state.row.as_ref() {
+            Some((row_digest, row)) if row_digest == digest => Some(row[rep+1].to_bytes()),
+            _ => None
+        }
+    }
+
+    /// State of a dealer's sharing. None for parties outside the deployment.
+    pub fn avss_state(&mut self, dealer: Replica) -> Option<&mut AVSSState>{
+        if dealer >= self.num_nodes{
+            return None;
+        }
+        Some(self.adkg_state.avss_states.entry(dealer).or_default())
+    }
+
+    // Rows and points are secret and travel encrypted with a keystream derived from the pairwise key of sender and recipient
+    fn seal(&self, kind: u8, dealer: Replica, sender: Replica, recipient: Replica, payload: &[u8]) -> Vec<u8>{
+        let peer = if sender == self.myid {recipient} else {sender};
+        match self.sec_key_map.get(&peer) {
+            Some(sec_key) => seal_payload(sec_key, &self.session, kind, dealer, sender, recipient, payload),
+            None => Vec::new(),
+        }
+    }
+
+    fn unseal(&self, kind: u8, dealer: Replica, sender: Replica, recipient: Replica, sealed: &[u8]) -> Vec<u8>{
+        self.seal(kind, dealer, sender, recipient, sealed)
+    }
+}
+
+const SEAL_TAG: &[u8] = b"adkg seal";
+
+/// XORs `payload` with a keystream seeded from the pairwise key, the session and the (kind, dealer, sender, recipient) tuple.
+/// Within a session every tuple is used for one message only, and sessions are never reused, so keystreams are never reused.
+/// Sealing twice unseals.
+pub fn seal_payload(sec_key: &SecretBytes, session: &[u8], kind: u8, dealer: Replica, sender: Replica, recipient: Replica, payload: &[u8]) -> Vec<u8>{
+    let mut label = SEAL_TAG.to_vec();
+    label.extend((session.len() as u64).to_be_bytes());
+    label.extend_from_slice(session);
+    label.push(kind);
+    for id in [dealer, sender, recipient]{
+        label.extend((id as u64).to_be_bytes());
+    }
+    let seed = Zeroizing::new(do_hash(sec_key.with_suffix(label.as_slice()).as_slice()));
+    let mut rng = ChaCha20Rng::from_seed(*seed);
+    let mut keystream = Zeroizing::new(vec![0u8; payload.len()]);
+    rng.fill_bytes(keystream.as_mut_slice());
+    payload.iter().zip(keystream.iter()).map(|(byte, key)| byte ^ key).collect()
+}
+
+#[cfg(test)]
+mod tests{
+    use consensus::SecretBytes;
+
+    use super::{SEND_PAYLOAD, ECHO_PAYLOAD, seal_payload};
+
+    #[test]
+    fn sealing_twice_unseals(){
+        let sec_key = SecretBytes::new(vec![7u8; 32]);
+        let payload = b"row of party 2".to_vec();
+        let sealed = seal_payload(&sec_key, b"run 1", SEND_PAYLOAD, 0, 0, 2, &payload);
+        assert_ne!(sealed, payload);
+        assert_eq!(seal_payload(&sec_key, b"run 1", SEND_PAYLOAD, 0, 0, 2, &sealed), payload);
+    }
+
+    #[test]
+    fn keystreams_differ_across_sessions_and_messages(){
+        let sec_key = SecretBytes::new(vec![7u8; 32]);
+        let zeros = vec![0u8; 64];
+        let keystream = seal_payload(&sec_key, b"run 1", ECHO_PAYLOAD, 0, 1, 2, &zeros);
+        assert_ne!(keystream, seal_payload(&sec_key, b"run 2", ECHO_PAYLOAD, 0, 1, 2, &zeros));
+        assert_ne!(keystream, seal_payload(&sec_key, b"run 1", SEND_PAYLOAD, 0, 1, 2, &zeros));
+        assert_ne!(keystream, seal_payload(&sec_key, b"run 1", ECHO_PAYLOAD, 3, 1, 2, &zeros));
+        assert_ne!(keystream, seal_payload(&sec_key, b"run 1", ECHO_PAYLOAD, 0, 2, 1, &zeros));
+    }
+}
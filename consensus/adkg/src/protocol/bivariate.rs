@@ -0,0 +1,201 @@
+use bls12_381::{G1Affine, G1Projective, Scalar};
+use crypto::hash::{Hash, do_hash};
+use tsig::rand_scalar;
+use types::Replica;
+use zeroize::Zeroize;
+
+/*
+    Symmetric bivariate polynomials with Feldman commitments, as in the AVSS of Cachin, Kursawe, Lysyanskaya and Strobl.
+    The dealer shares s with phi(x,y) = sum phi_kl x^k y^l of degree t in both variables, phi_kl = phi_lk and phi_00 = s,
+    and commits to it with C_kl = g1^{phi_kl}. Party i receives its row phi(x, i+1), and its share of s is phi(0, i+1).
+    By symmetry, party i's row evaluated at j+1 is a point on party j's row, so parties recover missing rows from each other's points.
+    Rows and points are checked against the commitments at a fixed cost of t+1 scalar multiplications each.
+*/
+pub struct BivariatePoly{
+    coefficients: Vec<Vec<Scalar>>,
+}
+
+impl BivariatePoly{
+    pub fn random(secret: Scalar, degree: usize) -> BivariatePoly{
+        let mut coefficients = vec![vec![Scalar::zero(); degree+1]; degree+1];
+        for k in 0..degree+1{
+            for l in k..degree+1{
+                let coefficient = if k == 0 && l == 0 {secret} else {rand_scalar()};
+                coefficients[k][l] = coefficient;
+                coefficients[l][k] = coefficient;
+            }
+        }
+        BivariatePoly {
+            coefficients: coefficients
+        }
+    }
+
+    /// Coefficients of party `rep`'s row phi(x, rep+1), a polynomial in x
+    pub fn row(&self, rep: Replica) -> Vec<Scalar>{
+        let y = Scalar::from((rep+1) as u64);
+        self.coefficients.iter().map(|coefficients| evaluate(coefficients, &y)).collect()
+    }
+
+    pub fn commit(&self) -> Commitment{
+        Commitment {
+            points: self.coefficients.iter().map(|coefficients| {
+                coefficients.iter().map(|coefficient| G1Affine::from(G1Affine::generator() * coefficient)).collect()
+            }).collect()
+        }
+    }
+}
+
+impl Drop for BivariatePoly{
+    fn drop(&mut self) {
+        for coefficients in self.coefficients.iter_mut(){
+            coefficients.zeroize();
+        }
+    }
+}
+
+/// Feldman commitments C_kl = g1^{phi_kl} to a symmetric bivariate polynomial
+#[derive(Clone)]
+pub struct Commitment{
+    pub points: Vec<Vec<G1Affine>>,
+}
+
+impl Commitment{
+    /// Compressed points in row-major order
+    pub fn to_bytes(&self) -> Vec<Vec<u8>>{
+        self.points.iter().flatten().map(|point| point.to_compressed().to_vec()).collect()
+    }
+
+    /// Parses and validates commitments of the given degree. Rejects commitments to polynomials that are not symmetric.
+    pub fn from_bytes(bytes: &Vec<Vec<u8>>, degree: usize) -> Option<Commitment>{
+        if bytes.len() != (degree+1)*(degree+1){
+            return None;
+        }
+        let mut points = Vec::new();
+        for row_bytes in bytes.chunks(degree+1){
+            let row = row_bytes.iter().map(|point_bytes| tsig::g1_from_bytes(point_bytes.as_slice())).collect::<Option<Vec<G1Affine>>>()?;
+            points.push(row);
+        }
+        for k in 0..degree+1{
+            for l in k+1..degree+1{
+                if points[k][l] != points[l][k]{
+                    return None;
+                }
+            }
+        }
+        Some(Commitment {
+            points: points
+        })
+    }
+
+    /// Commitments to the coefficients of party `rep`'s row, B_k = sum_l C_kl (rep+1)^l
+    pub fn row_commitment(&self, rep: Replica) -> Vec<G1Projective>{
+        let y = Scalar::from((rep+1) as u64);
+        self.points.iter().map(|points| evaluate_in_exponent(points, &y)).collect()
+    }
+}
+
+/// Digest of serialized commitments, used to agree on a dealing without resending the commitments
+pub fn commitment_digest(bytes: &Vec<Vec<u8>>) -> Hash{
+    do_hash(bytes.concat().as_slice())
+}
+
+/// Checks a row against its commitments
+pub fn verify_row(row_commitment: &Vec<G1Projective>, row: &Vec<Scalar>) -> bool{
+    row.len() == row_commitment.len()
+        && row.iter().zip(row_commitment.iter()).all(|(coefficient, commitment)| G1Projective::generator() * coefficient == *commitment)
+}
+
+/// Checks that `value` is the evaluation at sender+1 of the row committed to in `row_commitment`
+pub fn verify_point(row_commitment: &Vec<G1Projective>, sender: Replica, value: &Scalar) -> bool{
+    let x = Scalar::from((sender+1) as u64);
+    let mut expected = G1Projective::identity();
+    for commitment in row_commitment.iter().rev(){
+        expected = expected * x + commitment;
+    }
+    G1Projective::generator() * value == expected
+}
+
+/// Horner evaluation of a polynomial given by its coefficients
+pub fn evaluate(coefficients: &Vec<Scalar>, x: &Scalar) -> Scalar{
+    coefficients.iter().rev().fold(Scalar::zero(), |acc, coefficient| acc * x + coefficient)
+}
+
+/// Horner evaluation in the exponent of a polynomial committed to coefficient by coefficient
+pub fn evaluate_in_exponent(commitments: &Vec<G1Affine>, x: &Scalar) -> G1Projective{
+    commitments.iter().rev().fold(G1Projective::identity(), |acc, commitment| acc * x + commitment)
+}
+
+/// Evaluates at x the polynomial through the points (rep+1, value) of distinct parties
+pub fn interpolate_at(points: &Vec<(Replica, Scalar)>, x: &Scalar) -> Scalar{
+    let mut result = Scalar::zero();
+    for (i, (rep_i, value)) in points.iter().enumerate(){
+        let x_i = Scalar::from((rep_i+1) as u64);
+        let mut numerator = Scalar::one();
+        let mut denominator = Scalar::one();
+        for (j, (rep_j, _)) in points.iter().enumerate(){
+            if i != j{
+                let x_j = Scalar::from((rep_j+1) as u64);
+                numerator *= x_j - x;
+                denominator *= x_j - x_i;
+            }
+        }
+        result += value * numerator * denominator.invert().unwrap();
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests{
+    use bls12_381::Scalar;
+    use types::Replica;
+
+    use super::{BivariatePoly, Commitment, commitment_digest, evaluate, interpolate_at, verify_point, verify_row};
+
+    const NUM_NODES: usize = 4;
+    const NUM_FAULTS: usize = 1;
+
+    #[test]
+    fn rows_and_points_match_the_commitments(){
+        let poly = BivariatePoly::random(Scalar::from(42u64), NUM_FAULTS);
+        let commitment = Commitment::from_bytes(&poly.commit().to_bytes(), NUM_FAULTS).unwrap();
+        for rep in 0..NUM_NODES{
+            let row = poly.row(rep);
+            let row_commitment = commitment.row_commitment(rep);
+            assert!(verify_row(&row_commitment, &row));
+            // Party sender's row at rep+1 is a point on rep's row
+            for sender in 0..NUM_NODES{
+                let point = evaluate(&poly.row(sender), &Scalar::from((rep+1) as u64));
+                assert!(verify_point(&row_commitment, sender, &point));
+            }
+            let mut tampered = row.clone();
+            tampered[0] += Scalar::one();
+            assert!(!verify_row(&row_commitment, &tampered));
+            assert!(!verify_point(&row_commitment, 0, &(evaluate(&row, &Scalar::one()) + Scalar::one())));
+        }
+    }
+
+    #[test]
+    fn t_plus_one_points_recover_a_row_and_the_shares_open_the_secret(){
+        let secret = Scalar::from(42u64);
+        let poly = BivariatePoly::random(secret, NUM_FAULTS);
+        let row = poly.row(2);
+        let points: Vec<(Replica, Scalar)> = [0, 3].iter().map(|sender| (*sender, evaluate(&poly.row(*sender), &Scalar::from(3u64)))).collect();
+        assert_eq!(interpolate_at(&points, &Scalar::zero()), row[0]);
+
+        // Shares phi(0, i+1) of t+1 parties interpolate to the secret
+        let shares: Vec<(Replica, Scalar)> = (0..NUM_FAULTS+1).map(|rep| (rep, poly.row(rep)[0])).collect();
+        assert_eq!(interpolate_at(&shares, &Scalar::zero()), secret);
+    }
+
+    #[test]
+    fn asymmetric_or_short_commitments_are_rejected(){
+        let poly = BivariatePoly::random(Scalar::from(42u64), NUM_FAULTS);
+        let mut bytes = poly.commit().to_bytes();
+        assert!(Commitment::from_bytes(&bytes, NUM_FAULTS+1).is_none());
+        let digest = commitment_digest(&bytes);
+        // Swap C_00 and C_01, which breaks the symmetry C_01 = C_10
+        bytes.swap(0, 1);
+        assert!(Commitment::from_bytes(&bytes, NUM_FAULTS).is_none());
+        assert_ne!(commitment_digest(&bytes), digest);
+    }
+}
@@ -0,0 +1,79 @@
+use bls12_381::{G1Affine, G1Projective, Scalar};
+use tsig::{TsigKey, TsigKeyFile};
+use types::Replica;
+
+use crate::{Context, protocol::evaluate_in_exponent};
+
+impl Context{
+    // Every party deals a random secret. The key is the sum of the secrets of the dealers agreed through ACS.
+    pub async fn start_adkg(&mut self){
+        log::info!("Starting ADKG, sharing a random secret");
+        self.deal().await;
+    }
+
+    pub async fn process_avss_termination(&mut self, dealer: Replica){
+        if !self.adkg_state.acs_input.insert(dealer){
+            return;
+        }
+        let _status = self.acs_term_event.send((1, dealer, vec![])).await;
+        self.compute_key().await;
+    }
+
+    pub async fn process_acs_output(&mut self, mut acs_output: Vec<Replica>){
+        if self.adkg_state.acs_output.is_some(){
+            return;
+        }
+        acs_output.sort();
+        acs_output.dedup();
+        log::info!("ACS agreed on dealers {:?}", acs_output);
+        self.adkg_state.acs_output = Some(acs_output);
+        self.compute_key().await;
+    }
+
+    // Sums the shares of the agreed dealers. The verification keys follow from the dealers' commitments,
+    // g1^{f(j+1)} = sum over dealers of sum_l C_0l (j+1)^l, so no party has to publish them.
+    pub async fn compute_key(&mut self){
+        if self.adkg_state.terminated || self.adkg_state.acs_output.is_none(){
+            return;
+        }
+        let mut secret_share = Scalar::zero();
+        let mut key_commitments = vec![G1Projective::identity(); self.num_faults+1];
+        for dealer in self.adkg_state.acs_output.as_ref().unwrap().iter(){
+            let state = self.adkg_state.avss_states.get(dealer);
+            let (share, commitment) = match state.and_then(|state| state.share().zip(state.commitment())) {
+                Some(completed) => completed,
+                None => {
+                    log::info!("Sharing of dealer {} did not complete yet, will retry later for key generation", dealer);
+                    return;
+                }
+            };
+            secret_share += share;
+            for (key_commitment, commitment) in key_commitments.iter_mut().zip(commitment.points[0].iter()){
+                *key_commitment += commitment;
+            }
+        }
+        let key_commitments: Vec<G1Affine> = key_commitments.iter().map(|commitment| G1Affine::from(commitment)).collect();
+        let public_key_shares: Vec<G1Affine> = (0..self.num_nodes).map(|rep| {
+            G1Affine::from(evaluate_in_exponent(&key_commitments, &Scalar::from((rep+1) as u64)))
+        }).collect();
+
+        let key = TsigKey::new(self.myid, self.num_faults, secret_share, public_key_shares);
+        if !key.is_consistent(){
+            log::error!("Own key share does not match the dealers' commitments, this shouldn't happen");
+            return;
+        }
+        self.adkg_state.terminated = true;
+        log::info!("ADKG terminated with public key shares of {} parties", self.num_nodes);
+
+        if let Some(path) = self.export_path.as_ref(){
+            let status = TsigKeyFile::from_key(&key).write_to_file(path);
+            if status.is_err(){
+                log::error!("Error exporting key to {} because of {:?}", path, status.err().unwrap());
+            }
+        }
+        let status = self.key_out.send(key).await;
+        if status.is_err(){
+            log::error!("Error sending ADKG output to the key channel");
+        }
+    }
+}
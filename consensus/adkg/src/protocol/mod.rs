@@ -0,0 +1,9 @@
+pub mod state;
+pub use state::*;
+
+mod bivariate;
+pub use bivariate::*;
+
+mod avss;
+
+mod keygen;
@@ -0,0 +1,99 @@
+use std::collections::{HashMap, HashSet};
+
+use bls12_381::{G1Projective, Scalar};
+use crypto::hash::Hash;
+use types::Replica;
+use zeroize::Zeroize;
+
+use crate::protocol::Commitment;
+
+/// Parsed commitments of a dealing together with the commitments to this party's row
+pub struct CheckedCommitment{
+    pub commitment: Commitment,
+    pub row_commitment: Vec<G1Projective>,
+}
+
+/// AVSS of one dealer as seen by this party
+#[derive(Default)]
+pub struct AVSSState{
+    // Serialized commitments from the dealer's Send and the Echoes, per digest
+    pub commitments: HashMap<Hash, Vec<Vec<u8>>>,
+    // Commitments parsed once their digest is needed, None if they are invalid
+    pub checked: HashMap<Hash, Option<CheckedCommitment>>,
+
+    pub send_received: bool,
+    pub echo_senders: HashSet<Replica>,
+    pub ready_senders: HashSet<Replica>,
+    pub echoes: HashMap<Hash, HashSet<Replica>>,
+    pub readys: HashMap<Hash, HashSet<Replica>>,
+    pub ready_sent: bool,
+
+    // Points on this party's row from other parties, not yet checked against the commitments
+    pub points: HashMap<Hash, HashMap<Replica, Scalar>>,
+    // Points that matched the commitments
+    pub valid_points: HashMap<Hash, Vec<(Replica, Scalar)>>,
+
+    // This party's row, evaluated at 0 and at the point i+1 of every party i, with the digest of its commitments
+    pub row: Option<(Hash, Vec<Scalar>)>,
+    // Digest of the commitments of the completed sharing, the share is row(0)
+    pub completed: Option<Hash>,
+}
+
+impl AVSSState{
+    /// This party's share of the dealer's secret, once the sharing completed
+    pub fn share(&self) -> Option<Scalar>{
+        match (self.completed.as_ref(), self.row.as_ref()) {
+            (Some(digest), Some((row_digest, row))) if digest == row_digest => Some(row[0]),
+            _ => None
+        }
+    }
+
+    /// Commitments of the completed sharing
+    pub fn commitment(&self) -> Option<&Commitment>{
+        let digest = self.completed.as_ref()?;
+        self.checked.get(digest)?.as_ref().map(|checked| &checked.commitment)
+    }
+}
+
+impl Drop for AVSSState{
+    fn drop(&mut self) {
+        for points in self.points.values_mut(){
+            for point in points.values_mut(){
+                point.zeroize();
+            }
+        }
+        for points in self.valid_points.values_mut(){
+            for (_, point) in points.iter_mut(){
+                point.zeroize();
+            }
+        }
+        if let Some((_, row)) = self.row.as_mut(){
+            row.zeroize();
+        }
+    }
+}
+
+pub struct ADKGState{
+    // Sharing of every dealer
+    pub avss_states: HashMap<Replica, AVSSState>,
+    pub dealt: bool,
+    // Dealers whose sharing completed, given to the ACS
+    pub acs_input: HashSet<Replica>,
+    // Dealers agreed through ACS
+    pub acs_output: Option<Vec<Replica>>,
+
+    pub terminated: bool,
+}
+
+impl ADKGState{
+    pub fn new()-> ADKGState{
+        ADKGState {
+            avss_states: HashMap::default(),
+            dealt: false,
+            acs_input: HashSet::default(),
+            acs_output: None,
+
+            terminated: false,
+        }
+    }
+}
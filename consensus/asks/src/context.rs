@@ -7,7 +7,7 @@ use config::Node;
 
 use fnv::FnvHashMap;
 use network::{
-    plaintcp::{CancelHandler, TcpReceiver, TcpReliableSender},
+    plaintcp::CancelHandler,
     Acknowledgement,
 };
 use tokio::sync::{
@@ -17,15 +17,19 @@ use tokio::sync::{
 // use tokio_util::time::DelayQueue;
 use types::{Replica, WrapperMsg};
 
-use consensus::{LargeFieldSSS};
+use consensus::{HashKeys, LargeFieldSSS, SecretBytes, ReplicaSender};
 
 use ha_crypto::{aes_hash::HashState, LargeField};
 
-use crate::{protocol::ASKSState, msg::ProtMsg, handlers::Handler};
+use crate::{protocol::ASKSState, msg::ProtMsg};
+
+/// Instance ids of one party's ASKS instances are offset by the party's id times this bound.
+/// Instance ids from long-running callers (e.g. per-epoch ACS) must stay below it.
+pub const INSTANCE_THRESHOLD: usize = 1_000_000;
 
 pub struct Context {
     /// Networking context
-    pub net_send: TcpReliableSender<Replica, WrapperMsg<ProtMsg>, Acknowledgement>,
+    pub net_send: ReplicaSender<WrapperMsg<ProtMsg>>,
     pub net_recv: UnboundedReceiver<WrapperMsg<ProtMsg>>,
     
     /// Data context
@@ -35,7 +39,7 @@ pub struct Context {
     _byz: bool,
 
     /// Secret Key map
-    pub sec_key_map: HashMap<Replica, Vec<u8>>,
+    pub sec_key_map: HashMap<Replica, SecretBytes>,
 
     /// Hardware acceleration context
     pub hash_context: HashState,
@@ -65,6 +69,7 @@ pub struct Context {
 
 impl Context {
     pub fn spawn(config: Node,
+        hash_keys: HashKeys,
         input_reqs: Receiver<(usize, usize, bool, bool, Option<Vec<LargeField>>, Option<usize>)>, 
         output_shares: Sender<(usize,Replica,Option<Vec<LargeField>>)>,
         byz: bool) -> anyhow::Result<oneshot::Sender<()>> {
@@ -80,25 +85,14 @@ impl Context {
 
         // Setup networking
         let (tx_net_to_consensus, rx_net_to_consensus) = unbounded_channel();
-        TcpReceiver::<Acknowledgement, WrapperMsg<ProtMsg>, _>::spawn(
-            my_address,
-            Handler::new(tx_net_to_consensus),
-        );
-
-        let consensus_net = TcpReliableSender::<Replica, WrapperMsg<ProtMsg>, Acknowledgement>::with_peers(
-            consensus_addrs.clone(),
-        );
+        let consensus_net = consensus::spawn_replica_network(my_address, consensus_addrs.clone(), tx_net_to_consensus);
         
         let (exit_tx, exit_rx) = oneshot::channel();
 
         // Keyed AES ciphers
-        let key0 = [5u8; 16];
-        let key1 = [29u8; 16];
-        let key2 = [23u8; 16];
-        let hashstate = HashState::new(key0, key1, key2);
+        let hashstate = hash_keys.hash_state();
 
-        let threshold:usize = 10000;
-        let rbc_start_id = threshold*config.id;
+        let rbc_start_id = INSTANCE_THRESHOLD*config.id;
 
         let lf_uv_sss = LargeFieldSSS::new_with_vandermonde(
             config.num_faults +1,
@@ -120,7 +114,7 @@ impl Context {
                 exit_rx: exit_rx,
                 
                 //avid_context:HashMap::default(),
-                threshold: 10000,
+                threshold: INSTANCE_THRESHOLD,
 
                 max_id: rbc_start_id, 
 
@@ -134,8 +128,8 @@ impl Context {
             };
 
             // Populate secret keys from config
-            for (id, sk_data) in config.sk_map.clone() {
-                c.sec_key_map.insert(id, sk_data.clone());
+            for (id, sk_data) in config.sk_map.iter() {
+                c.sec_key_map.insert(*id, SecretBytes::new(sk_data.clone()));
             }
 
             // Run the consensus context
@@ -146,9 +140,11 @@ impl Context {
     }
 
     pub async fn broadcast(&mut self, protmsg: ProtMsg) {
-        let sec_key_map = self.sec_key_map.clone();
-        for (replica, sec_key) in sec_key_map.into_iter() {
+        let replicas: Vec<Replica> = self.sec_key_map.keys().cloned().collect();
+        for replica in replicas {
+            let sec_key = self.sec_key_map.get(&replica).unwrap();
             let wrapper_msg = WrapperMsg::new(protmsg.clone(), self.myid, &sec_key.as_slice());
+            consensus::count_sent_message(&wrapper_msg);
             let cancel_handler: CancelHandler<Acknowledgement> = self.net_send.send(replica, wrapper_msg).await;
             self.add_cancel_handler(cancel_handler);
         }
@@ -159,6 +155,7 @@ impl Context {
     }
 
     pub async fn send(&mut self, replica: Replica, wrapper_msg: WrapperMsg<ProtMsg>) {
+        consensus::count_sent_message(&wrapper_msg);
         let cancel_handler: CancelHandler<Acknowledgement> =
             self.net_send.send(replica, wrapper_msg).await;
         self.add_cancel_handler(cancel_handler);
@@ -176,7 +173,7 @@ impl Context {
                 },
                 msg = self.net_recv.recv() => {
                     // Received messages are processed here
-                    log::trace!("Got a consensus message from the network: {:?}", msg);
+                    log::trace!("Got a consensus message from the network from {:?}", msg.as_ref().map(|msg| msg.sender));
                     if msg.is_none(){
                         log::error!("Got none from the consensus layer, most likely it closed");
                         return;
@@ -190,7 +187,8 @@ impl Context {
                     }
                     let req_msg = req_msg.unwrap();
                     if !req_msg.3{
-                        let acss_inst_id = self.max_id + 1;
+                        // The caller picks the instance id so that concurrent callers (e.g. multiple ACS instances) can match instances across parties
+                        let acss_inst_id = self.threshold*self.myid + req_msg.0;
                         self.max_id = acss_inst_id;
                         
                         self.init_asks(acss_inst_id, req_msg.1, req_msg.2, req_msg.4).await;
@@ -7,4 +7,3 @@ mod protocol;
 
 mod process;
 
-mod handlers;
\ No newline at end of file
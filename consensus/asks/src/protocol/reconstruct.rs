@@ -51,6 +51,7 @@ impl Context{
                         self.myid, 
                         sec_key
                     );
+                    consensus::count_sent_message(&wrapper_msg);
                     let cancel_handler = self.net_send.send(index, wrapper_msg).await;
                     self.add_cancel_handler(cancel_handler);
                 }
@@ -0,0 +1,256 @@
+use std::{
+    collections::HashMap,
+    net::{SocketAddr, SocketAddrV4},
+    time::Duration,
+};
+
+use anyhow::{anyhow, Result};
+use config::Node;
+
+use fnv::FnvHashMap;
+use network::{
+    plaintcp::CancelHandler,
+    Acknowledgement,
+};
+use tokio::{sync::{
+    mpsc::{UnboundedReceiver, Sender, Receiver, channel, unbounded_channel},
+    oneshot,
+}, time};
+use consensus::{HashKeys, SecretBytes, ReplicaSender};
+use tpke::ThresholdKey;
+use types::{Replica, WrapperMsg};
+
+use crate::{msg::ProtMsg, protocol::ABCState};
+
+pub struct Context {
+    /// Networking context
+    pub net_send: ReplicaSender<WrapperMsg<ProtMsg>>,
+    pub net_recv: UnboundedReceiver<WrapperMsg<ProtMsg>>,
+
+    /// Data context
+    pub num_nodes: usize,
+    pub myid: usize,
+    pub num_faults: usize,
+    _byz: bool,
+
+    /// Secret Key map
+    pub sec_key_map: HashMap<Replica, SecretBytes>,
+
+    /// Cancel Handlers
+    pub cancel_handlers: HashMap<u64, Vec<CancelHandler<Acknowledgement>>>,
+
+    /// Maximum number of transactions in the queue considered for a single epoch
+    pub batch_size: usize,
+    /// Time in milliseconds after which a party proposes a partially filled (or empty) batch
+    pub batch_timeout: u64,
+
+    /// Threshold decryption key. If present, proposals are encrypted before ACS and decrypted jointly after the agreed subset is fixed.
+    pub threshold_key: Option<ThresholdKey>,
+
+    exit_rx: oneshot::Receiver<()>,
+    acs_exit_tx: Option<oneshot::Sender<()>>,
+
+    pub abc_state: ABCState,
+
+    /// Client submission and ordered log channels. Ordering continues after the clients disconnect.
+    pub tx_recv: Option<Receiver<Vec<u8>>>,
+    pub log_out: Sender<(usize, Vec<Vec<u8>>)>,
+
+    /// Channels to interact with ACS
+    pub acs_req: Sender<(usize, Vec<u8>)>,
+    pub acs_out_recv: Receiver<(usize, Vec<(Replica, Vec<u8>)>)>,
+}
+
+impl Context {
+    pub fn spawn(
+        config: Node,
+        hash_keys: HashKeys,
+        batch_size: usize,
+        batch_timeout: u64,
+        tx_recv: Receiver<Vec<u8>>,
+        log_out: Sender<(usize, Vec<Vec<u8>>)>,
+        byz: bool
+    ) -> anyhow::Result<(oneshot::Sender<()>, Vec<Result<oneshot::Sender<()>>>)> {
+        Self::spawn_inner(config, hash_keys, batch_size, batch_timeout, None, tx_recv, log_out, byz)
+    }
+
+    // Censorship-resistant variant: parties cannot see the contents of a proposal before ACS fixes the set of included proposals.
+    pub fn spawn_with_encryption(
+        config: Node,
+        hash_keys: HashKeys,
+        batch_size: usize,
+        batch_timeout: u64,
+        threshold_key: ThresholdKey,
+        tx_recv: Receiver<Vec<u8>>,
+        log_out: Sender<(usize, Vec<Vec<u8>>)>,
+        byz: bool
+    ) -> anyhow::Result<(oneshot::Sender<()>, Vec<Result<oneshot::Sender<()>>>)> {
+        if !threshold_key.is_consistent(){
+            return Err(anyhow!("Secret key share does not match the published verification key of party {}", config.id));
+        }
+        Self::spawn_inner(config, hash_keys, batch_size, batch_timeout, Some(threshold_key), tx_recv, log_out, byz)
+    }
+
+    fn spawn_inner(
+        config: Node,
+        hash_keys: HashKeys,
+        batch_size: usize,
+        batch_timeout: u64,
+        threshold_key: Option<ThresholdKey>,
+        tx_recv: Receiver<Vec<u8>>,
+        log_out: Sender<(usize, Vec<Vec<u8>>)>,
+        byz: bool
+    ) -> anyhow::Result<(oneshot::Sender<()>, Vec<Result<oneshot::Sender<()>>>)> {
+        let mut consensus_addrs: FnvHashMap<Replica, SocketAddr> = FnvHashMap::default();
+
+        // Decryption shares are exchanged on the node's ports, ACS runs on a separate set of ports
+        let mut acs_config = config.clone();
+        let port_acs: u16 = 900;
+
+        for (replica, address) in config.net_map.iter() {
+            let address: SocketAddr = address.parse().expect("Unable to parse address");
+            let acs_address: SocketAddr = SocketAddr::new(address.ip(), address.port() + port_acs);
+            acs_config.net_map.insert(*replica, acs_address.to_string());
+            consensus_addrs.insert(*replica, SocketAddr::from(address.clone()));
+        }
+        log::info!("Consensus addresses: {:?}", consensus_addrs);
+        let my_port = consensus_addrs.get(&config.id).unwrap();
+        let my_address = to_socket_address("0.0.0.0", my_port.port());
+
+        // Setup networking
+        let (tx_net_to_consensus, rx_net_to_consensus) = unbounded_channel();
+        let consensus_net = consensus::spawn_replica_network(my_address, consensus_addrs.clone(), tx_net_to_consensus);
+        let (exit_tx, exit_rx) = oneshot::channel();
+
+        let (acs_req_send_channel, acs_req_recv_channel) = channel(10000);
+        let (acs_out_send_channel, acs_out_recv_channel) = channel(10000);
+
+        let (acs_exit_tx, statuses) = acs::Context::spawn_with_values(
+            acs_config,
+            hash_keys.clone(),
+            acs_req_recv_channel,
+            acs_out_send_channel,
+            false
+        )?;
+
+        tokio::spawn(async move {
+            let mut c = Context {
+                net_send: consensus_net,
+                net_recv: rx_net_to_consensus,
+
+                num_nodes: config.num_nodes,
+                myid: config.id,
+                num_faults: config.num_faults,
+                _byz: byz,
+
+                sec_key_map: HashMap::default(),
+                cancel_handlers: HashMap::default(),
+
+                batch_size: batch_size,
+                batch_timeout: batch_timeout,
+
+                threshold_key: threshold_key,
+
+                exit_rx: exit_rx,
+                acs_exit_tx: Some(acs_exit_tx),
+
+                abc_state: ABCState::new(),
+
+                tx_recv: Some(tx_recv),
+                log_out: log_out,
+
+                acs_req: acs_req_send_channel,
+                acs_out_recv: acs_out_recv_channel,
+            };
+
+            // Populate secret keys from config
+            for (id, sk_data) in config.sk_map.iter() {
+                c.sec_key_map.insert(*id, SecretBytes::new(sk_data.clone()));
+            }
+
+            // Run the atomic broadcast context
+            if let Err(e) = c.run().await {
+                log::error!("Atomic broadcast error: {}", e);
+            }
+        });
+        Ok((exit_tx, statuses))
+    }
+
+    pub async fn broadcast(&mut self, protmsg: ProtMsg) {
+        let replicas: Vec<Replica> = self.sec_key_map.keys().cloned().collect();
+        for replica in replicas {
+            let sec_key = self.sec_key_map.get(&replica).unwrap();
+            let wrapper_msg = WrapperMsg::new(protmsg.clone(), self.myid, &sec_key.as_slice());
+            consensus::count_sent_message(&wrapper_msg);
+            let cancel_handler: CancelHandler<Acknowledgement> = self.net_send.send(replica, wrapper_msg).await;
+            self.add_cancel_handler(cancel_handler);
+        }
+    }
+
+    pub fn add_cancel_handler(&mut self, canc: CancelHandler<Acknowledgement>) {
+        self.cancel_handlers.entry(0).or_default().push(canc);
+    }
+
+    pub async fn run(&mut self) -> Result<()>{
+        let mut interval = time::interval(Duration::from_millis(self.batch_timeout));
+        loop {
+            tokio::select! {
+                // Receive exit handlers
+                exit_val = &mut self.exit_rx => {
+                    exit_val.map_err(anyhow::Error::new)?;
+                    log::info!("Termination signal received by the server. Exiting.");
+                    if let Some(acs_exit_tx) = self.acs_exit_tx.take(){
+                        let _status = acs_exit_tx.send(());
+                    }
+                    break
+                },
+                msg = self.net_recv.recv() => {
+                    // Received messages are processed here
+                    log::trace!("Got a consensus message from the network: {:?}", msg);
+                    let msg = msg.ok_or_else(||
+                        anyhow!("Networking layer has closed")
+                    )?;
+                    self.process_msg(msg).await;
+                },
+                tx = recv_if_present(&mut self.tx_recv) => {
+                    match tx {
+                        Some(tx) => {
+                            log::debug!("Received client transaction of {} bytes", tx.len());
+                            self.submit_transaction(tx).await;
+                        },
+                        None => {
+                            // Other parties' proposals still need this party in ACS and decryption
+                            log::info!("Client submission channel has closed, continuing without new transactions");
+                            self.tx_recv = None;
+                        }
+                    }
+                },
+                acs_output = self.acs_out_recv.recv() => {
+                    let acs_output = acs_output.ok_or_else(||
+                        anyhow!("Networking layer has closed")
+                    )?;
+                    log::debug!("Received ACS output for epoch {}", acs_output.0);
+                    self.process_acs_output(acs_output.0, acs_output.1).await;
+                },
+                _ = interval.tick() => {
+                    // Keep epochs moving even without enough transactions to fill a batch
+                    self.propose_batch(true).await;
+                }
+            };
+        }
+        Ok(())
+    }
+}
+
+// Receives from an optional channel. Channels that are not configured never yield a value.
+async fn recv_if_present<T>(channel: &mut Option<Receiver<T>>) -> Option<T> {
+    match channel {
+        Some(recv) => recv.recv().await,
+        None => futures::future::pending().await,
+    }
+}
+
+pub fn to_socket_address(ip_str: &str, port: u16) -> SocketAddr {
+    let addr = SocketAddrV4::new(ip_str.parse().unwrap(), port);
+    addr.into()
+}
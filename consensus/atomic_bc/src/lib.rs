@@ -0,0 +1,10 @@
+mod context;
+pub use context::*;
+
+pub mod protocol;
+pub use protocol::*;
+
+mod msg;
+pub use msg::*;
+
+mod process;
@@ -0,0 +1,17 @@
+use serde::{Serialize, Deserialize};
+use tpke::DecryptionShare;
+use types::Replica;
+
+// Proposal of a party for a single epoch, disseminated through ACS
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Batch{
+    pub epoch: usize,
+    pub txs: Vec<Vec<u8>>
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum ProtMsg{
+    // Decryption shares for the encrypted proposals agreed in an epoch
+    // epoch, [(proposer, decryption share)]
+    DecryptionShares(usize, Vec<(Replica, DecryptionShare)>),
+}
@@ -0,0 +1,143 @@
+use tpke::{Ciphertext, DecryptionShare};
+use types::Replica;
+
+use crate::{Context, msg::ProtMsg};
+
+use super::{DecryptionState, MAX_EPOCHS_AHEAD};
+
+impl Context{
+    // Called once ACS fixes the encrypted proposals of an epoch. Releases this party's decryption share for every valid ciphertext.
+    pub async fn start_decryption(&mut self, epoch: usize, values: Vec<(Replica, Vec<u8>)>){
+        let threshold_key = self.threshold_key.clone().unwrap();
+        let mut ciphertexts = Vec::new();
+        let mut dec_shares = Vec::new();
+        for (rep, payload) in values.into_iter(){
+            let ciphertext: Ciphertext = match bincode::deserialize(payload.as_slice()){
+                Ok(ciphertext) => ciphertext,
+                Err(_) => {
+                    log::warn!("Party {} proposed a malformed ciphertext in epoch {}, skipping it", rep, epoch);
+                    ciphertexts.push((rep, None));
+                    continue;
+                }
+            };
+            // Every honest party reaches the same verdict on a ciphertext, so invalid proposals are dropped consistently
+            if ciphertext.label != epoch.to_be_bytes().to_vec(){
+                log::warn!("Party {} proposed a ciphertext labelled for another epoch in epoch {}, skipping it", rep, epoch);
+                ciphertexts.push((rep, None));
+                continue;
+            }
+            match tpke::decryption_share(&threshold_key, &ciphertext){
+                Some(share) => {
+                    dec_shares.push((rep, share));
+                    ciphertexts.push((rep, Some(ciphertext)));
+                },
+                None => {
+                    log::warn!("Party {} proposed an invalid ciphertext in epoch {}, skipping it", rep, epoch);
+                    ciphertexts.push((rep, None));
+                }
+            }
+        }
+
+        let dec_state = self.abc_state.decryption_states.entry(epoch).or_insert_with(DecryptionState::new);
+        dec_state.ciphertexts = Some(ciphertexts);
+        let unverified_shares = std::mem::take(&mut dec_state.unverified_shares);
+
+        log::info!("Broadcasting decryption shares for {} proposals of epoch {}", dec_shares.len(), epoch);
+        self.broadcast(ProtMsg::DecryptionShares(epoch, dec_shares)).await;
+
+        for (sender, shares) in unverified_shares.into_iter(){
+            self.verify_decryption_shares(epoch, sender, shares);
+        }
+        self.check_decryption(epoch).await;
+    }
+
+    pub async fn process_decryption_shares(&mut self, epoch: usize, sender: Replica, shares: Vec<(Replica, DecryptionShare)>){
+        if epoch < self.abc_state.epoch && !self.abc_state.decryption_states.contains_key(&epoch){
+            // Epoch already delivered
+            return;
+        }
+        if epoch > self.abc_state.epoch + MAX_EPOCHS_AHEAD{
+            log::warn!("Party {} sent decryption shares for epoch {}, too far ahead of epoch {}", sender, epoch, self.abc_state.epoch);
+            return;
+        }
+        let dec_state = self.abc_state.decryption_states.entry(epoch).or_insert_with(DecryptionState::new);
+        if dec_state.terminated || !dec_state.share_senders.insert(sender){
+            return;
+        }
+        if dec_state.ciphertexts.is_none(){
+            dec_state.unverified_shares.push((sender, shares));
+            return;
+        }
+        self.verify_decryption_shares(epoch, sender, shares);
+        self.check_decryption(epoch).await;
+    }
+
+    fn verify_decryption_shares(&mut self, epoch: usize, sender: Replica, shares: Vec<(Replica, DecryptionShare)>){
+        let threshold_key = self.threshold_key.as_ref().unwrap();
+        let dec_state = self.abc_state.decryption_states.get_mut(&epoch).unwrap();
+        let ciphertexts = dec_state.ciphertexts.as_ref().unwrap();
+        for (proposer, share) in shares.into_iter(){
+            if share.party != sender{
+                log::warn!("Party {} sent a decryption share on behalf of party {}", sender, share.party);
+                continue;
+            }
+            let ciphertext = ciphertexts.iter().find(|(rep, _)| *rep == proposer);
+            let ciphertext = match ciphertext{
+                Some((_, Some(ciphertext))) => ciphertext,
+                _ => {
+                    log::warn!("Party {} sent a decryption share for proposal {} which is not a valid ciphertext of epoch {}", sender, proposer, epoch);
+                    continue;
+                }
+            };
+            if !tpke::verify_decryption_share(threshold_key, ciphertext, &share){
+                log::warn!("Decryption share of party {} for proposal {} in epoch {} failed verification", sender, proposer, epoch);
+                continue;
+            }
+            dec_state.verified_shares.entry(proposer).or_default().push(share);
+        }
+    }
+
+    // Decrypts every proposal with t+1 verified shares and delivers the epoch once all valid proposals are decrypted
+    pub async fn check_decryption(&mut self, epoch: usize){
+        let threshold_key = self.threshold_key.as_ref().unwrap();
+        let dec_state = self.abc_state.decryption_states.get_mut(&epoch).unwrap();
+        if dec_state.terminated || dec_state.ciphertexts.is_none(){
+            return;
+        }
+        let ciphertexts = dec_state.ciphertexts.as_ref().unwrap();
+        for (rep, ciphertext) in ciphertexts.iter(){
+            if ciphertext.is_none() || dec_state.decrypted.contains_key(rep){
+                continue;
+            }
+            let shares = match dec_state.verified_shares.get(rep){
+                Some(shares) => shares,
+                None => continue
+            };
+            if shares.len() < self.num_faults+1{
+                continue;
+            }
+            match tpke::combine(threshold_key, ciphertext.as_ref().unwrap(), shares){
+                Some(plaintext) => {
+                    dec_state.decrypted.insert(*rep, plaintext);
+                },
+                None => {
+                    log::error!("Unable to combine decryption shares for proposal {} in epoch {}", rep, epoch);
+                }
+            }
+        }
+
+        let num_valid = ciphertexts.iter().filter(|(_, ciphertext)| ciphertext.is_some()).count();
+        if dec_state.decrypted.len() < num_valid{
+            return;
+        }
+        dec_state.terminated = true;
+        let mut values = Vec::new();
+        for (rep, _) in ciphertexts.iter(){
+            if let Some(plaintext) = dec_state.decrypted.remove(rep){
+                values.push((*rep, plaintext));
+            }
+        }
+        log::info!("Decrypted {} proposals of epoch {}", values.len(), epoch);
+        self.deliver_epoch(epoch, values).await;
+    }
+}
@@ -0,0 +1,119 @@
+use crypto::hash::do_hash;
+use rand::seq::SliceRandom;
+use types::Replica;
+
+use crate::{Context, msg::Batch};
+
+use super::MAX_QUEUED_TXS;
+
+impl Context{
+    pub async fn submit_transaction(&mut self, tx: Vec<u8>){
+        if self.abc_state.committed_txs.contains(&do_hash(tx.as_slice())){
+            log::debug!("Transaction already committed to the log, ignoring it");
+            return;
+        }
+        if self.abc_state.tx_queue.len() >= MAX_QUEUED_TXS{
+            log::warn!("Transaction queue is full with {} transactions, dropping the submission", MAX_QUEUED_TXS);
+            return;
+        }
+        self.abc_state.tx_queue.push_back(tx);
+        self.propose_batch(false).await;
+    }
+
+    // Proposes a batch for the current epoch. Without force, a batch is proposed only once the queue holds batch_size transactions.
+    pub async fn propose_batch(&mut self, force: bool){
+        if self.abc_state.proposal_in_flight{
+            return;
+        }
+        if !force && self.abc_state.tx_queue.len() < self.batch_size{
+            return;
+        }
+        // Like HoneyBadgerBFT, propose a random subset of the head of the queue so that parties mostly propose disjoint transactions
+        let head: Vec<Vec<u8>> = self.abc_state.tx_queue.iter().take(self.batch_size).cloned().collect();
+        let subset_size = std::cmp::max(self.batch_size/self.num_nodes, 1);
+        let mut rng = rand::thread_rng();
+        let txs: Vec<Vec<u8>> = head.choose_multiple(&mut rng, subset_size).cloned().collect();
+
+        let epoch = self.abc_state.epoch;
+        log::info!("Proposing batch of {} transactions for epoch {}", txs.len(), epoch);
+        let batch = Batch{
+            epoch: epoch,
+            txs: txs
+        };
+        let mut ser_batch = bincode::serialize(&batch).unwrap();
+        if let Some(threshold_key) = self.threshold_key.as_ref(){
+            // Bind the ciphertext to the epoch so that it cannot be replayed in a later epoch
+            let ciphertext = tpke::encrypt(&threshold_key.public_key, ser_batch.as_slice(), &epoch.to_be_bytes());
+            ser_batch = bincode::serialize(&ciphertext).unwrap();
+        }
+        let status = self.acs_req.send((epoch, ser_batch)).await;
+        if status.is_err(){
+            log::error!("Error sending batch to the ACS queue for epoch {}", epoch);
+            return;
+        }
+        self.abc_state.proposal_in_flight = true;
+    }
+
+    pub async fn process_acs_output(&mut self, epoch: usize, values: Vec<(Replica, Vec<u8>)>){
+        log::info!("ACS for epoch {} terminated with proposals from {} parties", epoch, values.len());
+        if epoch < self.abc_state.epoch{
+            return;
+        }
+        if self.threshold_key.is_some(){
+            // Proposals are only revealed after the agreed subset is fixed
+            self.start_decryption(epoch, values).await;
+            return;
+        }
+        self.deliver_epoch(epoch, values).await;
+    }
+
+    // Appends the plaintext proposals of an epoch to the log once all previous epochs are delivered
+    pub async fn deliver_epoch(&mut self, epoch: usize, values: Vec<(Replica, Vec<u8>)>){
+        self.abc_state.pending_outputs.insert(epoch, values);
+
+        // Epochs are appended to the log strictly in order
+        while self.abc_state.pending_outputs.contains_key(&self.abc_state.epoch){
+            let epoch = self.abc_state.epoch;
+            let values = self.abc_state.pending_outputs.remove(&epoch).unwrap();
+            self.abc_state.decryption_states.remove(&epoch);
+
+            let mut ordered_txs = Vec::new();
+            let mut committed_hashes = Vec::new();
+            for (rep, payload) in values.into_iter(){
+                let batch: Batch = match bincode::deserialize(payload.as_slice()){
+                    Ok(batch) => batch,
+                    Err(_) => {
+                        log::warn!("Party {} proposed a malformed batch in epoch {}, skipping it", rep, epoch);
+                        continue;
+                    }
+                };
+                if batch.epoch != epoch{
+                    log::warn!("Party {} proposed a batch for epoch {} in epoch {}, skipping it", rep, batch.epoch, epoch);
+                    continue;
+                }
+                for tx in batch.txs.into_iter(){
+                    // Drop duplicates proposed by several parties or committed in earlier epochs
+                    let tx_hash = do_hash(tx.as_slice());
+                    if self.abc_state.committed_txs.insert(tx_hash){
+                        committed_hashes.push(tx_hash);
+                        ordered_txs.push(tx);
+                    }
+                }
+            }
+
+            let committed_txs = &self.abc_state.committed_txs;
+            self.abc_state.tx_queue.retain(|tx| !committed_txs.contains(&do_hash(tx.as_slice())));
+            self.abc_state.record_committed(committed_hashes);
+            self.abc_state.log_length += ordered_txs.len();
+            log::info!("Epoch {} committed {} transactions, log length {}", epoch, ordered_txs.len(), self.abc_state.log_length);
+
+            let status = self.log_out.send((epoch, ordered_txs)).await;
+            if status.is_err(){
+                log::error!("Error sending ordered transactions of epoch {} to the log channel", epoch);
+            }
+            self.abc_state.epoch = epoch+1;
+            self.abc_state.proposal_in_flight = false;
+        }
+        self.propose_batch(false).await;
+    }
+}
@@ -0,0 +1,6 @@
+pub mod state;
+pub use state::*;
+
+mod epoch;
+
+mod decrypt;
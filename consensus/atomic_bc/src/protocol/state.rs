@@ -0,0 +1,84 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use crypto::hash::Hash;
+use tpke::{Ciphertext, DecryptionShare};
+use types::Replica;
+
+/// Epochs whose committed transactions are remembered to drop resubmissions. Older hashes are pruned.
+pub const COMMITTED_EPOCHS_KEPT: usize = 100;
+/// Epochs ahead of the current one for which decryption shares are buffered
+pub const MAX_EPOCHS_AHEAD: usize = 10;
+/// Client transactions waiting to be proposed. Further submissions are dropped until the queue drains.
+pub const MAX_QUEUED_TXS: usize = 100_000;
+
+pub struct ABCState{
+    // Epoch whose ACS instance this party currently participates in
+    pub epoch: usize,
+    pub proposal_in_flight: bool,
+
+    // Client transactions not yet committed to the log
+    pub tx_queue: VecDeque<Vec<u8>>,
+    pub committed_txs: HashSet<Hash>,
+    // Hashes committed in each of the last COMMITTED_EPOCHS_KEPT epochs, oldest first
+    pub committed_by_epoch: VecDeque<Vec<Hash>>,
+
+    // ACS outputs of epochs that terminated before all previous epochs were delivered
+    pub pending_outputs: HashMap<usize, Vec<(Replica, Vec<u8>)>>,
+    pub log_length: usize,
+
+    // Joint decryption of the encrypted proposals of each epoch
+    pub decryption_states: HashMap<usize, DecryptionState>,
+}
+
+impl ABCState{
+    // Remembers the transactions of a delivered epoch and forgets those of epochs that fell out of the window
+    pub fn record_committed(&mut self, hashes: Vec<Hash>){
+        self.committed_by_epoch.push_back(hashes);
+        while self.committed_by_epoch.len() > COMMITTED_EPOCHS_KEPT{
+            for hash in self.committed_by_epoch.pop_front().unwrap(){
+                self.committed_txs.remove(&hash);
+            }
+        }
+    }
+
+    pub fn new()-> ABCState{
+        ABCState {
+            epoch: 1,
+            proposal_in_flight: false,
+
+            tx_queue: VecDeque::new(),
+            committed_txs: HashSet::default(),
+            committed_by_epoch: VecDeque::new(),
+
+            pending_outputs: HashMap::default(),
+            log_length: 0,
+
+            decryption_states: HashMap::default(),
+        }
+    }
+}
+
+pub struct DecryptionState{
+    // Ciphertexts in the ACS output, None for proposals with an invalid ciphertext. Unknown until ACS terminates locally.
+    pub ciphertexts: Option<Vec<(Replica, Option<Ciphertext>)>>,
+    // Verified decryption shares per proposer
+    pub verified_shares: HashMap<Replica, Vec<DecryptionShare>>,
+    // Shares received before the local ACS output, verified once the ciphertexts are known
+    pub unverified_shares: Vec<(Replica, Vec<(Replica, DecryptionShare)>)>,
+    pub share_senders: HashSet<Replica>,
+    pub decrypted: HashMap<Replica, Vec<u8>>,
+    pub terminated: bool,
+}
+
+impl DecryptionState{
+    pub fn new()-> DecryptionState{
+        DecryptionState {
+            ciphertexts: None,
+            verified_shares: HashMap::default(),
+            unverified_shares: Vec::new(),
+            share_senders: HashSet::default(),
+            decrypted: HashMap::default(),
+            terminated: false,
+        }
+    }
+}
@@ -9,7 +9,7 @@ use config::Node;
 
 use fnv::FnvHashMap;
 use network::{
-    plaintcp::{CancelHandler, TcpReceiver, TcpReliableSender},
+    plaintcp::CancelHandler,
     Acknowledgement,
 };
 use tokio::sync::{
@@ -21,13 +21,13 @@ use types::{Replica, WrapperMsg};
 
 use crate::AVIDState;
 
-use super::{Handler};
 use super::{ProtMsg};
+use consensus::{HashKeys, SecretBytes, ReplicaSender};
 use crypto::{aes_hash::HashState, hash::Hash};
 
 pub struct Context {
     /// Networking context
-    pub net_send: TcpReliableSender<Replica, WrapperMsg<ProtMsg>, Acknowledgement>,
+    pub net_send: ReplicaSender<WrapperMsg<ProtMsg>>,
     pub net_recv: UnboundedReceiver<WrapperMsg<ProtMsg>>,
 
     /// Data context
@@ -37,7 +37,7 @@ pub struct Context {
     _byz: bool,
 
     /// Secret Key map
-    pub sec_key_map: HashMap<Replica, Vec<u8>>,
+    pub sec_key_map: HashMap<Replica, SecretBytes>,
 
     /// Hardware acceleration context
     pub hash_context: HashState,
@@ -64,6 +64,7 @@ pub struct Context {
 impl Context {
     pub fn spawn(
         config: Node,
+        hash_keys: HashKeys,
         input_msgs: Receiver<Vec<(Replica,Option<Vec<u8>>)>>, 
         output_msgs: Sender<(usize, Replica,Option<Vec<u8>>)>, 
         _byz: bool
@@ -81,22 +82,12 @@ impl Context {
         
         // Setup networking
         let (tx_net_to_consensus, rx_net_to_consensus) = unbounded_channel();
-        TcpReceiver::<Acknowledgement, WrapperMsg<ProtMsg>, _>::spawn(
-            my_address,
-            Handler::new(tx_net_to_consensus),
-        );
-
-        let consensus_net = TcpReliableSender::<Replica, WrapperMsg<ProtMsg>, Acknowledgement>::with_peers(
-            consensus_addrs.clone(),
-        );
+        let consensus_net = consensus::spawn_replica_network(my_address, consensus_addrs.clone(), tx_net_to_consensus);
 
         let (exit_tx, exit_rx) = oneshot::channel();
 
         // Keyed AES ciphers
-        let key0 = [5u8; 16];
-        let key1 = [29u8; 16];
-        let key2 = [23u8; 16];
-        let hashstate = HashState::new(key0, key1, key2);
+        let hashstate = hash_keys.hash_state();
 
         let threshold:usize = 10000;
         let rbc_start_id = threshold*config.id;
@@ -126,8 +117,8 @@ impl Context {
             };
 
             // Populate secret keys from config
-            for (id, sk_data) in config.sk_map.clone() {
-                c.sec_key_map.insert(id, sk_data.clone());
+            for (id, sk_data) in config.sk_map.iter() {
+                c.sec_key_map.insert(*id, SecretBytes::new(sk_data.clone()));
             }
 
             // Run the consensus context
@@ -140,9 +131,11 @@ impl Context {
     }
 
     pub async fn broadcast(&mut self, protmsg: ProtMsg) {
-        let sec_key_map = self.sec_key_map.clone();
-        for (replica, sec_key) in sec_key_map.into_iter() {
+        let replicas: Vec<Replica> = self.sec_key_map.keys().cloned().collect();
+        for replica in replicas {
+            let sec_key = self.sec_key_map.get(&replica).unwrap();
             let wrapper_msg = WrapperMsg::new(protmsg.clone(), self.myid, &sec_key.as_slice());
+            consensus::count_sent_message(&wrapper_msg);
             let cancel_handler: CancelHandler<Acknowledgement> = self.net_send.send(replica, wrapper_msg).await;
             self.add_cancel_handler(cancel_handler);
         }
@@ -153,6 +146,7 @@ impl Context {
     }
 
     pub async fn send(&mut self, replica: Replica, wrapper_msg: WrapperMsg<ProtMsg>) {
+        consensus::count_sent_message(&wrapper_msg);
         let cancel_handler: CancelHandler<Acknowledgement> =
             self.net_send.send(replica, wrapper_msg).await;
         self.add_cancel_handler(cancel_handler);
@@ -171,7 +165,7 @@ impl Context {
                 },
                 msg = self.net_recv.recv() => {
                     // Received messages are processed here
-                    log::trace!("Got a consensus message from the network: {:?}", msg);
+                    log::trace!("Got a consensus message from the network from {:?}", msg.as_ref().map(|msg| msg.sender));
                     let msg = msg.ok_or_else(||
                         anyhow!("Networking layer has closed")
                     )?;
@@ -19,7 +19,4 @@ pub use protocol::*;
 // mod rbc_context;
 // pub use rbc_context::*;
 
-pub mod handlers;
-pub use handlers::*;
-
 mod process;
\ No newline at end of file
@@ -59,8 +59,9 @@ impl Context {
         }
         
         let concise_root = master_mt.root();
-        let sec_key_map = self.sec_key_map.clone();
-        for (replica, sec_key) in sec_key_map.into_iter() {
+        let replicas: Vec<Replica> = self.sec_key_map.keys().cloned().collect();
+        for replica in replicas {
+            let sec_key = self.sec_key_map.get(&replica).unwrap();
             // TODO: Encryption
             let avid_shards = party_wise_share_map.get(&replica).unwrap().clone();
             
@@ -72,6 +73,7 @@ impl Context {
             
             let protocol_msg = ProtMsg::Init(avid_msg, instance_id);
             let wrapper_msg = WrapperMsg::new(protocol_msg.clone(), self.myid, &sec_key.as_slice());
+            consensus::count_sent_message(&wrapper_msg);
             let cancel_handler: CancelHandler<Acknowledgement> = self.net_send.send(replica, wrapper_msg).await;
             self.add_cancel_handler(cancel_handler);
         }
@@ -107,6 +109,7 @@ impl Context {
             let protocol_msg = ProtMsg::Echo(index_msg, instance_id);
             let sec_key = self.sec_key_map.get(&recipient).unwrap().clone();
             let wrapper_msg = WrapperMsg::new(protocol_msg.clone(), self.myid, &sec_key.as_slice());
+            consensus::count_sent_message(&wrapper_msg);
             let cancel_handler: CancelHandler<Acknowledgement> = self.net_send.send(recipient, wrapper_msg).await;
             self.add_cancel_handler(cancel_handler);
         }
@@ -7,7 +7,7 @@ use config::Node;
 
 use fnv::FnvHashMap;
 use network::{
-    plaintcp::{CancelHandler, TcpReceiver, TcpReliableSender},
+    plaintcp::CancelHandler,
     Acknowledgement,
 };
 use tokio::sync::{
@@ -17,15 +17,16 @@ use tokio::sync::{
 // use tokio_util::time::DelayQueue;
 use types::{Replica, WrapperMsg};
 
-use consensus::{LargeFieldSer};
+use consensus::{HashKeys, LargeFieldSer, SecretBytes, ReplicaSender};
 
 use crypto::{aes_hash::HashState};
+use tsig::TsigKey;
 
-use crate::{msg::{ProtMsg, Handler}, protocol::{RoundStateBin, Val}};
+use crate::{msg::ProtMsg, protocol::{RoundStateBin, Val}};
 
 pub struct Context {
     /// Networking context
-    pub net_send: TcpReliableSender<Replica, WrapperMsg<ProtMsg>, Acknowledgement>,
+    pub net_send: ReplicaSender<WrapperMsg<ProtMsg>>,
     pub net_recv: UnboundedReceiver<WrapperMsg<ProtMsg>>,
     
     /// Data context
@@ -35,7 +36,7 @@ pub struct Context {
     _byz: bool,
 
     /// Secret Key map
-    pub sec_key_map: HashMap<Replica, Vec<u8>>,
+    pub sec_key_map: HashMap<Replica, SecretBytes>,
 
     /// Hardware acceleration context
     pub hash_context: HashState,
@@ -47,6 +48,10 @@ pub struct Context {
     /// State for Binary AA
     pub round_state: HashMap<usize,(HashMap<usize,RoundStateBin>, HashSet<usize>)>,
     pub coin_shares: HashMap<usize, VecDeque<LargeFieldSer>>,
+    /// If present, coins are threshold BLS signatures on the coin name instead of pre-shared coin shares
+    pub coin_key: Option<TsigKey>,
+    /// Session of this run, bound into the coin names so that coins do not repeat across runs under the same key
+    pub coin_session: Vec<u8>,
     pub terminated_rounds: HashSet<usize>,
 
     /// Input and output request channels
@@ -57,9 +62,38 @@ pub struct Context {
 
 impl Context {
     pub fn spawn(config: Node,
+        hash_keys: HashKeys,
         input_reqs: Receiver<(usize, Val, Vec<LargeFieldSer>)>, 
         output_shares: Sender<(usize,Val)>,
         byz: bool) -> anyhow::Result<oneshot::Sender<()>> {
+        Self::spawn_inner(config, hash_keys, input_reqs, output_shares, None, Vec::new(), byz)
+    }
+
+    // Publicly verifiable common coin from a threshold signing key. Coin shares in the input requests are ignored.
+    // All parties of a run must use the same coin session, and no two runs under one key may share it.
+    pub fn spawn_with_threshold_coin(config: Node,
+        hash_keys: HashKeys,
+        input_reqs: Receiver<(usize, Val, Vec<LargeFieldSer>)>, 
+        output_shares: Sender<(usize,Val)>,
+        coin_key: TsigKey,
+        coin_session: Vec<u8>,
+        byz: bool) -> anyhow::Result<oneshot::Sender<()>> {
+        if coin_key.myid != config.id || !coin_key.is_consistent(){
+            return Err(anyhow::anyhow!("Coin key share does not belong to party {}", config.id));
+        }
+        if coin_session.is_empty(){
+            return Err(anyhow::anyhow!("Threshold coin needs a session id"));
+        }
+        Self::spawn_inner(config, hash_keys, input_reqs, output_shares, Some(coin_key), coin_session, byz)
+    }
+
+    fn spawn_inner(config: Node,
+        hash_keys: HashKeys,
+        input_reqs: Receiver<(usize, Val, Vec<LargeFieldSer>)>, 
+        output_shares: Sender<(usize,Val)>,
+        coin_key: Option<TsigKey>,
+        coin_session: Vec<u8>,
+        byz: bool) -> anyhow::Result<oneshot::Sender<()>> {
         // Add a separate configuration for RBC service. 
 
         let mut consensus_addrs: FnvHashMap<Replica, SocketAddr> = FnvHashMap::default();
@@ -72,22 +106,12 @@ impl Context {
 
         // Setup networking
         let (tx_net_to_consensus, rx_net_to_consensus) = unbounded_channel();
-        TcpReceiver::<Acknowledgement, WrapperMsg<ProtMsg>, _>::spawn(
-            my_address,
-            Handler::new(tx_net_to_consensus),
-        );
-
-        let consensus_net = TcpReliableSender::<Replica, WrapperMsg<ProtMsg>, Acknowledgement>::with_peers(
-            consensus_addrs.clone(),
-        );
+        let consensus_net = consensus::spawn_replica_network(my_address, consensus_addrs.clone(), tx_net_to_consensus);
         
         let (exit_tx, exit_rx) = oneshot::channel();
 
         // Keyed AES ciphers
-        let key0 = [5u8; 16];
-        let key1 = [29u8; 16];
-        let key2 = [23u8; 16];
-        let hashstate = HashState::new(key0, key1, key2);
+        let hashstate = hash_keys.hash_state();
 
         tokio::spawn(async move {
             let mut c = Context {
@@ -106,14 +130,16 @@ impl Context {
                 round_state: HashMap::default(),
                 terminated_rounds: HashSet::default(),
                 coin_shares: HashMap::default(),
+                coin_key: coin_key,
+                coin_session: coin_session,
 
                 inp_bin_ba_requests: input_reqs,
                 out_bin_ba_values: output_shares
             };
 
             // Populate secret keys from config
-            for (id, sk_data) in config.sk_map.clone() {
-                c.sec_key_map.insert(id, sk_data.clone());
+            for (id, sk_data) in config.sk_map.iter() {
+                c.sec_key_map.insert(*id, SecretBytes::new(sk_data.clone()));
             }
 
             // Run the consensus context
@@ -124,9 +150,11 @@ impl Context {
     }
 
     pub async fn broadcast(&mut self, protmsg: ProtMsg) {
-        let sec_key_map = self.sec_key_map.clone();
-        for (replica, sec_key) in sec_key_map.into_iter() {
+        let replicas: Vec<Replica> = self.sec_key_map.keys().cloned().collect();
+        for replica in replicas {
+            let sec_key = self.sec_key_map.get(&replica).unwrap();
             let wrapper_msg = WrapperMsg::new(protmsg.clone(), self.myid, &sec_key.as_slice());
+            consensus::count_sent_message(&wrapper_msg);
             let cancel_handler: CancelHandler<Acknowledgement> = self.net_send.send(replica, wrapper_msg).await;
             self.add_cancel_handler(cancel_handler);
         }
@@ -137,6 +165,7 @@ impl Context {
     }
 
     pub async fn send(&mut self, replica: Replica, wrapper_msg: WrapperMsg<ProtMsg>) {
+        consensus::count_sent_message(&wrapper_msg);
         let cancel_handler: CancelHandler<Acknowledgement> =
             self.net_send.send(replica, wrapper_msg).await;
         self.add_cancel_handler(cancel_handler);
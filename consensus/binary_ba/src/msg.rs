@@ -1,9 +1,7 @@
-use async_trait::async_trait;
 use consensus::LargeFieldSer;
-use network::Acknowledgement;
 use serde::{Serialize, Deserialize};
-use tokio::sync::mpsc::UnboundedSender;
-use types::{Val, Replica, WrapperMsg};
+use tsig::PartialSignature;
+use types::{Val, Replica};
 
 #[derive(Debug,Serialize,Deserialize,Clone)]
 pub enum ProtMsg{
@@ -13,37 +11,7 @@ pub enum ProtMsg{
     FinBinAAEcho3(Val,Replica,usize,usize),
     
     // Leader Round, BBA number, Signature, Sender
-    BBACoin(usize,usize,LargeFieldSer,Replica)
+    BBACoin(usize,usize,LargeFieldSer,Replica),
+    // Leader Round, BBA number, BLS partial signature on the coin name, Sender
+    BBASigCoin(usize,usize,PartialSignature,Replica)
 }
-
-use futures_util::SinkExt;
-
-#[derive(Debug, Clone)]
-pub struct Handler {
-    consensus_tx: UnboundedSender<WrapperMsg<ProtMsg>>,
-}
-
-impl Handler {
-    pub fn new(consensus_tx: UnboundedSender<WrapperMsg<ProtMsg>>) -> Self {
-        Self { consensus_tx }
-    }
-}
-
-#[async_trait]
-impl network::Handler<Acknowledgement, WrapperMsg<ProtMsg>> for Handler {
-    async fn dispatch(&self, msg: WrapperMsg<ProtMsg>, writer: &mut network::Writer<Acknowledgement>) {
-        // Forward the message
-        let status = self.consensus_tx
-            .send(msg);
-        if status.is_err(){
-            log::error!("Failed to send consensus message to the channel because of {:?}", status.err().unwrap());
-        }
-        // Acknowledge
-        let status = writer
-            .send(Acknowledgement::Pong)
-            .await;
-        if status.is_err(){
-            log::error!("Failed to send consensus message to the channel because of {:?}", status.err().unwrap());
-        }
-    }
-}
\ No newline at end of file
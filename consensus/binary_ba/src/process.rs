@@ -40,6 +40,10 @@ impl Context{
                 },
                 ProtMsg::BBACoin(leader_round,baa_round, signature, sender) =>{
                     self.process_coin_share(signature, sender, leader_round,baa_round).await;
+                },
+                ProtMsg::BBASigCoin(leader_round,baa_round, partial_sig, _sender) =>{
+                    // Partial signatures are attributed to the authenticated sender
+                    self.process_sig_coin_share(partial_sig, wrapper_msg.sender, leader_round,baa_round).await;
                 }
             }
         }
@@ -1,6 +1,7 @@
 use std::collections::{HashMap, HashSet};
 
 use consensus::{LargeField, LargeFieldSer};
+use tsig::PartialSignature;
 use types::{Replica, Val};
 
 use lambdaworks_math::{traits::ByteConversion};
@@ -65,7 +66,12 @@ impl Context{
                 if echo3.is_some(){
                     msgs_to_send.push(ProtMsg::FinBinAAEcho3(echo3.unwrap(), self.myid, instance_id,baa_round));
                     let term = round_state.add_echo3(echo3.unwrap(), self.myid);
-                    if term && !round_state.contains_coin(self.myid) && self.coin_shares.contains_key(&instance_id){
+                    if term && !round_state.contains_coin(self.myid) && self.coin_key.is_some(){
+                        let (prot_msg, coin_terminate) = round_state.threshold_coin_share(self.coin_key.as_ref().unwrap(), &self.coin_session, self.myid, instance_id, baa_round);
+                        msgs_to_send.push(prot_msg);
+                        terminate = coin_terminate;
+                    }
+                    else if term && !round_state.contains_coin(self.myid) && self.coin_shares.contains_key(&instance_id){
                         // Create partial signature and broadcast
                         if !self.coin_shares.contains_key(&instance_id){
                             return;
@@ -138,7 +144,12 @@ impl Context{
                     let term = round_state.add_echo3(echo3.unwrap(), self.myid);
                     msgs_to_send.push(ProtMsg::FinBinAAEcho3(echo3.unwrap(), self.myid, instance_id,baa_round));
                     log::info!("Sending echo3 message {} for lround {}, bround {}",echo3.unwrap(),instance_id,baa_round);
-                    if term && !round_state.contains_coin(self.myid) && self.coin_shares.contains_key(&instance_id){
+                    if term && !round_state.contains_coin(self.myid) && self.coin_key.is_some(){
+                        let (prot_msg, coin_terminate) = round_state.threshold_coin_share(self.coin_key.as_ref().unwrap(), &self.coin_session, self.myid, instance_id, baa_round);
+                        msgs_to_send.push(prot_msg);
+                        terminate = coin_terminate;
+                    }
+                    else if term && !round_state.contains_coin(self.myid) && self.coin_shares.contains_key(&instance_id){
                         // Create partial signature and broadcast
                         // Create and broadcast coin
                         let coin_shares = self.coin_shares.get_mut(&instance_id).unwrap();
@@ -208,7 +219,12 @@ impl Context{
                     msg,
                     echo3_sender
                 );
-                if term && !round_state.contains_coin(self.myid) && self.coin_shares.contains_key(&instance_id){
+                if term && !round_state.contains_coin(self.myid) && self.coin_key.is_some(){
+                    let (prot_msg, coin_terminate) = round_state.threshold_coin_share(self.coin_key.as_ref().unwrap(), &self.coin_session, self.myid, instance_id, baa_round);
+                    terminate = coin_terminate;
+                    self.broadcast(prot_msg).await;
+                }
+                else if term && !round_state.contains_coin(self.myid) && self.coin_shares.contains_key(&instance_id){
                     // TODO: Broadcasting common coin
                     let coin_shares = self.coin_shares.get_mut(&instance_id).unwrap();
                     if coin_shares.len() <= baa_round{
@@ -303,6 +319,54 @@ impl Context{
         }
     }
 
+    pub async fn process_sig_coin_share(self:&mut Context, partial_sig: PartialSignature,share_sender:Replica,instance_id:usize,baa_round:usize){
+        if self.terminated_rounds.contains(&instance_id) || self.coin_key.is_none(){
+            return;
+        }
+        log::info!("Received threshold coin share from node {} with lround {}, bround: {}",share_sender,instance_id,baa_round);
+        let coin_key = self.coin_key.as_ref().unwrap();
+        if partial_sig.party != share_sender{
+            log::warn!("Threshold coin share from node {} claims party {} for lround {}, bround: {}",share_sender,partial_sig.party,instance_id,baa_round);
+            return;
+        }
+        // The pairing check is deferred until this party releases its own share for the round, so shares for rounds it never reaches cost nothing
+        let mut terminate = None;
+        if self.round_state.contains_key(&instance_id){
+            let baa_rnd_state_tup = self.round_state.get_mut(&instance_id).unwrap();
+            if baa_rnd_state_tup.1.contains(&baa_round){
+                return;
+            }
+
+            let baa_rnd_state = &mut baa_rnd_state_tup.0;
+            if baa_rnd_state.contains_key(&baa_round){
+                let rnd_state = baa_rnd_state.get_mut(&baa_round).unwrap();
+                terminate = rnd_state.add_sig_coin_share(coin_key, &self.coin_session, self.myid, partial_sig, instance_id, baa_round);
+            }
+            else {
+                let rnd_state = RoundStateBin::new_with_psig(
+                    partial_sig,
+                    self.num_faults,
+                    self.num_nodes
+                );
+                baa_rnd_state.insert(baa_round, rnd_state);
+            }
+        }
+        else {
+            let rnd_state = RoundStateBin::new_with_psig(
+                partial_sig,
+                self.num_faults,
+                self.num_nodes
+            );
+            let mut baa_rnd_state = HashMap::default();
+            baa_rnd_state.insert(baa_round, rnd_state);
+            self.round_state.insert(instance_id, (baa_rnd_state, HashSet::default()));
+        }
+        if terminate.is_some(){
+            self.round_state.get_mut(&instance_id).unwrap().1.insert(baa_round);
+            self.start_baa(instance_id,baa_round+1, terminate.unwrap().1, terminate.unwrap().0).await;
+        }
+    }
+
     pub async fn start_baa(self: &mut Context,instance_id:usize, baa_round:usize, term_val: Val, terminate: bool){
         if self.terminated_rounds.contains(&instance_id){
             return;
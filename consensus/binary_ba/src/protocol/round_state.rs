@@ -2,8 +2,12 @@ use std::collections::{HashSet, HashMap};
 
 use consensus::LargeField;
 use lambdaworks_math::polynomial::Polynomial;
+use crypto::hash::do_hash;
+use tsig::{PartialSignature, TsigKey, combine, sign_share, verify_share};
 use types::Replica;
 
+use crate::msg::ProtMsg;
+
 pub type Val = i64;
 
 /**
@@ -21,6 +25,10 @@ pub struct RoundStateBin{
     pub echo3sent: bool,
     pub termval: Option<Val>,
     pub coin_shares_vec: HashMap<Replica,LargeField>,
+    // Verified BLS partial signatures when the threshold coin is used
+    pub sig_coin_shares: Vec<PartialSignature>,
+    // Partial signatures received before this party released its own coin share, verified once it does
+    pub pending_sig_coin_shares: HashMap<Replica, PartialSignature>,
     pub coin_state: Option<bool>,
 
     pub num_nodes: usize,
@@ -40,6 +48,8 @@ impl RoundStateBin{
             echo3sent:false,
             termval:None,
             coin_shares_vec: HashMap::default(),
+            sig_coin_shares: Vec::new(),
+            pending_sig_coin_shares: HashMap::default(),
             coin_state: None,
 
             num_nodes: num_nodes,
@@ -70,6 +80,8 @@ impl RoundStateBin{
             echo3sent:false,
             termval:None,
             coin_shares_vec: HashMap::default(),
+            sig_coin_shares: Vec::new(),
+            pending_sig_coin_shares: HashMap::default(),
             coin_state: None,
 
             num_nodes: num_nodes,
@@ -104,6 +116,8 @@ impl RoundStateBin{
             echo3sent:false,
             termval:None,
             coin_shares_vec: HashMap::default(),
+            sig_coin_shares: Vec::new(),
+            pending_sig_coin_shares: HashMap::default(),
             coin_state: None,
 
             num_nodes: num_nodes,
@@ -130,6 +144,31 @@ impl RoundStateBin{
             echo3sent:false,
             termval:None,
             coin_shares_vec: coinmap,
+            sig_coin_shares: Vec::new(),
+            pending_sig_coin_shares: HashMap::default(),
+            coin_state: None,
+
+            num_nodes: num_nodes,
+            num_faults: num_faults,
+
+            min_threshold: num_faults+1,
+            high_threshold: num_nodes - num_faults,
+        }
+    }
+
+    pub fn new_with_psig(partial_sig: PartialSignature,
+        num_faults: usize, 
+        num_nodes: usize)-> RoundStateBin{
+        RoundStateBin{
+            state:Vec::new(),
+            echo1vals: HashSet::new(),
+            echo2vals: Vec::new(),
+            echo3vals: HashMap::default(),
+            echo3sent:false,
+            termval:None,
+            coin_shares_vec: HashMap::default(),
+            sig_coin_shares: Vec::new(),
+            pending_sig_coin_shares: HashMap::from([(partial_sig.party, partial_sig)]),
             coin_state: None,
 
             num_nodes: num_nodes,
@@ -300,6 +339,67 @@ impl RoundStateBin{
         let result = (coin.to_bytes_be()[0]%2) == 0;
 
         log::info!("Coin value {} from reconstructed coin {:?} and round termination value {:?}",result, coin,self.termval);
+        self.apply_coin(result)
+    }
+
+    // Aggregates t+1 verified BLS partial signatures on the coin name. The coin is a bit of the hash of the unique combined signature.
+    pub fn aggregate_sig_coins(&mut self, coin_key: &TsigKey)->Option<(bool,Val)>{
+        if self.sig_coin_shares.len() < self.min_threshold{
+            return None;
+        }
+        let sig = match combine(coin_key, &self.sig_coin_shares){
+            Some(sig) => sig,
+            None => {
+                log::error!("Unable to combine partial signatures for the coin");
+                return None;
+            }
+        };
+        let result = (do_hash(sig.as_slice())[0]%2) == 0;
+        log::info!("Coin value {} from threshold signature and round termination value {:?}",result,self.termval);
+        self.apply_coin(result)
+    }
+
+    // Creates this party's partial signature on the coin name, returns the message to broadcast and the result of aggregation.
+    // Partial signatures that arrived earlier are verified now that the coin is expected.
+    pub fn threshold_coin_share(&mut self, coin_key: &TsigKey, coin_session: &[u8], myid: Replica, instance_id: usize, baa_round: usize)->(ProtMsg, Option<(bool,Val)>){
+        let partial_sig = sign_share(coin_key, coin_name(coin_session, instance_id, baa_round).as_slice());
+        log::info!("Sending threshold coin share for lround {}, bround {}",instance_id,baa_round);
+        self.sig_coin_shares.push(partial_sig.clone());
+        let pending: Vec<PartialSignature> = self.pending_sig_coin_shares.drain().map(|(_, partial)| partial).collect();
+        for partial in pending{
+            self.verify_sig_coin_share(coin_key, coin_session, partial, instance_id, baa_round);
+        }
+        let prot_msg = ProtMsg::BBASigCoin(instance_id, baa_round, partial_sig, myid);
+        (prot_msg, self.aggregate_sig_coins(coin_key))
+    }
+
+    // Adds a partial signature on the coin of this round if it verifies. Only called once this party released its own coin share,
+    // so each party costs at most one pairing check per round this party reached.
+    fn verify_sig_coin_share(&mut self, coin_key: &TsigKey, coin_session: &[u8], partial_sig: PartialSignature, instance_id: usize, baa_round: usize)->bool{
+        if self.sig_coin_shares.len() >= self.min_threshold || self.contains_coin(partial_sig.party){
+            return false;
+        }
+        if !verify_share(coin_key, coin_name(coin_session, instance_id, baa_round).as_slice(), &partial_sig){
+            log::warn!("Invalid threshold coin share from node {} for lround {}, bround: {}",partial_sig.party,instance_id,baa_round);
+            return false;
+        }
+        self.sig_coin_shares.push(partial_sig);
+        true
+    }
+
+    /// Takes a partial signature on the coin of this round. It is verified right away if this party already released its own coin share, and kept otherwise.
+    pub fn add_sig_coin_share(&mut self, coin_key: &TsigKey, coin_session: &[u8], myid: Replica, partial_sig: PartialSignature, instance_id: usize, baa_round: usize)->Option<(bool,Val)>{
+        if !self.contains_coin(myid){
+            self.pending_sig_coin_shares.entry(partial_sig.party).or_insert(partial_sig);
+            return None;
+        }
+        if self.verify_sig_coin_share(coin_key, coin_session, partial_sig, instance_id, baa_round){
+            return self.aggregate_sig_coins(coin_key);
+        }
+        None
+    }
+
+    fn apply_coin(&mut self, result: bool)->Option<(bool,Val)>{
         if self.termval.is_some(){
             if (self.termval.unwrap() == 2 && result) || (self.termval.unwrap() == 0 && !result){
                 // Terminate and send message to syncer
@@ -332,6 +432,17 @@ impl RoundStateBin{
     }
 
     pub fn contains_coin(&self,id: Replica)->bool{
-        self.coin_shares_vec.contains_key(&id)
+        self.coin_shares_vec.contains_key(&id) || self.sig_coin_shares.iter().any(|partial| partial.party == id)
     }
+}
+
+// Message signed by the threshold coin of a BBA round. The coin key outlives a run, so the name binds the session of the run,
+// otherwise the coins of a later run with the same instance and round would be known from the signatures of an earlier one.
+pub fn coin_name(coin_session: &[u8], instance_id: usize, baa_round: usize)->Vec<u8>{
+    let mut name = b"bba-coin".to_vec();
+    name.extend((coin_session.len() as u64).to_be_bytes());
+    name.extend_from_slice(coin_session);
+    name.extend((instance_id as u64).to_be_bytes());
+    name.extend((baa_round as u64).to_be_bytes());
+    name
 }
\ No newline at end of file
@@ -1,7 +1,7 @@
 use std::{
     collections::{HashMap, HashSet, VecDeque},
     net::{SocketAddr, SocketAddrV4},
-    time::{SystemTime, UNIX_EPOCH},
+    time::{SystemTime, UNIX_EPOCH, Duration},
 };
 use anyhow::{anyhow, Result};
 use config::Node;
@@ -15,18 +15,26 @@ use signal_hook::{iterator::Signals, consts::{SIGINT, SIGTERM}};
 use tokio::{sync::{
     mpsc::{UnboundedReceiver, Sender, Receiver, channel, unbounded_channel},
     oneshot,
-}};
+}, time};
 // use tokio_util::time::DelayQueue;
 use types::{Replica, SyncMsg, SyncState, WrapperMsg};
 
-use consensus::{SyncHandler, LargeFieldSSS, LargeField, LargeFieldSer};
-use crypto::{aes_hash::HashState, hash::Hash};
+use consensus::{HashKeys, SyncHandler, LargeFieldSSS, LargeField, LargeFieldSer, HEARTBEAT, HEARTBEAT_INTERVAL, SecretBytes, SecretShares, ReplicaSender, Acss, AcssBackends, AcssEvent, AcssNeeds, AcssParams, AcssScheme, spawn_acss_backend, HandoverOutput};
+use crypto::aes_hash::HashState;
 
-use crate::{msg::ProtMsg, Handler, protocol::{DPSSState, BAState}};
+use crate::{msg::ProtMsg, protocol::{DPSSState, BAState, RefreshState, ThresholdKeyState, padded_per_batch}};
+
+// ACSS schemes the protocol can run on
+const ACSS_BACKENDS: AcssBackends = AcssBackends {
+    ske: acss_ske::Context::spawn_acss,
+    g_acss: g_acss::Context::spawn_acss,
+    hacss: hacss::Context::spawn_acss,
+    bv: acss_bv::Context::spawn_acss,
+};
 
 pub struct Context {
     /// Networking context
-    pub net_send: TcpReliableSender<Replica, WrapperMsg<ProtMsg>, Acknowledgement>,
+    pub net_send: ReplicaSender<WrapperMsg<ProtMsg>>,
     pub net_recv: UnboundedReceiver<WrapperMsg<ProtMsg>>,
     pub sync_send: TcpReliableSender<Replica, SyncMsg, Acknowledgement>,
     pub sync_recv: UnboundedReceiver<SyncMsg>,
@@ -41,10 +49,12 @@ pub struct Context {
     pub ibft: bool,
 
     pub terminated: bool,
+    // Time at which the syncer started the run, reported back on completion
+    pub start_time: u128,
 
     pub large_field_shamir_ss: LargeFieldSSS,
     /// Secret Key map
-    pub sec_key_map: HashMap<Replica, Vec<u8>>,
+    pub sec_key_map: HashMap<Replica, SecretBytes>,
 
     /// Hardware acceleration context
     pub hash_context: HashState,
@@ -55,6 +65,11 @@ pub struct Context {
 
     pub num_batches: usize,
     pub per_batch: usize,
+    // Number of epochs the output shares are proactively refreshed for before reporting
+    pub refresh_epochs: usize,
+    // Output shares go to the handover service instead of being reported, the handover's output is reported instead
+    pub handover_send: Option<Sender<SecretShares>>,
+    pub handover_out: Option<Receiver<HandoverOutput>>,
 
     pub coin_batch: usize,
     pub coin_shares: VecDeque<LargeField>,
@@ -70,13 +85,15 @@ pub struct Context {
     ///// State for GatherState and ACS
     pub dpss_state: DPSSState,
     pub ba_state: BAState,
+    pub refresh_state: RefreshState,
+    pub threshold_key_state: ThresholdKeyState,
 
     pub completed_batches: HashMap<Replica, HashSet<usize>>,
     pub acs_input_set: HashSet<Replica>,
     /// Channels to interact with other services
 
-    pub acss_req: Sender<(usize, Vec<LargeField>)>,
-    pub acss_out_recv: Receiver<(usize, usize, Hash, Option<Vec<LargeField>>)>,
+    /// ACSS backend, chosen at startup
+    pub acss: Box<dyn Acss>,
 
     pub bin_aa_req: Sender<(usize, i64, Vec<LargeFieldSer>)>,
     pub bin_aa_out_recv: Receiver<(usize, i64)>,
@@ -87,9 +104,6 @@ pub struct Context {
     pub acs_term_event: Sender<(usize,usize, Vec<LargeFieldSer>)>,
     pub acs_out_recv: Receiver<(usize,Vec<usize>)>,
 
-    pub pub_rec_req_send_channel: Sender<(usize, Replica)>,
-    pub pub_rec_out_recv_channel: Receiver<(usize, Replica, Vec<LargeField>)>,
-
     pub ra_req_send_channel: Sender<(usize, usize, usize)>,
     pub ra_out_recv_channel: Receiver<(usize, usize, usize)>,
 }
@@ -104,13 +118,23 @@ pub struct Context {
 impl Context {
     pub fn spawn(
         config: Node,
+        hash_keys: HashKeys,
+        acss_scheme: AcssScheme,
         num_batches: usize,
         per_batch: usize,
+        refresh_epochs: usize,
+        handover: Option<(Sender<SecretShares>, Receiver<HandoverOutput>)>,
         opt_or_pess: bool,
         lin_or_quad: bool,
         ibft: bool,
         byz: bool
     ) -> anyhow::Result<oneshot::Sender<()>> {
+        // Every refresh epoch and the handover open one output share as a mask, and at least one share must be left
+        let output_shares = num_batches*padded_per_batch(per_batch, config.num_faults)*(config.num_faults+1);
+        let masks = refresh_epochs + handover.is_some() as usize;
+        if masks >= output_shares{
+            return Err(anyhow!("{} output shares cannot cover {} masks, one per refresh epoch and one for the handover", output_shares, masks));
+        }
         // Add a separate configuration for RBC service. 
 
         let mut consensus_addrs: FnvHashMap<Replica, SocketAddr> = FnvHashMap::default();
@@ -153,10 +177,6 @@ impl Context {
 
         // Setup networking
         let (tx_net_to_consensus, rx_net_to_consensus) = unbounded_channel();
-        TcpReceiver::<Acknowledgement, WrapperMsg<ProtMsg>, _>::spawn(
-            my_address,
-            Handler::new(tx_net_to_consensus),
-        );
 
         let syncer_listen_port = config.client_port;
         let syncer_l_address = to_socket_address("0.0.0.0", syncer_listen_port);
@@ -168,18 +188,13 @@ impl Context {
             SyncHandler::new(tx_net_to_client),
         );
 
-        let consensus_net = TcpReliableSender::<Replica, WrapperMsg<ProtMsg>, Acknowledgement>::with_peers(
-            consensus_addrs.clone(),
-        );
+        let consensus_net = consensus::spawn_replica_network(my_address, consensus_addrs.clone(), tx_net_to_consensus);
         let sync_net =
             TcpReliableSender::<Replica, SyncMsg, Acknowledgement>::with_peers(syncer_map);
         let (exit_tx, exit_rx) = oneshot::channel();
 
         // Keyed AES ciphers
-        let key0 = [5u8; 16];
-        let key1 = [29u8; 16];
-        let key2 = [23u8; 16];
-        let hashstate = HashState::new(key0, key1, key2);
+        let hashstate = hash_keys.hash_state();
 
         let rbc_start_id = 1;
                 
@@ -195,19 +210,28 @@ impl Context {
         let (fin_mvba_req_send, fin_mvba_req_recv) = channel(10000);
         let (fin_mvba_out_send, fin_mvba_out_recv) = channel(10000);
         
-        let (acss_req_send_channel, acss_req_recv_channel) = channel(10000);
-        let (acss_out_send_channel, acss_out_recv_channel) = channel(10000);
-        
-        let (pub_rec_req_send_channel, pub_rec_req_recv_channel) = channel(10000);
-        let (pub_rec_out_send_channel, pub_rec_out_recv_channel) = channel(10000);
-
         let (acs_req_send_channel, acs_req_recv_channel) = channel(10000);
         let (acs_out_send_channel, acs_out_recv_channel) = channel(10000);
 
         let (ra_req_send_channel, ra_req_recv_channel) = channel(10000);
         let (ra_out_send_channel, ra_out_recv_channel) = channel(10000);        
 
+        // The ACSS service listens on its own ports and is owned by the context, which shuts it down on exit
+        // Coins and blinded outputs are opened through public reconstruction, and shares are combined at degree t
+        let acss_needs = AcssNeeds {
+            public_reconstruction: true,
+            degree: Some(config.num_faults),
+        };
+        let acss = spawn_acss_backend(&ACSS_BACKENDS, acss_scheme, acss_needs, acss_config, hash_keys.clone(), AcssParams {
+            use_fft: false,
+            lin_or_quad: lin_or_quad,
+            byz: false,
+        })?;
         let coin_secrets = (60/(config.num_faults+1))*(config.num_faults+1);
+        let (handover_send, handover_out) = match handover {
+            Some((handover_send, handover_out)) => (Some(handover_send), Some(handover_out)),
+            None => (None, None),
+        };
         tokio::spawn(async move {
             let mut c = Context {
                 net_send: consensus_net,
@@ -220,6 +244,7 @@ impl Context {
                 myid: config.id,
                 _byz: byz,
                 terminated: false,
+                start_time: 0,
 
                 // Protocol configuration
                 opt_or_pess: opt_or_pess,
@@ -238,9 +263,14 @@ impl Context {
                 max_id: rbc_start_id, 
                 dpss_state: DPSSState::new(),
                 ba_state: BAState::new(),
+                refresh_state: RefreshState::new(),
+                threshold_key_state: ThresholdKeyState::default(),
 
                 num_batches: num_batches,
                 per_batch: per_batch, 
+                refresh_epochs: refresh_epochs,
+                handover_send: handover_send,
+                handover_out: handover_out,
                 
                 coin_batch: coin_secrets,
                 coin_shares: VecDeque::new(),
@@ -251,8 +281,7 @@ impl Context {
 
                 nonce_seed: 1,
 
-                acss_req: acss_req_send_channel,
-                acss_out_recv: acss_out_recv_channel,
+                acss: acss,
 
                 bin_aa_req: bin_aa_req,
                 bin_aa_out_recv: bin_aa_out_recv,
@@ -263,16 +292,13 @@ impl Context {
                 acs_term_event: acs_req_send_channel,
                 acs_out_recv: acs_out_recv_channel,
 
-                pub_rec_req_send_channel: pub_rec_req_send_channel,
-                pub_rec_out_recv_channel: pub_rec_out_recv_channel,
-
                 ra_req_send_channel: ra_req_send_channel,
                 ra_out_recv_channel: ra_out_recv_channel,
             };
 
             // Populate secret keys from config
-            for (id, sk_data) in config.sk_map.clone() {
-                c.sec_key_map.insert(id, sk_data.clone());
+            for (id, sk_data) in config.sk_map.iter() {
+                c.sec_key_map.insert(*id, SecretBytes::new(sk_data.clone()));
             }
 
             // Run the consensus context
@@ -281,21 +307,11 @@ impl Context {
             }
         });
         let ibft_or_acs = ibft;
-        let _acss_serv_status = acss_ske::Context::spawn(
-            acss_config,
-            acss_req_recv_channel,
-            acss_out_send_channel, 
-            pub_rec_req_recv_channel,
-            pub_rec_out_send_channel,
-            false,
-            lin_or_quad,
-            false
-        );
-
         let _acs_serv_status; 
         if ibft_or_acs{
             _acs_serv_status = acs::Context::spawn(
                 acs_config,
+                hash_keys.clone(),
                 acs_req_recv_channel, 
                 acs_out_send_channel, 
                 false
@@ -317,6 +333,7 @@ impl Context {
 
         let _ba_serv_status = binary_ba::Context::spawn(
             ba_config,
+            hash_keys.clone(),
             bin_aa_req_recv,
             bin_aa_out_send,
             false
@@ -330,6 +347,7 @@ impl Context {
         if ibft_or_acs{
             _fin_mvba_status = fin_mvba::Context::spawn(
                 mvba_config,
+                hash_keys.clone(),
                 fin_mvba_req_recv,
                 fin_mvba_out_send,
                 false
@@ -351,6 +369,7 @@ impl Context {
 
         let _ra_status = ra::Context::spawn(
             ra_config,
+            hash_keys.clone(),
             ra_req_recv_channel,
             ra_out_send_channel,
             false,
@@ -377,9 +396,11 @@ impl Context {
     }
 
     pub async fn broadcast(&mut self, protmsg: ProtMsg) {
-        let sec_key_map = self.sec_key_map.clone();
-        for (replica, sec_key) in sec_key_map.into_iter() {
+        let replicas: Vec<Replica> = self.sec_key_map.keys().cloned().collect();
+        for replica in replicas {
+            let sec_key = self.sec_key_map.get(&replica).unwrap();
             let wrapper_msg = WrapperMsg::new(protmsg.clone(), self.myid, &sec_key.as_slice());
+            consensus::count_sent_message(&wrapper_msg);
             let cancel_handler: CancelHandler<Acknowledgement> = self.net_send.send(replica, wrapper_msg).await;
             self.add_cancel_handler(cancel_handler);
         }
@@ -390,6 +411,7 @@ impl Context {
     }
 
     pub async fn send(&mut self, replica: Replica, wrapper_msg: WrapperMsg<ProtMsg>) {
+        consensus::count_sent_message(&wrapper_msg);
         let cancel_handler: CancelHandler<Acknowledgement> =
             self.net_send.send(replica, wrapper_msg).await;
         self.add_cancel_handler(cancel_handler);
@@ -410,6 +432,8 @@ impl Context {
             )
             .await;
         self.add_cancel_handler(cancel_handler);
+        // Then keeps sending heartbeats so the syncer can tell dead nodes apart from slow ones
+        let mut heartbeat = time::interval(Duration::from_millis(HEARTBEAT_INTERVAL));
         loop {
             tokio::select! {
                 // Receive exit handlers
@@ -418,9 +442,23 @@ impl Context {
                     log::info!("Termination signal received by the server. Exiting.");
                     break
                 },
+                _ = heartbeat.tick() => {
+                    let cancel_handler = self
+                        .sync_send
+                        .send(
+                            0,
+                            SyncMsg {
+                                sender: self.myid,
+                                state: SyncState::ALIVE,
+                                value: HEARTBEAT.to_vec(),
+                            },
+                        )
+                        .await;
+                    self.add_cancel_handler(cancel_handler);
+                },
                 msg = self.net_recv.recv() => {
                     // Received messages are processed here
-                    log::trace!("Got a consensus message from the network: {:?}", msg);
+                    log::trace!("Got a consensus message from the network from {:?}", msg.as_ref().map(|msg| msg.sender));
                     let msg = msg.ok_or_else(||
                         anyhow!("Networking layer has closed")
                     )?;
@@ -432,10 +470,11 @@ impl Context {
                     )?;
                     match sync_msg.state {
                         SyncState::START =>{
-                            log::info!("Consensus Start time: {:?}", SystemTime::now()
+                            self.start_time = SystemTime::now()
                                 .duration_since(UNIX_EPOCH)
                                 .unwrap()
-                                .as_millis());
+                                .as_millis();
+                            log::info!("Consensus Start time: {:?}", self.start_time);
                             // Start your protocol from here
                             for _instance in 0..self.num_batches{
                                 let _status = self.start_acss(self.per_batch).await;
@@ -455,19 +494,36 @@ impl Context {
                         _=>{}
                     }
                 },
-                acss_msg = self.acss_out_recv.recv() => {
+                acss_msg = self.acss.output() => {
                     let acss_msg = acss_msg.ok_or_else(||
                         anyhow!("Networking layer has closed")
                     )?;
-                    log::debug!("Received message from CTRBC channel {:?}", acss_msg);
-                    self.process_acss_event(acss_msg.0, acss_msg.1, acss_msg.2, acss_msg.3).await;
+                    match acss_msg {
+                        AcssEvent::Shared((inst, dealer, root_comm, shares, opening)) => {
+                            log::debug!("Received ACSS output of instance {} from dealer {}", inst, dealer);
+                            self.process_acss_event(inst, dealer, root_comm, shares, opening).await;
+                        },
+                        AcssEvent::Reconstructed((_inst, dealer, secrets)) => {
+                            log::debug!("Received public reconstruction of dealer {}", dealer);
+                            self.process_acss_pubrec_output(dealer, secrets).await;
+                        }
+                        AcssEvent::Opened((inst, dealer, _indices, _secrets)) => {
+                            log::error!("Unexpected selected public reconstruction of instance {} of dealer {}", inst, dealer);
+                        }
+                    }
                 },
                 acs_output = self.acs_out_recv.recv() =>{
                     let acs_output = acs_output.ok_or_else(||
                         anyhow!("Networking layer has closed")
                     )?;
                     log::debug!("Received message from RBC channel {:?}", acs_output);
-                    self.process_consensus_output(acs_output.1).await;
+                    // ACS instance 1 agrees on the dealers of the sharing phase, instance 1+e on the contributors of refresh epoch e
+                    if acs_output.0 == 1{
+                        self.process_consensus_output(acs_output.1).await;
+                    }
+                    else{
+                        self.process_refresh_acs_output(acs_output.0-1, acs_output.1).await;
+                    }
                 },
                 bin_aa_out_msg = self.bin_aa_out_recv.recv() => {
                     let bin_aa_out_msg = bin_aa_out_msg.ok_or_else(||
@@ -490,12 +546,13 @@ impl Context {
                     }
                     self.process_fin_mvba_output(fin_mvba_out_msg.0, median_value).await;
                 },
-                pub_rec_out_msg = self.pub_rec_out_recv_channel.recv() => {
-                    let pub_rec_out_msg = pub_rec_out_msg.ok_or_else(||
-                        anyhow!("Networking layer has closed")
+                handover_output = recv_if_present(&mut self.handover_out) => {
+                    let handover_output = handover_output.ok_or_else(||
+                        anyhow!("Handover channel has closed")
                     )?;
-                    log::debug!("Received message from Pub Rec channel {:?}", pub_rec_out_msg);
-                    self.process_acss_pubrec_output(pub_rec_out_msg.1, pub_rec_out_msg.2).await;
+                    log::info!("Handover to epoch {} completed", handover_output.epoch);
+                    self.handover_out = None;
+                    self.report_completion(handover_output.report()).await;
                 },
                 ra_out_msg = self.ra_out_recv_channel.recv() => {
                     let ra_out_msg = ra_out_msg.ok_or_else(||
@@ -506,10 +563,18 @@ impl Context {
                 },
             };
         }
+        self.acss.shutdown();
         Ok(())
     }
 }
 
+async fn recv_if_present<T>(channel: &mut Option<Receiver<T>>) -> Option<T> {
+    match channel {
+        Some(recv) => recv.recv().await,
+        None => futures::future::pending().await,
+    }
+}
+
 pub fn to_socket_address(ip_str: &str, port: u16) -> SocketAddr {
     let addr = SocketAddrV4::new(ip_str.parse().unwrap(), port);
     addr.into()
@@ -10,7 +10,13 @@ pub enum ProtMsg{
     SecEq(usize, Replica, u8, LargeFieldSer),
     // PubRec
     PubRecEcho1(Vec<LargeFieldSer>),
-    PubRecEcho2(Vec<LargeFieldSer>)
+    PubRecEcho2(Vec<LargeFieldSer>),
+    // Refresh
+    // epoch, aggregated_old_share, (contributor, aggregated_subshares)
+    RefreshCheck(usize, LargeFieldSer, Vec<(Replica, LargeFieldSer)>),
+    // Threshold key export
+    // verification key g^s of the node's key share s
+    VerificationKey(Vec<u8>)
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
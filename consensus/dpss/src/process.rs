@@ -27,7 +27,8 @@ impl Context {
     }
 
     pub(crate) async fn process_msg(&mut self, wrapper_msg: WrapperMsg<ProtMsg>) {
-        log::trace!("Received protocol msg: {:?}", wrapper_msg);
+        // Payloads can carry shares, so only the sender is logged
+        log::trace!("Received protocol msg from {}", wrapper_msg.sender);
         delay_message_processing().await;
         let msg = Arc::new(wrapper_msg.clone());
 
@@ -46,6 +47,14 @@ impl Context {
                     log::debug!("Received PubRecEcho2 from node : {}", wrapper_msg.sender);
                     self.process_pub_rec_echo2_msg(shares_ser, wrapper_msg.sender).await;
                 }
+                ProtMsg::RefreshCheck(epoch, old_agg, new_aggs) => {
+                    log::debug!("Received RefreshCheck for epoch {} from node : {}", epoch, wrapper_msg.sender);
+                    self.process_refresh_check_msg(epoch, old_agg, new_aggs, wrapper_msg.sender).await;
+                }
+                ProtMsg::VerificationKey(key_ser) => {
+                    log::debug!("Received VerificationKey from node : {}", wrapper_msg.sender);
+                    self.process_verification_key_msg(key_ser, wrapper_msg.sender).await;
+                }
                 // ProtMsg::Deliver(avid_shard, origin, instance_id) => {
                     
                 //     log::debug!("Received Deliver for instance id {} from node : {}", instance_id, origin);
@@ -1,6 +1,6 @@
 use std::collections::{HashMap, HashSet};
 
-use consensus::LargeFieldSSS;
+use consensus::{LargeFieldSSS, SecretShares, ShareOpening, share_export_dir};
 use crypto::{LargeField, LargeFieldSer, hash::{Hash}, rand_field_element};
 use lambdaworks_math::traits::ByteConversion;
 use types::Replica;
@@ -15,14 +15,19 @@ impl Context{
             let rand_int = rand_field_element();
             random_points.push(rand_int);
         }
+        // Pad here so that both sharings carry the same padding and pass the secret equivalence check
+        let padded_len = self.acss.padded_len(num_points);
+        while random_points.len() < padded_len{
+            random_points.push(rand_field_element());
+        }
         let id = self.max_id;
-        let _status = self.acss_req.send((id, random_points.clone())).await;
-        let _status = self.acss_req.send((id+1, random_points)).await;
+        self.acss.share(id, random_points.clone()).await;
+        self.acss.share(id+1, random_points).await;
 
         self.max_id = id+2;
     }
 
-    pub async fn process_acss_event(&mut self, inst: usize, sender: usize, root_comm: Hash, shares_deser: Option<Vec<LargeField>>){
+    pub async fn process_acss_event(&mut self, inst: usize, sender: usize, root_comm: Hash, shares_deser: Option<Vec<LargeField>>, opening: Option<ShareOpening>){
         if shares_deser.is_none(){
             log::error!("Received ACSS terminated event for instance {}, dealer: {}, but shares are None", inst, sender);
             return;
@@ -32,7 +37,16 @@ impl Context{
         
         let inst_key = (inst+1)/2;
         let first_or_second = inst%2;
+        let refresh_epoch = self.refresh_epoch_of(inst_key);
+        if refresh_epoch.map(|epoch| epoch <= self.refresh_state.epoch).unwrap_or(false){
+            // Reshares of a completed refresh epoch are no longer needed
+            return;
+        }
 
+        // Exported shares carry their opening against the root
+        if let (Some(opening), Some(_)) = (opening, share_export_dir()){
+            self.dpss_state.share_openings.insert((sender, inst), opening);
+        }
         if !self.dpss_state.acss_map.contains_key(&sender){
             let hash_map = HashMap::default();
             self.dpss_state.acss_map.insert(sender, hash_map);
@@ -45,13 +59,20 @@ impl Context{
 
         let party_share_map_entry = party_share_map.get_mut(&inst_key).unwrap();
         if first_or_second == 1{
-            party_share_map_entry.0 = Some((shares_deser,root_comm));
+            party_share_map_entry.0 = Some((SecretShares::new(shares_deser),root_comm));
         }
         else{
-            party_share_map_entry.1 = Some((shares_deser,root_comm));
+            party_share_map_entry.1 = Some((SecretShares::new(shares_deser),root_comm));
         }
         
-        if party_share_map_entry.0.is_some() && party_share_map_entry.1.is_some(){
+        if let Some(epoch) = refresh_epoch{
+            // Reshares are dealt once, the refresh check proves them correct
+            if party_share_map_entry.0.is_some(){
+                self.check_refresh_reshare(epoch, sender).await;
+                self.advance_refresh().await;
+            }
+        }
+        else if party_share_map_entry.0.is_some() && party_share_map_entry.1.is_some(){
             // Initiate share equivalence protocol
             log::info!("Sending equivalence message for instance {}", inst_key);
             let party_shares_clone = party_share_map_entry.clone();
@@ -68,7 +89,7 @@ impl Context{
             let mut agg_share_c1 = LargeField::from(0);
             let mut agg_share_c2 = LargeField::from(0);
 
-            for (f_share, s_share) in first_comm_shares.0.into_iter().zip(second_comm_shares.0.into_iter()){
+            for (f_share, s_share) in first_comm_shares.0.iter().zip(second_comm_shares.0.iter()){
                 agg_share_c1 += &root_comm_mul*f_share;
                 agg_share_c2 += &root_comm_mul*s_share;
 
@@ -92,7 +113,8 @@ impl Context{
 
     pub async fn process_sec_equivalence_msg(&mut self, inst_key: usize,origin: Replica, sender: Replica, c1_c2: u8, eval_point: LargeFieldSer){
         log::info!("Received sec_equivalence message from party {} for origin {} in instance key {}",sender, origin, inst_key);
-        if self.acs_input_set.contains(&origin){
+        // Reshares of refresh epochs are not checked for secret equivalence
+        if self.refresh_epoch_of(inst_key).is_some() || self.acs_input_set.contains(&origin){
             return;
         }
         let eval_point_lf = LargeField::from_bytes_be(eval_point.as_slice()).unwrap();
@@ -148,7 +170,7 @@ impl Context{
                 for rep in 0..self.num_nodes{
                     if c2_val_map.contains_key(&rep){
                         eval_points.push(LargeField::from((rep+1) as u64));
-                        evaluations.push(c1_val_map.get(&rep).unwrap().clone());
+                        evaluations.push(c2_val_map.get(&rep).unwrap().clone());
                     }
                 }
 
@@ -163,19 +185,24 @@ impl Context{
                 log::info!("Reconstructed secret {:?} for instance id {} and origin {} in c_2", secret, inst_key, origin);
                 c2_val_map.insert(self.num_nodes+1, secret);
             }
+        }
 
-            if c1_val_map.contains_key(&(self.num_nodes+1)) && 
-                c2_val_map.contains_key(&(self.num_nodes+1)) &&
-                c1_val_map.get(&(self.num_nodes+1)).unwrap() == c2_val_map.get(&(self.num_nodes+1)).unwrap(){
-                // Add this instance to completed sharings
-                log::info!("Secret equivalence for instance {} and origin {} completed", inst_key, origin);
-                if !self.completed_batches.contains_key(&origin){
-                    self.completed_batches.insert(origin, HashSet::default());
-                }
-                self.completed_batches.get_mut(&origin).unwrap().insert(inst_key);
-                self.check_acss_and_secret_equivalence_termination(origin).await;
-            }
+        // Either sharing can be the last one to be reconstructed
+        let (c1_secret, c2_secret) = (c1_val_map.get(&(self.num_nodes+1)), c2_val_map.get(&(self.num_nodes+1)));
+        if c1_secret.is_none() || c2_secret.is_none(){
+            return;
+        }
+        if c1_secret != c2_secret{
+            log::error!("Secret equivalence for instance {} and origin {} failed, the two sharings differ", inst_key, origin);
+            return;
+        }
+        log::info!("Secret equivalence for instance {} and origin {} completed", inst_key, origin);
+        // Add this instance to completed sharings
+        if !self.completed_batches.contains_key(&origin){
+            self.completed_batches.insert(origin, HashSet::default());
         }
+        self.completed_batches.get_mut(&origin).unwrap().insert(inst_key);
+        self.check_acss_and_secret_equivalence_termination(origin).await;
     }
 
     pub async fn check_acss_and_secret_equivalence_termination(&mut self, origin: Replica){
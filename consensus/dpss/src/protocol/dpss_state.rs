@@ -1,5 +1,6 @@
 use std::collections::{HashMap, HashSet};
 
+use consensus::{SecretShares, ShareOpening};
 use crypto::{LargeField, hash::Hash};
 use types::Replica;
 
@@ -7,8 +8,8 @@ pub struct DPSSState{
     pub acss_map: HashMap<Replica, 
         HashMap<usize, 
         (
-            Option<(Vec<LargeField>, Hash)>,
-            Option<(Vec<LargeField>, Hash)>)>
+            Option<(SecretShares, Hash)>,
+            Option<(SecretShares, Hash)>)>
         >,
     
     pub sec_equivalence: HashMap<Replica, 
@@ -23,6 +24,8 @@ pub struct DPSSState{
     pub pub_rec_echo2s: HashMap<Replica, Vec<LargeField>>,
 
     pub acs_output: HashSet<Replica>,
+    // Openings of the shares of every dealer and instance against the roots, kept for the share export
+    pub share_openings: HashMap<(Replica, usize), ShareOpening>,
 }
 
 impl DPSSState{
@@ -33,6 +36,7 @@ impl DPSSState{
             pub_rec_echo1s: HashMap::default(),
             pub_rec_echo2s: HashMap::default(),
             acs_output: HashSet::default(),
+            share_openings: HashMap::default(),
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,61 @@
+use consensus::{ExportedSharing, ShareFile, ShareFileHeader, SHARE_FILE_FIELD, SHARE_FILE_VERSION, share_export_dir};
+use lambdaworks_math::traits::ByteConversion;
+use types::Replica;
+
+use crate::Context;
+
+impl Context{
+    /// Writes the shares this node holds of the ACS dealers' sharings, one file per batch, if share export is enabled.
+    /// Each batch is shared twice, in instances 2*batch-1 and 2*batch, and both sharings are exported.
+    pub fn export_shares(&self){
+        let export_dir = match share_export_dir() {
+            Some(dir) => dir,
+            None => return,
+        };
+        let mut dealers: Vec<Replica> = self.dpss_state.acs_output.iter().cloned().collect();
+        dealers.sort();
+
+        // The last batch holds the coin secrets
+        for batch in 1..self.num_batches+2{
+            let mut sharings = Vec::new();
+            for dealer in dealers.iter(){
+                let batch_shares = self.dpss_state.acss_map.get(dealer).and_then(|share_inst_map| share_inst_map.get(&batch));
+                if batch_shares.is_none(){
+                    continue;
+                }
+                let (first, second) = batch_shares.unwrap();
+                for (instance_id, shares_root) in [(2*batch-1, first), (2*batch, second)]{
+                    if let Some((shares, root)) = shares_root{
+                        sharings.push(ExportedSharing {
+                            dealer: *dealer,
+                            instance_id: instance_id,
+                            root: *root,
+                            shares: shares.iter().map(|share| share.to_bytes_be()).collect(),
+                            opening: self.dpss_state.share_openings.get(&(*dealer, instance_id)).cloned(),
+                        });
+                    }
+                }
+            }
+            let share_file = ShareFile {
+                header: ShareFileHeader {
+                    version: SHARE_FILE_VERSION,
+                    protocol: "dpss".to_string(),
+                    acss: self.acss.scheme().name().to_string(),
+                    field: SHARE_FILE_FIELD.to_string(),
+                    num_nodes: self.num_nodes,
+                    num_faults: self.num_faults,
+                    degree: self.num_faults,
+                    node: self.myid,
+                    batch: batch,
+                    instance_ids: vec![2*batch-1, 2*batch],
+                    dealers: dealers.clone(),
+                },
+                sharings: sharings,
+            };
+            match share_file.write_to_dir(export_dir) {
+                Ok(path) => log::info!("Exported shares of batch {} to {}", batch, path.display()),
+                Err(e) => log::error!("Share export of batch {} failed: {}", batch, e),
+            }
+        }
+    }
+}
@@ -1,9 +1,18 @@
 mod acss;
 
 mod rand_sh;
+pub use rand_sh::*;
 
 mod dpss_state;
 pub use dpss_state::*;
 
 mod pub_rec;
-pub use pub_rec::*;
\ No newline at end of file
+pub use pub_rec::*;
+
+mod export;
+
+mod refresh;
+pub use refresh::*;
+
+mod threshold_key;
+pub use threshold_key::*;
\ No newline at end of file
@@ -96,7 +96,7 @@ impl Context{
                 if self.ba_state.acs_output_sorted.len() > 0 && !self.ba_state.quad_pub_rec_started{
                     // Last t parties pubrec
                     for party in self.num_faults+1..self.num_nodes-self.num_faults{
-                        let _status = self.pub_rec_req_send_channel.send((1, self.ba_state.acs_output_sorted[party].clone())).await;
+                        self.acss.public_reconstruct(1, self.ba_state.acs_output_sorted[party].clone()).await;
                     }
                     self.ba_state.quad_pub_rec_started = true;
                 }
@@ -185,7 +185,7 @@ impl Context{
             return;
         }
         else{
-            let _status = self.pub_rec_req_send_channel.send((1, corrupted_party)).await;
+            self.acss.public_reconstruct(1, corrupted_party).await;
         }
     }
 
@@ -1,12 +1,19 @@
-use consensus::{LargeFieldSSS, RBCSyncMsg, inverse_vandermonde, matrix_vector_multiply, vandermonde_matrix};
+use consensus::{LargeFieldSSS, CompletionReport, SecretShares, inverse_vandermonde, matrix_vector_multiply, vandermonde_matrix};
 use crypto::{LargeField, LargeFieldSer};
 use lambdaworks_math::{traits::ByteConversion, polynomial::Polynomial};
 use rayon::prelude::IntoParallelIterator;
 use types::{WrapperMsg, Replica, SyncMsg, SyncState};
 use rayon::prelude::{ParallelIterator};
+use tpke::threshold_key_export_path;
 
 use crate::{Context, msg::ProtMsg};
 
+/// Number of random sharings per batch the sharing phase combines, `per_batch` rounded up past a multiple of t+1.
+/// Each combines into t+1 output shares.
+pub fn padded_per_batch(per_batch: usize, num_faults: usize) -> usize{
+    per_batch + (num_faults+1) - per_batch%(num_faults+1)
+}
+
 impl Context{
     pub async fn process_consensus_output(&mut self, mut acs_output: Vec<Replica>){
         self.dpss_state.acs_output.extend(acs_output.clone());
@@ -29,7 +36,7 @@ impl Context{
         let mut shares_to_be_combined = Vec::new();
         
         let mut coin_shares_to_be_combined = Vec::new();
-        let per_batch = self.per_batch + (self.num_faults+1) - (self.per_batch)%(self.num_faults+1);
+        let per_batch = padded_per_batch(self.per_batch, self.num_faults);
         
         for _ in 0..self.num_batches*per_batch{
             shares_to_be_combined.push(Vec::new());
@@ -60,13 +67,13 @@ impl Context{
                     }
                     if batch == self.num_batches+1{
                         // Coin shares
-                        for (coin_index,share) in batch_shares.unwrap().0.clone().into_iter().enumerate(){
-                            coin_shares_to_be_combined[coin_index].push(share);
+                        for (coin_index,share) in batch_shares.unwrap().0.iter().enumerate(){
+                            coin_shares_to_be_combined[coin_index].push(share.clone());
                         }
                     }
                     else{
-                        for share in batch_shares.unwrap().0.clone(){
-                            shares_to_be_combined[index].push(share);
+                        for share in batch_shares.unwrap().0.iter(){
+                            shares_to_be_combined[index].push(share.clone());
                             index +=1;
                         }
                     }
@@ -75,8 +82,10 @@ impl Context{
                 ht_indices.push(LargeField::from((rep+1) as u64));
             }
         }
+        // Shares of all ACS dealers are in, keep them for offline reconstruction if asked to
+        self.export_shares();
         let vandermonde = LargeFieldSSS::vandermonde_matrix(ht_indices);
-        let combined_shares: Vec<Vec<LargeField>> = shares_to_be_combined.into_par_iter().map(|vec| {
+        let mut combined_shares: Vec<Vec<LargeField>> = shares_to_be_combined.into_par_iter().map(|vec| {
             let mut mult_shares = LargeFieldSSS::matrix_vector_multiply(&vandermonde, &vec);
             mult_shares.truncate(self.num_faults+1);
             mult_shares
@@ -88,6 +97,17 @@ impl Context{
             mult_shares
         }).flatten().collect();
 
+        // The last output share is left out of the public reconstruction below, it becomes the threshold decryption key share
+        let key_share = match threshold_key_export_path() {
+            Some(_) => combined_shares.last_mut().and_then(|shares| shares.pop()),
+            None => None
+        };
+        if self.refresh_epochs > 0 || self.handover_send.is_some() || key_share.is_some(){
+            // Keep the output shares for proactive refresh, the handover and the key export. The key share stays last.
+            let mut shares: Vec<LargeField> = combined_shares.iter().flatten().cloned().collect();
+            shares.extend(key_share);
+            self.refresh_state.shares = Some(SecretShares::new(shares));
+        }
         
         // Encode and reconstruct these combined shares
         // Efficient Public Reconstruction
@@ -107,6 +127,7 @@ impl Context{
             let shares_ser = shares.into_iter().map(|x| x.to_bytes_be()).collect();
             let prot_msg = ProtMsg::PubRecEcho1(shares_ser);
             let wrapper = WrapperMsg::new(prot_msg, self.myid, secret_key.as_slice());
+            consensus::count_sent_message(&wrapper);
             let cancel_handler = self.net_send.send(rep, wrapper).await;
             self.add_cancel_handler(cancel_handler);
         }
@@ -197,14 +218,49 @@ impl Context{
 
     // Invoke this function once you terminate the protocol
     pub async fn terminate(&mut self, data: String) {
+        if self.terminated{
+            return;
+        }
+        // Output shares are refreshed for the configured number of epochs before reporting
+        if self.refresh_state.epoch < self.refresh_epochs && self.start_refresh_epoch().await{
+            self.advance_refresh().await;
+            return;
+        }
+        self.report_completion(data).await;
+    }
+
+    pub async fn report_completion(&mut self, data: String) {
+        // Shares handed over to the next committee are reported once the handover completes
+        if self.handover_out.is_some(){
+            if let Some(handover_send) = self.handover_send.take(){
+                match self.refresh_state.shares.take() {
+                    Some(shares) if shares.len() > 1 => {
+                        log::info!("Handing {} output shares over to the next committee", shares.len()-1);
+                        let _status = handover_send.send(shares).await;
+                    },
+                    _ => {
+                        log::error!("No output shares to hand over, reporting without a handover");
+                        self.handover_out = None;
+                    }
+                }
+            }
+            if self.handover_out.is_some(){
+                return;
+            }
+        }
+        // The threshold decryption key is written before reporting, once the verification keys of the other nodes arrived
+        let data = match threshold_key_export_path() {
+            Some(_) if !self.threshold_key_state.exported => match self.publish_verification_key(data).await {
+                Some(data) => data,
+                None => return
+            },
+            _ => data
+        };
         if !self.terminated{
             self.terminated = true;
-            let rbc_sync_msg = RBCSyncMsg{
-                id: 1,
-                msg: data,
-            };
+            let completion_report = CompletionReport::new(1, data, self.start_time);
 
-            let ser_msg = bincode::serialize(&rbc_sync_msg).unwrap();
+            let ser_msg = bincode::serialize(&completion_report).unwrap();
             let cancel_handler = self
                 .sync_send
                 .send(
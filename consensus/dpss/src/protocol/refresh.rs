@@ -0,0 +1,329 @@
+use std::collections::{HashMap, HashSet};
+
+use consensus::{SecretShares, inverse_vandermonde, robust_interpolate, vandermonde_matrix};
+use crypto::{LargeField, LargeFieldSer, hash::Hash, rand_field_element};
+use lambdaworks_math::traits::ByteConversion;
+use types::Replica;
+
+use crate::{Context, msg::ProtMsg};
+
+/// State of one refresh epoch
+#[derive(Default)]
+pub struct RefreshEpochState{
+    pub started: bool,
+    // Dealers whose reshares terminated and were sent to ACS
+    pub acs_input: HashSet<Replica>,
+    // Dealers the ACS of this epoch agreed on, sorted
+    pub contributors: Option<Vec<Replica>>,
+    pub check_sent: bool,
+    // Aggregated old share and aggregated subshares of every contributor, per sender
+    pub checks: HashMap<Replica, (LargeField, HashMap<Replica, LargeField>)>,
+}
+
+pub struct RefreshState{
+    // Last completed epoch, 0 holds the shares output by DPSS
+    pub epoch: usize,
+    pub shares: Option<SecretShares>,
+    pub epochs: HashMap<usize, RefreshEpochState>,
+}
+
+impl RefreshState{
+    pub fn new() -> RefreshState{
+        RefreshState {
+            epoch: 0,
+            shares: None,
+            epochs: HashMap::default(),
+        }
+    }
+}
+
+/*
+    Proactive refresh of the DPSS output shares. In epoch e, every node reshares its current shares once through ACSS,
+    in instance 2*key-1 with key = num_batches+1+e. The secret equivalence check of the sharing phase is not run on reshares,
+    the check below proves them correct.
+    The first of the old shares acts as a mask. It is a random DPSS output that hides the aggregated old shares opened
+    in the check, and is dropped after every epoch, so a run can refresh at most one epoch fewer than it has output shares.
+    ACS instance 1+e agrees on the contributors. Nodes then open a random linear combination of their old shares and of the
+    subshares of every contributor. A contributor's reshare is correct if its combined subshares interpolate to the
+    combined old share at the contributor's evaluation point. The first t+1 correct contributors are combined with
+    Lagrange coefficients into fresh shares of the same secrets, on new random polynomials.
+*/
+impl Context{
+    pub fn refresh_key(&self, epoch: usize) -> usize{
+        self.num_batches+1+epoch
+    }
+
+    /// Refresh epoch of a batch key, None for the batches of the sharing phase
+    pub fn refresh_epoch_of(&self, inst_key: usize) -> Option<usize>{
+        if inst_key > self.num_batches+1{
+            Some(inst_key-self.num_batches-1)
+        }
+        else{
+            None
+        }
+    }
+
+    /// Starts the epoch after the last completed one. Returns false if the shares cannot be refreshed.
+    pub async fn start_refresh_epoch(&mut self) -> bool{
+        let epoch = self.refresh_state.epoch+1;
+        if self.refresh_state.epochs.get(&epoch).map(|epoch_state| epoch_state.started).unwrap_or(false){
+            return true;
+        }
+        let mut reshare = match self.refresh_state.shares.as_ref() {
+            Some(shares) if shares.len() > 1 => shares.to_vec(),
+            _ => {
+                log::error!("No output shares to refresh in epoch {}, one mask and at least one share are needed", epoch);
+                return false;
+            }
+        };
+        log::info!("Starting refresh epoch {} for {} shares", epoch, reshare.len()-1);
+        let padded_len = self.acss.padded_len(reshare.len());
+        while reshare.len() < padded_len{
+            reshare.push(rand_field_element());
+        }
+
+        let key = self.refresh_key(epoch);
+        self.acss.share(2*key-1, reshare).await;
+        self.refresh_state.epochs.entry(epoch).or_default().started = true;
+        true
+    }
+
+    /// Runs the current epoch as far as the messages received so far allow, and moves on to the next epoch or reports once it completes
+    pub async fn advance_refresh(&mut self){
+        loop {
+            let epoch = self.refresh_state.epoch+1;
+            self.try_refresh_check(epoch).await;
+            if !self.try_refresh_complete(epoch).await{
+                return;
+            }
+            if self.refresh_state.epoch < self.refresh_epochs && self.start_refresh_epoch().await{
+                continue;
+            }
+            self.report_completion("Terminate".to_string()).await;
+            return;
+        }
+    }
+
+    /// Sends a dealer's reshare to the ACS of its epoch once its sharing terminated
+    pub async fn check_refresh_reshare(&mut self, epoch: usize, dealer: Replica){
+        if epoch <= self.refresh_state.epoch || epoch > self.refresh_epochs{
+            return;
+        }
+        let key = self.refresh_key(epoch);
+        let shared = self.dpss_state.acss_map.get(&dealer)
+            .and_then(|share_inst_map| share_inst_map.get(&key))
+            .map(|(reshare, _)| reshare.is_some())
+            .unwrap_or(false);
+        if !shared{
+            return;
+        }
+
+        let epoch_state = self.refresh_state.epochs.entry(epoch).or_default();
+        if epoch_state.acs_input.contains(&dealer){
+            return;
+        }
+        epoch_state.acs_input.insert(dealer);
+        log::info!("Sending reshare of dealer {} in refresh epoch {} to ACS", dealer, epoch);
+        let _status = self.acs_term_event.send((1+epoch, dealer, vec![])).await;
+    }
+
+    pub async fn process_refresh_acs_output(&mut self, epoch: usize, mut contributors: Vec<Replica>){
+        if epoch <= self.refresh_state.epoch{
+            return;
+        }
+        contributors.sort();
+        log::info!("ACS of refresh epoch {} output contributors {:?}", epoch, contributors);
+        self.refresh_state.epochs.entry(epoch).or_default().contributors = Some(contributors);
+        self.advance_refresh().await;
+    }
+
+    /// Opens the aggregated old share and the aggregated subshares of every contributor
+    pub async fn try_refresh_check(&mut self, epoch: usize){
+        if epoch != self.refresh_state.epoch+1{
+            return;
+        }
+        let epoch_state = match self.refresh_state.epochs.get(&epoch) {
+            Some(epoch_state) => epoch_state,
+            None => return,
+        };
+        if !epoch_state.started || epoch_state.check_sent || epoch_state.contributors.is_none(){
+            return;
+        }
+        let contributors = epoch_state.contributors.clone().unwrap();
+        let key = self.refresh_key(epoch);
+
+        // All reshares of the contributors must have terminated here
+        let mut roots = Vec::new();
+        for contributor in contributors.iter(){
+            match self.dpss_state.acss_map.get(contributor).and_then(|share_inst_map| share_inst_map.get(&key)) {
+                Some((Some((_, root)), _)) => {
+                    roots.push(*root);
+                },
+                _ => {
+                    log::info!("Reshares of contributor {} in refresh epoch {} did not terminate yet, will retry later", contributor, epoch);
+                    return;
+                }
+            }
+        }
+        let challenge = self.refresh_challenge(roots);
+
+        let old_shares = self.refresh_state.shares.as_ref().unwrap();
+        let old_agg = aggregate(old_shares, challenge);
+        let mut new_aggs = Vec::new();
+        for contributor in contributors.iter(){
+            let (subshares, _) = self.dpss_state.acss_map.get(contributor).unwrap().get(&key).unwrap().0.as_ref().unwrap();
+            if subshares.len() < old_shares.len(){
+                log::error!("Contributor {} reshared {} values in refresh epoch {}, expected at least {}", contributor, subshares.len(), epoch, old_shares.len());
+                continue;
+            }
+            new_aggs.push((*contributor, aggregate(&subshares[..old_shares.len()], challenge).to_bytes_be()));
+        }
+
+        self.refresh_state.epochs.get_mut(&epoch).unwrap().check_sent = true;
+        self.broadcast(ProtMsg::RefreshCheck(epoch, old_agg.to_bytes_be(), new_aggs)).await;
+    }
+
+    pub async fn process_refresh_check_msg(&mut self, epoch: usize, old_agg_ser: LargeFieldSer, new_aggs_ser: Vec<(Replica, LargeFieldSer)>, sender: Replica){
+        if epoch <= self.refresh_state.epoch || epoch > self.refresh_epochs{
+            return;
+        }
+        let old_agg = match LargeField::from_bytes_be(old_agg_ser.as_slice()) {
+            Ok(old_agg) => old_agg,
+            Err(_) => {
+                log::error!("Party {} sent a malformed refresh check for epoch {}", sender, epoch);
+                return;
+            }
+        };
+        let mut new_aggs = HashMap::default();
+        for (contributor, agg_ser) in new_aggs_ser.into_iter(){
+            match LargeField::from_bytes_be(agg_ser.as_slice()) {
+                Ok(agg) => {
+                    new_aggs.insert(contributor, agg);
+                },
+                Err(_) => {
+                    log::error!("Party {} sent a malformed refresh check for epoch {}", sender, epoch);
+                    return;
+                }
+            }
+        }
+
+        let epoch_state = self.refresh_state.epochs.entry(epoch).or_default();
+        if epoch_state.checks.contains_key(&sender){
+            return;
+        }
+        epoch_state.checks.insert(sender, (old_agg, new_aggs));
+        self.advance_refresh().await;
+    }
+
+    /// Combines the reshares of the first t+1 correct contributors into fresh shares. Returns true once the epoch completed.
+    pub async fn try_refresh_complete(&mut self, epoch: usize) -> bool{
+        if epoch != self.refresh_state.epoch+1{
+            return false;
+        }
+        let epoch_state = match self.refresh_state.epochs.get(&epoch) {
+            Some(epoch_state) => epoch_state,
+            None => return false,
+        };
+        if !epoch_state.check_sent || epoch_state.checks.len() < 2*self.num_faults+1{
+            return false;
+        }
+        let contributors = epoch_state.contributors.clone().unwrap();
+        let key = self.refresh_key(epoch);
+        let num_shares = self.refresh_state.shares.as_ref().unwrap().len();
+
+        // Decode the aggregated old sharing, faulty senders are corrected for
+        let mut eval_points = Vec::new();
+        let mut old_aggs = Vec::new();
+        for rep in 0..self.num_nodes{
+            if let Some((old_agg, _)) = epoch_state.checks.get(&rep){
+                eval_points.push(LargeField::from((rep+1) as u64));
+                old_aggs.push(*old_agg);
+            }
+        }
+        let old_poly = match robust_interpolate(&eval_points, &old_aggs, self.num_faults, 2*self.num_faults+1) {
+            Some(poly) => poly,
+            None => {
+                log::info!("Aggregated old shares of refresh epoch {} do not agree yet, waiting for more", epoch);
+                return false;
+            }
+        };
+
+        let mut accepted = Vec::new();
+        for contributor in contributors.iter(){
+            let (subshares, _) = self.dpss_state.acss_map.get(contributor).unwrap().get(&key).unwrap().0.as_ref().unwrap();
+            if subshares.len() < num_shares{
+                continue;
+            }
+            let mut eval_points = Vec::new();
+            let mut new_aggs = Vec::new();
+            for rep in 0..self.num_nodes{
+                if let Some(new_agg) = epoch_state.checks.get(&rep).and_then(|(_, new_aggs)| new_aggs.get(contributor)){
+                    eval_points.push(LargeField::from((rep+1) as u64));
+                    new_aggs.push(*new_agg);
+                }
+            }
+            let new_poly = match robust_interpolate(&eval_points, &new_aggs, self.num_faults, 2*self.num_faults+1) {
+                Some(poly) => poly,
+                None => {
+                    log::info!("Aggregated subshares of contributor {} in refresh epoch {} do not agree yet, waiting for more", contributor, epoch);
+                    return false;
+                }
+            };
+            if new_poly.evaluate(&LargeField::zero()) == old_poly.evaluate(&LargeField::from((contributor+1) as u64)){
+                accepted.push(*contributor);
+            }
+            else{
+                log::error!("Contributor {} did not reshare its shares in refresh epoch {}", contributor, epoch);
+            }
+        }
+        if accepted.len() < self.num_faults+1{
+            log::error!("Only {} correct contributors in refresh epoch {}, cannot refresh", accepted.len(), epoch);
+            return false;
+        }
+        accepted.truncate(self.num_faults+1);
+
+        // Lagrange coefficients at 0 of the accepted contributors
+        let vandermonde = vandermonde_matrix(accepted.iter().map(|rep| LargeField::from((rep+1) as u64)).collect());
+        let lagrange_coeffs = inverse_vandermonde(vandermonde).swap_remove(0);
+        let mut new_shares = vec![LargeField::zero(); num_shares];
+        for (contributor, coeff) in accepted.iter().zip(lagrange_coeffs.iter()){
+            let (subshares, _) = self.dpss_state.acss_map.get(contributor).unwrap().get(&key).unwrap().0.as_ref().unwrap();
+            for (new_share, subshare) in new_shares.iter_mut().zip(subshares.iter()){
+                *new_share += coeff*subshare;
+            }
+        }
+        // The mask was opened in this epoch's check
+        new_shares.remove(0);
+
+        // Old shares and reshares are wiped when dropped
+        self.refresh_state.shares = Some(SecretShares::new(new_shares));
+        for share_inst_map in self.dpss_state.acss_map.values_mut(){
+            share_inst_map.remove(&key);
+        }
+        self.refresh_state.epochs.remove(&epoch);
+        self.refresh_state.epoch = epoch;
+        log::info!("Completed refresh epoch {} with contributors {:?}, holding {} fresh shares", epoch, accepted, num_shares-1);
+        true
+    }
+
+    // Common random challenge of an epoch, bound to the reshares of all contributors
+    fn refresh_challenge(&self, roots: Vec<Hash>) -> LargeField{
+        let mut roots = roots.into_iter();
+        let mut core_root = roots.next().unwrap();
+        for root in roots{
+            core_root = self.hash_context.hash_two(core_root, root);
+        }
+        LargeField::from_bytes_be(core_root.as_slice()).unwrap()
+    }
+}
+
+// Sum of challenge^(k+1)*shares[k]
+fn aggregate(shares: &[LargeField], challenge: LargeField) -> LargeField{
+    let mut challenge_mul = challenge;
+    let mut agg = LargeField::zero();
+    for share in shares.iter(){
+        agg += &challenge_mul*share;
+        challenge_mul = &challenge_mul*&challenge;
+    }
+    agg
+}
@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+
+use tpke::{GroupElement, ThresholdKey, ThresholdKeyFile, threshold_key_export_path};
+use types::Replica;
+
+use crate::{Context, msg::ProtMsg};
+
+/// State of the threshold decryption key export
+#[derive(Default)]
+pub struct ThresholdKeyState{
+    // Completion report held back until the key is written
+    pub report: Option<String>,
+    pub verification_keys: HashMap<Replica, GroupElement>,
+    pub exported: bool,
+}
+
+/*
+    Threshold decryption key derived from the DPSS output. The last output share of every node is kept out of the
+    public reconstruction and, after the refresh epochs, is the node's share s_i of the secret decryption key.
+    Every node publishes g^{s_i}, and the verification keys of all nodes are taken from a degree-t curve in the exponent
+    through 2t+1 published keys (see ThresholdKey::from_published_keys). The key is written to the export path
+    before the node reports completion, for atomic_bc to encrypt proposals under it.
+*/
+impl Context{
+    /// Publishes the verification key of this node's key share and holds the completion report back until the key is written.
+    /// Returns the report if there is no key share to export.
+    pub async fn publish_verification_key(&mut self, data: String) -> Option<String>{
+        if self.threshold_key_state.report.is_some(){
+            return None;
+        }
+        let key_share = match self.refresh_state.shares.as_ref().and_then(|shares| shares.last().cloned()) {
+            Some(key_share) => key_share,
+            None => {
+                log::error!("No output share left for the threshold decryption key, reporting without a key");
+                self.threshold_key_state.exported = true;
+                return Some(data);
+            }
+        };
+        self.threshold_key_state.report = Some(data);
+        // Our own key comes back over the network like everyone else's, which completes the export once enough keys are in
+        let verification_key = ThresholdKey::public_key_share(&key_share);
+        self.broadcast(ProtMsg::VerificationKey(verification_key.to_bytes())).await;
+        None
+    }
+
+    pub async fn process_verification_key_msg(&mut self, key_ser: Vec<u8>, sender: Replica){
+        let verification_key = match GroupElement::from_bytes(key_ser.as_slice()) {
+            Some(verification_key) => verification_key,
+            None => {
+                log::warn!("Invalid verification key from node {}", sender);
+                return;
+            }
+        };
+        self.threshold_key_state.verification_keys.entry(sender).or_insert(verification_key);
+        self.export_threshold_key().await;
+    }
+
+    async fn export_threshold_key(&mut self){
+        if self.threshold_key_state.exported || self.threshold_key_state.report.is_none(){
+            return;
+        }
+        let key_share = self.refresh_state.shares.as_ref().unwrap().last().cloned().unwrap();
+        let key = match ThresholdKey::from_published_keys(self.myid, self.num_nodes, self.num_faults, key_share, &self.threshold_key_state.verification_keys) {
+            Some(key) => key,
+            None => {
+                log::info!("Verification keys do not determine the threshold key yet, waiting for more");
+                return;
+            }
+        };
+        self.threshold_key_state.exported = true;
+        let path = threshold_key_export_path().unwrap();
+        if !key.is_consistent(){
+            log::error!("Key share does not match the agreed verification key, not writing {}", path);
+        }
+        else{
+            match ThresholdKeyFile::from_key(&key).write_to_file(path) {
+                Ok(()) => log::info!("Wrote the threshold decryption key to {}", path),
+                Err(e) => log::error!("Threshold key export failed: {}", e),
+            }
+        }
+        let data = self.threshold_key_state.report.take().unwrap();
+        self.report_completion(data).await;
+    }
+}
@@ -7,7 +7,7 @@ use config::Node;
 
 use fnv::FnvHashMap;
 use network::{
-    plaintcp::{CancelHandler, TcpReceiver, TcpReliableSender},
+    plaintcp::CancelHandler,
     Acknowledgement,
 };
 use tokio::sync::{
@@ -17,15 +17,15 @@ use tokio::sync::{
 // use tokio_util::time::DelayQueue;
 use types::{Replica, WrapperMsg};
 
-use consensus::{LargeFieldSer};
+use consensus::{HashKeys, LargeFieldSer, SecretBytes, ReplicaSender};
 
 use crypto::{aes_hash::HashState};
 
-use crate::{msg::{ProtMsg, Handler}, protocol::{MVBAExecState}};
+use crate::{msg::ProtMsg, protocol::{MVBAExecState}};
 
 pub struct Context {
     /// Networking context
-    pub net_send: TcpReliableSender<Replica, WrapperMsg<ProtMsg>, Acknowledgement>,
+    pub net_send: ReplicaSender<WrapperMsg<ProtMsg>>,
     pub net_recv: UnboundedReceiver<WrapperMsg<ProtMsg>>,
     
     /// Data context
@@ -35,7 +35,7 @@ pub struct Context {
     _byz: bool,
 
     /// Secret Key map
-    pub sec_key_map: HashMap<Replica, Vec<u8>>,
+    pub sec_key_map: HashMap<Replica, SecretBytes>,
 
     /// Hardware acceleration context
     pub hash_context: HashState,
@@ -67,6 +67,7 @@ pub struct Context {
 
 impl Context {
     pub fn spawn(config: Node,
+        hash_keys: HashKeys,
         input_reqs: Receiver<(usize, usize, Vec<LargeFieldSer>)>, 
         output_shares: Sender<(usize, Vec<usize>)>,
         byz: bool) -> anyhow::Result<(oneshot::Sender<()>, Vec<anyhow::Result<oneshot::Sender<()>>>)> {
@@ -101,22 +102,12 @@ impl Context {
 
         // Setup networking
         let (tx_net_to_consensus, rx_net_to_consensus) = unbounded_channel();
-        TcpReceiver::<Acknowledgement, WrapperMsg<ProtMsg>, _>::spawn(
-            my_address,
-            Handler::new(tx_net_to_consensus),
-        );
-
-        let consensus_net = TcpReliableSender::<Replica, WrapperMsg<ProtMsg>, Acknowledgement>::with_peers(
-            consensus_addrs.clone(),
-        );
+        let consensus_net = consensus::spawn_replica_network(my_address, consensus_addrs.clone(), tx_net_to_consensus);
         
         let (exit_tx, exit_rx) = oneshot::channel();
 
         // Keyed AES ciphers
-        let key0 = [5u8; 16];
-        let key1 = [29u8; 16];
-        let key2 = [23u8; 16];
-        let hashstate = HashState::new(key0, key1, key2);
+        let hashstate = hash_keys.hash_state();
 
         let (ctrbc_req_send_channel, ctrbc_req_recv_channel) = channel(10000);
         let (ctrbc_out_send_channel, ctrbc_out_recv_channel) = channel(10000);
@@ -159,8 +150,8 @@ impl Context {
             };
 
             // Populate secret keys from config
-            for (id, sk_data) in config.sk_map.clone() {
-                c.sec_key_map.insert(id, sk_data.clone());
+            for (id, sk_data) in config.sk_map.iter() {
+                c.sec_key_map.insert(*id, SecretBytes::new(sk_data.clone()));
             }
 
             // Run the consensus context
@@ -180,6 +171,7 @@ impl Context {
 
         let _ba_serv_status = binary_ba::Context::spawn(
             ba_config,
+            hash_keys.clone(),
             bin_aa_req_recv,
             bin_aa_out_send,
             false
@@ -189,6 +181,7 @@ impl Context {
 
         let _ra_serv_status = ra::Context::spawn(
             ra_config,
+            hash_keys.clone(),
             ra_aa_req_recv,
             ra_aa_out_send,
             false
@@ -201,9 +194,11 @@ impl Context {
     }
 
     pub async fn broadcast(&mut self, protmsg: ProtMsg) {
-        let sec_key_map = self.sec_key_map.clone();
-        for (replica, sec_key) in sec_key_map.into_iter() {
+        let replicas: Vec<Replica> = self.sec_key_map.keys().cloned().collect();
+        for replica in replicas {
+            let sec_key = self.sec_key_map.get(&replica).unwrap();
             let wrapper_msg = WrapperMsg::new(protmsg.clone(), self.myid, &sec_key.as_slice());
+            consensus::count_sent_message(&wrapper_msg);
             let cancel_handler: CancelHandler<Acknowledgement> = self.net_send.send(replica, wrapper_msg).await;
             self.add_cancel_handler(cancel_handler);
         }
@@ -214,6 +209,7 @@ impl Context {
     }
 
     pub async fn send(&mut self, replica: Replica, wrapper_msg: WrapperMsg<ProtMsg>) {
+        consensus::count_sent_message(&wrapper_msg);
         let cancel_handler: CancelHandler<Acknowledgement> =
             self.net_send.send(replica, wrapper_msg).await;
         self.add_cancel_handler(cancel_handler);
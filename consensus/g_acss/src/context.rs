@@ -10,10 +10,10 @@ use config::Node;
 use fnv::FnvHashMap;
 use lambdaworks_math::{ fft::cpu::roots_of_unity::get_powers_of_primitive_root, field::traits::RootsConfig};
 use network::{
-    plaintcp::{CancelHandler, TcpReceiver, TcpReliableSender},
+    plaintcp::CancelHandler,
     Acknowledgement,
 };
-use consensus::{rand_field_element, FoldingDZKContext, LargeField, LargeFieldSSS};
+use consensus::{AcssOutput, HashKeys, rand_field_element, FoldingDZKContext, LargeField, LargeFieldSSS, SecretBytes, ReplicaSender, Acss, AcssParams, AcssScheme, AcssService};
 
 use tokio::{sync::{
     mpsc::{Receiver, Sender, channel, unbounded_channel, UnboundedReceiver},
@@ -22,15 +22,14 @@ use tokio::{sync::{
 // use tokio_util::time::DelayQueue;
 use types::{Replica, WrapperMsg};
 
-use ha_crypto::{aes_hash::HashState, hash::Hash};
+use ha_crypto::aes_hash::HashState;
 
 use crate::{protocol::{ACSSABState, SymmetricKeyState}, msg::ProtMsg};
 
-use crate::Handler;
 
 pub struct Context {
     /// Networking context
-    pub net_send: TcpReliableSender<Replica, WrapperMsg<ProtMsg>, Acknowledgement>,
+    pub net_send: ReplicaSender<WrapperMsg<ProtMsg>>,
     pub net_recv: UnboundedReceiver<WrapperMsg<ProtMsg>>,
     
     /// Data context
@@ -39,7 +38,7 @@ pub struct Context {
     pub num_faults: usize,
 
     /// Secret Key map
-    pub sec_key_map: HashMap<Replica, Vec<u8>>,
+    pub sec_key_map: HashMap<Replica, SecretBytes>,
 
     /// Hardware acceleration context
     pub hash_context: HashState,
@@ -64,7 +63,7 @@ pub struct Context {
     
     // Input queue for receiving acss requests with bool field indicating ACSS or AVSS.
     pub inp_acss: Receiver<(usize, Vec<LargeField>)>,
-    pub out_acss: Sender<(usize, Replica, Hash, Option<Vec<LargeField>>)>,
+    pub out_acss: Sender<AcssOutput>,
 
     pub inp_pub_rec_in: Receiver<(usize, Replica)>,
     pub out_pub_rec_out: Sender<(usize, Replica, Vec<LargeField>)>,
@@ -98,8 +97,9 @@ pub struct Context {
 impl Context {
     pub fn spawn(
         config: Node,
+        hash_keys: HashKeys,
         input_acss: Receiver<(usize,Vec<LargeField>)>, 
-        output_acss: Sender<(usize,Replica,Hash,Option<Vec<LargeField>>)>,
+        output_acss: Sender<AcssOutput>,
         input_pubrec: Receiver<(usize, Replica)>,
         output_pubrec: Sender<(usize, Replica, Vec<LargeField>)>, 
         use_fft: bool,
@@ -141,10 +141,6 @@ impl Context {
 
         // Setup networking
         let (tx_net_to_consensus, rx_net_to_consensus) = unbounded_channel();
-        TcpReceiver::<Acknowledgement, WrapperMsg<ProtMsg>, _>::spawn(
-            my_address,
-            Handler::new(tx_net_to_consensus),
-        );
 
         // let syncer_listen_port = config.client_port;
         // let syncer_l_address = to_socket_address("0.0.0.0", syncer_listen_port);
@@ -156,20 +152,15 @@ impl Context {
         //     SyncHandler::new(tx_net_to_client),
         // );
 
-        let consensus_net = TcpReliableSender::<Replica, WrapperMsg<ProtMsg>, Acknowledgement>::with_peers(
-            consensus_addrs.clone(),
-        );
+        let consensus_net = consensus::spawn_replica_network(my_address, consensus_addrs.clone(), tx_net_to_consensus);
         // let sync_net =
         //     TcpReliableSender::<Replica, SyncMsg, Acknowledgement>::with_peers(syncer_map);
         
         let (exit_tx, exit_rx) = oneshot::channel();
 
         // Hardware accelerated Hash functions - Keyed AES ciphers
-        let key0 = [5u8; 16];
-        let key1 = [29u8; 16];
-        let key2 = [23u8; 16];
-        let hashstate = HashState::new(key0, key1, key2);
-        let hashstate2 =  HashState::new(key0, key1, key2);
+        let hashstate = hash_keys.hash_state();
+        let hashstate2 =  hash_keys.hash_state();
 
         let threshold:usize = 10000;
         let rbc_start_id = threshold*config.id;
@@ -274,8 +265,8 @@ impl Context {
             };
 
             // Populate secret keys from config
-            for (id, sk_data) in config.sk_map.clone() {
-                c.sec_key_map.insert(id, sk_data.clone());
+            for (id, sk_data) in config.sk_map.iter() {
+                c.sec_key_map.insert(*id, SecretBytes::new(sk_data.clone()));
             }
 
             // Run the consensus context
@@ -286,7 +277,8 @@ impl Context {
         let mut vector_statuses = Vec::new();
         
         let _status =  asks::Context::spawn(
-            asks_config, 
+            asks_config,
+            hash_keys.clone(), 
             asks_req_recv_channel, 
             asks_out_send_channel, 
             false
@@ -302,7 +294,8 @@ impl Context {
 
         vector_statuses.push(_status);
         let _status =  avid::Context::spawn(
-            avid_config, 
+            avid_config,
+            hash_keys.clone(), 
             avid_req_recv_channel, 
             avid_out_send_channel, 
             false
@@ -310,6 +303,7 @@ impl Context {
         vector_statuses.push(_status);
         let _status = ra::Context::spawn(
             ra_config,
+            hash_keys.clone(),
             ra_req_recv_channel,
             ra_out_send_channel,
             false
@@ -318,10 +312,47 @@ impl Context {
         Ok((exit_tx, vector_statuses))
     }
 
+    /// Spawns the service behind the common `Acss` interface, the way DPSS drives it
+    pub fn spawn_acss(config: Node, hash_keys: HashKeys, params: AcssParams) -> Result<Box<dyn Acss>>{
+        let (acss_req_send, acss_req_recv) = channel(10000);
+        let (acss_out_send, acss_out_recv) = channel(10000);
+        let (pub_rec_req_send, pub_rec_req_recv) = channel(10000);
+        let (pub_rec_out_send, pub_rec_out_recv) = channel(10000);
+        let num_faults = config.num_faults;
+
+        let (exit_tx, statuses) = Self::spawn(
+            config,
+            hash_keys,
+            acss_req_recv,
+            acss_out_send,
+            pub_rec_req_recv,
+            pub_rec_out_send,
+            params.use_fft,
+            params.lin_or_quad,
+            params.byz
+        )?;
+        let mut exit_txs = vec![exit_tx];
+        for status in statuses{
+            exit_txs.push(status?);
+        }
+        Ok(Box::new(AcssService {
+            scheme: AcssScheme::GAcss,
+            num_faults: num_faults,
+            acss_req: acss_req_send,
+            acss_out: acss_out_recv,
+            pub_rec_req: pub_rec_req_send,
+            pub_rec_out: pub_rec_out_recv,
+            pub_rec_selected: None,
+            exit_txs: exit_txs,
+        }))
+    }
+
     pub async fn broadcast(&mut self, protmsg: ProtMsg) {
-        let sec_key_map = self.sec_key_map.clone();
-        for (replica, sec_key) in sec_key_map.into_iter() {
+        let replicas: Vec<Replica> = self.sec_key_map.keys().cloned().collect();
+        for replica in replicas {
+            let sec_key = self.sec_key_map.get(&replica).unwrap();
             let wrapper_msg = WrapperMsg::new(protmsg.clone(), self.myid, &sec_key.as_slice());
+            consensus::count_sent_message(&wrapper_msg);
             let cancel_handler: CancelHandler<Acknowledgement> = self.net_send.send(replica, wrapper_msg).await;
             self.add_cancel_handler(cancel_handler);
         }
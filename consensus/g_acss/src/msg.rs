@@ -1,7 +1,10 @@
+use std::fmt;
+
 use consensus::{LargeFieldSer};
 use ha_crypto::hash::Hash;
 use serde::{Serialize, Deserialize};
 use types::Replica;
+use zeroize::Zeroize;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct CommDZKMsg{
@@ -16,7 +19,7 @@ pub struct CommDZKMsg{
     pub instance_id: usize,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Serialize, Deserialize, Clone)]
 pub struct AcssSKEShares{
     // Share and Nonce
     pub evaluations: (Vec<LargeFieldSer>,LargeFieldSer),
@@ -25,46 +28,29 @@ pub struct AcssSKEShares{
     pub batch: usize,
 }
 
+// Shares must not end up in logs, so only their counts are printed
+impl fmt::Debug for AcssSKEShares{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AcssSKEShares")
+            .field("evaluations", &self.evaluations.0.len())
+            .field("rep", &self.rep)
+            .field("batch", &self.batch)
+            .finish()
+    }
+}
+
+impl Drop for AcssSKEShares{
+    fn drop(&mut self) {
+        self.evaluations.0.zeroize();
+        self.evaluations.1.zeroize();
+        self.blinding_evaluations.0.zeroize();
+        self.blinding_evaluations.1.zeroize();
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub enum ProtMsg{
     PubRec(usize, AcssSKEShares),
     PubRecL1(usize,AcssSKEShares),
     PubRecL2(usize, Replica,Vec<LargeFieldSer>),
 }
-
-use async_trait::async_trait;
-use futures_util::SinkExt;
-use network::Acknowledgement;
-use tokio::sync::mpsc::UnboundedSender;
-
-use types::WrapperMsg;
-
-#[derive(Debug, Clone)]
-pub struct Handler {
-    consensus_tx: UnboundedSender<WrapperMsg<ProtMsg>>,
-}
-
-impl Handler {
-    pub fn new(consensus_tx: UnboundedSender<WrapperMsg<ProtMsg>>) -> Self {
-        Self { consensus_tx }
-    }
-}
-
-#[async_trait]
-impl network::Handler<Acknowledgement, WrapperMsg<ProtMsg>> for Handler {
-    async fn dispatch(&self, msg: WrapperMsg<ProtMsg>, writer: &mut network::Writer<Acknowledgement>) {
-        // Forward the message
-        let status = self.consensus_tx
-            .send(msg);
-        if status.is_err(){
-            log::error!("Failed to send consensus message to the channel because of {:?}", status.err().unwrap());
-        }
-        // Acknowledge
-        let status = writer
-            .send(Acknowledgement::Pong)
-            .await;
-        if status.is_err(){
-            log::error!("Failed to send consensus message to the channel because of {:?}", status.err().unwrap());
-        }
-    }
-}
\ No newline at end of file
@@ -2,7 +2,7 @@ use std::collections::{HashMap, HashSet, VecDeque};
 
 use ha_crypto::hash::Hash;
 use lambdaworks_math::polynomial::Polynomial;
-use consensus::{LargeField};
+use consensus::{LargeField, SecretBytes, SecretShares};
 use types::Replica;
 
 use crate::{msg::AcssSKEShares, CommDZKMsg};
@@ -13,7 +13,7 @@ pub struct ACSSABState{
     
     // Shares, Nonce, Blinding nonce share in each tuple
     pub batch_wise_shares: HashMap<Replica, HashMap<usize, AcssSKEShares>>,
-    pub shares: HashMap<Replica, Vec<LargeField>>,
+    pub shares: HashMap<Replica, SecretShares>,
 
     // Commitments to shares, commitments to blinding polynomial, and DZK polynomial
     pub commitments: HashMap<Replica, CommDZKMsg>,
@@ -67,8 +67,8 @@ impl ACSSABState{
 }
 
 pub struct SymmetricKeyState{
-    pub keys_from_me: HashMap<Replica, Vec<u8>>,
-    pub keys_to_me: HashMap<Replica, Vec<u8>>,
+    pub keys_from_me: HashMap<Replica, SecretBytes>,
+    pub keys_to_me: HashMap<Replica, SecretBytes>,
     pub term_asks_sharing: HashSet<Replica>,
     pub term_asks_recon: HashSet<Replica>,
 }
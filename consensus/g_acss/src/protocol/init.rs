@@ -3,10 +3,10 @@ use std::time::{SystemTime, UNIX_EPOCH};
 use crate::{msg::AcssSKEShares, CommDZKMsg, Context};
 use ha_crypto::encrypt;
 use lambdaworks_math::{unsigned_integer::element::UnsignedInteger, traits::ByteConversion};
-use consensus::{LargeField, LargeFieldSer, expand_sharing_to_n_evaluation_points, expand_sharing_to_n_evaluation_points_opt, sample_polynomials_from_prf, rand_field_element};
+use consensus::{LargeField, LargeFieldSer, expand_sharing_to_n_evaluation_points, expand_sharing_to_n_evaluation_points_opt, sample_polynomials_from_prf, PrfDomain, sharing_batch, SecretBytes, SecretShares, PRF_SHARES, PRF_NONCE, PRF_BLINDING, PRF_BLINDING_NONCE, rand_field_element};
 use types::Replica;
 
-use super::ACSSABState;
+use super::{ACSSABState, PRF_PROTOCOL};
 
 impl Context{
     pub async fn init_symmetric_key_setup(&mut self){
@@ -17,9 +17,9 @@ impl Context{
             for i in 0..self.num_nodes{
                 let key = rand_field_element();
                 symm_keys.push(key.clone());
-                self.symmetric_keys_avid.keys_from_me.insert(i, key.to_bytes_be().to_vec());
+                self.symmetric_keys_avid.keys_from_me.insert(i, SecretBytes::new(key.to_bytes_be().to_vec()));
             }
-            log::info!("Symmetric keys generated: {:?}", symm_keys);
+            log::info!("Generated {} symmetric keys", symm_keys.len());
             // Now share these keys through ASKS
             let _status = self.asks_inp_channel.send((
                 1,
@@ -59,10 +59,10 @@ impl Context{
     }
 
     pub async fn process_symmetric_key_reconstruction(&mut self, party: Replica, secret: Vec<LargeField>){
-        log::info!("Received reconstructed symmetric keys from party {} {:?}", party, secret);
+        log::info!("Received reconstructed symmetric keys from party {}", party);
         if !self.symmetric_keys_avid.keys_to_me.contains_key(&party){
             let secret = secret[0].clone().to_bytes_be();
-            self.symmetric_keys_avid.keys_to_me.insert(party, secret.to_vec());
+            self.symmetric_keys_avid.keys_to_me.insert(party, SecretBytes::new(secret.to_vec()));
             // Now that the key is available, we can use it to decrypt the secrets initialized by the party {party}
             self.decrypt_shares_all_instances(party).await;
         } else {
@@ -106,12 +106,13 @@ impl Context{
         
         // if !self.use_fft{
         // Generate Shamir secret shares using a PRG
+        let prf_domain = PrfDomain::new(PRF_PROTOCOL, instance_id, sharing_batch(instance_id), self.myid);
         let evaluations_prf = sample_polynomials_from_prf(
             secrets, 
-            self.symmetric_keys_avid.keys_from_me.clone(), 
+            &self.symmetric_keys_avid.keys_from_me, 
             self.num_faults, 
-            false, 
-            1u8
+            &prf_domain, 
+            PRF_SHARES
         );
 
         // Expand sampled degree-t univariate polynomials to all n points
@@ -152,10 +153,10 @@ impl Context{
         let nonce_secrets:Vec<LargeField> = (0..self.num_faults+1).into_iter().map(|_| rand_field_element()).collect();
         let evaluations_nonce_prf = sample_polynomials_from_prf(
             nonce_secrets,
-            self.symmetric_keys_avid.keys_from_me.clone(), 
+            &self.symmetric_keys_avid.keys_from_me, 
             self.num_faults, 
-            true, 
-            1u8
+            &prf_domain, 
+            PRF_NONCE
         );
         let (nonce_evaluations_ret,_nonce_coefficients) = expand_sharing_to_n_evaluation_points(
             evaluations_nonce_prf,
@@ -168,10 +169,10 @@ impl Context{
         let blinding_secrets: Vec<LargeField> = (0..self.num_faults+1).into_iter().map(|_| rand_field_element()).collect();
         let blinding_prf = sample_polynomials_from_prf(
             blinding_secrets, 
-            self.symmetric_keys_avid.keys_from_me.clone(), 
+            &self.symmetric_keys_avid.keys_from_me, 
             self.num_faults, 
-            true,
-            2u8
+            &prf_domain, 
+            PRF_BLINDING
         );
         let (blinding_poly_evaluations_vec, _blinding_poly_coefficients_vec) = expand_sharing_to_n_evaluation_points(
             blinding_prf,
@@ -184,10 +185,10 @@ impl Context{
         let blinding_nonce_secrets: Vec<LargeField> = (0..self.num_faults+1).into_iter().map(|_| rand_field_element()).collect();
         let blinding_nonce_prf = sample_polynomials_from_prf(
             blinding_nonce_secrets, 
-            self.symmetric_keys_avid.keys_from_me.clone(), 
+            &self.symmetric_keys_avid.keys_from_me, 
             self.num_faults, 
-            true,
-            3u8
+            &prf_domain, 
+            PRF_BLINDING_NONCE
         );
 
         let (nonce_blinding_poly_evaluations_vec, _nonce_blinding_poly_coefficients_vec) = expand_sharing_to_n_evaluation_points(
@@ -329,7 +330,7 @@ impl Context{
 
                 let shares_ser = bincode::serialize(&shares_struct).unwrap();
                 
-                let sec_key = self.symmetric_keys_avid.keys_from_me.get(&rep).unwrap().clone();
+                let sec_key = self.symmetric_keys_avid.keys_from_me.get(&rep).unwrap();
                 let enc_shares = encrypt(sec_key.as_slice(), shares_ser);
                 
                 let ser_enc_msg = bincode::serialize(&(instance_id,batch,enc_shares)).unwrap();
@@ -441,7 +442,7 @@ impl Context{
             }
         }
 
-        acss_ab_state.shares.insert(sender, accepted_shares);
+        acss_ab_state.shares.insert(sender, SecretShares::new(accepted_shares));
         log::info!("All DZK shares from sender {} in instance_id {} verified successfully", sender, instance_id);
         // Accumulate all shares from batches
         acss_ab_state.verification_status.insert(sender,true);
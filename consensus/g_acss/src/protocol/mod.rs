@@ -11,6 +11,9 @@ mod ra;
 
 mod ske;
 
+// Protocol name bound into the PRF seeds of this crate
+pub const PRF_PROTOCOL: &str = "g_acss";
+
 mod bv;
 
 mod comm;
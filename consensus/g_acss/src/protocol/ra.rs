@@ -31,12 +31,12 @@ impl Context{
                 log::info!("Sending shares back to syncer for sender {} for instance id {}",sender, instance_id);
                 let root_comm = acss_state.commitment_root_fe.get(&sender).unwrap().clone();
 
-                let shares: Vec<LargeField> = acss_state.shares.get(&sender).unwrap().clone();
-                let _status = self.out_acss.send((instance_id,sender, root_comm,Some(shares))).await;
+                let shares: Vec<LargeField> = acss_state.shares.get(&sender).unwrap().to_vec();
+                let _status = self.out_acss.send((instance_id,sender, root_comm,Some(shares), None)).await;
                 acss_state.acss_status.insert(sender);
             }
             else{
-                let _status = self.out_acss.send((instance_id, sender, [0;32],None)).await;
+                let _status = self.out_acss.send((instance_id, sender, [0;32],None, None)).await;
                 acss_state.acss_status.insert(sender);
             }
         }
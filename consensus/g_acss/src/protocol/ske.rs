@@ -1,12 +1,12 @@
 use std::collections::HashMap;
 
-use consensus::{interpolate_shares, inverse_vandermonde, matrix_vector_multiply, vandermonde_matrix, LargeField};
+use consensus::{interpolate_shares, PrfDomain, sharing_batch, PRF_SHARES, PRF_NONCE, PRF_BLINDING, PRF_BLINDING_NONCE, inverse_vandermonde, matrix_vector_multiply, vandermonde_matrix, LargeField};
 use ha_crypto::{decrypt};
 use lambdaworks_math::{polynomial::Polynomial, unsigned_integer::element::UnsignedInteger};
 use rayon::iter::{IntoParallelRefMutIterator, ParallelIterator};
 use types::Replica;
 
-use crate::{Context, msg::AcssSKEShares};
+use crate::{Context, msg::AcssSKEShares, protocol::PRF_PROTOCOL};
 
 impl Context{
     pub async fn interpolate_shares(&mut self, sender_rep: Replica, instance_id: usize){
@@ -35,8 +35,9 @@ impl Context{
         // Interpolate shares here for first t parties
         if !self.use_fft && self.myid < self.num_faults{
             // Interpolate your shares in this case
-            let secret_key = self.symmetric_keys_avid.keys_to_me.get(&sender_rep).clone().unwrap().clone();
-            let shares: Vec<LargeField> = interpolate_shares(secret_key.clone(), comm_dzk_vals.tot_shares, false, 1).into_iter().map(|el| el).collect();
+            let secret_key = self.symmetric_keys_avid.keys_to_me.get(&sender_rep).unwrap();
+            let prf_domain = PrfDomain::new(PRF_PROTOCOL, instance_id, sharing_batch(instance_id), sender_rep);
+            let shares: Vec<LargeField> = interpolate_shares(secret_key, comm_dzk_vals.tot_shares, &prf_domain, self.myid, PRF_SHARES).into_iter().map(|el| el).collect();
 
             let mut expanded_shares = shares.chunks(self.num_faults+1).map(|chunk| chunk.to_vec()).collect::<Vec<Vec<LargeField>>>();
             
@@ -62,7 +63,7 @@ impl Context{
                 }
             }
 
-            let mut expanded_nonce_shares = vec![interpolate_shares(secret_key.clone(),self.num_faults+1, true, 1u8)];
+            let mut expanded_nonce_shares = vec![interpolate_shares(secret_key, self.num_faults+1, &prf_domain, self.myid, PRF_NONCE)];
             
             expanded_nonce_shares.par_iter_mut().for_each(|grp| {
                 let coefficients = matrix_vector_multiply(&inv_vdm_matrix, &grp);
@@ -73,7 +74,7 @@ impl Context{
             });
             
 
-            let mut expanded_blinding_shares = vec![interpolate_shares(secret_key.clone(), self.num_faults+1, true, 2u8)];
+            let mut expanded_blinding_shares = vec![interpolate_shares(secret_key, self.num_faults+1, &prf_domain, self.myid, PRF_BLINDING)];
             expanded_blinding_shares.par_iter_mut().for_each(|grp| {
                 let coefficients = matrix_vector_multiply(&inv_vdm_matrix, &grp);
                 let poly = Polynomial::new(&coefficients);
@@ -82,7 +83,7 @@ impl Context{
                 grp.extend(new_evaluations);
             });
 
-            let mut expanded_blinding_nonce_shares = vec![interpolate_shares(secret_key, self.num_faults+1, true, 3u8)];
+            let mut expanded_blinding_nonce_shares = vec![interpolate_shares(secret_key, self.num_faults+1, &prf_domain, self.myid, PRF_BLINDING_NONCE)];
             expanded_blinding_nonce_shares.par_iter_mut().for_each(|grp| {
                 let coefficients = matrix_vector_multiply(&inv_vdm_matrix, &grp);
                 let poly = Polynomial::new(&coefficients);
@@ -119,7 +120,7 @@ impl Context{
             return;
         }
         
-        let sec_key = self.symmetric_keys_avid.keys_to_me.get(&sender_rep).unwrap().clone();
+        let sec_key = self.symmetric_keys_avid.keys_to_me.get(&sender_rep).unwrap();
         let enc_shares = acss_ab_state.enc_shares.get_mut(&sender_rep).unwrap();
         
         if !acss_ab_state.batch_wise_shares.contains_key(&sender_rep){
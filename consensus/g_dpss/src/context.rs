@@ -1,7 +1,7 @@
 use std::{
     collections::{HashMap, HashSet, VecDeque},
     net::{SocketAddr, SocketAddrV4},
-    time::{SystemTime, UNIX_EPOCH},
+    time::{SystemTime, UNIX_EPOCH, Duration},
 };
 use anyhow::{anyhow, Result};
 use config::Node;
@@ -15,18 +15,26 @@ use signal_hook::{iterator::Signals, consts::{SIGINT, SIGTERM}};
 use tokio::{sync::{
     mpsc::{UnboundedReceiver, Sender, Receiver, channel, unbounded_channel},
     oneshot,
-}};
+}, time};
 // use tokio_util::time::DelayQueue;
 use types::{Replica, SyncMsg, SyncState, WrapperMsg};
 
-use consensus::{SyncHandler, LargeFieldSSS, LargeField, LargeFieldSer};
-use crypto::{aes_hash::HashState, hash::Hash};
+use consensus::{HashKeys, SyncHandler, LargeFieldSSS, LargeField, LargeFieldSer, HEARTBEAT, HEARTBEAT_INTERVAL, SecretBytes, ReplicaSender, Acss, AcssBackends, AcssEvent, AcssNeeds, AcssParams, AcssScheme, spawn_acss_backend};
+use crypto::aes_hash::HashState;
 
-use crate::{msg::ProtMsg, Handler, protocol::{DPSSState, BAState}};
+use crate::{msg::ProtMsg, protocol::{DPSSState, BAState}};
+
+// ACSS schemes the protocol can run on
+const ACSS_BACKENDS: AcssBackends = AcssBackends {
+    ske: acss_ske::Context::spawn_acss,
+    g_acss: g_acss::Context::spawn_acss,
+    hacss: hacss::Context::spawn_acss,
+    bv: acss_bv::Context::spawn_acss,
+};
 
 pub struct Context {
     /// Networking context
-    pub net_send: TcpReliableSender<Replica, WrapperMsg<ProtMsg>, Acknowledgement>,
+    pub net_send: ReplicaSender<WrapperMsg<ProtMsg>>,
     pub net_recv: UnboundedReceiver<WrapperMsg<ProtMsg>>,
     pub sync_send: TcpReliableSender<Replica, SyncMsg, Acknowledgement>,
     pub sync_recv: UnboundedReceiver<SyncMsg>,
@@ -41,10 +49,12 @@ pub struct Context {
     pub ibft: bool,
 
     pub terminated: bool,
+    // Time at which the syncer started the run, reported back on completion
+    pub start_time: u128,
 
     pub large_field_shamir_ss: LargeFieldSSS,
     /// Secret Key map
-    pub sec_key_map: HashMap<Replica, Vec<u8>>,
+    pub sec_key_map: HashMap<Replica, SecretBytes>,
 
     /// Hardware acceleration context
     pub hash_context: HashState,
@@ -75,15 +85,12 @@ pub struct Context {
     pub acs_input_set: HashSet<Replica>,
     /// Channels to interact with other services
 
-    pub acss_req: Sender<(usize, Vec<LargeField>)>,
-    pub acss_out_recv: Receiver<(usize, usize, Hash, Option<Vec<LargeField>>)>,
+    /// ACSS backend, chosen at startup
+    pub acss: Box<dyn Acss>,
 
     pub acs_term_event: Sender<(usize,usize, Vec<LargeFieldSer>)>,
     pub acs_out_recv: Receiver<(usize,Vec<usize>)>,
 
-    pub pub_rec_req_send_channel: Sender<(usize, Replica)>,
-    pub pub_rec_out_recv_channel: Receiver<(usize, Replica, Vec<LargeField>)>,
-
     pub ra_req_send_channel: Sender<(usize, usize, usize)>,
     pub ra_out_recv_channel: Receiver<(usize, usize, usize)>,
 }
@@ -98,6 +105,8 @@ pub struct Context {
 impl Context {
     pub fn spawn(
         config: Node,
+        hash_keys: HashKeys,
+        acss_scheme: AcssScheme,
         num_batches: usize,
         per_batch: usize,
         opt_or_pess: bool,
@@ -147,10 +156,6 @@ impl Context {
 
         // Setup networking
         let (tx_net_to_consensus, rx_net_to_consensus) = unbounded_channel();
-        TcpReceiver::<Acknowledgement, WrapperMsg<ProtMsg>, _>::spawn(
-            my_address,
-            Handler::new(tx_net_to_consensus),
-        );
 
         let syncer_listen_port = config.client_port;
         let syncer_l_address = to_socket_address("0.0.0.0", syncer_listen_port);
@@ -162,18 +167,13 @@ impl Context {
             SyncHandler::new(tx_net_to_client),
         );
 
-        let consensus_net = TcpReliableSender::<Replica, WrapperMsg<ProtMsg>, Acknowledgement>::with_peers(
-            consensus_addrs.clone(),
-        );
+        let consensus_net = consensus::spawn_replica_network(my_address, consensus_addrs.clone(), tx_net_to_consensus);
         let sync_net =
             TcpReliableSender::<Replica, SyncMsg, Acknowledgement>::with_peers(syncer_map);
         let (exit_tx, exit_rx) = oneshot::channel();
 
         // Keyed AES ciphers
-        let key0 = [5u8; 16];
-        let key1 = [29u8; 16];
-        let key2 = [23u8; 16];
-        let hashstate = HashState::new(key0, key1, key2);
+        let hashstate = hash_keys.hash_state();
 
         let rbc_start_id = 1;
                 
@@ -183,18 +183,23 @@ impl Context {
             config.num_nodes
         );
         // Prepare ACSS context
-        let (acss_req_send_channel, acss_req_recv_channel) = channel(10000);
-        let (acss_out_send_channel, acss_out_recv_channel) = channel(10000);
-        
-        let (pub_rec_req_send_channel, pub_rec_req_recv_channel) = channel(10000);
-        let (pub_rec_out_send_channel, pub_rec_out_recv_channel) = channel(10000);
-
         let (acs_req_send_channel, acs_req_recv_channel) = channel(10000);
         let (acs_out_send_channel, acs_out_recv_channel) = channel(10000);
 
         let (ra_req_send_channel, ra_req_recv_channel) = channel(10000);
         let (ra_out_send_channel, ra_out_recv_channel) = channel(10000);        
 
+        // The ACSS service listens on its own ports and is owned by the context, which shuts it down on exit
+        // The secret equivalence check reconstructs at degree t
+        let acss_needs = AcssNeeds {
+            public_reconstruction: false,
+            degree: Some(config.num_faults),
+        };
+        let acss = spawn_acss_backend(&ACSS_BACKENDS, acss_scheme, acss_needs, acss_config, hash_keys.clone(), AcssParams {
+            use_fft: false,
+            lin_or_quad: lin_or_quad,
+            byz: false,
+        })?;
         let coin_secrets = (60/(config.num_faults+1))*(config.num_faults+1);
         tokio::spawn(async move {
             let mut c = Context {
@@ -208,6 +213,7 @@ impl Context {
                 myid: config.id,
                 _byz: byz,
                 terminated: false,
+                start_time: 0,
 
                 // Protocol configuration
                 opt_or_pess: opt_or_pess,
@@ -239,22 +245,18 @@ impl Context {
 
                 nonce_seed: 1,
 
-                acss_req: acss_req_send_channel,
-                acss_out_recv: acss_out_recv_channel,
+                acss: acss,
 
                 acs_term_event: acs_req_send_channel,
                 acs_out_recv: acs_out_recv_channel,
 
-                pub_rec_req_send_channel: pub_rec_req_send_channel,
-                pub_rec_out_recv_channel: pub_rec_out_recv_channel,
-
                 ra_req_send_channel: ra_req_send_channel,
                 ra_out_recv_channel: ra_out_recv_channel,
             };
 
             // Populate secret keys from config
-            for (id, sk_data) in config.sk_map.clone() {
-                c.sec_key_map.insert(id, sk_data.clone());
+            for (id, sk_data) in config.sk_map.iter() {
+                c.sec_key_map.insert(*id, SecretBytes::new(sk_data.clone()));
             }
 
             // Run the consensus context
@@ -263,21 +265,11 @@ impl Context {
             }
         });
         let ibft_or_acs = ibft;
-        let _acss_serv_status = g_acss::Context::spawn(
-            acss_config,
-            acss_req_recv_channel,
-            acss_out_send_channel, 
-            pub_rec_req_recv_channel,
-            pub_rec_out_send_channel,
-            false,
-            lin_or_quad,
-            false
-        );
-
         let _acs_serv_status; 
         if ibft_or_acs{
             _acs_serv_status = acs::Context::spawn(
                 acs_config,
+                hash_keys.clone(),
                 acs_req_recv_channel, 
                 acs_out_send_channel, 
                 false
@@ -299,6 +291,7 @@ impl Context {
 
         let _ra_status = ra::Context::spawn(
             ra_config,
+            hash_keys.clone(),
             ra_req_recv_channel,
             ra_out_send_channel,
             false,
@@ -325,9 +318,11 @@ impl Context {
     }
 
     pub async fn broadcast(&mut self, protmsg: ProtMsg) {
-        let sec_key_map = self.sec_key_map.clone();
-        for (replica, sec_key) in sec_key_map.into_iter() {
+        let replicas: Vec<Replica> = self.sec_key_map.keys().cloned().collect();
+        for replica in replicas {
+            let sec_key = self.sec_key_map.get(&replica).unwrap();
             let wrapper_msg = WrapperMsg::new(protmsg.clone(), self.myid, &sec_key.as_slice());
+            consensus::count_sent_message(&wrapper_msg);
             let cancel_handler: CancelHandler<Acknowledgement> = self.net_send.send(replica, wrapper_msg).await;
             self.add_cancel_handler(cancel_handler);
         }
@@ -338,6 +333,7 @@ impl Context {
     }
 
     pub async fn send(&mut self, replica: Replica, wrapper_msg: WrapperMsg<ProtMsg>) {
+        consensus::count_sent_message(&wrapper_msg);
         let cancel_handler: CancelHandler<Acknowledgement> =
             self.net_send.send(replica, wrapper_msg).await;
         self.add_cancel_handler(cancel_handler);
@@ -358,6 +354,8 @@ impl Context {
             )
             .await;
         self.add_cancel_handler(cancel_handler);
+        // Then keeps sending heartbeats so the syncer can tell dead nodes apart from slow ones
+        let mut heartbeat = time::interval(Duration::from_millis(HEARTBEAT_INTERVAL));
         loop {
             tokio::select! {
                 // Receive exit handlers
@@ -366,9 +364,23 @@ impl Context {
                     log::info!("Termination signal received by the server. Exiting.");
                     break
                 },
+                _ = heartbeat.tick() => {
+                    let cancel_handler = self
+                        .sync_send
+                        .send(
+                            0,
+                            SyncMsg {
+                                sender: self.myid,
+                                state: SyncState::ALIVE,
+                                value: HEARTBEAT.to_vec(),
+                            },
+                        )
+                        .await;
+                    self.add_cancel_handler(cancel_handler);
+                },
                 msg = self.net_recv.recv() => {
                     // Received messages are processed here
-                    log::trace!("Got a consensus message from the network: {:?}", msg);
+                    log::trace!("Got a consensus message from the network from {:?}", msg.as_ref().map(|msg| msg.sender));
                     let msg = msg.ok_or_else(||
                         anyhow!("Networking layer has closed")
                     )?;
@@ -380,10 +392,11 @@ impl Context {
                     )?;
                     match sync_msg.state {
                         SyncState::START =>{
-                            log::info!("Consensus Start time: {:?}", SystemTime::now()
+                            self.start_time = SystemTime::now()
                                 .duration_since(UNIX_EPOCH)
                                 .unwrap()
-                                .as_millis());
+                                .as_millis();
+                            log::info!("Consensus Start time: {:?}", self.start_time);
                             // Start your protocol from here
                             let _status = self.start_acss(self.num_batches*self.per_batch).await;
                             // Start code from here
@@ -400,12 +413,22 @@ impl Context {
                         _=>{}
                     }
                 },
-                acss_msg = self.acss_out_recv.recv() => {
+                acss_msg = self.acss.output() => {
                     let acss_msg = acss_msg.ok_or_else(||
                         anyhow!("Networking layer has closed")
                     )?;
-                    log::debug!("Received message from CTRBC channel {:?}", acss_msg);
-                    self.process_acss_event(acss_msg.0, acss_msg.1, acss_msg.2, acss_msg.3).await;
+                    match acss_msg {
+                        AcssEvent::Shared((inst, dealer, root_comm, shares, _opening)) => {
+                            log::debug!("Received ACSS output of instance {} from dealer {}", inst, dealer);
+                            self.process_acss_event(inst, dealer, root_comm, shares).await;
+                        },
+                        AcssEvent::Reconstructed((inst, dealer, _secrets)) => {
+                            log::error!("Unexpected public reconstruction of instance {} of dealer {}", inst, dealer);
+                        }
+                        AcssEvent::Opened((inst, dealer, _indices, _secrets)) => {
+                            log::error!("Unexpected selected public reconstruction of instance {} of dealer {}", inst, dealer);
+                        }
+                    }
                 },
                 acs_output = self.acs_out_recv.recv() =>{
                     let acs_output = acs_output.ok_or_else(||
@@ -416,6 +439,7 @@ impl Context {
                 },
             };
         }
+        self.acss.shutdown();
         Ok(())
     }
 }
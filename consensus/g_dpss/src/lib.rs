@@ -3,9 +3,6 @@ pub use context::*;
 
 mod protocol;
 
-mod handlers;
-pub use handlers::*;
-
 mod msg;
 
 mod process;
\ No newline at end of file
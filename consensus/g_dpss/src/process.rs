@@ -27,7 +27,8 @@ impl Context {
     }
 
     pub(crate) async fn process_msg(&mut self, wrapper_msg: WrapperMsg<ProtMsg>) {
-        log::trace!("Received protocol msg: {:?}", wrapper_msg);
+        // Payloads can carry shares, so only the sender is logged
+        log::trace!("Received protocol msg from {}", wrapper_msg.sender);
         delay_message_processing().await;
         let msg = Arc::new(wrapper_msg.clone());
 
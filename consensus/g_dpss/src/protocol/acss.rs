@@ -1,6 +1,6 @@
 use std::collections::{HashMap, HashSet};
 
-use consensus::LargeFieldSSS;
+use consensus::{LargeFieldSSS, SecretShares};
 use crypto::{LargeField, LargeFieldSer, hash::{Hash}, rand_field_element};
 use lambdaworks_math::traits::ByteConversion;
 use types::Replica;
@@ -16,8 +16,8 @@ impl Context{
             random_points.push(rand_int);
         }
         let id = self.max_id;
-        let _status = self.acss_req.send((id, random_points.clone())).await;
-        let _status = self.acss_req.send((id+1, random_points)).await;
+        self.acss.share(id, random_points.clone()).await;
+        self.acss.share(id+1, random_points).await;
 
     }
 
@@ -44,10 +44,10 @@ impl Context{
 
         let party_share_map_entry = party_share_map.get_mut(&inst_key).unwrap();
         if first_or_second == 1{
-            party_share_map_entry.0 = Some((shares_deser,root_comm));
+            party_share_map_entry.0 = Some((SecretShares::new(shares_deser),root_comm));
         }
         else{
-            party_share_map_entry.1 = Some((shares_deser,root_comm));
+            party_share_map_entry.1 = Some((SecretShares::new(shares_deser),root_comm));
         }
         
         if party_share_map_entry.0.is_some() && party_share_map_entry.1.is_some(){
@@ -67,7 +67,7 @@ impl Context{
             let mut agg_share_c1 = LargeField::from(0);
             let mut agg_share_c2 = LargeField::from(0);
 
-            for (f_share, s_share) in first_comm_shares.0.into_iter().zip(second_comm_shares.0.into_iter()){
+            for (f_share, s_share) in first_comm_shares.0.iter().zip(second_comm_shares.0.iter()){
                 agg_share_c1 += &root_comm_mul*f_share;
                 agg_share_c2 += &root_comm_mul*s_share;
 
@@ -126,7 +126,10 @@ impl Context{
                     eval_points.clone(), 
                     vec![evaluations], 
                     self.num_faults+1);
-                assert!(verf);
+                if !verf{
+                    log::error!("Points in c_1 for instance id {} and origin {} do not lie on a degree-t polynomial", inst_key, origin);
+                    return;
+                }
 
                 // reconstruct point
                 let secret = poly.unwrap()[0].evaluate(&LargeField::zero());
@@ -147,7 +150,7 @@ impl Context{
                 for rep in 0..self.num_nodes{
                     if c2_val_map.contains_key(&rep){
                         eval_points.push(LargeField::from((rep+1) as u64));
-                        evaluations.push(c1_val_map.get(&rep).unwrap().clone());
+                        evaluations.push(c2_val_map.get(&rep).unwrap().clone());
                     }
                 }
 
@@ -155,7 +158,10 @@ impl Context{
                     eval_points.clone(), 
                     vec![evaluations], 
                     self.num_faults+1);
-                assert!(verf);
+                if !verf{
+                    log::error!("Points in c_2 for instance id {} and origin {} do not lie on a degree-t polynomial", inst_key, origin);
+                    return;
+                }
 
                 // reconstruct point
                 let secret = poly.unwrap()[0].evaluate(&LargeField::zero());
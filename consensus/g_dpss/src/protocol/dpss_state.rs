@@ -1,5 +1,6 @@
 use std::collections::{HashMap, HashSet};
 
+use consensus::SecretShares;
 use crypto::{LargeField, hash::Hash};
 use types::Replica;
 
@@ -7,8 +8,8 @@ pub struct DPSSState{
     pub acss_map: HashMap<Replica, 
         HashMap<usize, 
         (
-            Option<(Vec<LargeField>, Hash)>,
-            Option<(Vec<LargeField>, Hash)>)>
+            Option<(SecretShares, Hash)>,
+            Option<(SecretShares, Hash)>)>
         >,
     
     pub sec_equivalence: HashMap<Replica, 
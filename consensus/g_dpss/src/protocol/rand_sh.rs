@@ -1,4 +1,4 @@
-use consensus::{LargeFieldSSS, RBCSyncMsg, inverse_vandermonde, matrix_vector_multiply, vandermonde_matrix};
+use consensus::{LargeFieldSSS, CompletionReport, inverse_vandermonde, matrix_vector_multiply, vandermonde_matrix};
 use crypto::{LargeField, LargeFieldSer};
 use lambdaworks_math::{traits::ByteConversion, polynomial::Polynomial};
 use rayon::prelude::IntoParallelIterator;
@@ -49,8 +49,8 @@ impl Context{
                         log::info!("ACSS did not terminate yet, will retry later for share generation");
                         return;
                     }
-                    for share in batch_shares.unwrap().0.clone(){
-                        shares_to_be_combined[index].push(share);
+                    for share in batch_shares.unwrap().0.iter(){
+                        shares_to_be_combined[index].push(share.clone());
                         index +=1;
                     }
                 }
@@ -83,6 +83,7 @@ impl Context{
             let shares_ser = shares.into_iter().map(|x| x.to_bytes_be()).collect();
             let prot_msg = ProtMsg::PubRecEcho1(shares_ser);
             let wrapper = WrapperMsg::new(prot_msg, self.myid, secret_key.as_slice());
+            consensus::count_sent_message(&wrapper);
             let cancel_handler = self.net_send.send(rep, wrapper).await;
             self.add_cancel_handler(cancel_handler);
         }
@@ -173,12 +174,9 @@ impl Context{
     pub async fn terminate(&mut self, data: String) {
         if !self.terminated{
             self.terminated = true;
-            let rbc_sync_msg = RBCSyncMsg{
-                id: 1,
-                msg: data,
-            };
+            let completion_report = CompletionReport::new(1, data, self.start_time);
 
-            let ser_msg = bincode::serialize(&rbc_sync_msg).unwrap();
+            let ser_msg = bincode::serialize(&completion_report).unwrap();
             let cancel_handler = self
                 .sync_send
                 .send(
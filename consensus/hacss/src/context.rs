@@ -7,12 +7,10 @@ use anyhow::{anyhow, Result};
 use config::Node;
 
 use fnv::FnvHashMap;
-use lambdaworks_math::traits::ByteConversion;
 use network::{
-    plaintcp::{CancelHandler, TcpReceiver, TcpReliableSender},
+    plaintcp::CancelHandler,
     Acknowledgement,
 };
-use num_bigint_dig::{BigInt};
 use tokio::sync::{
     mpsc::{unbounded_channel, UnboundedReceiver, Receiver, Sender},
     oneshot,
@@ -20,15 +18,15 @@ use tokio::sync::{
 // use tokio_util::time::DelayQueue;
 use types::{Replica, WrapperMsg};
 
-use crate::{Handler, ACSSVAState};
-use consensus::{LargeFieldSSS,FoldingDZKContext};
+use crate::ACSSVAState;
+use consensus::{AcssOutput, HashKeys, LargeFieldSSS, FoldingDZKContext, PubRecOutput, SecretBytes, ReplicaSender};
 
 use super::{ProtMsg};
-use ha_crypto::{aes_hash::HashState, LargeField, LargeFieldSer, hash::Hash};
+use ha_crypto::{aes_hash::HashState, LargeField};
 
 pub struct Context {
     /// Networking context
-    pub net_send: TcpReliableSender<Replica, WrapperMsg<ProtMsg>, Acknowledgement>,
+    pub net_send: ReplicaSender<WrapperMsg<ProtMsg>>,
     pub net_recv: UnboundedReceiver<WrapperMsg<ProtMsg>>,
     
     /// Data context
@@ -37,12 +35,8 @@ pub struct Context {
     pub num_faults: usize,
     byz: bool,
 
-    /// Primes for computation
-    pub small_field_prime: u64,
-    pub large_field_prime: BigInt,
-
     /// Secret Key map
-    pub sec_key_map: HashMap<Replica, Vec<u8>>,
+    pub sec_key_map: HashMap<Replica, SecretBytes>,
 
     /// Hardware acceleration context
     pub hash_context: HashState,
@@ -72,14 +66,21 @@ pub struct Context {
     pub acss_state: HashMap<usize, ACSSVAState>,
 
     /// Input and output request channels
-    pub inp_acss_requests: Receiver<(usize, Vec<LargeFieldSer>)>,
-    pub out_acss_shares: Sender<(usize, usize, Hash, Vec<LargeFieldSer>)>
+    pub inp_acss_requests: Receiver<(usize, Vec<LargeField>)>,
+    pub out_acss_shares: Sender<AcssOutput>,
+
+    /// Public reconstruction requests and outputs
+    pub inp_pub_rec: Receiver<(usize, Replica)>,
+    pub out_pub_rec: Sender<PubRecOutput>,
 }
 
 impl Context {
-    pub fn spawn(config: Node, 
-        inp_req_channel: Receiver<(usize, Vec<LargeFieldSer>)>,
-        out_shares_channel: Sender<(usize, usize, Hash, Vec<LargeFieldSer>)>,
+    pub fn spawn(config: Node,
+        hash_keys: HashKeys,
+        inp_req_channel: Receiver<(usize, Vec<LargeField>)>,
+        out_shares_channel: Sender<AcssOutput>,
+        inp_pub_rec_channel: Receiver<(usize, Replica)>,
+        out_pub_rec_channel: Sender<PubRecOutput>,
         byz: bool) -> anyhow::Result<oneshot::Sender<()>> {
         // Add a separate configuration for RBC service. 
 
@@ -90,34 +91,19 @@ impl Context {
         }
         let my_port = consensus_addrs.get(&config.id).unwrap();
         let my_address = to_socket_address("0.0.0.0", my_port.port());
-        let mut syncer_map: FnvHashMap<Replica, SocketAddr> = FnvHashMap::default();
-        syncer_map.insert(0, config.client_addr);
 
         // Setup networking
         let (tx_net_to_consensus, rx_net_to_consensus) = unbounded_channel();
-        TcpReceiver::<Acknowledgement, WrapperMsg<ProtMsg>, _>::spawn(
-            my_address,
-            Handler::new(tx_net_to_consensus),
-        );
-
-        let consensus_net = TcpReliableSender::<Replica, WrapperMsg<ProtMsg>, Acknowledgement>::with_peers(
-            consensus_addrs.clone(),
-        );
+        let consensus_net = consensus::spawn_replica_network(my_address, consensus_addrs.clone(), tx_net_to_consensus);
         let (exit_tx, exit_rx) = oneshot::channel();
 
         // Keyed AES ciphers
-        let key0 = [5u8; 16];
-        let key1 = [29u8; 16];
-        let key2 = [23u8; 16];
-        let hashstate = HashState::new(key0.clone(), key1.clone(), key2.clone());
-        let hashstate2 = HashState::new(key0, key1, key2);
+        let hashstate = hash_keys.hash_state();
+        let hashstate2 = hash_keys.hash_state();
 
         let threshold:usize = 10000;
         let rbc_start_id = threshold*config.id;
 
-        let small_field_prime:u64 = 4294967291;
-        let large_field_prime: BigInt = BigInt::parse_bytes(b"115792088158918333131516597762172392628570465465856793992332884130307292657121",10).unwrap();
-        
         // Blinding and Nonce polynomials
         let largefield_ss = LargeFieldSSS::new(
             config.num_faults+1, 
@@ -174,9 +160,6 @@ impl Context {
                 cancel_handlers: HashMap::default(),
                 exit_rx: exit_rx,
                 
-                small_field_prime: small_field_prime,
-                large_field_prime: large_field_prime,
-
                 //avid_context:HashMap::default(),
                 threshold: 10000,
 
@@ -192,12 +175,15 @@ impl Context {
                 acss_state: HashMap::default(),
 
                 inp_acss_requests: inp_req_channel,
-                out_acss_shares: out_shares_channel
+                out_acss_shares: out_shares_channel,
+
+                inp_pub_rec: inp_pub_rec_channel,
+                out_pub_rec: out_pub_rec_channel,
             };
 
             // Populate secret keys from config
-            for (id, sk_data) in config.sk_map.clone() {
-                c.sec_key_map.insert(id, sk_data.clone());
+            for (id, sk_data) in config.sk_map.iter() {
+                c.sec_key_map.insert(*id, SecretBytes::new(sk_data.clone()));
             }
 
             // Run the consensus context
@@ -210,14 +196,16 @@ impl Context {
     }
 
     pub async fn broadcast(&mut self, protmsg: ProtMsg) {
-        let sec_key_map = self.sec_key_map.clone();
-        for (replica, sec_key) in sec_key_map.into_iter() {
+        let replicas: Vec<Replica> = self.sec_key_map.keys().cloned().collect();
+        for replica in replicas {
+            let sec_key = self.sec_key_map.get(&replica).unwrap();
             if self.byz && replica % 2 == 0 {
                 // Simulates a crash fault
                 continue;
             }
             if replica != self.myid {
                 let wrapper_msg = WrapperMsg::new(protmsg.clone(), self.myid, &sec_key.as_slice());
+                consensus::count_sent_message(&wrapper_msg);
                 let cancel_handler: CancelHandler<Acknowledgement> =
                     self.net_send.send(replica, wrapper_msg).await;
                 self.add_cancel_handler(cancel_handler);
@@ -230,6 +218,7 @@ impl Context {
     }
 
     pub async fn send(&mut self, replica: Replica, wrapper_msg: WrapperMsg<ProtMsg>) {
+        consensus::count_sent_message(&wrapper_msg);
         let cancel_handler: CancelHandler<Acknowledgement> =
             self.net_send.send(replica, wrapper_msg).await;
         self.add_cancel_handler(cancel_handler);
@@ -248,7 +237,7 @@ impl Context {
                 },
                 msg = self.net_recv.recv() => {
                     // Received messages are processed here
-                    log::trace!("Got a consensus message from the network: {:?}", msg);
+                    log::trace!("Got a consensus message from the network from {:?}", msg.as_ref().map(|msg| msg.sender));
                     let msg = msg.ok_or_else(||
                         anyhow!("Networking layer has closed")
                     )?;
@@ -259,10 +248,13 @@ impl Context {
                         anyhow!("Networking layer has closed")
                     )?;
                     let acss_inst_id = self.myid*self.threshold + acss_msg.0;
-                    let acss_share_msgs_ser = acss_msg.1;
-                    let acss_lf_secrets: Vec<LargeField> = acss_share_msgs_ser.into_iter().map(|x| LargeField::from_bytes_be(x.as_slice()).unwrap()).collect();
-                    
-                    self.init_verifiable_abort(acss_lf_secrets, acss_inst_id, 2*self.num_faults+1).await;
+                    self.init_verifiable_abort(acss_msg.1, acss_inst_id, 2*self.num_faults+1).await;
+                },
+                pub_rec_req = self.inp_pub_rec.recv() => {
+                    let (instance_id, dealer) = pub_rec_req.ok_or_else(||
+                        anyhow!("Networking layer has closed")
+                    )?;
+                    self.init_pub_rec(instance_id, dealer).await;
                 }
             };
         }
@@ -1,13 +1,13 @@
 mod context;
 pub use context::*;
 
+mod service;
+pub use service::*;
+
 mod msg;
 pub use msg::*;
 
 mod protocol;
 pub use protocol::*;
 
-mod handlers;
-pub use handlers::*;
-
 mod process;
\ No newline at end of file
@@ -1,23 +1,9 @@
-use consensus::{VACommitment};
-use ha_crypto::{hash::Hash, LargeFieldSer};
+use consensus::VACommitment;
 use ctrbc::CTRBCMsg;
+use ha_crypto::LargeFieldSer;
 use serde::{Serialize, Deserialize};
 use types::Replica;
 
-pub type Polynomial<T> = Vec<T>;
-
-pub type Commitment = Vec<Hash>;
-pub type VSSCommitments = (Commitment,Commitment);
-
-pub type Sig = Vec<(usize,Hash)>;
-pub type SigOpening = (usize,Hash);
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct Shares{
-    pub poly_shares: Option<Vec<LargeFieldSer>>, // Shares of polynomials
-    pub nonce_shares: Option<(LargeFieldSer,LargeFieldSer)>, // Nonce and Blinding nonce shares
-}
-
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub enum ProtMsg{
     Init(
@@ -36,13 +22,9 @@ pub enum ProtMsg{
         Vec<u8>, // Encrypted shares on row and column
         usize // ACSS Instance ID
     ),
-
-    InitAB(
-        Vec<u8>, // Encrypted shares
-        usize, // Number of secrets
-        VSSCommitments,
-        Polynomial<LargeFieldSer>, // dZK polynomial
-        Replica, // Dealer
-        usize // ACSS Instance ID (For PRF and share generation)
+    PubRec(
+        usize, // ACSS Instance ID
+        Vec<Vec<LargeFieldSer>>, // Sender's column of every secret, at the points 0..t
+        Vec<LargeFieldSer> // Nonce column, at the points 0..t
     ),
-}
\ No newline at end of file
+}
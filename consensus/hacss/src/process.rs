@@ -35,11 +35,6 @@ impl Context {
         // Verify the message's authenticity before proceeding
         if self.check_proposal(msg) {
             match wrapper_msg.clone().protmsg {
-                // ProtMsg::Init(enc_shares, num_secrets, comm, polynomial, dealer, instance_id) => {
-                //     // RBC initialized
-                //     log::debug!("Received Init for instance id {} from node : {}", instance_id, dealer);
-                //     self.process_acss_init(enc_shares,num_secrets,comm,polynomial, dealer, instance_id).await;
-                // },
                 ProtMsg::Init(enc_shares, comm, dealer, instance_id) => {
                     // RBC initialized
                     log::debug!("Received Init for instance id {} from node : {}", instance_id, dealer);
@@ -55,12 +50,15 @@ impl Context {
                     log::debug!("Received Ready for instance id {} from node : {}", instance_id, main_msg.origin);
                     self.process_ready_vf(main_msg,encrypted_share, wrapper_msg.sender,instance_id).await;
                 },
+                ProtMsg::PubRec(instance_id, columns, nonces) => {
+                    log::debug!("Received PubRec for instance id {} from node : {}", instance_id, wrapper_msg.sender);
+                    self.process_pub_rec(instance_id, columns, nonces, wrapper_msg.sender).await;
+                },
                 // ProtMsg::Deliver(avid_shard, origin, instance_id) => {
                     
                 //     log::debug!("Received Deliver for instance id {} from node : {}", instance_id, origin);
                 //     self.handle_deliver(avid_shard, origin, wrapper_msg.sender, instance_id).await;
                 // }
-                _ => {}
             }
         } else {
             log::warn!(
@@ -96,6 +96,8 @@ impl Context{
 
                 // Deserialize commitments
                 let comm: VACommitment = bincode::deserialize(message.as_slice()).unwrap();
+                // Public reconstruction checks revealed columns against these roots
+                acss_va_state.column_roots = comm.column_roots.clone();
                 
                 if acss_va_state.verified_hash.is_some(){
 
@@ -122,6 +124,7 @@ impl Context{
                             }
                             let ready_msg = ProtMsg::Ready(rbc_msg.clone(), enc_share, instance_id);
                             let wrapper_msg = WrapperMsg::new(ready_msg, self.myid, &secret_key);
+                            consensus::count_sent_message(&wrapper_msg);
                             let cancel_handler: CancelHandler<Acknowledgement> = self.net_send.send(rep, wrapper_msg).await;
                             self.add_cancel_handler(cancel_handler);
                         }
@@ -175,6 +178,7 @@ impl Context{
                         }
                         let ready_msg = ProtMsg::Ready(rbc_msg.clone(), enc_share, instance_id);
                         let wrapper_msg = WrapperMsg::new(ready_msg, self.myid, &secret_key);
+                        consensus::count_sent_message(&wrapper_msg);
                         let cancel_handler: CancelHandler<Acknowledgement> = self.net_send.send(rep, wrapper_msg).await;
                         self.add_cancel_handler(cancel_handler);
                     }
@@ -214,13 +218,14 @@ impl Context{
                     }
                     let ready_msg = ProtMsg::Ready(ctrbc_msg.clone(), enc_share, instance_id);
                     let wrapper_msg = WrapperMsg::new(ready_msg, self.myid, &secret_key);
+                    consensus::count_sent_message(&wrapper_msg);
                     let cancel_handler: CancelHandler<Acknowledgement> = self.net_send.send(rep, wrapper_msg).await;
                     self.add_cancel_handler(cancel_handler);
                 }
                 let acss_va_state = self.acss_state.get_mut(&instance_id).unwrap();
-                let row_secret_shares = acss_va_state.row_secret_shares.clone().unwrap();
+                let secret_shares = acss_va_state.secret_shares.clone();
                 let root_comm = acss_va_state.verified_hash.clone().unwrap();
-                self.terminate(row_secret_shares, root_comm, instance_id).await;
+                self.terminate(secret_shares, root_comm, instance_id).await;
             }
         }
     }
@@ -36,7 +36,8 @@ impl Context{
         let inverse_vandermonde = LargeFieldSSS::inverse_vandermonde(vandermonde_matrix_lt);
         
         let num_cores = 4;
-        let chunk_size = secrets.len()/num_cores;
+        // Round up, so that fewer secrets than cores still make a non-empty chunk
+        let chunk_size = std::cmp::max(1, (secrets.len()+num_cores-1)/num_cores);
         let secret_batches: Vec<Vec<LargeField>> = secrets.chunks(chunk_size).into_iter().map(|el| el.to_vec()).collect();
         let mut handles = Vec::new();
         for secret_batch in secret_batches{
@@ -346,6 +347,7 @@ impl Context{
             );
 
             let wrapper_msg = WrapperMsg::new(prot_msg_va, self.myid, &secret_key);
+            consensus::count_sent_message(&wrapper_msg);
             let cancel_handler: CancelHandler<Acknowledgement> = self.net_send.send(rep, wrapper_msg).await;
             self.add_cancel_handler(cancel_handler);
         }
@@ -556,14 +558,15 @@ impl Context{
         }
         let secret_shares = columns.iter().map(|col| col[0].clone()).collect();
 
-        for (rep,((share,bshare),(nonce,bnonce))) in (0..self.num_nodes+1).into_iter().zip(
-            (columns.into_iter().zip(blinding_shares.into_iter())).zip(column_nonces.into_iter().zip(blinding_nonces.into_iter()))){
-            acss_va_state.column_shares.insert(rep, (share,nonce));
-            acss_va_state.bcolumn_shares.insert(rep, (bshare,bnonce));
+        // Replica rep's point on the columns is at rep+1, the same way the ECHO and READY phases fill them
+        for rep in 0..self.num_nodes{
+            let column_shares = columns.iter().map(|col| col[rep+1].clone()).collect();
+            acss_va_state.column_shares.insert(rep, (column_shares, column_nonces[rep+1].clone()));
+            acss_va_state.bcolumn_shares.insert(rep, (blinding_shares[rep+1].clone(), blinding_nonces[rep+1].clone()));
         }
 
-        acss_va_state.column_roots.extend(comm.column_roots.clone());
-        acss_va_state.blinding_column_roots.extend(comm.blinding_column_roots.clone());
+        acss_va_state.column_roots = comm.column_roots.clone();
+        acss_va_state.blinding_column_roots = comm.blinding_column_roots.clone();
         acss_va_state.dzk_polynomial_roots.extend(comm.dzk_roots.clone());
         acss_va_state.dzk_polynomials.extend(comm.polys.clone());
 
@@ -612,6 +615,7 @@ impl Context{
             };
             let echo_msg = ProtMsg::Echo(rbc_msg, enc_share, instance_id);
             let wrapper_msg = WrapperMsg::new(echo_msg, self.myid, secret_key.as_slice());
+            consensus::count_sent_message(&wrapper_msg);
             let cancel_handler: CancelHandler<Acknowledgement> = self.net_send.send(rep, wrapper_msg).await;
             self.add_cancel_handler(cancel_handler);
         }
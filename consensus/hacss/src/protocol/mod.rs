@@ -7,4 +7,6 @@ pub use state::*;
 
 mod init_vf;
 
-mod commitment;
\ No newline at end of file
+mod commitment;
+
+mod pub_rec;
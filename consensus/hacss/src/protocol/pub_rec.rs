@@ -0,0 +1,113 @@
+use ha_crypto::{LargeField, LargeFieldSer};
+use lambdaworks_math::traits::ByteConversion;
+use types::{Replica, WrapperMsg};
+
+use crate::{Context, ProtMsg};
+
+impl Context{
+    /// Reveals this node's column of every secret in `dealer`'s sharing in `instance_id`.
+    /// Every node checks the column against the dealer's column root, takes this node's share from it, and the secrets from 2t+1 such shares.
+    pub async fn init_pub_rec(&mut self, instance_id: usize, dealer: Replica){
+        let acss_inst_id = dealer*self.threshold + instance_id;
+        log::info!("Received request to publicly reconstruct secrets of dealer {} in instance {}", dealer, instance_id);
+        if !self.acss_state.contains_key(&acss_inst_id){
+            log::error!("No ACSS state found for instance {} of dealer {}", instance_id, dealer);
+            return;
+        }
+        let acss_state = self.acss_state.get(&acss_inst_id).unwrap();
+        if !acss_state.terminated || acss_state.secret_shares.is_none() || acss_state.column_shares.len() < self.num_faults+1{
+            log::error!("No verified column for instance {} of dealer {}, cannot reveal shares", instance_id, dealer);
+            return;
+        }
+
+        // The column at point 0 is this node's share, the points 1..t are the shares of the first t nodes on it
+        let secret_shares = acss_state.secret_shares.as_ref().unwrap();
+        let mut columns: Vec<Vec<LargeFieldSer>> = secret_shares.iter().map(|share| vec![share.to_bytes_be()]).collect();
+        let mut nonce_points = Vec::new();
+        for rep in 0..self.num_faults+1{
+            let (shares, nonce) = acss_state.column_shares.get(&rep).unwrap();
+            if rep < self.num_faults{
+                for (column, share) in columns.iter_mut().zip(shares.iter()){
+                    column.push(share.to_bytes_be());
+                }
+            }
+            nonce_points.push((rep+1, nonce.clone()));
+        }
+        // The nonce at point 0 is not kept, so interpolate it from the points 1..t+1
+        let mut nonces = vec![self.large_field_uv_sss.recover(&nonce_points).to_bytes_be()];
+        nonces.extend(nonce_points.into_iter().take(self.num_faults).map(|(_, nonce)| nonce.to_bytes_be()));
+
+        let pub_rec_msg = ProtMsg::PubRec(acss_inst_id, columns, nonces);
+        for rep in 0..self.num_nodes{
+            let secret_key = self.sec_key_map.get(&rep).unwrap().clone();
+            let wrapper_msg = WrapperMsg::new(pub_rec_msg.clone(), self.myid, &secret_key);
+            self.send(rep, wrapper_msg).await;
+        }
+    }
+
+    pub async fn process_pub_rec(&mut self, instance_id: usize, columns_ser: Vec<Vec<LargeFieldSer>>, nonces_ser: Vec<LargeFieldSer>, share_sender: Replica){
+        if !self.acss_state.contains_key(&instance_id){
+            log::error!("No ACSS state found for instance {}, ignoring column of node {}", instance_id, share_sender);
+            return;
+        }
+        let acss_state = self.acss_state.get(&instance_id).unwrap();
+        if acss_state.pub_rec_terminated || acss_state.pub_rec_shares.contains_key(&share_sender){
+            return;
+        }
+        if acss_state.column_roots.len() != self.num_nodes{
+            log::error!("No commitment found for ACSS instance {}, ignoring column of node {}", instance_id, share_sender);
+            return;
+        }
+        let column_root = acss_state.column_roots[share_sender];
+
+        if columns_ser.iter().any(|column| column.len() != self.num_faults+1) || nonces_ser.len() != self.num_faults+1{
+            log::error!("Malformed column from node {} in public reconstruction of ACSS instance {}", share_sender, instance_id);
+            return;
+        }
+        let columns: Result<Vec<Vec<LargeField>>, _> = columns_ser.iter().map(|column| {
+            column.iter().map(|point| LargeField::from_bytes_be(point)).collect()
+        }).collect();
+        let nonces: Result<Vec<LargeField>, _> = nonces_ser.iter().map(|point| LargeField::from_bytes_be(point)).collect();
+        if columns.is_err() || nonces.is_err(){
+            log::error!("Unable to parse column from node {} in public reconstruction of ACSS instance {}", share_sender, instance_id);
+            return;
+        }
+        let mut columns = columns.unwrap();
+        let mut nonces = nonces.unwrap();
+        for column in columns.iter_mut(){
+            self.large_field_uv_sss.fill_evaluation_at_all_points(column);
+        }
+        self.large_field_uv_sss.fill_evaluation_at_all_points(&mut nonces);
+
+        if !self.verify_column_share_commitments(&columns, &nonces, column_root){
+            log::error!("Column of node {} does not match its root in public reconstruction of ACSS instance {}", share_sender, instance_id);
+            return;
+        }
+        let shares: Vec<LargeField> = columns.into_iter().map(|column| column[0].clone()).collect();
+        log::info!("Verified shares of node {} for public reconstruction of ACSS instance {}", share_sender, instance_id);
+
+        let acss_state = self.acss_state.get_mut(&instance_id).unwrap();
+        acss_state.pub_rec_shares.insert(share_sender, shares);
+
+        // Shares lie on degree 2t polynomials
+        if acss_state.pub_rec_shares.len() == 2*self.num_faults+1{
+            log::info!("2t+1 shares received for public reconstruction of ACSS instance {}", instance_id);
+            let num_secrets = acss_state.pub_rec_shares.values().next().unwrap().len();
+            let mut shares_indexed: Vec<Vec<(usize, LargeField)>> = vec![Vec::new(); num_secrets];
+            for (party, shares_party) in acss_state.pub_rec_shares.iter(){
+                for (index, share) in shares_party.iter().enumerate(){
+                    shares_indexed[index].push((party+1, share.clone()));
+                }
+            }
+            let secrets: Vec<LargeField> = shares_indexed.iter().map(|points| self.large_field_bv_sss.recover(points)).collect();
+
+            acss_state.pub_rec_terminated = true;
+            acss_state.pub_rec_shares.clear();
+
+            let true_inst_id = instance_id%self.threshold;
+            let dealer = instance_id/self.threshold;
+            log::info!("Publicly reconstructed {} secrets of dealer {} in instance {}", secrets.len(), dealer, true_inst_id);
+            let _status = self.out_pub_rec.send((true_inst_id, dealer, secrets)).await;
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use consensus::{reconstruct_data, VACommitment};
-use ha_crypto::{decrypt, hash::{Hash, do_hash}, aes_hash::MerkleTree, LargeField};
+use ha_crypto::{decrypt, hash::Hash, aes_hash::MerkleTree, LargeField};
 use ctrbc::CTRBCMsg;
 use network::{plaintcp::CancelHandler, Acknowledgement};
 use types::{Replica, WrapperMsg};
@@ -87,7 +87,7 @@ impl Context{
 
                 let my_share:Vec<u8> = shards[self.myid].clone();
                 // Reconstruct Merkle Root
-                let shard_hashes: Vec<Hash> = shards.clone().into_iter().map(|v| do_hash(v.as_slice())).collect();
+                let shard_hashes: Vec<Hash> = shards.clone().into_iter().map(|v| self.hash_context.do_hash_aes(v.as_slice())).collect();
                 let merkle_tree = MerkleTree::new(shard_hashes, &self.hash_context);
 
                 if merkle_tree.root() == root{
@@ -110,6 +110,8 @@ impl Context{
                     
                     // Used for error correction
                     let acss_va_state = self.acss_state.get_mut(&instance_id).unwrap();
+                    // Public reconstruction checks revealed columns against these roots
+                    acss_va_state.column_roots = comm.column_roots.clone();
 
                     let secret_shares = poly_coeffs.iter().map(|poly| poly[0].clone()).collect();
                     acss_va_state.verified_hash = Some(root);
@@ -146,6 +148,7 @@ impl Context{
                         }
                         let ready_msg = ProtMsg::Ready(rbc_msg.clone(), enc_share, instance_id);
                         let wrapper_msg = WrapperMsg::new(ready_msg, self.myid, &secret_key);
+                        consensus::count_sent_message(&wrapper_msg);
                         let cancel_handler: CancelHandler<Acknowledgement> = self.net_send.send(rep, wrapper_msg).await;
                         self.add_cancel_handler(cancel_handler);
                     }
@@ -156,22 +159,22 @@ impl Context{
             log::info!("Received n-f READY messages for ACSS Instance ID {}, terminating",instance_id);
             // Terminate protocol
             acss_va_context.terminated = true;
-            let _term_msg = "Terminated";
-            let row_secret_shares = acss_va_context.row_secret_shares.clone().unwrap();
-            let root_comm = acss_va_context.verified_hash.clone().unwrap();
-            self.terminate(row_secret_shares, root_comm, instance_id).await;
+            let secret_shares = acss_va_context.secret_shares.clone();
+            self.terminate(secret_shares, root, instance_id).await;
         }
     }
 
-    // Invoke this function once you terminate the protocol
-    // Invoke this function once you terminate the protocol
-    pub async fn terminate(&mut self, shares: Vec<LargeField>, root_comm: Hash, instance_id: usize) {
+    // Invoke this function once you terminate the protocol.
+    // The shares are this node's points on the degree-2t sharing of each secret, or None if it could not verify them.
+    pub async fn terminate(&mut self, shares: Option<Vec<LargeField>>, root_comm: Hash, instance_id: usize) {
         
         let true_inst_id = instance_id%self.threshold;
         let sender_party = instance_id/self.threshold;
         log::info!("Terminating ACSS for instance id {}, true_inst_id: {}, sender_party: {}",instance_id, true_inst_id, sender_party);
 
-        let shares_ser = shares.into_iter().map(|share| share.to_bytes_be()).collect();
-        let _status = self.out_acss_shares.send((true_inst_id, sender_party, root_comm, shares_ser)).await;
+        if shares.is_none(){
+            log::error!("ACSS instance {} of dealer {} terminated without verified shares", true_inst_id, sender_party);
+        }
+        let _status = self.out_acss_shares.send((true_inst_id, sender_party, root_comm, shares, None)).await;
     }
 }
\ No newline at end of file
@@ -35,6 +35,10 @@ pub struct ACSSVAState{
     
     pub rbc_state: RBCState,
     pub terminated: bool,
+
+    // Shares revealed in public reconstruction, by the node they belong to
+    pub pub_rec_shares: HashMap<Replica, Vec<LargeField>>,
+    pub pub_rec_terminated: bool,
 }
 
 impl ACSSVAState{
@@ -66,6 +70,9 @@ impl ACSSVAState{
             rbc_state: RBCState::new(origin),
 
             terminated: false,
+
+            pub_rec_shares: HashMap::default(),
+            pub_rec_terminated: false,
         }
     }
 }
\ No newline at end of file
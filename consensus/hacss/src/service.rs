@@ -0,0 +1,37 @@
+use anyhow::Result;
+use config::Node;
+use consensus::{Acss, AcssParams, AcssScheme, AcssService, HashKeys};
+use tokio::sync::mpsc::channel;
+
+use crate::Context;
+
+impl Context{
+    /// Spawns the service behind the common `Acss` interface, the way DPSS drives it
+    pub fn spawn_acss(config: Node, hash_keys: HashKeys, params: AcssParams) -> Result<Box<dyn Acss>>{
+        let (acss_req_send, acss_req_recv) = channel(10000);
+        let (acss_out_send, acss_out_recv) = channel(10000);
+        let (pub_rec_req_send, pub_rec_req_recv) = channel(10000);
+        let (pub_rec_out_send, pub_rec_out_recv) = channel(10000);
+        let num_faults = config.num_faults;
+
+        let exit_tx = Self::spawn(
+            config,
+            hash_keys,
+            acss_req_recv,
+            acss_out_send,
+            pub_rec_req_recv,
+            pub_rec_out_send,
+            params.byz
+        )?;
+        Ok(Box::new(AcssService {
+            scheme: AcssScheme::HAcss,
+            num_faults: num_faults,
+            acss_req: acss_req_send,
+            acss_out: acss_out_recv,
+            pub_rec_req: pub_rec_req_send,
+            pub_rec_out: pub_rec_out_recv,
+            pub_rec_selected: None,
+            exit_txs: vec![exit_tx],
+        }))
+    }
+}
@@ -0,0 +1,237 @@
+use std::{
+    collections::HashMap,
+    net::{SocketAddr, SocketAddrV4},
+};
+
+use anyhow::{anyhow, Result};
+use config::Node;
+
+use fnv::FnvHashMap;
+use network::{
+    plaintcp::CancelHandler,
+    Acknowledgement,
+};
+use consensus::{HashKeys, HandoverConfig, HandoverOutput, LargeFieldSer, SecretBytes, SecretShares, ReplicaSender};
+
+use tokio::sync::{
+    mpsc::{Receiver, Sender, channel, unbounded_channel, UnboundedReceiver},
+    oneshot,
+};
+use types::{Replica, WrapperMsg};
+
+use crate::{msg::ProtMsg, protocol::HandoverState};
+
+pub struct Context {
+    /// Networking context, over the nodes of both committees
+    pub net_send: ReplicaSender<WrapperMsg<ProtMsg>>,
+    pub net_recv: UnboundedReceiver<WrapperMsg<ProtMsg>>,
+
+    /// Data context
+    pub myid: Replica,
+    pub committees: HandoverConfig,
+
+    /// Secret Key map
+    pub sec_key_map: HashMap<Replica, SecretBytes>,
+
+    /// Cancel Handlers
+    pub cancel_handlers: HashMap<u64, Vec<CancelHandler<Acknowledgement>>>,
+    exit_rx: oneshot::Receiver<()>,
+
+    pub state: HandoverState,
+
+    // Shares to hand over, from this node's dpss run. Only at old members.
+    pub shares_recv: Option<Receiver<SecretShares>>,
+    pub out_handover: Sender<HandoverOutput>,
+
+    // ACS of the old committee on the dealers. Only at old members.
+    pub acs_term_event: Option<Sender<(usize, usize, Vec<LargeFieldSer>)>>,
+    pub acs_out_recv: Option<Receiver<(usize, Vec<usize>)>>,
+}
+
+impl Context {
+    /// Spawns the handover on the nodes of both committees of `committees`. Old members get the shares to hand over on `input_shares`.
+    pub fn spawn(
+        config: Node,
+        hash_keys: HashKeys,
+        committees: HandoverConfig,
+        input_shares: Option<Receiver<SecretShares>>,
+        output_handover: Sender<HandoverOutput>,
+    ) -> anyhow::Result<(oneshot::Sender<()>, Vec<Result<oneshot::Sender<()>>>)> {
+        committees.validate_for(&config).map_err(|e| anyhow!(e))?;
+        if !committees.is_member(config.id){
+            return Err(anyhow!("Node {} is in neither committee of the handover", config.id));
+        }
+        if committees.old.contains(config.id) != input_shares.is_some(){
+            return Err(anyhow!("Old members and only old members hand over shares"));
+        }
+
+        // Above the ports of dpss, which runs next to the handover. Sweeps size their run windows to fit both.
+        let port_handover: u16 = 3000;
+        let port_acs: u16 = 3300;
+
+        let mut consensus_addrs: FnvHashMap<Replica, SocketAddr> = FnvHashMap::default();
+        for (replica, address) in config.net_map.iter() {
+            if !committees.is_member(*replica){
+                continue;
+            }
+            let address: SocketAddr = address.parse().expect("Unable to parse address");
+            let handover_address: SocketAddr = SocketAddr::new(address.ip(), address.port() + port_handover);
+            consensus_addrs.insert(*replica, handover_address);
+        }
+        log::info!("Handover addresses: {:?}", consensus_addrs);
+        let my_port = consensus_addrs.get(&config.id).unwrap();
+        let my_address = to_socket_address("0.0.0.0", my_port.port());
+
+        // Setup networking
+        let (tx_net_to_consensus, rx_net_to_consensus) = unbounded_channel();
+        let consensus_net = consensus::spawn_replica_network(my_address, consensus_addrs, tx_net_to_consensus);
+        let (exit_tx, exit_rx) = oneshot::channel();
+
+        // The old committee agrees on the dealers with its own ACS
+        let mut vector_statuses = Vec::new();
+        let (acs_term_event, acs_out_recv) = if committees.old.contains(config.id){
+            let mut acs_config = committees.old.node_config(&config).map_err(|e| anyhow!(e))?;
+            for (replica, address) in config.net_map.iter() {
+                if !committees.old.contains(*replica){
+                    continue;
+                }
+                let address: SocketAddr = address.parse().expect("Unable to parse address");
+                let acs_address: SocketAddr = SocketAddr::new(address.ip(), address.port() + port_acs);
+                acs_config.net_map.insert(*replica, acs_address.to_string());
+            }
+            let (acs_req_send_channel, acs_req_recv_channel) = channel(10000);
+            let (acs_out_send_channel, acs_out_recv_channel) = channel(10000);
+            let (acs_exit_tx, acs_statuses) = acs::Context::spawn(
+                acs_config,
+                hash_keys.clone(),
+                acs_req_recv_channel,
+                acs_out_send_channel,
+                false
+            )?;
+            vector_statuses.push(Ok(acs_exit_tx));
+            vector_statuses.extend(acs_statuses);
+            (Some(acs_req_send_channel), Some(acs_out_recv_channel))
+        }
+        else{
+            (None, None)
+        };
+
+        let mut sec_key_map = HashMap::default();
+        for (id, sk_data) in config.sk_map.iter() {
+            if committees.is_member(*id){
+                sec_key_map.insert(*id, SecretBytes::new(sk_data.clone()));
+            }
+        }
+        tokio::spawn(async move {
+            let mut c = Context {
+                net_send: consensus_net,
+                net_recv: rx_net_to_consensus,
+
+                myid: config.id,
+                committees: committees,
+
+                sec_key_map: sec_key_map,
+
+                cancel_handlers: HashMap::default(),
+                exit_rx: exit_rx,
+
+                state: HandoverState::new(),
+
+                shares_recv: input_shares,
+                out_handover: output_handover,
+
+                acs_term_event: acs_term_event,
+                acs_out_recv: acs_out_recv,
+            };
+
+            // Run the handover context
+            if let Err(e) = c.run().await {
+                log::error!("Handover error: {}", e);
+            }
+        });
+        Ok((exit_tx, vector_statuses))
+    }
+
+    /// Epoch of the new committee, which tags every message of the handover
+    pub fn epoch(&self) -> usize{
+        self.committees.new.epoch
+    }
+
+    pub async fn send_to(&mut self, replicas: Vec<Replica>, protmsg: ProtMsg) {
+        for replica in replicas {
+            let sec_key = self.sec_key_map.get(&replica).unwrap();
+            let wrapper_msg = WrapperMsg::new(protmsg.clone(), self.myid, &sec_key.as_slice());
+            self.send(replica, wrapper_msg).await;
+        }
+    }
+
+    /// Sends to every member of the old committee
+    pub async fn send_to_old(&mut self, protmsg: ProtMsg) {
+        self.send_to(self.committees.old.members.clone(), protmsg).await;
+    }
+
+    /// Sends to every member of the new committee
+    pub async fn send_to_new(&mut self, protmsg: ProtMsg) {
+        self.send_to(self.committees.new.members.clone(), protmsg).await;
+    }
+
+    pub fn add_cancel_handler(&mut self, canc: CancelHandler<Acknowledgement>) {
+        self.cancel_handlers.entry(0).or_default().push(canc);
+    }
+
+    pub async fn send(&mut self, replica: Replica, wrapper_msg: WrapperMsg<ProtMsg>) {
+        consensus::count_sent_message(&wrapper_msg);
+        let cancel_handler: CancelHandler<Acknowledgement> =
+            self.net_send.send(replica, wrapper_msg).await;
+        self.add_cancel_handler(cancel_handler);
+    }
+
+    pub async fn run(&mut self) -> Result<()>{
+        loop {
+            tokio::select! {
+                // Receive exit handlers
+                exit_val = &mut self.exit_rx => {
+                    exit_val.map_err(anyhow::Error::new)?;
+                    log::info!("Termination signal received by the handover. Exiting.");
+                    break
+                },
+                msg = self.net_recv.recv() => {
+                    // Received messages are processed here
+                    log::trace!("Got a handover message from the network from {:?}", msg.as_ref().map(|msg| msg.sender));
+                    let msg = msg.ok_or_else(||
+                        anyhow!("Networking layer has closed")
+                    )?;
+                    self.process_msg(msg).await;
+                },
+                shares = recv_if_present(&mut self.shares_recv) => {
+                    let shares = shares.ok_or_else(||
+                        anyhow!("Share channel has closed")
+                    )?;
+                    // Only one set of shares is handed over
+                    self.shares_recv = None;
+                    self.start_handover(shares).await;
+                },
+                acs_output = recv_if_present(&mut self.acs_out_recv) => {
+                    let acs_output = acs_output.ok_or_else(||
+                        anyhow!("Networking layer has closed")
+                    )?;
+                    log::debug!("Received message from ACS channel {:?}", acs_output);
+                    self.process_acs_output(acs_output.1).await;
+                },
+            };
+        }
+        Ok(())
+    }
+}
+
+async fn recv_if_present<T>(channel: &mut Option<Receiver<T>>) -> Option<T> {
+    match channel {
+        Some(recv) => recv.recv().await,
+        None => futures::future::pending().await,
+    }
+}
+
+pub fn to_socket_address(ip_str: &str, port: u16) -> SocketAddr {
+    let addr = SocketAddrV4::new(ip_str.parse().unwrap(), port);
+    addr.into()
+}
@@ -0,0 +1,9 @@
+mod context;
+pub use context::*;
+
+mod protocol;
+
+mod msg;
+pub use msg::*;
+
+mod process;
@@ -0,0 +1,32 @@
+use crypto::{LargeFieldSer, hash::Hash};
+use serde::{Serialize, Deserialize};
+use types::Replica;
+
+/// Messages of the handover. Every message carries the epoch of the new committee.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum ProtMsg{
+    // Old member to new member
+    // epoch, recipient's subshares encrypted under the pairwise key, digests of the subshares of all recipients, coefficients of the dealer's check polynomial
+    Deal(usize, Vec<u8>, Vec<Hash>, Vec<LargeFieldSer>),
+    // New member to new members, once its own subshares passed the dealer's check
+    // epoch, dealer, commitment of the dealing
+    Echo(usize, Replica, Hash),
+    // New member to old members, once 2t'+1 new members echoed the same commitment
+    // epoch, dealer, commitment of the dealing
+    Confirm(usize, Replica, Hash),
+    // Old member to new members
+    // epoch, dealers the old committee agreed on
+    Dealers(usize, Vec<Replica>),
+    // Old member to new members
+    // epoch, aggregated old share
+    OldAgg(usize, LargeFieldSer),
+    // New member to new members
+    // epoch, (dealer, aggregated subshares)
+    NewAgg(usize, Vec<(Replica, LargeFieldSer)>),
+    // New member to new members, for a dealer whose subshares it is missing
+    // epoch, dealer
+    HelpReq(usize, Replica),
+    // New member to new member, the helper's own dealing
+    // epoch, dealer, helper's subshares encrypted under the pairwise key, digests, coefficients of the check polynomial
+    Help(usize, Replica, Vec<u8>, Vec<Hash>, Vec<LargeFieldSer>),
+}
@@ -0,0 +1,90 @@
+use std::sync::Arc;
+
+use crate::{context::Context, msg::ProtMsg};
+use consensus::delay_message_processing;
+use crypto::hash::verf_mac;
+use types::WrapperMsg;
+
+impl Context {
+    // This function verifies the Message Authentication Code (MAC) of a sent message
+    // A node cannot impersonate as another node because of MACs
+    pub fn check_proposal(&self, wrapper_msg: Arc<WrapperMsg<ProtMsg>>) -> bool {
+        // validate MAC
+        let byte_val =
+            bincode::serialize(&wrapper_msg.protmsg).expect("Failed to serialize object");
+        let sec_key = match self.sec_key_map.get(&wrapper_msg.clone().sender) {
+            Some(val) => val,
+            None => {
+                log::warn!("Message from node {} outside both committees", wrapper_msg.sender);
+                return false;
+            }
+        };
+        if !verf_mac(&byte_val, &sec_key.as_slice(), &wrapper_msg.mac) {
+            log::warn!("MAC Verification failed.");
+            return false;
+        }
+        true
+    }
+
+    pub(crate) async fn process_msg(&mut self, wrapper_msg: WrapperMsg<ProtMsg>) {
+        // Payloads can carry shares, so only the sender is logged
+        log::trace!("Received protocol msg from {}", wrapper_msg.sender);
+        delay_message_processing().await;
+        let msg = Arc::new(wrapper_msg.clone());
+
+        // Verify the message's authenticity before proceeding
+        if !self.check_proposal(msg) {
+            log::warn!("MAC Verification failed for a message from node {}", wrapper_msg.sender);
+            return;
+        }
+        let sender = wrapper_msg.sender;
+        let epoch = match &wrapper_msg.protmsg {
+            ProtMsg::Deal(epoch, ..) => *epoch,
+            ProtMsg::Echo(epoch, ..) => *epoch,
+            ProtMsg::Confirm(epoch, ..) => *epoch,
+            ProtMsg::Dealers(epoch, ..) => *epoch,
+            ProtMsg::OldAgg(epoch, ..) => *epoch,
+            ProtMsg::NewAgg(epoch, ..) => *epoch,
+            ProtMsg::HelpReq(epoch, ..) => *epoch,
+            ProtMsg::Help(epoch, ..) => *epoch,
+        };
+        if epoch != self.epoch(){
+            log::warn!("Node {} sent a handover message for epoch {}, this handover is to epoch {}", sender, epoch, self.epoch());
+            return;
+        }
+        match wrapper_msg.protmsg {
+            ProtMsg::Deal(_, subshares, digests, check_poly) => {
+                log::debug!("Received Deal from node : {}", sender);
+                self.process_deal_msg(subshares, digests, check_poly, sender).await;
+            }
+            ProtMsg::Echo(_, dealer, commitment) => {
+                log::debug!("Received Echo for dealer {} from node : {}", dealer, sender);
+                self.process_echo_msg(dealer, commitment, sender).await;
+            }
+            ProtMsg::Confirm(_, dealer, commitment) => {
+                log::debug!("Received Confirm for dealer {} from node : {}", dealer, sender);
+                self.process_confirm_msg(dealer, commitment, sender).await;
+            }
+            ProtMsg::Dealers(_, dealers) => {
+                log::debug!("Received Dealers {:?} from node : {}", dealers, sender);
+                self.process_dealers_msg(dealers, sender).await;
+            }
+            ProtMsg::OldAgg(_, old_agg) => {
+                log::debug!("Received OldAgg from node : {}", sender);
+                self.process_old_agg_msg(old_agg, sender).await;
+            }
+            ProtMsg::NewAgg(_, new_aggs) => {
+                log::debug!("Received NewAgg from node : {}", sender);
+                self.process_new_agg_msg(new_aggs, sender).await;
+            }
+            ProtMsg::HelpReq(_, dealer) => {
+                log::debug!("Received HelpReq for dealer {} from node : {}", dealer, sender);
+                self.process_help_req_msg(dealer, sender).await;
+            }
+            ProtMsg::Help(_, dealer, subshares, digests, check_poly) => {
+                log::debug!("Received Help for dealer {} from node : {}", dealer, sender);
+                self.process_help_msg(dealer, subshares, digests, check_poly, sender).await;
+            }
+        }
+    }
+}
@@ -0,0 +1,282 @@
+use std::collections::HashMap;
+
+use consensus::{HandoverOutput, LargeField, LargeFieldSer, SecretShares, inverse_vandermonde, robust_interpolate, vandermonde_matrix};
+use crypto::hash::{Hash, do_hash};
+use lambdaworks_math::traits::ByteConversion;
+use types::Replica;
+
+use crate::{Context, msg::ProtMsg, protocol::deal::deser_field_elements};
+
+/*
+    Check of the dealings the old committee agreed on. Old members open a random linear combination of their shares,
+    and new members the same combination of their subshares of every agreed dealer. The combination is fixed by the
+    commitments of all agreed dealings. A dealer's reshare is correct if its combined subshares interpolate to the
+    combined old share at the dealer's evaluation point. The mask is part of the combination and hides the old shares.
+    New members combine the reshares of the first t+1 correct dealers with Lagrange coefficients into their new shares.
+*/
+impl Context{
+    pub async fn process_acs_output(&mut self, mut dealers: Vec<Replica>){
+        if self.state.acs_dealers.is_some(){
+            return;
+        }
+        dealers.sort();
+        log::info!("ACS of the old committee output dealers {:?} for epoch {}", dealers, self.epoch());
+        self.state.acs_dealers = Some(dealers.clone());
+        self.send_to_new(ProtMsg::Dealers(self.epoch(), dealers)).await;
+        self.try_old_agg().await;
+    }
+
+    /// Opens the combined old share once the commitments of all agreed dealers are known. The old shares are wiped after.
+    pub async fn try_old_agg(&mut self){
+        if self.state.old_agg_sent || self.state.old_shares.is_none(){
+            return;
+        }
+        let dealers = match self.state.acs_dealers.as_ref() {
+            Some(dealers) => dealers.clone(),
+            None => return,
+        };
+        let challenge = match self.check_challenge(&dealers, &self.state.commitments) {
+            Some(challenge) => challenge,
+            None => {
+                log::info!("Commitments of some dealers for epoch {} are not confirmed yet, will retry later", self.epoch());
+                return;
+            }
+        };
+        let old_shares = self.state.old_shares.take().unwrap();
+        let old_agg = aggregate(&old_shares, challenge);
+        self.state.old_agg_sent = true;
+        self.send_to_new(ProtMsg::OldAgg(self.epoch(), old_agg.to_bytes_be())).await;
+
+        log::info!("Handed the old shares over to epoch {}", self.epoch());
+        self.state.old_done = true;
+        self.try_output().await;
+    }
+
+    pub async fn process_dealers_msg(&mut self, dealers: Vec<Replica>, sender: Replica){
+        if !self.committees.new.contains(self.myid) || !self.committees.old.contains(sender) || self.state.dealers.is_some(){
+            return;
+        }
+        if !dealers.windows(2).all(|pair| pair[0] < pair[1]) || !dealers.iter().all(|dealer| self.committees.old.contains(*dealer)){
+            log::error!("Node {} sent malformed dealers {:?} for epoch {}", sender, dealers, self.epoch());
+            return;
+        }
+        let votes = self.state.dealer_votes.entry(dealers.clone()).or_default();
+        votes.insert(sender);
+        if votes.len() < self.committees.old.num_faults+1{
+            return;
+        }
+        log::info!("Old committee agreed on dealers {:?} for epoch {}", dealers, self.epoch());
+        self.state.dealers = Some(dealers);
+        self.state.dealer_votes.clear();
+        self.try_new_agg().await;
+    }
+
+    /// Opens the combined subshares of every agreed dealer, after recovering missing subshares from the other new members
+    pub async fn try_new_agg(&mut self){
+        if self.state.new_agg_sent || !self.committees.new.contains(self.myid){
+            return;
+        }
+        let dealers = match self.state.dealers.as_ref() {
+            Some(dealers) => dealers.clone(),
+            None => return,
+        };
+        let challenge = match self.check_challenge(&dealers, &self.state.confirmed) {
+            Some(challenge) => challenge,
+            None => return,
+        };
+        let mut missing = Vec::new();
+        for dealer in dealers.iter(){
+            let confirmed = self.state.confirmed.get(dealer);
+            if self.state.dealings.get(dealer).map(|dealing| Some(&dealing.commitment) != confirmed).unwrap_or(true){
+                missing.push(*dealer);
+            }
+        }
+        if !missing.is_empty(){
+            for dealer in missing{
+                self.request_help(dealer).await;
+            }
+            return;
+        }
+
+        let mut new_aggs = Vec::new();
+        for dealer in dealers.iter(){
+            // The last subshare is the dealer's pad
+            let subshares = &self.state.dealings.get(dealer).unwrap().subshares;
+            new_aggs.push((*dealer, aggregate(&subshares[..subshares.len()-1], challenge).to_bytes_be()));
+        }
+        self.state.new_agg_sent = true;
+        self.send_to_new(ProtMsg::NewAgg(self.epoch(), new_aggs)).await;
+        self.try_complete().await;
+    }
+
+    pub async fn process_old_agg_msg(&mut self, old_agg_ser: LargeFieldSer, sender: Replica){
+        if !self.committees.new.contains(self.myid) || !self.committees.old.contains(sender) || self.state.new_done{
+            return;
+        }
+        let old_agg = match LargeField::from_bytes_be(old_agg_ser.as_slice()) {
+            Ok(old_agg) => old_agg,
+            Err(_) => {
+                log::error!("Node {} sent a malformed aggregated old share for epoch {}", sender, self.epoch());
+                return;
+            }
+        };
+        self.state.old_aggs.entry(sender).or_insert(old_agg);
+        self.try_complete().await;
+    }
+
+    pub async fn process_new_agg_msg(&mut self, new_aggs_ser: Vec<(Replica, LargeFieldSer)>, sender: Replica){
+        if !self.committees.new.contains(self.myid) || !self.committees.new.contains(sender) || self.state.new_done{
+            return;
+        }
+        if self.state.new_aggs.contains_key(&sender){
+            return;
+        }
+        let (dealers, aggs_ser): (Vec<Replica>, Vec<LargeFieldSer>) = new_aggs_ser.into_iter().unzip();
+        let aggs = match deser_field_elements(&aggs_ser) {
+            Ok(aggs) => aggs,
+            Err(e) => {
+                log::error!("Node {} sent malformed aggregated subshares for epoch {}: {}", sender, self.epoch(), e);
+                return;
+            }
+        };
+        self.state.new_aggs.insert(sender, dealers.into_iter().zip(aggs.into_iter()).collect());
+        self.try_complete().await;
+    }
+
+    /// Combines the reshares of the first t+1 correct dealers into this member's new shares
+    pub async fn try_complete(&mut self){
+        if self.state.new_done || !self.state.new_agg_sent{
+            return;
+        }
+        let old_faults = self.committees.old.num_faults;
+        let new_faults = self.committees.new.num_faults;
+        if self.state.old_aggs.len() < 2*old_faults+1 || self.state.new_aggs.len() < 2*new_faults+1{
+            return;
+        }
+        let dealers = self.state.dealers.clone().unwrap();
+
+        // Decode the combined old sharing, faulty old members are corrected for
+        let mut eval_points = Vec::new();
+        let mut old_aggs = Vec::new();
+        for (position, member) in self.committees.old.members.iter().enumerate(){
+            if let Some(old_agg) = self.state.old_aggs.get(member){
+                eval_points.push(LargeField::from((position+1) as u64));
+                old_aggs.push(*old_agg);
+            }
+        }
+        let old_poly = match robust_interpolate(&eval_points, &old_aggs, old_faults, 2*old_faults+1) {
+            Some(poly) => poly,
+            None => {
+                log::info!("Aggregated old shares for epoch {} do not agree yet, waiting for more", self.epoch());
+                return;
+            }
+        };
+
+        let mut accepted = Vec::new();
+        let mut accepted_secrets = Vec::new();
+        for dealer in dealers.iter(){
+            let mut eval_points = Vec::new();
+            let mut new_aggs = Vec::new();
+            for (position, member) in self.committees.new.members.iter().enumerate(){
+                if let Some(new_agg) = self.state.new_aggs.get(member).and_then(|new_aggs| new_aggs.get(dealer)){
+                    eval_points.push(LargeField::from((position+1) as u64));
+                    new_aggs.push(*new_agg);
+                }
+            }
+            let new_poly = match robust_interpolate(&eval_points, &new_aggs, new_faults, 2*new_faults+1) {
+                Some(poly) => poly,
+                None => {
+                    log::info!("Aggregated subshares of dealer {} for epoch {} do not agree yet, waiting for more", dealer, self.epoch());
+                    return;
+                }
+            };
+            let dealer_point = LargeField::from((self.committees.old.position(*dealer).unwrap()+1) as u64);
+            let secret = new_poly.evaluate(&LargeField::zero());
+            if secret == old_poly.evaluate(&dealer_point){
+                accepted.push(*dealer);
+                accepted_secrets.push(secret);
+            }
+            else{
+                log::error!("Dealer {} did not reshare its shares to epoch {}", dealer, self.epoch());
+            }
+        }
+        if accepted.len() < old_faults+1{
+            log::error!("Only {} correct dealers for epoch {}, cannot complete the handover", accepted.len(), self.epoch());
+            return;
+        }
+        accepted.truncate(old_faults+1);
+
+        // Lagrange coefficients at 0 of the accepted dealers
+        let vandermonde = vandermonde_matrix(accepted.iter().map(|dealer| LargeField::from((self.committees.old.position(*dealer).unwrap()+1) as u64)).collect());
+        let lagrange_coeffs = inverse_vandermonde(vandermonde).swap_remove(0);
+        let mut combined_secret = LargeField::zero();
+        for (secret, coeff) in accepted_secrets.iter().zip(lagrange_coeffs.iter()){
+            combined_secret += coeff*secret;
+        }
+        if combined_secret != old_poly.evaluate(&LargeField::zero()){
+            log::error!("Combined reshares for epoch {} do not open to the combined old secret", self.epoch());
+        }
+
+        // Every accepted dealer dealt its shares and a pad
+        let num_shares = accepted.iter().map(|dealer| self.state.dealings.get(dealer).unwrap().subshares.len()-1).min().unwrap();
+        let mut new_shares = vec![LargeField::zero(); num_shares];
+        for (dealer, coeff) in accepted.iter().zip(lagrange_coeffs.iter()){
+            let subshares = &self.state.dealings.get(dealer).unwrap().subshares;
+            for (new_share, subshare) in new_shares.iter_mut().zip(subshares.iter()){
+                *new_share += coeff*subshare;
+            }
+        }
+        // The mask was opened in the check
+        new_shares.remove(0);
+
+        // Subshares are wiped when dropped
+        self.state.dealings.clear();
+        self.state.new_shares = Some(SecretShares::new(new_shares));
+        self.state.new_done = true;
+        log::info!("Completed the handover to epoch {} with dealers {:?}, holding {} shares", self.epoch(), accepted, num_shares-1);
+        self.try_output().await;
+    }
+
+    /// Outputs once this node finished its roles in both committees
+    async fn try_output(&mut self){
+        if self.state.output_sent{
+            return;
+        }
+        if self.committees.old.contains(self.myid) && !self.state.old_done{
+            return;
+        }
+        if self.committees.new.contains(self.myid) && !self.state.new_done{
+            return;
+        }
+        // Old members report the ACS output, new members the dealers the old committee agreed on
+        let dealers = self.state.dealers.clone()
+            .or_else(|| self.state.acs_dealers.clone())
+            .unwrap_or_default();
+        self.state.output_sent = true;
+        let _status = self.out_handover.send(HandoverOutput {
+            epoch: self.epoch(),
+            dealers: dealers,
+            shares: self.state.new_shares.take(),
+        }).await;
+    }
+
+    // Common random challenge of the check, bound to the commitments of all agreed dealers. None while some are unknown.
+    fn check_challenge(&self, dealers: &[Replica], commitments: &HashMap<Replica, Hash>) -> Option<LargeField>{
+        let mut bytes = Vec::new();
+        for dealer in dealers.iter(){
+            bytes.extend_from_slice(commitments.get(dealer)?);
+        }
+        Some(LargeField::from_bytes_be(do_hash(bytes.as_slice()).as_slice()).unwrap())
+    }
+}
+
+// Sum of challenge^(k+1)*shares[k]
+fn aggregate(shares: &[LargeField], challenge: LargeField) -> LargeField{
+    let mut challenge_mul = challenge;
+    let mut agg = LargeField::zero();
+    for share in shares.iter(){
+        agg += &challenge_mul*share;
+        challenge_mul = &challenge_mul*&challenge;
+    }
+    agg
+}
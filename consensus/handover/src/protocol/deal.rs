@@ -0,0 +1,390 @@
+use consensus::{LargeField, LargeFieldSer, SecretBytes, SecretShares, rand_field_element};
+use crypto::{encrypt, decrypt, hash::{Hash, do_hash}};
+use lambdaworks_math::{polynomial::Polynomial, traits::ByteConversion};
+use types::Replica;
+
+use crate::{Context, msg::ProtMsg, protocol::Dealing};
+
+// Kinds of messages that carry subshares, bound into their encryption keys
+const SEAL_DEAL: u8 = 0;
+const SEAL_HELP: u8 = 1;
+
+/*
+    Resharing of the old committee's shares to the new committee. Every old member deals its shares, with a fresh random
+    pad appended, on random degree-t' polynomials and sends every new member its subshares together with digests of the
+    subshares of all new members and a check polynomial. Subshares travel encrypted under the pairwise key of sender and recipient. The check polynomial is the combination of the dealing's
+    polynomials with powers of a challenge derived from the digests, so the dealer cannot adapt it to the subshares.
+    New members check their subshares against it and agree on the dealing's commitment in two rounds, Echo and Confirm,
+    like Bracha's broadcast. Members with missing or wrong subshares recover them from t'+1 other new members.
+*/
+impl Context{
+    /// Deals this old member's shares to the new committee. The first share is the mask that is opened in the check.
+    pub async fn start_handover(&mut self, shares: SecretShares){
+        if self.state.dealt{
+            return;
+        }
+        if shares.len() < 2{
+            log::error!("Received {} shares to hand over to epoch {}, one mask and at least one share are needed", shares.len(), self.epoch());
+            return;
+        }
+        log::info!("Handing {} shares over to the committee of epoch {}", shares.len()-1, self.epoch());
+        let mut values = shares.to_vec();
+        values.push(rand_field_element());
+        self.state.old_shares = Some(shares);
+
+        let num_new = self.committees.new.num_nodes();
+        let new_faults = self.committees.new.num_faults;
+        let polys: Vec<Polynomial<LargeField>> = values.iter().map(|value| {
+            let mut coefficients = vec![*value];
+            coefficients.extend((0..new_faults).map(|_| rand_field_element()));
+            Polynomial::new(&coefficients)
+        }).collect();
+        let subshares: Vec<Vec<LargeField>> = (0..num_new).map(|position| {
+            let point = LargeField::from((position+1) as u64);
+            polys.iter().map(|poly| poly.evaluate(&point)).collect()
+        }).collect();
+        let digests: Vec<Hash> = subshares.iter().enumerate().map(|(position, subshares)| subshare_digest(position, subshares)).collect();
+
+        let challenge = dealing_challenge(&digests);
+        let mut check_poly = Polynomial::zero();
+        let mut challenge_mul = challenge;
+        for poly in polys.iter(){
+            check_poly = check_poly + poly.scale_coeffs(&challenge_mul);
+            challenge_mul = &challenge_mul*&challenge;
+        }
+        let mut check_coeffs = check_poly.coefficients().to_vec();
+        check_coeffs.resize(new_faults+1, LargeField::zero());
+        let check_coeffs_ser: Vec<LargeFieldSer> = check_coeffs.iter().map(|coeff| coeff.to_bytes_be()).collect();
+
+        self.state.dealt = true;
+        let members = self.committees.new.members.clone();
+        for (member, subshares) in members.into_iter().zip(subshares.into_iter()){
+            // Subshares of the dealer's shares, wiped when dropped
+            let subshares = SecretShares::new(subshares);
+            let sealed = self.seal_subshares(SEAL_DEAL, self.myid, self.myid, member, &subshares);
+            self.send_to(vec![member], ProtMsg::Deal(self.epoch(), sealed, digests.clone(), check_coeffs_ser.clone())).await;
+        }
+        self.try_old_agg().await;
+    }
+
+    pub async fn process_deal_msg(&mut self, sealed: Vec<u8>, digests: Vec<Hash>, check_poly_ser: Vec<LargeFieldSer>, dealer: Replica){
+        let position = match self.committees.new.position(self.myid) {
+            Some(position) => position,
+            None => return,
+        };
+        if !self.committees.old.contains(dealer){
+            log::error!("Node {} dealt to epoch {} but is not a member of the old committee", dealer, self.epoch());
+            return;
+        }
+        if self.state.dealings.contains_key(&dealer){
+            return;
+        }
+        let subshares_ser = match self.open_subshares(SEAL_DEAL, dealer, dealer, self.myid, sealed) {
+            Ok(subshares_ser) => subshares_ser,
+            Err(e) => {
+                log::error!("Unable to open the subshares of dealer {} for epoch {}: {}", dealer, self.epoch(), e);
+                return;
+            }
+        };
+        let dealing = match self.verify_dealing(position, subshares_ser, digests, check_poly_ser) {
+            Ok(dealing) => dealing,
+            Err(e) => {
+                log::error!("Dealing of dealer {} for epoch {} is invalid: {}", dealer, self.epoch(), e);
+                return;
+            }
+        };
+        let commitment = dealing.commitment;
+        self.state.dealings.insert(dealer, dealing);
+        self.send_to_new(ProtMsg::Echo(self.epoch(), dealer, commitment)).await;
+        self.try_new_agg().await;
+    }
+
+    pub async fn process_echo_msg(&mut self, dealer: Replica, commitment: Hash, sender: Replica){
+        if !self.committees.new.contains(self.myid) || !self.committees.new.contains(sender) || !self.committees.old.contains(dealer){
+            return;
+        }
+        let echoes = self.state.echoes.entry(dealer).or_default().entry(commitment).or_default();
+        echoes.insert(sender);
+        if echoes.len() >= 2*self.committees.new.num_faults+1{
+            self.send_confirm(dealer, commitment).await;
+        }
+    }
+
+    pub async fn process_confirm_msg(&mut self, dealer: Replica, commitment: Hash, sender: Replica){
+        if !self.committees.new.contains(sender) || !self.committees.old.contains(dealer){
+            return;
+        }
+        let new_faults = self.committees.new.num_faults;
+        let confirms = self.state.confirms.entry(dealer).or_default().entry(commitment).or_default();
+        confirms.insert(sender);
+        let num_confirms = confirms.len();
+
+        // t'+1 confirms include an honest one, so new members amplify them
+        if self.committees.new.contains(self.myid) && num_confirms >= new_faults+1{
+            self.send_confirm(dealer, commitment).await;
+        }
+        // 2t'+1 confirms reach every honest member of both committees
+        if self.committees.old.contains(self.myid) && num_confirms >= 2*new_faults+1 && !self.state.commitments.contains_key(&dealer){
+            log::info!("Dealing of dealer {} for epoch {} is confirmed by the new committee", dealer, self.epoch());
+            self.state.commitments.insert(dealer, commitment);
+            if self.state.acs_input.insert(dealer){
+                if let Some(acs_term_event) = self.acs_term_event.as_ref(){
+                    let _status = acs_term_event.send((1, dealer, vec![])).await;
+                }
+            }
+            self.try_old_agg().await;
+        }
+    }
+
+    async fn send_confirm(&mut self, dealer: Replica, commitment: Hash){
+        if self.state.confirmed.contains_key(&dealer){
+            return;
+        }
+        self.state.confirmed.insert(dealer, commitment);
+        let mut members = self.committees.old.members.clone();
+        members.extend(self.committees.new.members.iter().filter(|member| !self.committees.old.contains(**member)));
+        self.send_to(members, ProtMsg::Confirm(self.epoch(), dealer, commitment)).await;
+        self.try_new_agg().await;
+    }
+
+    /// Asks the other new members for their subshares of a dealing this member is missing
+    pub async fn request_help(&mut self, dealer: Replica){
+        if !self.state.help_requested.insert(dealer){
+            return;
+        }
+        log::info!("Missing the subshares of dealer {} for epoch {}, requesting help", dealer, self.epoch());
+        self.send_to_new(ProtMsg::HelpReq(self.epoch(), dealer)).await;
+    }
+
+    pub async fn process_help_req_msg(&mut self, dealer: Replica, requester: Replica){
+        if !self.committees.new.contains(requester) || requester == self.myid{
+            return;
+        }
+        let dealing = match self.state.dealings.get(&dealer) {
+            Some(dealing) if self.state.confirmed.get(&dealer) == Some(&dealing.commitment) => dealing,
+            _ => {
+                log::info!("No confirmed dealing of dealer {} to help node {} with", dealer, requester);
+                return;
+            }
+        };
+        if !self.state.help_sent.insert((requester, dealer)){
+            return;
+        }
+        let sealed = self.seal_subshares(SEAL_HELP, dealer, self.myid, requester, &dealing.subshares);
+        let check_poly_ser: Vec<LargeFieldSer> = dealing.check_poly.iter().map(|coeff| coeff.to_bytes_be()).collect();
+        let digests = dealing.digests.clone();
+        self.send_to(vec![requester], ProtMsg::Help(self.epoch(), dealer, sealed, digests, check_poly_ser)).await;
+    }
+
+    /// Recovers this member's subshares from t'+1 helpers whose subshares match the confirmed commitment.
+    /// Helpers passed the check polynomial, so their subshares lie on the dealing's polynomials.
+    pub async fn process_help_msg(&mut self, dealer: Replica, sealed: Vec<u8>, digests: Vec<Hash>, check_poly_ser: Vec<LargeFieldSer>, helper: Replica){
+        let position = match self.committees.new.position(self.myid) {
+            Some(position) => position,
+            None => return,
+        };
+        let helper_position = match self.committees.new.position(helper) {
+            Some(position) => position,
+            None => return,
+        };
+        let commitment = match self.state.confirmed.get(&dealer) {
+            Some(commitment) if self.state.help_requested.contains(&dealer) => *commitment,
+            _ => return,
+        };
+        if self.state.dealings.get(&dealer).map(|dealing| dealing.commitment == commitment).unwrap_or(false){
+            return;
+        }
+        let subshares_ser = match self.open_subshares(SEAL_HELP, dealer, helper, self.myid, sealed) {
+            Ok(subshares_ser) => subshares_ser,
+            Err(e) => {
+                log::error!("Unable to open the subshares node {} helped with for dealer {}: {}", helper, dealer, e);
+                return;
+            }
+        };
+        let dealing = match self.verify_dealing(helper_position, subshares_ser, digests, check_poly_ser) {
+            Ok(dealing) if dealing.commitment == commitment => dealing,
+            Ok(_) => {
+                log::error!("Node {} helped with a dealing of dealer {} that was not confirmed", helper, dealer);
+                return;
+            },
+            Err(e) => {
+                log::error!("Node {} helped with an invalid dealing of dealer {}: {}", helper, dealer, e);
+                return;
+            }
+        };
+        let helpers = self.state.helpers.entry(dealer).or_default();
+        helpers.insert(helper_position, dealing.subshares);
+        if helpers.len() < self.committees.new.num_faults+1{
+            return;
+        }
+
+        let helpers = self.state.helpers.remove(&dealer).unwrap();
+        let (points, helper_subshares): (Vec<LargeField>, Vec<&SecretShares>) = helpers.iter()
+            .map(|(position, subshares)| (LargeField::from((position+1) as u64), subshares))
+            .unzip();
+        let num_values = helper_subshares.iter().map(|subshares| subshares.len()).min().unwrap();
+        let my_point = LargeField::from((position+1) as u64);
+        let mut subshares = Vec::with_capacity(num_values);
+        for index in 0..num_values{
+            let evals: Vec<LargeField> = helper_subshares.iter().map(|subshares| subshares[index]).collect();
+            let poly = Polynomial::interpolate(points.as_slice(), evals.as_slice()).unwrap();
+            subshares.push(poly.evaluate(&my_point));
+        }
+        log::info!("Recovered the subshares of dealer {} for epoch {} from helpers", dealer, self.epoch());
+        self.state.dealings.insert(dealer, Dealing {
+            subshares: SecretShares::new(subshares),
+            digests: dealing.digests,
+            check_poly: dealing.check_poly,
+            commitment: commitment,
+        });
+        self.try_new_agg().await;
+    }
+
+    // Key of the subshares `sender` sends to `recipient` in this epoch, from the key this node shares with the other one
+    fn subshare_key(&self, kind: u8, dealer: Replica, sender: Replica, recipient: Replica) -> Option<SecretBytes>{
+        let peer = if sender == self.myid {recipient} else {sender};
+        let sec_key = self.sec_key_map.get(&peer)?;
+        Some(subshare_key(sec_key, kind, self.epoch(), dealer, sender, recipient))
+    }
+
+    fn seal_subshares(&self, kind: u8, dealer: Replica, sender: Replica, recipient: Replica, subshares: &SecretShares) -> Vec<u8>{
+        match self.subshare_key(kind, dealer, sender, recipient) {
+            Some(key) => seal_subshares(&key, subshares),
+            None => Vec::new(),
+        }
+    }
+
+    fn open_subshares(&self, kind: u8, dealer: Replica, sender: Replica, recipient: Replica, sealed: Vec<u8>) -> Result<Vec<LargeFieldSer>, String>{
+        match self.subshare_key(kind, dealer, sender, recipient) {
+            Some(key) => open_subshares(&key, sealed),
+            None => Err(format!("no key shared with node {}", sender)),
+        }
+    }
+
+    /// Checks the subshares of the new member at `position` against the dealing's digests and check polynomial
+    fn verify_dealing(&self, position: usize, subshares_ser: Vec<LargeFieldSer>, digests: Vec<Hash>, check_poly_ser: Vec<LargeFieldSer>) -> Result<Dealing, String>{
+        if digests.len() != self.committees.new.num_nodes(){
+            return Err(format!("{} digests for {} new members", digests.len(), self.committees.new.num_nodes()));
+        }
+        if check_poly_ser.len() != self.committees.new.num_faults+1{
+            return Err(format!("check polynomial has {} coefficients, expected {}", check_poly_ser.len(), self.committees.new.num_faults+1));
+        }
+        if subshares_ser.len() < 3{
+            return Err(format!("{} subshares, a mask, a share and a pad are needed", subshares_ser.len()));
+        }
+        let subshares = deser_field_elements(&subshares_ser)?;
+        let check_poly = deser_field_elements(&check_poly_ser)?;
+        if subshare_digest(position, &subshares) != digests[position]{
+            return Err(format!("subshares of position {} do not match their digest", position));
+        }
+
+        let challenge = dealing_challenge(&digests);
+        let mut challenge_mul = challenge;
+        let mut combined = LargeField::zero();
+        for subshare in subshares.iter(){
+            combined += &challenge_mul*subshare;
+            challenge_mul = &challenge_mul*&challenge;
+        }
+        if Polynomial::new(&check_poly).evaluate(&LargeField::from((position+1) as u64)) != combined{
+            return Err(format!("subshares of position {} do not lie on the check polynomial", position));
+        }
+
+        let mut commitment_bytes = Vec::new();
+        for digest in digests.iter(){
+            commitment_bytes.extend_from_slice(digest);
+        }
+        for coeff in check_poly_ser.iter(){
+            commitment_bytes.extend_from_slice(coeff);
+        }
+        Ok(Dealing {
+            subshares: SecretShares::new(subshares),
+            digests: digests,
+            check_poly: check_poly,
+            commitment: do_hash(commitment_bytes.as_slice()),
+        })
+    }
+}
+
+// Key of one message carrying subshares, bound to the kind of message, the epoch, the dealer, the sender and the recipient.
+// Every tuple is used for one message only.
+fn subshare_key(sec_key: &SecretBytes, kind: u8, epoch: usize, dealer: Replica, sender: Replica, recipient: Replica) -> SecretBytes{
+    let mut label = vec![kind];
+    for id in [epoch, dealer, sender, recipient]{
+        label.extend((id as u64).to_be_bytes());
+    }
+    SecretBytes::new(do_hash(sec_key.with_suffix(label.as_slice()).as_slice()).to_vec())
+}
+
+fn seal_subshares(key: &SecretBytes, subshares: &SecretShares) -> Vec<u8>{
+    let subshares_ser: Vec<LargeFieldSer> = subshares.iter().map(|subshare| subshare.to_bytes_be()).collect();
+    encrypt(key.as_slice(), bincode::serialize(&subshares_ser).unwrap())
+}
+
+fn open_subshares(key: &SecretBytes, sealed: Vec<u8>) -> Result<Vec<LargeFieldSer>, String>{
+    if sealed.is_empty(){
+        return Err("no subshares".to_string());
+    }
+    let subshares_ser = SecretBytes::new(decrypt(key.as_slice(), sealed));
+    bincode::deserialize(subshares_ser.as_slice()).map_err(|e| format!("malformed subshares: {}", e))
+}
+
+// Binds the subshares of one new member to its position
+fn subshare_digest(position: usize, subshares: &[LargeField]) -> Hash{
+    let mut bytes = (position as u64).to_be_bytes().to_vec();
+    for subshare in subshares.iter(){
+        bytes.extend_from_slice(&subshare.to_bytes_be());
+    }
+    do_hash(bytes.as_slice())
+}
+
+// Challenge of a dealing, fixed by the digests of all subshares
+fn dealing_challenge(digests: &[Hash]) -> LargeField{
+    let mut bytes = Vec::new();
+    for digest in digests.iter(){
+        bytes.extend_from_slice(digest);
+    }
+    LargeField::from_bytes_be(do_hash(bytes.as_slice()).as_slice()).unwrap()
+}
+
+pub(crate) fn deser_field_elements(elements_ser: &[LargeFieldSer]) -> Result<Vec<LargeField>, String>{
+    elements_ser.iter().map(|element| {
+        LargeField::from_bytes_be(element.as_slice()).map_err(|_| "malformed field element".to_string())
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn pairwise_key() -> SecretBytes{
+        SecretBytes::new((0u8..16).collect())
+    }
+
+    #[test]
+    fn sealed_subshares_open_with_the_same_key() {
+        let subshares = SecretShares::new((0..4).map(|_| rand_field_element()).collect());
+        let key = subshare_key(&pairwise_key(), SEAL_DEAL, 1, 2, 2, 5);
+        let sealed = seal_subshares(&key, &subshares);
+
+        let subshares_ser: Vec<LargeFieldSer> = subshares.iter().map(|subshare| subshare.to_bytes_be()).collect();
+        assert!(!sealed.windows(subshares_ser[0].len()).any(|window| window == subshares_ser[0].as_slice()));
+        assert_eq!(open_subshares(&key, sealed).unwrap(), subshares_ser);
+    }
+
+    #[test]
+    fn subshare_keys_differ_per_message() {
+        let key = subshare_key(&pairwise_key(), SEAL_HELP, 1, 2, 3, 4);
+        // Epoch, dealer, direction and kind all change the key
+        assert!(key != subshare_key(&pairwise_key(), SEAL_HELP, 2, 2, 3, 4));
+        assert!(key != subshare_key(&pairwise_key(), SEAL_HELP, 1, 0, 3, 4));
+        assert!(key != subshare_key(&pairwise_key(), SEAL_HELP, 1, 2, 4, 3));
+        assert!(key != subshare_key(&pairwise_key(), SEAL_DEAL, 1, 2, 3, 4));
+        assert!(key == subshare_key(&pairwise_key(), SEAL_HELP, 1, 2, 3, 4));
+    }
+
+    #[test]
+    fn empty_subshares_do_not_open() {
+        let key = subshare_key(&pairwise_key(), SEAL_DEAL, 1, 2, 2, 5);
+        assert!(open_subshares(&key, Vec::new()).is_err());
+    }
+}
@@ -0,0 +1,6 @@
+mod state;
+pub use state::*;
+
+mod deal;
+
+mod check;
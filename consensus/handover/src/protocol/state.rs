@@ -0,0 +1,82 @@
+use std::collections::{HashMap, HashSet};
+
+use consensus::{LargeField, SecretShares};
+use crypto::hash::Hash;
+use types::Replica;
+
+/// Reshare of one old dealer as seen by one new member
+pub struct Dealing{
+    // Subshares of this member, the last one is the dealer's pad
+    pub subshares: SecretShares,
+    // Digests of the subshares of every new member
+    pub digests: Vec<Hash>,
+    // Coefficients of the dealer's check polynomial
+    pub check_poly: Vec<LargeField>,
+    pub commitment: Hash,
+}
+
+pub struct HandoverState{
+    /// Old committee role
+    pub old_shares: Option<SecretShares>,
+    pub dealt: bool,
+    // Commitments of dealings confirmed by 2t'+1 new members
+    pub commitments: HashMap<Replica, Hash>,
+    pub acs_input: HashSet<Replica>,
+    // Dealers output by the ACS, sorted
+    pub acs_dealers: Option<Vec<Replica>>,
+    pub old_agg_sent: bool,
+
+    /// New committee role
+    pub dealings: HashMap<Replica, Dealing>,
+    pub echoes: HashMap<Replica, HashMap<Hash, HashSet<Replica>>>,
+    pub confirms: HashMap<Replica, HashMap<Hash, HashSet<Replica>>>,
+    // Commitment this member confirmed for every dealer
+    pub confirmed: HashMap<Replica, Hash>,
+    pub dealer_votes: HashMap<Vec<Replica>, HashSet<Replica>>,
+    // Dealers agreed on by t+1 old members, sorted
+    pub dealers: Option<Vec<Replica>>,
+    pub help_requested: HashSet<Replica>,
+    pub help_sent: HashSet<(Replica, Replica)>,
+    // Verified subshares of helpers, per dealer
+    pub helpers: HashMap<Replica, HashMap<Replica, SecretShares>>,
+    pub new_agg_sent: bool,
+    pub old_aggs: HashMap<Replica, LargeField>,
+    pub new_aggs: HashMap<Replica, HashMap<Replica, LargeField>>,
+
+    /// Completion
+    pub old_done: bool,
+    pub new_done: bool,
+    pub new_shares: Option<SecretShares>,
+    pub output_sent: bool,
+}
+
+impl HandoverState{
+    pub fn new() -> HandoverState{
+        HandoverState {
+            old_shares: None,
+            dealt: false,
+            commitments: HashMap::default(),
+            acs_input: HashSet::default(),
+            acs_dealers: None,
+            old_agg_sent: false,
+
+            dealings: HashMap::default(),
+            echoes: HashMap::default(),
+            confirms: HashMap::default(),
+            confirmed: HashMap::default(),
+            dealer_votes: HashMap::default(),
+            dealers: None,
+            help_requested: HashSet::default(),
+            help_sent: HashSet::default(),
+            helpers: HashMap::default(),
+            new_agg_sent: false,
+            old_aggs: HashMap::default(),
+            new_aggs: HashMap::default(),
+
+            old_done: false,
+            new_done: false,
+            new_shares: None,
+            output_sent: false,
+        }
+    }
+}
@@ -1,2 +0,0 @@
-mod handler;
-pub use handler::*;
\ No newline at end of file
@@ -7,7 +7,4 @@ pub use msg::*;
 mod protocol;
 pub use protocol::*;
 
-mod handlers;
-pub use handlers::*;
-
 mod process;
\ No newline at end of file
@@ -11,6 +11,7 @@ impl Context{
         let secret_key = self.sec_key_map.get(&self.leader_id).unwrap().clone();
         let wrapper_msg = WrapperMsg::new(prot_msg,self.myid, &secret_key);
 
+        consensus::count_sent_message(&wrapper_msg);
         let cancel_handler: CancelHandler<Acknowledgement> = self.net_send.send(self.leader_id, wrapper_msg).await;
         self.add_cancel_handler(cancel_handler);
     }
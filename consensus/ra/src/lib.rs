@@ -1,11 +1,8 @@
 mod protocol;
 
-
 mod context;
 pub use context::*;
 
 mod msg;
 
-mod handlers;
-
 mod process;
\ No newline at end of file
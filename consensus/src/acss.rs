@@ -0,0 +1,306 @@
+use std::{fmt, str::FromStr};
+
+use async_trait::async_trait;
+use config::Node;
+use ha_crypto::hash::Hash;
+use tokio::sync::{mpsc::{Receiver, Sender}, oneshot};
+use types::Replica;
+
+use crate::{HashKeys, LargeField, ShareOpening};
+
+/// Sharing terminated at this node: instance id, dealer, root commitment, this node's shares and their opening against the root.
+/// The shares are `None` if the dealer's sharing terminated without valid shares for this node.
+/// Only schemes that commit to shares with Merkle trees, like acss_ske, give an opening.
+pub type AcssOutput = (usize, Replica, Hash, Option<Vec<LargeField>>, Option<ShareOpening>);
+
+/// Publicly reconstructed secrets: instance id, dealer, secrets
+pub type PubRecOutput = (usize, Replica, Vec<LargeField>);
+
+/// Publicly reconstructed subset of a sharing: instance id, dealer, requested secret indices and the secrets at them
+pub type SelectedPubRecOutput = (usize, Replica, Vec<usize>, Vec<LargeField>);
+
+/// Secrets an application deals itself, under a tag of its choosing
+#[derive(Debug, Clone)]
+pub struct SecretInput{
+    pub tag: u64,
+    pub secrets: Vec<LargeField>,
+}
+
+/// What became of a `SecretInput`. The first `real` shares of the instance are the application's secrets,
+/// the `padding` sharings after them are filler the scheme added.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SharingReceipt{
+    pub tag: u64,
+    pub instance_id: usize,
+    pub real: usize,
+    pub padding: usize,
+}
+
+/// Batch of the caller a sharing in `instance_id` belongs to. DPSS deals batch `b` in the instance pair `2b-1, 2b`,
+/// so both sharings of a batch map to `b`. Refresh epochs deal their reshare only in `2b-1`. The ACSS services bind it into their PRF seeds.
+pub fn sharing_batch(instance_id: usize) -> usize{
+    (instance_id+1)/2
+}
+
+/// Output of an ACSS service
+#[derive(Debug)]
+pub enum AcssEvent{
+    Shared(AcssOutput),
+    Reconstructed(PubRecOutput),
+    Opened(SelectedPubRecOutput),
+}
+
+/// ACSS schemes a protocol like DPSS can run on, by the name used on the command line and in experiment plans
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AcssScheme{
+    /// Low-threshold ACSS with symmetric-key encrypted shares
+    Ske,
+    /// High-threshold ACSS with symmetric-key encrypted shares
+    GAcss,
+    /// High-threshold ACSS with verifiable abort
+    HAcss,
+    /// Batched ACSS over bivariate polynomials
+    Bv,
+}
+
+impl AcssScheme{
+    pub const ALL: [AcssScheme; 4] = [AcssScheme::Ske, AcssScheme::GAcss, AcssScheme::HAcss, AcssScheme::Bv];
+
+    pub fn name(&self) -> &'static str{
+        match self {
+            AcssScheme::Ske => "acss_ske",
+            AcssScheme::GAcss => "g_acss",
+            AcssScheme::HAcss => "hacss",
+            AcssScheme::Bv => "acss_bv",
+        }
+    }
+
+    /// Number of sharings the scheme deals and outputs for `num_secrets` secrets.
+    /// acss_ske fills up to a multiple of t+1. acss_bv pads internally but drops the padding from its outputs.
+    pub fn padded_len(&self, num_secrets: usize, num_faults: usize) -> usize{
+        match self {
+            AcssScheme::Ske => {
+                let group = num_faults+1;
+                ((num_secrets+group-1)/group)*group
+            },
+            AcssScheme::GAcss | AcssScheme::HAcss | AcssScheme::Bv => num_secrets,
+        }
+    }
+
+    /// Degree of the polynomials the scheme's shares lie on. hacss shares on degree 2t, the others on degree t.
+    pub fn degree(&self, num_faults: usize) -> usize{
+        match self {
+            AcssScheme::HAcss => 2*num_faults,
+            AcssScheme::Ske | AcssScheme::GAcss | AcssScheme::Bv => num_faults,
+        }
+    }
+
+    pub fn supports_public_reconstruction(&self) -> bool{
+        match self {
+            AcssScheme::Ske | AcssScheme::GAcss | AcssScheme::HAcss | AcssScheme::Bv => true,
+        }
+    }
+}
+
+impl fmt::Display for AcssScheme{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result{
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for AcssScheme{
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<AcssScheme, String>{
+        AcssScheme::ALL.iter()
+            .find(|scheme| scheme.name() == s)
+            .copied()
+            .ok_or_else(|| format!("unknown ACSS scheme {}", s))
+    }
+}
+
+/// What a protocol needs from the ACSS scheme it runs on
+#[derive(Debug, Clone, Copy)]
+pub struct AcssNeeds{
+    pub public_reconstruction: bool,
+    /// Degree the protocol reconstructs shares at, `None` if it reconstructs at the scheme's degree
+    pub degree: Option<usize>,
+}
+
+impl AcssNeeds{
+    pub fn check(&self, scheme: AcssScheme, num_faults: usize) -> Result<(), String>{
+        if self.public_reconstruction && !scheme.supports_public_reconstruction(){
+            return Err(format!("{} has no public reconstruction", scheme));
+        }
+        match self.degree {
+            Some(degree) if degree != scheme.degree(num_faults) => {
+                Err(format!("{} shares on degree {}, not degree {}", scheme, scheme.degree(num_faults), degree))
+            },
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Spawns the service of one ACSS scheme on the ports of `config`
+pub type AcssSpawn = fn(Node, HashKeys, AcssParams) -> anyhow::Result<Box<dyn Acss>>;
+
+/// Spawners of every ACSS scheme. The scheme crates depend on this crate, so the protocols running on them list the spawners.
+pub struct AcssBackends{
+    pub ske: AcssSpawn,
+    pub g_acss: AcssSpawn,
+    pub hacss: AcssSpawn,
+    pub bv: AcssSpawn,
+}
+
+/// Spawns the ACSS service of `scheme` for a protocol with the given needs. Schemes that cannot serve the protocol are rejected before they open any port.
+pub fn spawn_acss_backend(backends: &AcssBackends, scheme: AcssScheme, needs: AcssNeeds, config: Node, hash_keys: HashKeys, params: AcssParams) -> anyhow::Result<Box<dyn Acss>>{
+    needs.check(scheme, config.num_faults)
+        .map_err(|e| anyhow::anyhow!("{}, which the protocol needs", e))?;
+    let spawn = match scheme {
+        AcssScheme::Ske => backends.ske,
+        AcssScheme::GAcss => backends.g_acss,
+        AcssScheme::HAcss => backends.hacss,
+        AcssScheme::Bv => backends.bv,
+    };
+    spawn(config, hash_keys, params)
+}
+
+/// Options shared by the ACSS services. Backends ignore the ones that do not apply to them.
+#[derive(Debug, Clone, Copy)]
+pub struct AcssParams{
+    pub use_fft: bool,
+    pub lin_or_quad: bool,
+    pub byz: bool,
+}
+
+/// A running ACSS service of this node
+#[async_trait]
+pub trait Acss: Send{
+    fn scheme(&self) -> AcssScheme;
+
+    /// Deals `secrets` in instance `instance_id`, with this node as the dealer
+    async fn share(&mut self, instance_id: usize, secrets: Vec<LargeField>);
+
+    /// Number of sharings this service deals and outputs for `num_secrets` secrets
+    fn padded_len(&self, num_secrets: usize) -> usize;
+
+    /// Deals an application's own secrets in instance `instance_id` and says which of the resulting sharings are its secrets
+    async fn share_input(&mut self, instance_id: usize, input: SecretInput) -> SharingReceipt{
+        let real = input.secrets.len();
+        let padding = self.padded_len(real) - real;
+        log::info!("Dealing {} secrets with tag {} in instance {} of {}, padded with {} sharings", real, input.tag, instance_id, self.scheme(), padding);
+        self.share(instance_id, input.secrets).await;
+        SharingReceipt {
+            tag: input.tag,
+            instance_id: instance_id,
+            real: real,
+            padding: padding,
+        }
+    }
+
+    /// Next sharing that terminated at this node, of any dealer, or next publicly reconstructed batch.
+    /// `None` once the service stopped.
+    async fn output(&mut self) -> Option<AcssEvent>;
+
+    fn supports_public_reconstruction(&self) -> bool;
+
+    /// Starts reconstructing the secrets `dealer` shared in `instance_id` in public.
+    /// The secrets come out of `output` as `AcssEvent::Reconstructed`.
+    async fn public_reconstruct(&mut self, instance_id: usize, dealer: Replica);
+
+    fn supports_selected_reconstruction(&self) -> bool{
+        false
+    }
+
+    /// Starts reconstructing only the secrets at `indices` of what `dealer` shared in `instance_id`, the others stay hidden.
+    /// The secrets come out of `output` as `AcssEvent::Opened`, in the order of `indices`.
+    async fn public_reconstruct_selected(&mut self, instance_id: usize, dealer: Replica, indices: Vec<usize>){
+        log::error!("{} cannot reconstruct selected secrets, dropping request for {} secrets of instance {} of dealer {}", self.scheme(), indices.len(), instance_id, dealer);
+    }
+
+    /// Stops the service and its sub-protocols
+    fn shutdown(&mut self);
+}
+
+/// ACSS service driven over the channels of `acss_ske` and `g_acss`
+pub struct AcssService{
+    pub scheme: AcssScheme,
+    pub num_faults: usize,
+    pub acss_req: Sender<(usize, Vec<LargeField>)>,
+    pub acss_out: Receiver<AcssOutput>,
+    pub pub_rec_req: Sender<(usize, Replica)>,
+    pub pub_rec_out: Receiver<PubRecOutput>,
+    /// Requests and outputs of selected public reconstruction, for services that have it
+    pub pub_rec_selected: Option<(Sender<(usize, Replica, Vec<usize>)>, Receiver<SelectedPubRecOutput>)>,
+    pub exit_txs: Vec<oneshot::Sender<()>>,
+}
+
+#[async_trait]
+impl Acss for AcssService{
+    fn scheme(&self) -> AcssScheme{
+        self.scheme
+    }
+
+    async fn share(&mut self, instance_id: usize, secrets: Vec<LargeField>){
+        if self.acss_req.send((instance_id, secrets)).await.is_err(){
+            log::error!("{} service stopped, dropping sharing of instance {}", self.scheme, instance_id);
+        }
+    }
+
+    fn padded_len(&self, num_secrets: usize) -> usize{
+        self.scheme.padded_len(num_secrets, self.num_faults)
+    }
+
+    async fn output(&mut self) -> Option<AcssEvent>{
+        let pub_rec_selected = &mut self.pub_rec_selected;
+        let pub_rec_selected_out = async move {
+            match pub_rec_selected {
+                Some((_, pub_rec_selected_out)) => pub_rec_selected_out.recv().await,
+                None => std::future::pending().await,
+            }
+        };
+        tokio::select! {
+            acss_out = self.acss_out.recv() => acss_out.map(AcssEvent::Shared),
+            pub_rec_out = self.pub_rec_out.recv() => pub_rec_out.map(AcssEvent::Reconstructed),
+            pub_rec_selected_out = pub_rec_selected_out => pub_rec_selected_out.map(AcssEvent::Opened),
+        }
+    }
+
+    fn supports_public_reconstruction(&self) -> bool{
+        true
+    }
+
+    async fn public_reconstruct(&mut self, instance_id: usize, dealer: Replica){
+        if self.pub_rec_req.send((instance_id, dealer)).await.is_err(){
+            log::error!("{} service stopped, dropping public reconstruction of instance {} of dealer {}", self.scheme, instance_id, dealer);
+        }
+    }
+
+    fn supports_selected_reconstruction(&self) -> bool{
+        self.pub_rec_selected.is_some()
+    }
+
+    async fn public_reconstruct_selected(&mut self, instance_id: usize, dealer: Replica, indices: Vec<usize>){
+        let pub_rec_selected_req = match self.pub_rec_selected.as_ref() {
+            Some((pub_rec_selected_req, _)) => pub_rec_selected_req,
+            None => {
+                log::error!("{} cannot reconstruct selected secrets, dropping request for instance {} of dealer {}", self.scheme, instance_id, dealer);
+                return;
+            }
+        };
+        if pub_rec_selected_req.send((instance_id, dealer, indices)).await.is_err(){
+            log::error!("{} service stopped, dropping selected public reconstruction of instance {} of dealer {}", self.scheme, instance_id, dealer);
+        }
+    }
+
+    fn shutdown(&mut self){
+        shutdown_services(&mut self.exit_txs);
+    }
+}
+
+/// Signals every service in `exit_txs` to stop. Services that already stopped are skipped.
+pub fn shutdown_services(exit_txs: &mut Vec<oneshot::Sender<()>>){
+    for exit_tx in exit_txs.drain(..){
+        let _ = exit_tx.send(());
+    }
+}
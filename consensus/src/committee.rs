@@ -0,0 +1,143 @@
+use std::{collections::HashSet, fs, sync::OnceLock};
+
+use config::Node;
+use serde::{Deserialize, Serialize};
+use types::Replica;
+
+use crate::SecretShares;
+
+/// Members of the committee of one epoch. Members are node ids of the deployment config, which lists the nodes of all epochs.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Committee{
+    pub epoch: usize,
+    pub members: Vec<Replica>,
+    pub num_faults: usize,
+}
+
+impl Committee{
+    pub fn num_nodes(&self) -> usize{
+        self.members.len()
+    }
+
+    pub fn contains(&self, replica: Replica) -> bool{
+        self.members.contains(&replica)
+    }
+
+    /// Position of a member in the committee. Its evaluation point is the position plus one.
+    pub fn position(&self, replica: Replica) -> Option<usize>{
+        self.members.iter().position(|member| *member == replica)
+    }
+
+    pub fn validate(&self) -> Result<(), String>{
+        let unique: HashSet<&Replica> = self.members.iter().collect();
+        if unique.len() != self.members.len(){
+            return Err(format!("committee of epoch {} lists a member twice", self.epoch));
+        }
+        if self.members.len() < 3*self.num_faults+1{
+            return Err(format!("committee of epoch {} has {} members, at least {} are needed for {} faults", self.epoch, self.members.len(), 3*self.num_faults+1, self.num_faults));
+        }
+        Ok(())
+    }
+
+    /// Config of a member for the services that run within this committee only, with the committee's n and t.
+    /// Members keep their node ids, so the committee must be the first nodes of the deployment.
+    pub fn node_config(&self, config: &Node) -> Result<Node, String>{
+        if !self.members.iter().enumerate().all(|(position, member)| position == *member){
+            return Err(format!("committee of epoch {} is not the first {} nodes of the config", self.epoch, self.members.len()));
+        }
+        if !self.contains(config.id){
+            return Err(format!("node {} is not a member of the committee of epoch {}", config.id, self.epoch));
+        }
+        let mut committee_config = config.clone();
+        committee_config.num_nodes = self.num_nodes();
+        committee_config.num_faults = self.num_faults;
+        committee_config.net_map.retain(|replica, _| self.contains(*replica));
+        committee_config.sk_map.retain(|replica, _| self.contains(*replica));
+        Ok(committee_config)
+    }
+}
+
+/// Handover of the secrets of one committee to the committee of the next epoch. Every node of both committees loads the same file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HandoverConfig{
+    pub old: Committee,
+    pub new: Committee,
+}
+
+impl HandoverConfig{
+    pub fn validate(&self) -> Result<(), String>{
+        self.old.validate()?;
+        self.new.validate()?;
+        if self.new.epoch != self.old.epoch+1{
+            return Err(format!("handover from epoch {} to epoch {}, epochs must be consecutive", self.old.epoch, self.new.epoch));
+        }
+        // The old committee runs dpss on its own config
+        if !self.old.members.iter().enumerate().all(|(position, member)| position == *member){
+            return Err(format!("old committee must be the first {} nodes of the config", self.old.num_nodes()));
+        }
+        Ok(())
+    }
+
+    /// Both committees must be nodes of the deployment config
+    pub fn validate_for(&self, config: &Node) -> Result<(), String>{
+        self.validate()?;
+        match self.old.members.iter().chain(self.new.members.iter()).find(|member| **member >= config.num_nodes) {
+            Some(member) => Err(format!("member {} of the handover is not a node of the config with {} nodes", member, config.num_nodes)),
+            None => Ok(()),
+        }
+    }
+
+    pub fn is_member(&self, replica: Replica) -> bool{
+        self.old.contains(replica) || self.new.contains(replica)
+    }
+
+    pub fn from_file(path: &str) -> Result<HandoverConfig, String>{
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("unable to read handover config from {}: {}", path, e))?;
+        let config: HandoverConfig = serde_json::from_str(&contents)
+            .map_err(|e| format!("unable to parse handover config in {}: {}", path, e))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn write_to_file(&self, path: &str) -> Result<(), String>{
+        let contents = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("unable to serialize handover config: {}", e))?;
+        fs::write(path, contents)
+            .map_err(|e| format!("unable to write handover config to {}: {}", path, e))
+    }
+}
+
+/// Result of a handover at one node
+#[derive(Debug)]
+pub struct HandoverOutput{
+    pub epoch: usize,
+    // Old committee members whose reshares the new committee agreed on
+    pub dealers: Vec<Replica>,
+    // Shares of the new committee, None at nodes that left
+    pub shares: Option<SecretShares>,
+}
+
+impl HandoverOutput{
+    /// Completion message, the same at every node of both committees
+    pub fn report(&self) -> String{
+        format!("Handover to epoch {} from dealers {:?}", self.epoch, self.dealers)
+    }
+}
+
+// Handover of the deployment. Set once at startup.
+static HANDOVER: OnceLock<HandoverConfig> = OnceLock::new();
+
+/// Makes dpss hand its output shares over to the new committee of `config` instead of reporting
+pub fn enable_handover(config: HandoverConfig) -> Result<(), String>{
+    config.validate()?;
+    if crate::weights().is_some(){
+        return Err("committee handover cannot be combined with weights".to_string());
+    }
+    HANDOVER.set(config)
+        .map_err(|_| "handover already enabled".to_string())
+}
+
+pub fn handover_config() -> Option<&'static HandoverConfig>{
+    HANDOVER.get()
+}
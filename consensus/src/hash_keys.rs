@@ -0,0 +1,66 @@
+use std::fs;
+
+use ha_crypto::aes_hash::HashState;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// Keys of the AES based hash used for Merkle trees and commitments. All nodes of a deployment must use the same keys,
+/// separate deployments should use different ones so their commitments are domain separated.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HashKeys{
+    pub key0: [u8; 16],
+    pub key1: [u8; 16],
+    pub key2: [u8; 16],
+}
+
+impl HashKeys{
+    /// Keys every context used before they were part of the deployment. Only for configs generated without keys.
+    pub fn legacy() -> HashKeys{
+        HashKeys {
+            key0: [5u8; 16],
+            key1: [29u8; 16],
+            key2: [23u8; 16],
+        }
+    }
+
+    pub fn generate() -> HashKeys{
+        let mut rng = rand::thread_rng();
+        HashKeys {
+            key0: rng.gen(),
+            key1: rng.gen(),
+            key2: rng.gen(),
+        }
+    }
+
+    /// The three keys must be distinct and non-zero
+    pub fn validate(&self) -> Result<(), String>{
+        let keys = [self.key0, self.key1, self.key2];
+        if keys.iter().any(|key| key.iter().all(|byte| *byte == 0)){
+            return Err("hash keys must not be zero".to_string());
+        }
+        if self.key0 == self.key1 || self.key1 == self.key2 || self.key0 == self.key2{
+            return Err("hash keys must be distinct".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn hash_state(&self) -> HashState{
+        HashState::new(self.key0, self.key1, self.key2)
+    }
+
+    pub fn from_file(path: &str) -> Result<HashKeys, String>{
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("unable to read hash keys from {}: {}", path, e))?;
+        let hash_keys: HashKeys = serde_json::from_str(&contents)
+            .map_err(|e| format!("unable to parse hash keys in {}: {}", path, e))?;
+        hash_keys.validate()?;
+        Ok(hash_keys)
+    }
+
+    pub fn write_to_file(&self, path: &str) -> Result<(), String>{
+        let contents = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("unable to serialize hash keys: {}", e))?;
+        fs::write(path, contents)
+            .map_err(|e| format!("unable to write hash keys to {}: {}", path, e))
+    }
+}
@@ -17,4 +17,28 @@ pub mod poly;
 pub use poly::*;
 
 pub mod timer;
-pub use timer::*;
\ No newline at end of file
+pub use timer::*;
+
+pub mod stats;
+pub use stats::*;
+
+pub mod hash_keys;
+pub use hash_keys::*;
+
+pub mod secret;
+pub use secret::*;
+
+pub mod transport;
+pub use transport::*;
+
+pub mod acss;
+pub use acss::*;
+
+pub mod share_file;
+pub use share_file::*;
+
+pub mod committee;
+pub use committee::*;
+
+pub mod weights;
+pub use weights::*;
@@ -8,14 +8,66 @@ use rand_core::{SeedableRng, RngCore};
 use rayon::prelude::{IntoParallelIterator, ParallelIterator, IntoParallelRefIterator};
 use types::Replica;
 
-use crate::LargeField;
+use crate::{LargeField, SecretBytes};
 
+// Purposes of the PRF streams a dealer derives from one key within an instance
+pub const PRF_SHARES: u8 = 0;
+pub const PRF_NONCE: u8 = 1;
+pub const PRF_BLINDING: u8 = 2;
+pub const PRF_BLINDING_NONCE: u8 = 3;
+
+const PRF_DOMAIN_TAG: &[u8] = b"acss-rs/prf/v1";
+
+/// Sharing a PRF stream belongs to. The symmetric keys live as long as the context, so the seed binds the
+/// protocol, instance, batch, dealer and recipient to keep streams of different sharings independent.
+#[derive(Debug, Clone)]
+pub struct PrfDomain{
+    pub protocol: &'static str,
+    pub instance_id: usize,
+    pub batch: usize,
+    pub dealer: Replica,
+}
+
+impl PrfDomain{
+    pub fn new(protocol: &'static str, instance_id: usize, batch: usize, dealer: Replica) -> PrfDomain{
+        PrfDomain { 
+            protocol: protocol, 
+            instance_id: instance_id, 
+            batch: batch,
+            dealer: dealer 
+        }
+    }
+
+    /// PRF seed of the stream `purpose` between the dealer and `recipient`. Variable length fields are length prefixed.
+    pub fn seed(&self, sec_key: &[u8], recipient: Replica, purpose: u8) -> SecretBytes{
+        // Sized up front so the key is never left behind in a reallocated buffer
+        let mut seed = Vec::with_capacity(PRF_DOMAIN_TAG.len() + sec_key.len() + self.protocol.len() + 6*8 + 1);
+        seed.extend_from_slice(PRF_DOMAIN_TAG);
+        seed.extend((sec_key.len() as u64).to_be_bytes());
+        seed.extend_from_slice(sec_key);
+        seed.extend((self.protocol.len() as u64).to_be_bytes());
+        seed.extend_from_slice(self.protocol.as_bytes());
+        seed.extend((self.instance_id as u64).to_be_bytes());
+        seed.extend((self.batch as u64).to_be_bytes());
+        seed.extend((self.dealer as u64).to_be_bytes());
+        seed.extend((recipient as u64).to_be_bytes());
+        seed.push(purpose);
+        SecretBytes::new(seed)
+    }
+
+    /// The first `num` values of the stream `purpose` between the dealer and `recipient`
+    pub fn stream(&self, sec_key: &[u8], recipient: Replica, purpose: u8, num: usize) -> Vec<LargeField>{
+        pseudorandom_lf(&self.seed(sec_key, recipient, purpose), num)
+    }
+}
+
+/// Dealer side: samples the evaluations of the first `degree` parties from the PRF keyed with their symmetric keys
 pub fn sample_polynomials_from_prf(
     secrets: Vec<LargeField>, 
-    sec_key_map: HashMap<Replica, Vec<u8>>, 
+    sec_key_map: &HashMap<Replica, SecretBytes>, 
     degree: usize,
-    is_nonce: bool,
-    nonce: u8
+    domain: &PrfDomain,
+    purpose: u8
 )-> Vec<Vec<LargeField>>{
     let tot_evaluations = secrets.len();
     let mut evaluations = Vec::new();
@@ -23,11 +75,8 @@ pub fn sample_polynomials_from_prf(
         evaluations.push(vec![secret]);
     }
     for i in 0..degree{
-        let mut sec_key = sec_key_map.get(&(i as Replica)).unwrap().clone();
-        if is_nonce{
-            sec_key.push(nonce);
-        }
-        let samples = pseudorandom_lf(&sec_key, tot_evaluations);
+        let sec_key = sec_key_map.get(&(i as Replica)).unwrap();
+        let samples = domain.stream(sec_key, i as Replica, purpose, tot_evaluations);
         for (i,sample) in samples.into_iter().enumerate() {
             evaluations[i].push(sample);
         }
@@ -147,11 +196,9 @@ pub fn rand_field_element() -> LargeField {
 }
 
 
-pub fn interpolate_shares( mut secret_key: Vec<u8>, num_shares: usize, is_nonce: bool, padding: u8) -> Vec<LargeField>{
-    if is_nonce{
-        secret_key.push(padding);
-    }
-    let prf_values = pseudorandom_lf(&secret_key, num_shares);
+/// Recipient side of `sample_polynomials_from_prf`: the recipient's evaluations in the given domain and purpose
+pub fn interpolate_shares(secret_key: &[u8], num_shares: usize, domain: &PrfDomain, recipient: Replica, purpose: u8) -> Vec<LargeField>{
+    let prf_values = domain.stream(secret_key, recipient, purpose, num_shares);
     prf_values
 }
 
@@ -253,4 +300,114 @@ pub fn matrix_vector_multiply(
                 .fold(LargeField::zero(), |sum, (a, b)| sum.add(a.mul(b)))
         })
         .collect()
-}
\ No newline at end of file
+}
+/// Online error correction: finds the polynomial of degree `degree` that at least `min_agreement` of the points lie on.
+/// Corrects up to points.len()-min_agreement wrong evaluations with Berlekamp-Welch.
+/// Returns None if no such polynomial exists yet, more points can still decide it.
+pub fn robust_interpolate(eval_points: &Vec<LargeField>, evaluations: &Vec<LargeField>, degree: usize, min_agreement: usize) -> Option<Polynomial<LargeField>>{
+    let num_points = eval_points.len();
+    if min_agreement < degree+1 || num_points < min_agreement{
+        return None;
+    }
+    let num_errors = std::cmp::min(num_points - min_agreement, (num_points - degree - 1)/2);
+
+    // Unknowns are the coefficients of the error locator E below its monic leading term, then those of Q = P*E.
+    // Every point (x,y) gives Q(x) - y*E(x) = 0.
+    let num_unknowns = num_errors + degree + num_errors + 1;
+    let rows: Vec<Vec<LargeField>> = eval_points.iter().zip(evaluations.iter()).map(|(x, y)|{
+        let mut x_powers = Vec::with_capacity(degree+num_errors+1);
+        let mut power = LargeField::one();
+        for _ in 0..degree+num_errors+1{
+            x_powers.push(power);
+            power = power.mul(x);
+        }
+        let mut row = Vec::with_capacity(num_unknowns+1);
+        for x_power in x_powers[..num_errors].iter(){
+            row.push(LargeField::zero().sub(y.mul(x_power)));
+        }
+        row.extend(x_powers.iter().cloned());
+        row.push(y.mul(&x_powers[num_errors]));
+        row
+    }).collect();
+    let solution = solve_linear_system(rows, num_unknowns)?;
+
+    let mut error_coefficients = solution[..num_errors].to_vec();
+    error_coefficients.push(LargeField::one());
+    let error_locator = Polynomial::new(&error_coefficients);
+    let product = Polynomial::new(&solution[num_errors..]);
+
+    // P = Q/E wherever E does not vanish, which leaves at least degree+1 points
+    let mut points = Vec::new();
+    let mut values = Vec::new();
+    for x in eval_points.iter(){
+        let error_eval = error_locator.evaluate(x);
+        if error_eval == LargeField::zero(){
+            continue;
+        }
+        points.push(*x);
+        values.push(product.evaluate(x).mul(&error_eval.inv().unwrap()));
+        if points.len() == degree+1{
+            break;
+        }
+    }
+    if points.len() < degree+1{
+        return None;
+    }
+    let coefficients = matrix_vector_multiply(&inverse_vandermonde(vandermonde_matrix(points)), &values);
+    let polynomial = Polynomial::new(&coefficients);
+
+    let agreements = eval_points.iter().zip(evaluations.iter()).filter(|(x, y)| polynomial.evaluate(*x) == **y).count();
+    if agreements >= min_agreement{
+        Some(polynomial)
+    }
+    else{
+        None
+    }
+}
+
+/// Solves the system given by `rows`, each holding `num_unknowns` coefficients followed by the constant, with Gaussian elimination.
+/// Free unknowns are set to zero. Returns None if the system has no solution.
+fn solve_linear_system(mut rows: Vec<Vec<LargeField>>, num_unknowns: usize) -> Option<Vec<LargeField>>{
+    let mut pivot_cols = Vec::new();
+    for col in 0..num_unknowns{
+        let pivot_row = pivot_cols.len();
+        if pivot_row == rows.len(){
+            break;
+        }
+        let found = (pivot_row..rows.len()).find(|row| rows[*row][col] != LargeField::zero());
+        let row = match found {
+            Some(row) => row,
+            None => continue,
+        };
+        rows.swap(pivot_row, row);
+
+        // Normalize pivot row
+        let inv = &rows[pivot_row][col].inv().unwrap();
+        for value in rows[pivot_row][col..].iter_mut(){
+            *value = value.mul(inv);
+        }
+
+        // Eliminate other rows
+        for other in 0..rows.len(){
+            if other != pivot_row && rows[other][col] != LargeField::zero(){
+                let factor = rows[other][col];
+                for k in col..num_unknowns+1{
+                    rows[other][k] = rows[other][k].sub(factor.mul(rows[pivot_row][k]));
+                }
+            }
+        }
+        pivot_cols.push(col);
+    }
+
+    // Rows left without a pivot read 0 = constant
+    for row in rows[pivot_cols.len()..].iter(){
+        if row[num_unknowns] != LargeField::zero(){
+            return None;
+        }
+    }
+    let mut solution = vec![LargeField::zero(); num_unknowns];
+    for (row, col) in pivot_cols.into_iter().enumerate(){
+        solution[col] = rows[row][num_unknowns];
+    }
+    Some(solution)
+}
@@ -0,0 +1,110 @@
+use std::{fmt, hint::black_box, ops::Deref, ptr, sync::atomic::{compiler_fence, Ordering}};
+
+use zeroize::Zeroize;
+
+use crate::LargeField;
+
+/// Key material that is wiped when dropped and never printed.
+/// It has no mutable access, so the buffer is never reallocated and left behind unwiped.
+#[derive(Clone, Default)]
+pub struct SecretBytes(Vec<u8>);
+
+impl SecretBytes{
+    pub fn new(bytes: Vec<u8>) -> SecretBytes{
+        SecretBytes(bytes)
+    }
+
+    /// The key followed by `suffix`, for PRF seeds derived from the key
+    pub fn with_suffix(&self, suffix: &[u8]) -> SecretBytes{
+        let mut bytes = Vec::with_capacity(self.0.len() + suffix.len());
+        bytes.extend_from_slice(&self.0);
+        bytes.extend_from_slice(suffix);
+        SecretBytes(bytes)
+    }
+}
+
+impl From<Vec<u8>> for SecretBytes{
+    fn from(bytes: Vec<u8>) -> Self {
+        SecretBytes(bytes)
+    }
+}
+
+impl Deref for SecretBytes{
+    type Target = Vec<u8>;
+
+    fn deref(&self) -> &Vec<u8> {
+        &self.0
+    }
+}
+
+impl Drop for SecretBytes{
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+// Compares in time independent of where the bytes differ. Only the lengths are compared in variable time.
+impl PartialEq for SecretBytes{
+    fn eq(&self, other: &Self) -> bool {
+        if self.0.len() != other.0.len(){
+            return false;
+        }
+        let difference = self.0.iter().zip(other.0.iter())
+            .fold(0u8, |difference, (a, b)| black_box(difference | (a ^ b)));
+        difference == 0
+    }
+}
+
+impl Eq for SecretBytes{}
+
+impl fmt::Debug for SecretBytes{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SecretBytes(<{} bytes redacted>)", self.0.len())
+    }
+}
+
+/// Shares held by a node, wiped when dropped and never printed
+#[derive(Clone, Default)]
+pub struct SecretShares(Vec<LargeField>);
+
+impl SecretShares{
+    pub fn new(shares: Vec<LargeField>) -> SecretShares{
+        SecretShares(shares)
+    }
+}
+
+impl From<Vec<LargeField>> for SecretShares{
+    fn from(shares: Vec<LargeField>) -> Self {
+        SecretShares(shares)
+    }
+}
+
+impl Deref for SecretShares{
+    type Target = Vec<LargeField>;
+
+    fn deref(&self) -> &Vec<LargeField> {
+        &self.0
+    }
+}
+
+impl Drop for SecretShares{
+    fn drop(&mut self) {
+        wipe_field_elements(&mut self.0);
+    }
+}
+
+impl fmt::Debug for SecretShares{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SecretShares(<{} shares redacted>)", self.0.len())
+    }
+}
+
+/// Overwrites field elements with zero. Field elements do not implement `Zeroize`,
+/// so the writes are volatile to keep them from being optimized away before the buffer is freed.
+pub fn wipe_field_elements(elements: &mut [LargeField]){
+    for element in elements.iter_mut(){
+        // Safe because the pointer comes from a mutable reference and field elements own no heap memory
+        unsafe { ptr::write_volatile(element, LargeField::zero()); }
+    }
+    compiler_fence(Ordering::SeqCst);
+}
@@ -0,0 +1,210 @@
+use std::{collections::HashSet, fs, path::{Path, PathBuf}, sync::OnceLock};
+
+use ha_crypto::{hash::Hash, aes_hash::Proof};
+use lambdaworks_math::{polynomial::Polynomial, traits::ByteConversion};
+use serde::{Deserialize, Serialize};
+use types::Replica;
+
+use crate::{LargeField, LargeFieldSer, vandermonde_matrix, inverse_vandermonde, matrix_vector_multiply};
+
+const SHARE_FILE_MAGIC: &[u8; 8] = b"ACSSSHRS";
+/// Version of the share file layout. Readers reject files of other versions.
+pub const SHARE_FILE_VERSION: u32 = 2;
+/// Field of the shares in a share file
+pub const SHARE_FILE_FIELD: &str = "stark252";
+
+/// What a share file holds shares of. All files of one batch share the header except for `node`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ShareFileHeader{
+    pub version: u32,
+    /// Protocol that produced the shares, like dpss
+    pub protocol: String,
+    /// ACSS scheme the dealers shared with
+    pub acss: String,
+    pub field: String,
+    pub num_nodes: usize,
+    pub num_faults: usize,
+    /// Degree of the sharing polynomials. degree+1 files reconstruct the secrets.
+    pub degree: usize,
+    /// Node holding the shares. Its evaluation point is node+1.
+    pub node: Replica,
+    pub batch: usize,
+    /// ACSS instances of the batch
+    pub instance_ids: Vec<usize>,
+    /// Dealers agreed on through ACS, in sorted order
+    pub dealers: Vec<Replica>,
+}
+
+/// What ties a node's shares to the dealer's commitment root, for schemes that commit to shares with Merkle trees.
+/// acss_ske hashes the shares of every column with a nonce into a leaf, and its root hashes the column roots and the blinding column roots.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShareOpening{
+    /// Whether the dealer evaluated the polynomials at roots of unity instead of 1..n
+    pub use_fft: bool,
+    pub nonces: Vec<LargeFieldSer>,
+    /// Merkle proof of every leaf against its column root
+    pub proofs: Vec<Proof>,
+    pub blinding_roots: Vec<Hash>,
+}
+
+/// Shares of one dealer's sharing, with the root of the dealer's commitment and, if the scheme has one, the opening of the shares against it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportedSharing{
+    pub dealer: Replica,
+    pub instance_id: usize,
+    pub root: Hash,
+    pub shares: Vec<LargeFieldSer>,
+    pub opening: Option<ShareOpening>,
+}
+
+/// Shares of one node in one batch, written to disk for audits and recovery
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShareFile{
+    pub header: ShareFileHeader,
+    pub sharings: Vec<ExportedSharing>,
+}
+
+/// Secrets of a sharing, recovered from share files
+#[derive(Debug, Clone)]
+pub struct ReconstructedSharing{
+    pub dealer: Replica,
+    pub instance_id: usize,
+    pub root: Hash,
+    pub secrets: Vec<LargeField>,
+}
+
+impl ShareFile{
+    pub fn file_name(protocol: &str, node: Replica, batch: usize) -> String{
+        format!("{}-shares-node{}-batch{}.bin", protocol, node, batch)
+    }
+
+    /// Writes the file into `dir` under its standard name
+    pub fn write_to_dir(&self, dir: &Path) -> Result<PathBuf, String>{
+        let path = dir.join(Self::file_name(&self.header.protocol, self.header.node, self.header.batch));
+        let body = bincode::serialize(self)
+            .map_err(|e| format!("unable to serialize share file: {}", e))?;
+        let mut contents = Vec::with_capacity(SHARE_FILE_MAGIC.len() + 4 + body.len());
+        contents.extend_from_slice(SHARE_FILE_MAGIC);
+        contents.extend_from_slice(&SHARE_FILE_VERSION.to_le_bytes());
+        contents.extend(body);
+        fs::write(&path, contents)
+            .map_err(|e| format!("unable to write share file {}: {}", path.display(), e))?;
+        Ok(path)
+    }
+
+    pub fn from_file(path: &Path) -> Result<ShareFile, String>{
+        let contents = fs::read(path)
+            .map_err(|e| format!("unable to read share file {}: {}", path.display(), e))?;
+        let prefix_len = SHARE_FILE_MAGIC.len() + 4;
+        if contents.len() < prefix_len || &contents[..SHARE_FILE_MAGIC.len()] != SHARE_FILE_MAGIC{
+            return Err(format!("{} is not a share file", path.display()));
+        }
+        let mut version = [0u8; 4];
+        version.copy_from_slice(&contents[SHARE_FILE_MAGIC.len()..prefix_len]);
+        let version = u32::from_le_bytes(version);
+        if version != SHARE_FILE_VERSION{
+            return Err(format!("share file {} has version {}, expected {}", path.display(), version, SHARE_FILE_VERSION));
+        }
+        let share_file: ShareFile = bincode::deserialize(&contents[prefix_len..])
+            .map_err(|e| format!("unable to parse share file {}: {}", path.display(), e))?;
+        if share_file.header.version != version{
+            return Err(format!("share file {} has a header of version {}", path.display(), share_file.header.version));
+        }
+        Ok(share_file)
+    }
+}
+
+/// Recovers the secrets of every sharing from the share files of one batch.
+/// The files must agree on everything but the node, including the commitment roots, and `check_opening` must accept
+/// the shares of every file against the root before they are interpolated. Agreeing roots alone do not bind the shares,
+/// a file can carry any shares next to the right root. The first degree+1 files define the sharing polynomials
+/// and the shares of any further file must lie on them.
+pub fn reconstruct_share_files<F>(files: &Vec<ShareFile>, check_opening: F) -> Result<Vec<ReconstructedSharing>, String>
+    where F: Fn(&ShareFileHeader, &ExportedSharing) -> Result<(), String>
+{
+    if files.is_empty(){
+        return Err("no share files given".to_string());
+    }
+    let first = &files[0];
+    if first.header.field != SHARE_FILE_FIELD{
+        return Err(format!("shares are over the field {}, only {} is supported", first.header.field, SHARE_FILE_FIELD));
+    }
+    let degree = first.header.degree;
+    if files.len() < degree+1{
+        return Err(format!("{} share files given, reconstruction needs {}", files.len(), degree+1));
+    }
+
+    let mut nodes = HashSet::new();
+    for file in files.iter(){
+        let mut header = file.header.clone();
+        header.node = first.header.node;
+        if header != first.header{
+            return Err(format!("share file of node {} belongs to a different run or batch than that of node {}", file.header.node, first.header.node));
+        }
+        if file.header.node >= file.header.num_nodes || !nodes.insert(file.header.node){
+            return Err(format!("node {} is out of range or has more than one share file", file.header.node));
+        }
+        if file.sharings.len() != first.sharings.len(){
+            return Err(format!("share file of node {} holds {} sharings, that of node {} holds {}", file.header.node, file.sharings.len(), first.header.node, first.sharings.len()));
+        }
+        for (sharing, first_sharing) in file.sharings.iter().zip(first.sharings.iter()){
+            if sharing.dealer != first_sharing.dealer || sharing.instance_id != first_sharing.instance_id{
+                return Err(format!("share files of nodes {} and {} list different sharings", file.header.node, first.header.node));
+            }
+            if sharing.root != first_sharing.root{
+                return Err(format!("node {} holds a different commitment for instance {} of dealer {}", file.header.node, sharing.instance_id, sharing.dealer));
+            }
+            if sharing.shares.len() != first_sharing.shares.len(){
+                return Err(format!("node {} holds {} shares for instance {} of dealer {}, node {} holds {}", file.header.node, sharing.shares.len(), sharing.instance_id, sharing.dealer, first.header.node, first_sharing.shares.len()));
+            }
+            check_opening(&file.header, sharing)?;
+        }
+    }
+
+    let eval_points: Vec<LargeField> = files[..degree+1].iter().map(|file| LargeField::from((file.header.node+1) as u64)).collect();
+    let inverse_vandermonde = inverse_vandermonde(vandermonde_matrix(eval_points));
+
+    let mut reconstructed = Vec::new();
+    for (index, first_sharing) in first.sharings.iter().enumerate(){
+        let mut secrets = Vec::new();
+        for share_index in 0..first_sharing.shares.len(){
+            let mut points = Vec::new();
+            for file in files.iter(){
+                let share = LargeField::from_bytes_be(file.sharings[index].shares[share_index].as_slice())
+                    .map_err(|_| format!("node {} holds a malformed share in instance {} of dealer {}", file.header.node, first_sharing.instance_id, first_sharing.dealer))?;
+                points.push(share);
+            }
+            let coefficients = matrix_vector_multiply(&inverse_vandermonde, &points[..degree+1].to_vec());
+            let polynomial = Polynomial::new(&coefficients);
+            for (file, share) in files.iter().zip(points.iter()).skip(degree+1){
+                if polynomial.evaluate(&LargeField::from((file.header.node+1) as u64)) != *share{
+                    return Err(format!("share {} of node {} in instance {} of dealer {} does not lie on the polynomial of the other shares", share_index, file.header.node, first_sharing.instance_id, first_sharing.dealer));
+                }
+            }
+            secrets.push(polynomial.evaluate(&LargeField::from(0 as u64)));
+        }
+        reconstructed.push(ReconstructedSharing {
+            dealer: first_sharing.dealer,
+            instance_id: first_sharing.instance_id,
+            root: first_sharing.root,
+            secrets: secrets,
+        });
+    }
+    Ok(reconstructed)
+}
+
+// Directory nodes export their shares to. Set once at startup, shared by all protocol services of the process.
+static SHARE_EXPORT_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Makes protocols that support it write their shares to `dir`, one file per node and batch
+pub fn enable_share_export(dir: &str) -> Result<(), String>{
+    let dir = PathBuf::from(dir);
+    fs::create_dir_all(&dir)
+        .map_err(|e| format!("unable to create share export directory {}: {}", dir.display(), e))?;
+    SHARE_EXPORT_DIR.set(dir)
+        .map_err(|_| "share export already enabled".to_string())
+}
+
+pub fn share_export_dir() -> Option<&'static Path>{
+    SHARE_EXPORT_DIR.get().map(|dir| dir.as_path())
+}
@@ -0,0 +1,40 @@
+use std::{sync::atomic::{AtomicU64, Ordering}, time::{SystemTime, UNIX_EPOCH}};
+
+use serde::Serialize;
+
+use crate::CompletionReport;
+
+// Process wide counters. All protocol services of a node run in the same process, so these are the node's totals.
+// Messages sent by external crates (e.g. CTRBC) are not counted.
+static MESSAGES_SENT: AtomicU64 = AtomicU64::new(0);
+static BYTES_SENT: AtomicU64 = AtomicU64::new(0);
+
+/// Counts a message handed to the network layer. Call this at every send site with the wrapped message.
+pub fn count_sent_message<T: Serialize>(msg: &T){
+    let size = bincode::serialized_size(msg).unwrap_or(0);
+    MESSAGES_SENT.fetch_add(1, Ordering::Relaxed);
+    BYTES_SENT.fetch_add(size, Ordering::Relaxed);
+}
+
+/// (messages, bytes) sent by this node so far
+pub fn sent_message_stats() -> (u64, u64){
+    (MESSAGES_SENT.load(Ordering::Relaxed), BYTES_SENT.load(Ordering::Relaxed))
+}
+
+impl CompletionReport{
+    /// Report for a run that started at `start_time`, completed now, with the node's current message counters
+    pub fn new(id: usize, msg: String, start_time: u128) -> CompletionReport{
+        let (messages_sent, bytes_sent) = sent_message_stats();
+        CompletionReport {
+            id: id,
+            msg: msg,
+            start_time: start_time,
+            completion_time: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_millis(),
+            messages_sent: messages_sent,
+            bytes_sent: bytes_sent,
+        }
+    }
+}
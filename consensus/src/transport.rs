@@ -0,0 +1,617 @@
+use std::{collections::BTreeSet, fmt, fs, marker::PhantomData, net::SocketAddr, path::Path, sync::{Arc, Mutex, OnceLock}, time::{SystemTime, UNIX_EPOCH}};
+
+use async_trait::async_trait;
+use chacha20poly1305::{aead::{Aead, KeyInit, Payload}, ChaCha20Poly1305, Key, Nonce};
+use fnv::FnvHashMap;
+use futures_util::SinkExt;
+use ha_crypto::hash::do_hash;
+use network::{plaintcp::{CancelHandler, TcpReceiver, TcpReliableSender}, Acknowledgement};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::UnboundedSender;
+use types::Replica;
+use x25519_dalek::{PublicKey, StaticSecret};
+use zeroize::Zeroize;
+
+const TRANSPORT_KEY_TAG: &[u8] = b"acss-rs/transport/v1";
+
+/// Transport settings of one node. Nodes authenticate each other with static X25519 keys,
+/// so every node's config lists the public keys of all nodes.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TransportConfig{
+    pub id: Replica,
+    /// Encrypt replica links. Without it, the file is ignored and links stay in cleartext.
+    pub encrypt: bool,
+    pub static_secret: [u8; 32],
+    pub public_keys: FnvHashMap<Replica, [u8; 32]>,
+}
+
+impl TransportConfig{
+    /// Fresh static keys for all nodes of a deployment
+    pub fn generate(num_nodes: usize, encrypt: bool) -> Vec<TransportConfig>{
+        let secrets: Vec<[u8; 32]> = (0..num_nodes).map(|_| rand::random::<[u8; 32]>()).collect();
+        let mut public_keys = FnvHashMap::default();
+        for (id, secret) in secrets.iter().enumerate(){
+            public_keys.insert(id, PublicKey::from(&StaticSecret::from(*secret)).to_bytes());
+        }
+        secrets.into_iter().enumerate().map(|(id, secret)| TransportConfig {
+            id: id,
+            encrypt: encrypt,
+            static_secret: secret,
+            public_keys: public_keys.clone(),
+        }).collect()
+    }
+
+    /// The static secret must match this node's listed public key
+    pub fn validate(&self) -> Result<(), String>{
+        let public_key = PublicKey::from(&StaticSecret::from(self.static_secret)).to_bytes();
+        match self.public_keys.get(&self.id){
+            Some(listed) if *listed == public_key => Ok(()),
+            Some(_) => Err(format!("static secret of node {} does not match its public key", self.id)),
+            None => Err(format!("public key of node {} missing from the transport config", self.id)),
+        }
+    }
+
+    pub fn from_file(path: &str) -> Result<TransportConfig, String>{
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("unable to read transport config from {}: {}", path, e))?;
+        let config: TransportConfig = serde_json::from_str(&contents)
+            .map_err(|e| format!("unable to parse transport config in {}: {}", path, e))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn write_to_file(&self, path: &str) -> Result<(), String>{
+        let contents = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("unable to serialize transport config: {}", e))?;
+        fs::write(path, contents)
+            .map_err(|e| format!("unable to write transport config to {}: {}", path, e))
+    }
+}
+
+impl Drop for TransportConfig{
+    fn drop(&mut self) {
+        self.static_secret.zeroize();
+    }
+}
+
+impl fmt::Debug for TransportConfig{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TransportConfig")
+            .field("id", &self.id)
+            .field("encrypt", &self.encrypt)
+            .field("peers", &self.public_keys.len())
+            .finish()
+    }
+}
+
+/// Sessions of the encrypted transport, kept on disk so that a restarted node neither reuses one of its own sessions
+/// nor accepts a session of a peer it already saw before the restart, whose messages it could no longer tell from replays
+#[derive(Default, Serialize, Deserialize)]
+struct SessionRecord{
+    // Last session of this node
+    session: u64,
+    // Highest session adopted from every peer, by listener port
+    peers: FnvHashMap<Replica, FnvHashMap<u16, u64>>,
+}
+
+struct SessionStore{
+    path: String,
+    record: Mutex<SessionRecord>,
+}
+
+impl SessionStore{
+    fn open(path: &str) -> Result<SessionStore, String>{
+        let record = if Path::new(path).exists(){
+            let contents = fs::read_to_string(path)
+                .map_err(|e| format!("unable to read transport sessions from {}: {}", path, e))?;
+            serde_json::from_str(&contents)
+                .map_err(|e| format!("unable to parse transport sessions in {}: {}", path, e))?
+        }
+        else{
+            SessionRecord::default()
+        };
+        Ok(SessionStore {
+            path: path.to_string(),
+            record: Mutex::new(record),
+        })
+    }
+
+    // Starts a session of this node later than all earlier ones, even if the clock went back
+    fn next_session(&self) -> Result<u64, String>{
+        let mut record = self.record.lock().unwrap();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+        record.session = now.max(record.session+1);
+        self.save(&record)?;
+        Ok(record.session)
+    }
+
+    fn highest(&self, peer: Replica, port: u16) -> u64{
+        let record = self.record.lock().unwrap();
+        record.peers.get(&peer)
+            .and_then(|ports| ports.get(&port))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    // Records a new session of `peer` on `port`. Fails if it is not later than every session seen before.
+    fn adopt(&self, peer: Replica, port: u16, session: u64) -> Result<(), String>{
+        let mut record = self.record.lock().unwrap();
+        let highest = record.peers.entry(peer).or_default().entry(port).or_insert(0);
+        if session <= *highest{
+            return Err(format!("session {} of node {} was already seen", session, peer));
+        }
+        *highest = session;
+        // The session is genuine, so a failed write does not reject it. Only a restart before the next write could accept a replay of it.
+        if let Err(e) = self.save(&record){
+            log::error!("{}", e);
+        }
+        Ok(())
+    }
+
+    // Written to a temporary file first, so a crash never leaves a truncated record behind
+    fn save(&self, record: &SessionRecord) -> Result<(), String>{
+        let contents = serde_json::to_string(record)
+            .map_err(|e| format!("unable to serialize transport sessions: {}", e))?;
+        let tmp_path = format!("{}.tmp", self.path);
+        fs::write(&tmp_path, contents)
+            .map_err(|e| format!("unable to write transport sessions to {}: {}", tmp_path, e))?;
+        fs::rename(&tmp_path, &self.path)
+            .map_err(|e| format!("unable to write transport sessions to {}: {}", self.path, e))
+    }
+}
+
+// Keys of the node's encrypted transport. Set once at startup, shared by all protocol services of the process.
+static SECURE_TRANSPORT: OnceLock<Arc<SecureTransport>> = OnceLock::new();
+
+/// Switches all replica links opened after this call to the encrypted transport. Does nothing if `encrypt` is off.
+/// Sessions are recorded in `session_file`, which must survive restarts of the node.
+pub fn enable_secure_transport(config: &TransportConfig, session_file: &str) -> Result<(), String>{
+    config.validate()?;
+    if !config.encrypt{
+        return Ok(());
+    }
+    if crate::weights().is_some(){
+        return Err("the encrypted transport cannot be combined with weights".to_string());
+    }
+    let transport = SecureTransport::new(config, session_file)?;
+    SECURE_TRANSPORT.set(Arc::new(transport))
+        .map_err(|_| "secure transport already enabled".to_string())
+}
+
+pub fn secure_transport_enabled() -> bool{
+    SECURE_TRANSPORT.get().is_some()
+}
+
+struct SecureTransport{
+    id: Replica,
+    secret: StaticSecret,
+    public_keys: FnvHashMap<Replica, PublicKey>,
+    session: u64,
+    sessions: SessionStore,
+}
+
+impl SecureTransport{
+    fn new(config: &TransportConfig, session_file: &str) -> Result<SecureTransport, String>{
+        let mut public_keys = FnvHashMap::default();
+        for (replica, public_key) in config.public_keys.iter(){
+            public_keys.insert(*replica, PublicKey::from(*public_key));
+        }
+        let sessions = SessionStore::open(session_file)?;
+        Ok(SecureTransport {
+            id: config.id,
+            secret: StaticSecret::from(config.static_secret),
+            public_keys: public_keys,
+            // Later sessions of a restarted node supersede earlier ones
+            session: sessions.next_session()?,
+            sessions: sessions,
+        })
+    }
+
+    // Key of the link from `from` to `to` on the listener `port`, for one session of the sender.
+    // Binding the port keeps messages of one protocol service from being replayed to another.
+    fn link_cipher(&self, peer: Replica, from: Replica, to: Replica, port: u16, session: u64) -> Result<ChaCha20Poly1305, String>{
+        let peer_key = self.public_keys.get(&peer)
+            .ok_or_else(|| format!("no public key for node {}", peer))?;
+        let shared = self.secret.diffie_hellman(peer_key);
+        let mut input = Vec::with_capacity(32 + TRANSPORT_KEY_TAG.len() + 8*3 + 2);
+        input.extend_from_slice(shared.as_bytes());
+        input.extend_from_slice(TRANSPORT_KEY_TAG);
+        input.extend((from as u64).to_be_bytes());
+        input.extend((to as u64).to_be_bytes());
+        input.extend(port.to_be_bytes());
+        input.extend(session.to_be_bytes());
+        let mut key = do_hash(&input);
+        input.zeroize();
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(&key));
+        key.zeroize();
+        Ok(cipher)
+    }
+}
+
+fn sealed_nonce(seq: u64) -> [u8; 12]{
+    let mut nonce = [0u8; 12];
+    nonce[4..].copy_from_slice(&seq.to_be_bytes());
+    nonce
+}
+
+fn sealed_aad(sender: Replica, recipient: Replica, port: u16, session: u64, seq: u64) -> Vec<u8>{
+    let mut aad = Vec::new();
+    aad.extend((sender as u64).to_be_bytes());
+    aad.extend((recipient as u64).to_be_bytes());
+    aad.extend(port.to_be_bytes());
+    aad.extend(session.to_be_bytes());
+    aad.extend(seq.to_be_bytes());
+    aad
+}
+
+/// Message of the encrypted transport: a serialized protocol message sealed for one link
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SealedMsg{
+    pub sender: Replica,
+    pub session: u64,
+    pub seq: u64,
+    pub ciphertext: Vec<u8>,
+}
+
+/// Sender of a protocol service to the other replicas, in cleartext or over the encrypted transport
+pub enum ReplicaSender<T>{
+    Plain(TcpReliableSender<Replica, T, Acknowledgement>),
+    Secure(SecureSender<T>),
+}
+
+impl<T: network::Message> ReplicaSender<T>{
+    pub async fn send(&mut self, replica: Replica, msg: T) -> CancelHandler<Acknowledgement>{
+        match self {
+            ReplicaSender::Plain(net_send) => net_send.send(replica, msg).await,
+            ReplicaSender::Secure(net_send) => net_send.send(replica, msg).await,
+        }
+    }
+}
+
+pub struct SecureSender<T>{
+    net_send: TcpReliableSender<Replica, SealedMsg, Acknowledgement>,
+    transport: Arc<SecureTransport>,
+    ports: FnvHashMap<Replica, u16>,
+    // Cipher and last sequence number of every link
+    links: FnvHashMap<Replica, (ChaCha20Poly1305, u64)>,
+    _msg: PhantomData<T>,
+}
+
+impl<T: network::Message> SecureSender<T>{
+    pub async fn send(&mut self, replica: Replica, msg: T) -> CancelHandler<Acknowledgement>{
+        let transport = self.transport.clone();
+        let port = *self.ports.get(&replica).expect("Replica missing from the peer map");
+        if !self.links.contains_key(&replica){
+            let cipher = transport.link_cipher(replica, transport.id, replica, port, transport.session)
+                .expect("Unable to derive the link key");
+            self.links.insert(replica, (cipher, 0));
+        }
+        let (cipher, seq) = self.links.get_mut(&replica).unwrap();
+        *seq += 1;
+        let plaintext = bincode::serialize(&msg).expect("Failed to serialize protocol message");
+        let aad = sealed_aad(transport.id, replica, port, transport.session, *seq);
+        let ciphertext = cipher.encrypt(Nonce::from_slice(&sealed_nonce(*seq)), Payload {
+            msg: plaintext.as_slice(),
+            aad: aad.as_slice(),
+        }).expect("Failed to seal protocol message");
+        let sealed_msg = SealedMsg {
+            sender: transport.id,
+            session: transport.session,
+            seq: *seq,
+            ciphertext: ciphertext,
+        };
+        self.net_send.send(replica, sealed_msg).await
+    }
+}
+
+// Receive state of one sender: its current session and the sequence numbers seen in it.
+// Every sequence number is tracked exactly, so a message that arrives late after a reconnect is never taken for a replay.
+struct LinkState{
+    session: u64,
+    cipher: ChaCha20Poly1305,
+    // Every sequence number up to this one was received
+    contiguous: u64,
+    // Sequence numbers received above `contiguous`
+    ahead: BTreeSet<u64>,
+}
+
+impl LinkState{
+    fn new(session: u64, cipher: ChaCha20Poly1305) -> LinkState{
+        LinkState {
+            session: session,
+            cipher: cipher,
+            contiguous: 0,
+            ahead: BTreeSet::new(),
+        }
+    }
+
+    fn is_replay(&self, seq: u64) -> bool{
+        seq == 0 || seq <= self.contiguous || self.ahead.contains(&seq)
+    }
+
+    fn mark(&mut self, seq: u64){
+        if seq != self.contiguous+1{
+            self.ahead.insert(seq);
+            return;
+        }
+        self.contiguous = seq;
+        while self.ahead.remove(&(self.contiguous+1)){
+            self.contiguous += 1;
+        }
+    }
+}
+
+/// Opens sealed messages, drops forgeries, replays and stale sessions, and forwards the rest to the protocol
+#[derive(Clone)]
+pub struct SecureHandler<T>{
+    transport: Arc<SecureTransport>,
+    port: u16,
+    links: Arc<Mutex<FnvHashMap<Replica, LinkState>>>,
+    consensus_tx: UnboundedSender<T>,
+}
+
+impl<T: network::Message> SecureHandler<T>{
+    fn open(&self, sealed_msg: SealedMsg) -> Result<T, String>{
+        let sender = sealed_msg.sender;
+        let mut links = self.links.lock().unwrap();
+        let same_session = match links.get(&sender){
+            Some(link) => {
+                if sealed_msg.session < link.session{
+                    return Err(format!("stale session {} of node {}", sealed_msg.session, sender));
+                }
+                if sealed_msg.session == link.session && link.is_replay(sealed_msg.seq){
+                    return Err(format!("replayed message {} from node {}", sealed_msg.seq, sender));
+                }
+                sealed_msg.session == link.session
+            },
+            None => {
+                if sealed_msg.session <= self.transport.sessions.highest(sender, self.port){
+                    return Err(format!("session {} of node {} predates a restart", sealed_msg.session, sender));
+                }
+                false
+            },
+        };
+        let aad = sealed_aad(sender, self.transport.id, self.port, sealed_msg.session, sealed_msg.seq);
+        let payload = Payload {
+            msg: sealed_msg.ciphertext.as_slice(),
+            aad: aad.as_slice(),
+        };
+        let nonce = sealed_nonce(sealed_msg.seq);
+        let plaintext = if same_session{
+            links.get(&sender).unwrap().cipher.decrypt(Nonce::from_slice(&nonce), payload)
+                .map_err(|_| format!("message from node {} failed authentication", sender))?
+        }
+        else{
+            // First message of a new session, only adopted once it authenticates
+            let cipher = self.transport.link_cipher(sender, sender, self.transport.id, self.port, sealed_msg.session)?;
+            let plaintext = cipher.decrypt(Nonce::from_slice(&nonce), payload)
+                .map_err(|_| format!("message from node {} failed authentication", sender))?;
+            self.transport.sessions.adopt(sender, self.port, sealed_msg.session)?;
+            links.insert(sender, LinkState::new(sealed_msg.session, cipher));
+            plaintext
+        };
+        links.get_mut(&sender).unwrap().mark(sealed_msg.seq);
+        bincode::deserialize(&plaintext)
+            .map_err(|e| format!("unable to deserialize message from node {}: {}", sender, e))
+    }
+}
+
+#[async_trait]
+impl<T: network::Message> network::Handler<Acknowledgement, SealedMsg> for SecureHandler<T>{
+    async fn dispatch(&self, msg: SealedMsg, writer: &mut network::Writer<Acknowledgement>) {
+        match self.open(msg){
+            Ok(msg) => {
+                let _status = self.consensus_tx.send(msg);
+            },
+            Err(e) => {
+                log::warn!("Dropping message on port {}: {}", self.port, e);
+            }
+        }
+        // Rejected messages are acknowledged too. Each is a forgery, a replay or from a stale session, which resending cannot fix.
+        let status = writer
+            .send(Acknowledgement::Pong)
+            .await;
+        if status.is_err(){
+            log::error!("Failed to acknowledge message because of {:?}", status.err().unwrap());
+        }
+    }
+}
+
+/// Forwards cleartext protocol messages, the same as the handlers of the protocol crates
+#[derive(Clone)]
+pub struct ForwardHandler<T>{
+    consensus_tx: UnboundedSender<T>,
+}
+
+#[async_trait]
+impl<T: network::Message> network::Handler<Acknowledgement, T> for ForwardHandler<T>{
+    async fn dispatch(&self, msg: T, writer: &mut network::Writer<Acknowledgement>) {
+        let _status = self.consensus_tx.send(msg);
+        let status = writer
+            .send(Acknowledgement::Pong)
+            .await;
+        if status.is_err(){
+            log::error!("Failed to acknowledge message because of {:?}", status.err().unwrap());
+        }
+    }
+}
+
+/// Listens for the protocol messages of the other replicas on `my_address` and returns the sender to `peers`.
+/// Uses the encrypted transport if it was enabled at startup.
+pub fn spawn_replica_network<T: network::Message>(
+    my_address: SocketAddr,
+    peers: FnvHashMap<Replica, SocketAddr>,
+    consensus_tx: UnboundedSender<T>,
+) -> ReplicaSender<T>{
+    match SECURE_TRANSPORT.get(){
+        None => {
+            TcpReceiver::<Acknowledgement, T, _>::spawn(
+                my_address,
+                ForwardHandler { consensus_tx: consensus_tx },
+            );
+            ReplicaSender::Plain(TcpReliableSender::<Replica, T, Acknowledgement>::with_peers(peers))
+        },
+        Some(transport) => {
+            TcpReceiver::<Acknowledgement, SealedMsg, _>::spawn(
+                my_address,
+                SecureHandler {
+                    transport: transport.clone(),
+                    port: my_address.port(),
+                    links: Arc::new(Mutex::new(FnvHashMap::default())),
+                    consensus_tx: consensus_tx,
+                },
+            );
+            let ports = peers.iter().map(|(replica, address)| (*replica, address.port())).collect();
+            ReplicaSender::Secure(SecureSender {
+                net_send: TcpReliableSender::<Replica, SealedMsg, Acknowledgement>::with_peers(peers),
+                transport: transport.clone(),
+                ports: ports,
+                links: FnvHashMap::default(),
+                _msg: PhantomData,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use tokio::sync::mpsc::unbounded_channel;
+
+    const PORT: u16 = 9000;
+
+    // Fresh session file in the temp directory, unique to this test process
+    fn session_file(name: &str) -> String{
+        let path = std::env::temp_dir().join(format!("acss-rs-sessions-{}-{}", std::process::id(), name));
+        let _ = fs::remove_file(&path);
+        path.to_str().unwrap().to_string()
+    }
+
+    fn handler(transport: Arc<SecureTransport>) -> SecureHandler<Vec<u8>>{
+        let (consensus_tx, _consensus_rx) = unbounded_channel();
+        SecureHandler {
+            transport: transport,
+            port: PORT,
+            links: Arc::new(Mutex::new(FnvHashMap::default())),
+            consensus_tx: consensus_tx,
+        }
+    }
+
+    // Seals `msg` the way SecureSender does, with an explicit sequence number
+    fn seal(sender: &SecureTransport, recipient: Replica, port: u16, seq: u64, msg: &Vec<u8>) -> SealedMsg{
+        let cipher = sender.link_cipher(recipient, sender.id, recipient, port, sender.session).unwrap();
+        let aad = sealed_aad(sender.id, recipient, port, sender.session, seq);
+        let ciphertext = cipher.encrypt(Nonce::from_slice(&sealed_nonce(seq)), Payload {
+            msg: bincode::serialize(msg).unwrap().as_slice(),
+            aad: aad.as_slice(),
+        }).unwrap();
+        SealedMsg {
+            sender: sender.id,
+            session: sender.session,
+            seq: seq,
+            ciphertext: ciphertext,
+        }
+    }
+
+    #[test]
+    fn link_state_rejects_replays_and_accepts_late_messages(){
+        let mut link = LinkState::new(1, ChaCha20Poly1305::new(Key::from_slice(&[0u8; 32])));
+        assert!(link.is_replay(0));
+        for seq in [1, 2, 4]{
+            assert!(!link.is_replay(seq));
+            link.mark(seq);
+        }
+        assert!(link.is_replay(1) && link.is_replay(2) && link.is_replay(4));
+        assert!(!link.is_replay(3));
+        link.mark(3);
+        assert_eq!(link.contiguous, 4);
+        assert!(link.ahead.is_empty());
+
+        // A message far behind the highest one seen is still accepted once
+        link.mark(500);
+        assert!(!link.is_replay(5));
+        link.mark(5);
+        assert!(link.is_replay(5));
+        assert!(link.is_replay(500));
+    }
+
+    #[test]
+    fn session_store_survives_a_restart(){
+        let path = session_file("store");
+        let store = SessionStore::open(&path).unwrap();
+        let first = store.next_session().unwrap();
+        store.adopt(3, PORT, 10).unwrap();
+        assert!(store.adopt(3, PORT, 10).is_err());
+        assert!(store.adopt(3, PORT, 9).is_err());
+        // Sessions are tracked per listener port
+        store.adopt(3, PORT+1, 5).unwrap();
+        drop(store);
+
+        let store = SessionStore::open(&path).unwrap();
+        assert!(store.next_session().unwrap() > first);
+        assert_eq!(store.highest(3, PORT), 10);
+        assert_eq!(store.highest(3, PORT+1), 5);
+        assert_eq!(store.highest(4, PORT), 0);
+        assert!(store.adopt(3, PORT, 10).is_err());
+
+        // Sessions keep increasing even if the clock is behind the last one
+        store.record.lock().unwrap().session = u64::MAX-1;
+        assert_eq!(store.next_session().unwrap(), u64::MAX);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn handler_opens_each_message_once(){
+        let configs = TransportConfig::generate(2, true);
+        let sender_path = session_file("open-sender");
+        let receiver_path = session_file("open-receiver");
+        let sender = SecureTransport::new(&configs[0], &sender_path).unwrap();
+        let handler = handler(Arc::new(SecureTransport::new(&configs[1], &receiver_path).unwrap()));
+        let msg = vec![1u8, 2, 3];
+
+        assert_eq!(handler.open(seal(&sender, 1, PORT, 1, &msg)).unwrap(), msg);
+        assert!(handler.open(seal(&sender, 1, PORT, 1, &msg)).is_err());
+        assert!(handler.open(seal(&sender, 1, PORT, 3, &msg)).is_ok());
+        assert!(handler.open(seal(&sender, 1, PORT, 2, &msg)).is_ok());
+
+        // A forgery does not use up its sequence number
+        let mut forged = seal(&sender, 1, PORT, 4, &msg);
+        forged.ciphertext[0] ^= 1;
+        assert!(handler.open(forged).is_err());
+        assert!(handler.open(seal(&sender, 1, PORT, 4, &msg)).is_ok());
+
+        // Messages sealed for another service's port do not open
+        assert!(handler.open(seal(&sender, 1, PORT+1, 5, &msg)).is_err());
+        fs::remove_file(&sender_path).unwrap();
+        fs::remove_file(&receiver_path).unwrap();
+    }
+
+    #[test]
+    fn restarts_reject_sessions_seen_before(){
+        let configs = TransportConfig::generate(2, true);
+        let sender_path = session_file("restart-sender");
+        let receiver_path = session_file("restart-receiver");
+        let msg = vec![4u8, 5, 6];
+
+        let sender = SecureTransport::new(&configs[0], &sender_path).unwrap();
+        let receiver = handler(Arc::new(SecureTransport::new(&configs[1], &receiver_path).unwrap()));
+        assert!(receiver.open(seal(&sender, 1, PORT, 1, &msg)).is_ok());
+        drop(receiver);
+
+        // The restarted receiver remembers the sender's session, so its messages cannot be replayed
+        let receiver = handler(Arc::new(SecureTransport::new(&configs[1], &receiver_path).unwrap()));
+        assert!(receiver.open(seal(&sender, 1, PORT, 1, &msg)).is_err());
+        assert!(receiver.open(seal(&sender, 1, PORT, 2, &msg)).is_err());
+
+        // A restarted sender starts a later session, which supersedes the earlier one
+        let restarted_sender = SecureTransport::new(&configs[0], &sender_path).unwrap();
+        assert!(restarted_sender.session > sender.session);
+        assert!(receiver.open(seal(&restarted_sender, 1, PORT, 1, &msg)).is_ok());
+        assert!(receiver.open(seal(&sender, 1, PORT, 3, &msg)).is_err());
+        fs::remove_file(&sender_path).unwrap();
+        fs::remove_file(&receiver_path).unwrap();
+    }
+}
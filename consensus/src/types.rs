@@ -73,4 +73,20 @@ impl RBCState{
 pub struct RBCSyncMsg {
     pub id: usize,
     pub msg: String,
-}
\ No newline at end of file
+}
+
+/// Payload of a node's COMPLETED message to the syncer. Timestamps are milliseconds since the UNIX epoch.
+#[derive(Debug,Serialize,Deserialize,Clone)]
+pub struct CompletionReport {
+    pub id: usize,
+    pub msg: String,
+    pub start_time: u128,
+    pub completion_time: u128,
+    pub messages_sent: u64,
+    pub bytes_sent: u64,
+}
+
+/// Value of the ALIVE messages a node keeps sending to the syncer as a heartbeat
+pub const HEARTBEAT: &[u8] = b"Heartbeat";
+/// Milliseconds between two heartbeats
+pub const HEARTBEAT_INTERVAL: u64 = 1000;
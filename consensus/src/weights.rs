@@ -0,0 +1,211 @@
+use std::{fs, net::SocketAddr, ops::Range, sync::OnceLock};
+
+use config::Node;
+use ha_crypto::hash::do_hash;
+use serde::{Deserialize, Serialize};
+use types::Replica;
+
+use crate::SecretBytes;
+
+/// Distance between the port ranges of two virtual replicas of one node. Covers the deepest sub-protocol offset.
+pub const VIRTUAL_PORT_STRIDE: u16 = 4000;
+
+const VIRTUAL_KEY_TAG: &[u8] = b"acss-rs/virtual-key/v1";
+
+fn default_port_stride() -> u16{
+    VIRTUAL_PORT_STRIDE
+}
+
+/*
+    Stake weights of the nodes of a deployment. A node of weight w runs w virtual replicas, each of them a full party
+    of a deployment with W = sum of weights parties and threshold T. Virtual replica v holds the evaluation point of party v,
+    so a node of weight w holds w evaluation points, and every quorum of the protocols (n-t, t+1, 2t+1 and reconstruction)
+    counts weight instead of nodes. Security needs the weight of the faulty nodes to be at most T, and W >= 3T+1.
+*/
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Weights{
+    /// Weight of every node, in node order
+    pub weights: Vec<usize>,
+    /// Maximum total weight of the faulty nodes
+    pub num_faults: usize,
+    /// Port distance between the virtual replicas of a node, on both the replica and the client ports
+    #[serde(default = "default_port_stride")]
+    pub port_stride: u16,
+}
+
+impl Weights{
+    pub fn new(weights: Vec<usize>, num_faults: usize) -> Weights{
+        Weights {
+            weights: weights,
+            num_faults: num_faults,
+            port_stride: VIRTUAL_PORT_STRIDE,
+        }
+    }
+
+    /// Total weight W, the number of virtual replicas
+    pub fn total_weight(&self) -> usize{
+        self.weights.iter().sum()
+    }
+
+    pub fn weight(&self, replica: Replica) -> usize{
+        self.weights.get(replica).cloned().unwrap_or(0)
+    }
+
+    pub fn validate(&self) -> Result<(), String>{
+        if let Some(replica) = self.weights.iter().position(|weight| *weight == 0){
+            return Err(format!("node {} has weight 0, every node needs a positive weight", replica));
+        }
+        let total_weight = self.total_weight();
+        if total_weight < 3*self.num_faults+1{
+            return Err(format!("total weight {} is too small, at least {} is needed for a fault weight of {}", total_weight, 3*self.num_faults+1, self.num_faults));
+        }
+        if self.port_stride == 0{
+            return Err("port stride must be positive".to_string());
+        }
+        Ok(())
+    }
+
+    /// Weights must cover exactly the nodes of the deployment config
+    pub fn validate_for(&self, config: &Node) -> Result<(), String>{
+        self.validate()?;
+        if self.weights.len() != config.num_nodes{
+            return Err(format!("weights list {} nodes, the config has {}", self.weights.len(), config.num_nodes));
+        }
+        Ok(())
+    }
+
+    /// Virtual replicas of a node, consecutive in node order
+    pub fn virtual_replicas(&self, replica: Replica) -> Range<Replica>{
+        let first: usize = self.weights.iter().take(replica).sum();
+        first..first+self.weight(replica)
+    }
+
+    /// Node running a virtual replica
+    pub fn owner(&self, virtual_replica: Replica) -> Option<Replica>{
+        let mut first = 0;
+        for (replica, weight) in self.weights.iter().enumerate(){
+            if virtual_replica < first+weight{
+                return Some(replica);
+            }
+            first += weight;
+        }
+        None
+    }
+
+    /// Config of one virtual replica of the node of `config`. The virtual deployment has W nodes and T faults.
+    /// Virtual replica k of a node listens k port strides above the node's ports. Two virtual replicas share a key derived
+    /// from the key of their nodes and both their ids, see `virtual_pair_key`. Entries of the net map above the nodes, like the syncer, are kept after the virtual replicas.
+    pub fn virtual_config(&self, config: &Node, virtual_replica: Replica) -> Result<Node, String>{
+        if self.owner(virtual_replica) != Some(config.id){
+            return Err(format!("virtual replica {} does not belong to node {}", virtual_replica, config.id));
+        }
+        let num_nodes = self.weights.len();
+        let total_weight = self.total_weight();
+        let slot = virtual_replica - self.virtual_replicas(config.id).start;
+
+        let mut virtual_config = config.clone();
+        virtual_config.id = virtual_replica;
+        virtual_config.num_nodes = total_weight;
+        virtual_config.num_faults = self.num_faults;
+        virtual_config.client_port = self.slot_port(config.client_port, slot)?;
+        virtual_config.net_map.clear();
+        virtual_config.sk_map.clear();
+        for (replica, address) in config.net_map.iter(){
+            if *replica >= num_nodes{
+                virtual_config.net_map.insert(total_weight + replica - num_nodes, address.clone());
+                continue;
+            }
+            let address: SocketAddr = address.parse()
+                .map_err(|e| format!("invalid address of node {}: {}", replica, e))?;
+            for (slot, virtual_peer) in self.virtual_replicas(*replica).enumerate(){
+                let port = self.slot_port(address.port(), slot)?;
+                virtual_config.net_map.insert(virtual_peer, SocketAddr::new(address.ip(), port).to_string());
+            }
+        }
+        for (replica, sec_key) in config.sk_map.iter(){
+            for virtual_peer in self.virtual_replicas(*replica){
+                virtual_config.sk_map.insert(virtual_peer, virtual_pair_key(sec_key, virtual_replica, virtual_peer));
+            }
+        }
+        Ok(virtual_config)
+    }
+
+    fn slot_port(&self, port: u16, slot: usize) -> Result<u16, String>{
+        let slot_port = port as usize + slot*(self.port_stride as usize);
+        if slot_port > u16::MAX as usize{
+            return Err(format!("port {} leaves no room for virtual replica {} of a node", port, slot));
+        }
+        Ok(slot_port as u16)
+    }
+
+    pub fn from_file(path: &str) -> Result<Weights, String>{
+        let contents = fs::read_to_string(path)
+            .map_err(|e| format!("unable to read weights from {}: {}", path, e))?;
+        let weights: Weights = serde_json::from_str(&contents)
+            .map_err(|e| format!("unable to parse weights in {}: {}", path, e))?;
+        weights.validate()?;
+        Ok(weights)
+    }
+
+    pub fn write_to_file(&self, path: &str) -> Result<(), String>{
+        let contents = serde_json::to_string_pretty(self)
+            .map_err(|e| format!("unable to serialize weights: {}", e))?;
+        fs::write(path, contents)
+            .map_err(|e| format!("unable to write weights to {}: {}", path, e))
+    }
+}
+
+/// Key of the link between virtual replicas `a` and `b`, derived from the key of their nodes. The ACSS schemes derive
+/// PRF rows from these keys, so two virtual replicas of one node must not share a key with the same peer.
+/// The order of `a` and `b` does not matter, so both ends of a link derive the same key.
+pub fn virtual_pair_key(node_key: &[u8], a: Replica, b: Replica) -> Vec<u8>{
+    let (low, high) = if a <= b {(a, b)} else {(b, a)};
+    let mut input = Vec::with_capacity(VIRTUAL_KEY_TAG.len() + node_key.len() + 3*8);
+    input.extend_from_slice(VIRTUAL_KEY_TAG);
+    input.extend((node_key.len() as u64).to_be_bytes());
+    input.extend_from_slice(node_key);
+    input.extend((low as u64).to_be_bytes());
+    input.extend((high as u64).to_be_bytes());
+    let input = SecretBytes::new(input);
+    do_hash(&input).to_vec()
+}
+
+// Weights of the deployment. Set once at startup.
+static WEIGHTS: OnceLock<Weights> = OnceLock::new();
+
+/// Makes nodes run one virtual replica per unit of weight. The encrypted transport keys links by node and the handover
+/// moves shares between committees of nodes, so neither can be combined with weights.
+pub fn enable_weights(weights: Weights) -> Result<(), String>{
+    weights.validate()?;
+    if crate::secure_transport_enabled(){
+        return Err("weights cannot be combined with the encrypted transport".to_string());
+    }
+    if crate::handover_config().is_some(){
+        return Err("weights cannot be combined with committee handover".to_string());
+    }
+    WEIGHTS.set(weights)
+        .map_err(|_| "weights already enabled".to_string())
+}
+
+pub fn weights() -> Option<&'static Weights>{
+    WEIGHTS.get()
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    #[test]
+    fn virtual_pair_key_is_symmetric(){
+        let node_key = vec![7u8; 32];
+        assert_eq!(virtual_pair_key(&node_key, 2, 5), virtual_pair_key(&node_key, 5, 2));
+    }
+
+    #[test]
+    fn virtual_replicas_of_one_node_get_different_keys(){
+        // Virtual replicas 0 and 1 belong to the same node and share its key with the node of replica 4
+        let node_key = vec![7u8; 32];
+        assert_ne!(virtual_pair_key(&node_key, 0, 4), virtual_pair_key(&node_key, 1, 4));
+        assert_ne!(virtual_pair_key(&node_key, 0, 4), virtual_pair_key(&node_key, 0, 5));
+    }
+}
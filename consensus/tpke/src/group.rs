@@ -0,0 +1,168 @@
+use num_bigint::BigUint;
+use num_traits::{One, Zero};
+
+use consensus::LargeField;
+use lambdaworks_math::traits::ByteConversion;
+
+// Schnorr group used for threshold encryption. Secret keys are shared with the Stark252 field used by the sharing protocols,
+// so the group must have prime order p = 2^251 + 17*2^192 + 1. No standard elliptic curve has this order,
+// hence we use the order-p subgroup of Z_q^* for a 2047-bit prime q = k*p + 1.
+// k is derived by hashing the string "acss-rs threshold elgamal group" with SHAKE256 and incrementing a counter until q is prime,
+// and the generator is g = 2^((q-1)/p) mod q.
+const MODULUS_HEX: [&str; 8] = [
+    "767a3cb10c34144fe0ed34bc07a6d2652da86a1c10fb6a41a39c44e381043482",
+    "f9678f72ad4fec74f5ef24a8d0c54463d08c29b2b791d1fa321cae93cdf9abc2",
+    "0b742e697310b13694ad2c0e3744dc94aeb37c3befe57c94d983b2a5426c945e",
+    "8bb18bf4d1b852a6eb53a069eb4e99c6e95f28523956b12e5d62884ce01a221f",
+    "706b6515325c0e04039fe2c463e1ade6e94934362c7bf362adfa06774151424a",
+    "a5c3aea3870e7bba0748cfbd6f4708dd13b3fea540281eec234f48ce9b43c0aa",
+    "4edb3212b9a3ec19a7da75347e7aca7235c73f78107d3fba211bf02fad76c2e4",
+    "32b9534e514885bdef8a142b5cc7990c7a4875564fe93419401cd91b3b3e73f9",
+];
+
+const GENERATOR_HEX: [&str; 8] = [
+    "4ce6ece50ca77e5813cfa70c60e1eb07c97eda32312bc62ea14d70a426e46731",
+    "6c9a5399f2516defb4b2c430caaa5264ba2f4922f91899b66dfcf9b5fcf81d4b",
+    "18be609dbbb483693d2dbe59c9e3cb9339e48a5451704d7b9b6ac6b4a579e212",
+    "08044a4ff9c1b221d04ca7a60a1705297f05184d8e821dc6b3225119b896481e",
+    "1a767f3bd43229926cf97df62622c1d9708c51247bcddf50aa05869d46165aec",
+    "d1e7653f73a6448577132d64906fab06fb081113872025df845c6008c9145802",
+    "a4b803cdcd5570a0a833c4961e67bfef2c65257a45c67d198cee0a3a8ae96ad3",
+    "7658d51dde778217dffe7d57c6d13f629b78cd65bf91f29ae3a215c76b28fe14",
+];
+
+const ORDER_HEX: &str = "800000000000011000000000000000000000000000000000000000000000001";
+
+/// Size of a serialized group element in bytes
+pub const GROUP_ELEMENT_BYTES: usize = 256;
+
+fn parse_hex(chunks: &[&str]) -> BigUint{
+    BigUint::parse_bytes(chunks.concat().as_bytes(), 16).unwrap()
+}
+
+pub fn modulus() -> BigUint{
+    parse_hex(&MODULUS_HEX)
+}
+
+pub fn order() -> BigUint{
+    parse_hex(&[ORDER_HEX])
+}
+
+/// Element of the order-p subgroup of Z_q^*, written multiplicatively
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupElement(BigUint);
+
+impl GroupElement{
+    pub fn generator() -> GroupElement{
+        GroupElement(parse_hex(&GENERATOR_HEX))
+    }
+
+    pub fn identity() -> GroupElement{
+        GroupElement(BigUint::one())
+    }
+
+    /// Computes g^exponent for the fixed generator g
+    pub fn exp_generator(exponent: &LargeField) -> GroupElement{
+        Self::generator().exp(exponent)
+    }
+
+    pub fn exp(&self, exponent: &LargeField) -> GroupElement{
+        let exp_big = BigUint::from_bytes_be(exponent.to_bytes_be().as_slice());
+        GroupElement(self.0.modpow(&exp_big, &modulus()))
+    }
+
+    pub fn mul(&self, other: &GroupElement) -> GroupElement{
+        GroupElement((&self.0 * &other.0) % modulus())
+    }
+
+    /// Checks that the element lies in the prime order subgroup. Must be called on every element received from the network.
+    pub fn is_valid(&self) -> bool{
+        let q = modulus();
+        if self.0.is_zero() || self.0 >= q{
+            return false;
+        }
+        self.0.modpow(&order(), &q).is_one()
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8>{
+        let bytes = self.0.to_bytes_be();
+        let mut padded = vec![0u8; GROUP_ELEMENT_BYTES - bytes.len()];
+        padded.extend(bytes);
+        padded
+    }
+
+    /// Deserializes and validates a group element
+    pub fn from_bytes(bytes: &[u8]) -> Option<GroupElement>{
+        if bytes.len() != GROUP_ELEMENT_BYTES{
+            return None;
+        }
+        let element = GroupElement(BigUint::from_bytes_be(bytes));
+        if !element.is_valid(){
+            return None;
+        }
+        Some(element)
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use num_bigint::BigUint;
+    use num_traits::{One, Zero};
+
+    use consensus::LargeField;
+    use lambdaworks_math::traits::ByteConversion;
+
+    use crate::{GroupElement, modulus, order};
+
+    // Miller-Rabin with the first 20 primes as bases. A composite passes with probability at most 4^-20.
+    fn is_probable_prime(n: &BigUint) -> bool{
+        let one = BigUint::one();
+        let n_minus_one = n - &one;
+        let mut d = n_minus_one.clone();
+        let mut r = 0;
+        while (&d % 2u32).is_zero(){
+            d = d >> 1;
+            r += 1;
+        }
+        for base in [2u32, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 67, 71]{
+            let mut x = BigUint::from(base).modpow(&d, n);
+            if x == one || x == n_minus_one{
+                continue;
+            }
+            let mut witness = true;
+            for _ in 1..r{
+                x = (&x * &x) % n;
+                if x == n_minus_one{
+                    witness = false;
+                    break;
+                }
+            }
+            if witness{
+                return false;
+            }
+        }
+        true
+    }
+
+    #[test]
+    fn order_is_the_field_modulus(){
+        let minus_one = LargeField::zero() - LargeField::one();
+        assert_eq!(order(), BigUint::from_bytes_be(minus_one.to_bytes_be().as_slice()) + BigUint::one());
+        assert!(is_probable_prime(&order()));
+    }
+
+    #[test]
+    fn modulus_is_a_2047_bit_prime(){
+        assert_eq!(modulus().bits(), 2047);
+        assert!(is_probable_prime(&modulus()));
+        assert!(((modulus() - BigUint::one()) % order()).is_zero());
+    }
+
+    #[test]
+    fn generator_has_order_p(){
+        let generator = GroupElement::generator();
+        assert_ne!(generator, GroupElement::identity());
+        assert!(generator.0.modpow(&order(), &modulus()).is_one());
+        assert!(generator.is_valid());
+    }
+}
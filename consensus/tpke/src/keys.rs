@@ -0,0 +1,403 @@
+use std::{collections::HashMap, fmt, fs::{File, OpenOptions}, io::{BufReader, BufWriter}, sync::OnceLock};
+
+use consensus::{LargeField, LargeFieldSSS, SecretBytes};
+use crypto::rand_field_element;
+use lambdaworks_math::traits::ByteConversion;
+use serde::{Serialize, Deserialize};
+use types::Replica;
+
+use crate::group::GroupElement;
+
+/// A party's share of the threshold decryption key. The secret key is shared with a degree-t polynomial f,
+/// party i holds f(i+1), and everyone knows the verification keys g^{f(j+1)} of all parties.
+#[derive(Clone)]
+pub struct ThresholdKey{
+    pub myid: Replica,
+    pub num_faults: usize,
+    secret_share: LargeField,
+    pub public_key: GroupElement,
+    pub public_key_shares: Vec<GroupElement>,
+}
+
+impl ThresholdKey{
+    /// Builds a key from a share generated by the sharing pipeline (e.g. a DPSS random share) and the published verification keys of all parties.
+    /// The public key is interpolated in the exponent from the first t+1 verification keys.
+    pub fn new(myid: Replica, num_faults: usize, secret_share: LargeField, public_key_shares: Vec<GroupElement>) -> Result<ThresholdKey, String>{
+        if public_key_shares.len() < num_faults+1{
+            return Err(format!("need at least {} verification keys, received {}", num_faults+1, public_key_shares.len()));
+        }
+        if myid >= public_key_shares.len(){
+            return Err(format!("party {} has no verification key among {}", myid, public_key_shares.len()));
+        }
+        let indices: Vec<Replica> = (0..num_faults+1).collect();
+        let public_key = interpolate_in_exponent(
+            &indices.iter().map(|rep| (*rep, public_key_shares[*rep].clone())).collect()
+        );
+        Ok(ThresholdKey {
+            myid: myid,
+            num_faults: num_faults,
+            secret_share: secret_share,
+            public_key: public_key,
+            public_key_shares: public_key_shares
+        })
+    }
+
+    /// Verification key of a secret share, published by every party after the sharing terminates
+    pub fn public_key_share(secret_share: &LargeField) -> GroupElement{
+        GroupElement::exp_generator(secret_share)
+    }
+
+    /// Trusted dealer setup for benchmarks and local testing. Returns the key of every party.
+    pub fn deal(num_nodes: usize, num_faults: usize) -> Vec<ThresholdKey>{
+        let shamir_ss = LargeFieldSSS::new(num_faults+1, num_nodes);
+        let secret_shares = shamir_ss.split(rand_field_element());
+        let public_key_shares: Vec<GroupElement> = secret_shares.iter().map(|share| Self::public_key_share(share)).collect();
+        secret_shares.into_iter().enumerate().map(|(rep, share)| {
+            Self::new(rep, num_faults, share, public_key_shares.clone())
+                .expect("dealt keys have a verification key for every party")
+        }).collect()
+    }
+
+    /// Builds a key from this party's share of a random secret produced by the sharing pipeline, like a DPSS output share,
+    /// and the verification keys the parties published for their shares. Faulty parties may publish wrong keys or none,
+    /// so the verification keys are taken from a degree-t curve in the exponent through at least 2t+1 published keys.
+    /// Such a curve passes through t+1 keys of honest parties, so it is the curve of the honest shares, and the keys
+    /// of the other parties are interpolated from it. Returns `None` while the published keys admit no such curve.
+    /// Candidate curves are tried through the subsets of t+1 published keys in order. The first one fits unless
+    /// a faulty party published a wrong key, so the search is only long under attack.
+    pub fn from_published_keys(myid: Replica, num_nodes: usize, num_faults: usize, secret_share: LargeField, published: &HashMap<Replica, GroupElement>) -> Option<ThresholdKey>{
+        let mut parties: Vec<Replica> = published.keys().cloned().filter(|rep| *rep < num_nodes).collect();
+        parties.sort();
+        if parties.len() < 2*num_faults+1{
+            return None;
+        }
+        let mut base: Vec<usize> = (0..num_faults+1).collect();
+        loop {
+            let points: Vec<(Replica, GroupElement)> = base.iter().map(|index| (parties[*index], published[&parties[*index]].clone())).collect();
+            let public_key_shares: Vec<GroupElement> = (0..num_nodes).map(|rep| {
+                interpolate_in_exponent_at(&points, &LargeField::from((rep+1) as u64))
+            }).collect();
+            let agreeing = parties.iter().filter(|rep| published[*rep] == public_key_shares[**rep]).count();
+            if agreeing >= 2*num_faults+1{
+                return Self::new(myid, num_faults, secret_share, public_key_shares).ok();
+            }
+            if !next_subset(&mut base, parties.len()){
+                return None;
+            }
+        }
+    }
+
+    pub fn secret_share(&self) -> &LargeField{
+        &self.secret_share
+    }
+
+    /// Checks that this party's secret share matches its published verification key
+    pub fn is_consistent(&self) -> bool{
+        self.public_key_shares.len() > self.myid
+            && Self::public_key_share(&self.secret_share) == self.public_key_shares[self.myid]
+    }
+}
+
+impl fmt::Debug for ThresholdKey{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ThresholdKey")
+            .field("myid", &self.myid)
+            .field("num_faults", &self.num_faults)
+            .field("secret_share", &"<redacted>")
+            .field("public_key", &self.public_key)
+            .field("public_key_shares", &self.public_key_shares.len())
+            .finish()
+    }
+}
+
+// Advances `subset`, sorted indices into 0..len, to the next subset of the same size in lexicographic order
+fn next_subset(subset: &mut Vec<usize>, len: usize) -> bool{
+    let size = subset.len();
+    for i in (0..size).rev(){
+        if subset[i] < len - size + i{
+            subset[i] += 1;
+            for j in i+1..size{
+                subset[j] = subset[j-1] + 1;
+            }
+            return true;
+        }
+    }
+    false
+}
+
+/// Lagrange coefficients for evaluating at 0 the polynomial through the points (rep+1) of the given parties
+pub fn lagrange_coefficients_at_zero(parties: &Vec<Replica>) -> Vec<LargeField>{
+    lagrange_coefficients_at(parties, &LargeField::zero())
+}
+
+/// Lagrange coefficients for evaluating at x the polynomial through the points (rep+1) of the given parties
+pub fn lagrange_coefficients_at(parties: &Vec<Replica>, x: &LargeField) -> Vec<LargeField>{
+    let xs: Vec<LargeField> = parties.iter().map(|rep| LargeField::from((rep+1) as u64)).collect();
+    let mut coefficients = Vec::new();
+    for (i, x_i) in xs.iter().enumerate(){
+        let mut numerator = LargeField::one();
+        let mut denominator = LargeField::one();
+        for (j, x_j) in xs.iter().enumerate(){
+            if i != j{
+                numerator = numerator * (x_j - x);
+                denominator = denominator * (x_j - x_i);
+            }
+        }
+        coefficients.push(numerator * denominator.inv().unwrap());
+    }
+    coefficients
+}
+
+/// Given (rep, h^{f(rep+1)}) for t+1 distinct parties, computes h^{f(0)}
+pub fn interpolate_in_exponent(points: &Vec<(Replica, GroupElement)>) -> GroupElement{
+    interpolate_in_exponent_at(points, &LargeField::zero())
+}
+
+/// Given (rep, h^{f(rep+1)}) for t+1 distinct parties, computes h^{f(x)}
+pub fn interpolate_in_exponent_at(points: &Vec<(Replica, GroupElement)>, x: &LargeField) -> GroupElement{
+    let parties: Vec<Replica> = points.iter().map(|(rep, _)| *rep).collect();
+    let coefficients = lagrange_coefficients_at(&parties, x);
+    let mut result = GroupElement::identity();
+    for ((_, element), coefficient) in points.iter().zip(coefficients.iter()){
+        result = result.mul(&element.exp(coefficient));
+    }
+    result
+}
+
+/// Version of the threshold decryption key file format
+pub const THRESHOLD_KEY_VERSION: u32 = 1;
+
+const THRESHOLD_KEY_GROUP: &str = "schnorr-stark252";
+
+/// On-disk format of a threshold decryption key share. Field elements and group elements are hex encoded big-endian.
+/// The file holds the party's secret share and must be kept secret, so it is created readable by its owner only.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ThresholdKeyFile{
+    pub version: u32,
+    pub group: String,
+    pub num_nodes: usize,
+    pub num_faults: usize,
+    pub myid: usize,
+    #[serde(with = "secret_hex")]
+    pub secret_share: SecretBytes,
+    pub public_key: String,
+    pub public_key_shares: Vec<String>,
+}
+
+fn to_hex(bytes: &[u8]) -> String{
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(hex: &str) -> Result<Vec<u8>, String>{
+    if hex.len() % 2 != 0{
+        return Err("odd length hex string".to_string());
+    }
+    (0..hex.len()).step_by(2).map(|i| {
+        u8::from_str_radix(&hex[i..i+2], 16).map_err(|e| e.to_string())
+    }).collect()
+}
+
+// Hex encoding of the secret share. The intermediate strings are wiped after use.
+mod secret_hex{
+    use consensus::SecretBytes;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use zeroize::Zeroizing;
+
+    pub fn serialize<S: Serializer>(secret: &SecretBytes, serializer: S) -> Result<S::Ok, S::Error>{
+        let hex = Zeroizing::new(super::to_hex(secret));
+        serializer.serialize_str(&hex)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<SecretBytes, D::Error>{
+        let hex = Zeroizing::new(String::deserialize(deserializer)?);
+        super::from_hex(&hex).map(SecretBytes::new).map_err(serde::de::Error::custom)
+    }
+}
+
+impl ThresholdKeyFile{
+    pub fn from_key(key: &ThresholdKey) -> ThresholdKeyFile{
+        ThresholdKeyFile {
+            version: THRESHOLD_KEY_VERSION,
+            group: THRESHOLD_KEY_GROUP.to_string(),
+            num_nodes: key.public_key_shares.len(),
+            num_faults: key.num_faults,
+            myid: key.myid,
+            secret_share: SecretBytes::new(key.secret_share.to_bytes_be()),
+            public_key: to_hex(&key.public_key.to_bytes()),
+            public_key_shares: key.public_key_shares.iter().map(|pk_share| to_hex(&pk_share.to_bytes())).collect(),
+        }
+    }
+
+    pub fn to_key(&self) -> Result<ThresholdKey, String>{
+        if self.version != THRESHOLD_KEY_VERSION || self.group != THRESHOLD_KEY_GROUP{
+            return Err(format!("unsupported threshold key file version {} for group {}", self.version, self.group));
+        }
+        if self.public_key_shares.len() != self.num_nodes || self.myid >= self.num_nodes || self.num_faults >= self.num_nodes{
+            return Err("inconsistent parameters in threshold key file".to_string());
+        }
+        let secret_share = LargeField::from_bytes_be(&self.secret_share)
+            .map_err(|_| "invalid secret share".to_string())?;
+        let public_key_shares = self.public_key_shares.iter().map(|pk_share| {
+            GroupElement::from_bytes(from_hex(pk_share)?.as_slice()).ok_or_else(|| "invalid public key share".to_string())
+        }).collect::<Result<Vec<GroupElement>, String>>()?;
+        let key = ThresholdKey::new(self.myid, self.num_faults, secret_share, public_key_shares)?;
+        if to_hex(&key.public_key.to_bytes()) != self.public_key{
+            return Err("public key does not match the public key shares".to_string());
+        }
+        if !key.is_consistent(){
+            return Err(format!("secret share does not match the verification key of party {}", self.myid));
+        }
+        Ok(key)
+    }
+
+    /// Writes the key file. A new file is created readable and writable by its owner only, because it holds the secret share.
+    pub fn write_to_file(&self, path: &str) -> Result<(), String>{
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let file = options.open(path)
+            .map_err(|e| format!("unable to create threshold key file {}: {}", path, e))?;
+        serde_json::to_writer_pretty(BufWriter::new(file), self)
+            .map_err(|e| format!("unable to write threshold key file {}: {}", path, e))
+    }
+
+    pub fn read_from_file(path: &str) -> Result<ThresholdKeyFile, String>{
+        let file = File::open(path)
+            .map_err(|e| format!("unable to open threshold key file {}: {}", path, e))?;
+        serde_json::from_reader(BufReader::new(file))
+            .map_err(|e| format!("unable to parse threshold key file {}: {}", path, e))
+    }
+}
+
+// File the sharing pipeline writes this node's threshold decryption key to. Set once at startup.
+static THRESHOLD_KEY_EXPORT: OnceLock<String> = OnceLock::new();
+
+/// Makes protocols that support it, like dpss, derive a threshold decryption key from their output and write it to `path`
+pub fn enable_threshold_key_export(path: &str) -> Result<(), String>{
+    THRESHOLD_KEY_EXPORT.set(path.to_string())
+        .map_err(|_| "threshold key export already enabled".to_string())
+}
+
+pub fn threshold_key_export_path() -> Option<&'static str>{
+    THRESHOLD_KEY_EXPORT.get().map(|path| path.as_str())
+}
+
+#[cfg(test)]
+mod tests{
+    use std::collections::HashMap;
+
+    use consensus::LargeField;
+    use types::Replica;
+
+    use crate::{GroupElement, ThresholdKey, ThresholdKeyFile, combine, decryption_share, encrypt, interpolate_in_exponent, verify_decryption_share};
+
+    const NUM_NODES: usize = 4;
+    const NUM_FAULTS: usize = 1;
+
+    #[test]
+    fn group_elements_round_trip(){
+        let element = GroupElement::exp_generator(&LargeField::from(12345u64));
+        let bytes = element.to_bytes();
+        assert_eq!(GroupElement::from_bytes(bytes.as_slice()), Some(element));
+        assert!(GroupElement::from_bytes(&bytes[1..]).is_none());
+    }
+
+    #[test]
+    fn dealt_keys_interpolate_to_the_public_key(){
+        let keys = ThresholdKey::deal(NUM_NODES, NUM_FAULTS);
+        for key in keys.iter(){
+            assert!(key.is_consistent());
+            assert_eq!(key.public_key, keys[0].public_key);
+        }
+        // Any t+1 verification keys give the same public key
+        let points = vec![(1, keys[0].public_key_shares[1].clone()), (3, keys[0].public_key_shares[3].clone())];
+        assert_eq!(interpolate_in_exponent(&points), keys[0].public_key);
+    }
+
+    #[test]
+    fn new_rejects_missing_verification_keys(){
+        let keys = ThresholdKey::deal(NUM_NODES, NUM_FAULTS);
+        let secret_share = keys[0].secret_share().clone();
+        assert!(ThresholdKey::new(0, NUM_FAULTS, secret_share.clone(), keys[0].public_key_shares[..NUM_FAULTS].to_vec()).is_err());
+        assert!(ThresholdKey::new(NUM_NODES, NUM_FAULTS, secret_share, keys[0].public_key_shares.clone()).is_err());
+    }
+
+    #[test]
+    fn key_from_published_keys_matches_the_dealt_key(){
+        let keys = ThresholdKey::deal(NUM_NODES, NUM_FAULTS);
+        let mut published: HashMap<Replica, GroupElement> = keys[0].public_key_shares.iter().cloned().enumerate().collect();
+        let key = ThresholdKey::from_published_keys(2, NUM_NODES, NUM_FAULTS, keys[2].secret_share().clone(), &published).unwrap();
+        assert!(key.is_consistent());
+        assert_eq!(key.public_key, keys[0].public_key);
+
+        // A missing key is interpolated, a wrong one is replaced
+        published.remove(&3);
+        published.insert(0, GroupElement::exp_generator(&LargeField::from(7u64)));
+        let key = ThresholdKey::from_published_keys(2, NUM_NODES, NUM_FAULTS, keys[2].secret_share().clone(), &published).unwrap();
+        assert_eq!(key.public_key, keys[0].public_key);
+        assert_eq!(key.public_key_shares, keys[0].public_key_shares);
+    }
+
+    #[test]
+    fn key_needs_2t_plus_1_consistent_published_keys(){
+        let keys = ThresholdKey::deal(NUM_NODES, NUM_FAULTS);
+        let mut published: HashMap<Replica, GroupElement> = keys[0].public_key_shares.iter().cloned().enumerate().take(2*NUM_FAULTS).collect();
+        assert!(ThresholdKey::from_published_keys(0, NUM_NODES, NUM_FAULTS, keys[0].secret_share().clone(), &published).is_none());
+        published.insert(2*NUM_FAULTS, GroupElement::exp_generator(&LargeField::from(7u64)));
+        assert!(ThresholdKey::from_published_keys(0, NUM_NODES, NUM_FAULTS, keys[0].secret_share().clone(), &published).is_none());
+    }
+
+    #[test]
+    fn key_file_round_trip(){
+        let keys = ThresholdKey::deal(NUM_NODES, NUM_FAULTS);
+        let key = ThresholdKeyFile::from_key(&keys[1]).to_key().unwrap();
+        assert_eq!(key.secret_share(), keys[1].secret_share());
+        assert_eq!(key.public_key, keys[1].public_key);
+
+        let mut key_file = ThresholdKeyFile::from_key(&keys[1]);
+        key_file.myid = 2;
+        assert!(key_file.to_key().is_err());
+    }
+
+    #[test]
+    fn debug_redacts_the_secret_share(){
+        let keys = ThresholdKey::deal(NUM_NODES, NUM_FAULTS);
+        let printed = format!("{:?}", keys[0]);
+        assert!(printed.contains("<redacted>"));
+        assert!(!printed.contains(&format!("{:?}", keys[0].secret_share())));
+    }
+
+    #[test]
+    fn encrypt_and_decrypt_with_t_plus_one_shares(){
+        let keys = ThresholdKey::deal(NUM_NODES, NUM_FAULTS);
+        let payload = b"threshold encrypted payload".to_vec();
+        let ciphertext = encrypt(&keys[0].public_key, payload.as_slice(), b"epoch 0");
+
+        let shares: Vec<_> = keys.iter().skip(1).take(NUM_FAULTS+1).map(|key| decryption_share(key, &ciphertext).unwrap()).collect();
+        for share in shares.iter(){
+            assert!(verify_decryption_share(&keys[0], &ciphertext, share));
+        }
+        assert_eq!(combine(&keys[0], &ciphertext, &shares), Some(payload));
+    }
+
+    #[test]
+    fn t_shares_do_not_decrypt(){
+        let keys = ThresholdKey::deal(NUM_NODES, NUM_FAULTS);
+        let payload = b"threshold encrypted payload".to_vec();
+        let ciphertext = encrypt(&keys[0].public_key, payload.as_slice(), b"epoch 0");
+
+        let mut shares: Vec<_> = keys.iter().take(NUM_FAULTS).map(|key| decryption_share(key, &ciphertext).unwrap()).collect();
+        assert_eq!(combine(&keys[0], &ciphertext, &shares), None);
+
+        // Completing t shares with a guessed share gives a wrong key, and the guessed share does not verify
+        let guessed_key = ThresholdKey::new(NUM_FAULTS, NUM_FAULTS, LargeField::from(7u64), keys[0].public_key_shares.clone()).unwrap();
+        let guessed_share = decryption_share(&guessed_key, &ciphertext).unwrap();
+        assert!(!verify_decryption_share(&keys[0], &ciphertext, &guessed_share));
+        shares.push(guessed_share);
+        assert_ne!(combine(&keys[0], &ciphertext, &shares), Some(payload));
+    }
+}
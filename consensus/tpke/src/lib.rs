@@ -0,0 +1,8 @@
+pub mod group;
+pub use group::*;
+
+mod keys;
+pub use keys::*;
+
+mod scheme;
+pub use scheme::*;
@@ -0,0 +1,163 @@
+use consensus::{LargeField, pseudorandom_lf};
+use crypto::{LargeFieldSer, hash::do_hash, rand_field_element};
+use lambdaworks_math::traits::ByteConversion;
+use rand_chacha::ChaCha20Rng;
+use rand_core::{SeedableRng, RngCore};
+use serde::{Serialize, Deserialize};
+use types::Replica;
+
+use crate::{group::GroupElement, keys::{ThresholdKey, interpolate_in_exponent}};
+
+// Hashed ElGamal with a Schnorr proof of knowledge of the encryption randomness (TDH1 of Shoup and Gennaro, simplified).
+// The proof makes the ciphertext non-malleable, so an adversary cannot submit a mauled copy of an honest party's ciphertext
+// to the ACS and learn its contents from the joint decryption.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Ciphertext{
+    // u = g^r
+    pub u: Vec<u8>,
+    // Payload XORed with a keystream derived from pk^r
+    pub c: Vec<u8>,
+    // Public data bound to the ciphertext, e.g. the epoch it was proposed in
+    pub label: Vec<u8>,
+    // Proof of knowledge of r
+    pub e: LargeFieldSer,
+    pub f: LargeFieldSer,
+}
+
+// u^{s_i} together with a Chaum-Pedersen proof that log_g(pk_i) = log_u(d_i)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DecryptionShare{
+    pub party: Replica,
+    pub d: Vec<u8>,
+    pub e: LargeFieldSer,
+    pub z: LargeFieldSer,
+}
+
+fn challenge(domain: &[u8], elements: Vec<&[u8]>) -> LargeField{
+    let mut transcript = domain.to_vec();
+    for element in elements{
+        transcript.extend((element.len() as u64).to_be_bytes());
+        transcript.extend(element);
+    }
+    pseudorandom_lf(transcript.as_slice(), 1)[0].clone()
+}
+
+fn keystream_xor(key: &GroupElement, data: &[u8]) -> Vec<u8>{
+    let mut rng = ChaCha20Rng::from_seed(do_hash(key.to_bytes().as_slice()));
+    let mut keystream = vec![0u8; data.len()];
+    rng.fill_bytes(keystream.as_mut_slice());
+    data.iter().zip(keystream.into_iter()).map(|(x, k)| x ^ k).collect()
+}
+
+fn field_from_ser(ser: &LargeFieldSer) -> Option<LargeField>{
+    LargeField::from_bytes_be(ser.as_slice()).ok()
+}
+
+pub fn encrypt(public_key: &GroupElement, payload: &[u8], label: &[u8]) -> Ciphertext{
+    let r = rand_field_element();
+    let u = GroupElement::exp_generator(&r);
+    let c = keystream_xor(&public_key.exp(&r), payload);
+
+    let s = rand_field_element();
+    let w = GroupElement::exp_generator(&s);
+    let u_ser = u.to_bytes();
+    let e = challenge(b"tpke-encrypt", vec![c.as_slice(), label, u_ser.as_slice(), w.to_bytes().as_slice()]);
+    let f = s + &r * &e;
+    Ciphertext {
+        u: u_ser,
+        c: c,
+        label: label.to_vec(),
+        e: e.to_bytes_be(),
+        f: f.to_bytes_be()
+    }
+}
+
+/// Checks the proof of a ciphertext. Parties must only release decryption shares for valid ciphertexts.
+pub fn verify_ciphertext(ciphertext: &Ciphertext) -> bool{
+    let u = match GroupElement::from_bytes(ciphertext.u.as_slice()){
+        Some(u) => u,
+        None => return false
+    };
+    let (e, f) = match (field_from_ser(&ciphertext.e), field_from_ser(&ciphertext.f)){
+        (Some(e), Some(f)) => (e, f),
+        _ => return false
+    };
+    // w = g^f u^{-e}
+    let w = GroupElement::exp_generator(&f).mul(&u.exp(&(-&e)));
+    let e_computed = challenge(b"tpke-encrypt", vec![ciphertext.c.as_slice(), ciphertext.label.as_slice(), ciphertext.u.as_slice(), w.to_bytes().as_slice()]);
+    e == e_computed
+}
+
+/// Computes this party's decryption share. Returns None if the ciphertext is invalid.
+pub fn decryption_share(key: &ThresholdKey, ciphertext: &Ciphertext) -> Option<DecryptionShare>{
+    if !verify_ciphertext(ciphertext){
+        return None;
+    }
+    let u = GroupElement::from_bytes(ciphertext.u.as_slice()).unwrap();
+    let d = u.exp(key.secret_share());
+
+    let k = rand_field_element();
+    let a1 = GroupElement::exp_generator(&k);
+    let a2 = u.exp(&k);
+    let d_ser = d.to_bytes();
+    let e = challenge(b"tpke-share", vec![
+        key.public_key_shares[key.myid].to_bytes().as_slice(),
+        ciphertext.u.as_slice(),
+        d_ser.as_slice(),
+        a1.to_bytes().as_slice(),
+        a2.to_bytes().as_slice()
+    ]);
+    let z = k + key.secret_share() * &e;
+    Some(DecryptionShare {
+        party: key.myid,
+        d: d_ser,
+        e: e.to_bytes_be(),
+        z: z.to_bytes_be()
+    })
+}
+
+/// Verifies a decryption share against the verification key of the party that sent it
+pub fn verify_decryption_share(key: &ThresholdKey, ciphertext: &Ciphertext, share: &DecryptionShare) -> bool{
+    if share.party >= key.public_key_shares.len(){
+        return false;
+    }
+    let (u, d) = match (GroupElement::from_bytes(ciphertext.u.as_slice()), GroupElement::from_bytes(share.d.as_slice())){
+        (Some(u), Some(d)) => (u, d),
+        _ => return false
+    };
+    let (e, z) = match (field_from_ser(&share.e), field_from_ser(&share.z)){
+        (Some(e), Some(z)) => (e, z),
+        _ => return false
+    };
+    let pk_share = &key.public_key_shares[share.party];
+    let neg_e = -&e;
+    // a1 = g^z pk_i^{-e}, a2 = u^z d_i^{-e}
+    let a1 = GroupElement::exp_generator(&z).mul(&pk_share.exp(&neg_e));
+    let a2 = u.exp(&z).mul(&d.exp(&neg_e));
+    let e_computed = challenge(b"tpke-share", vec![
+        pk_share.to_bytes().as_slice(),
+        ciphertext.u.as_slice(),
+        share.d.as_slice(),
+        a1.to_bytes().as_slice(),
+        a2.to_bytes().as_slice()
+    ]);
+    e == e_computed
+}
+
+/// Combines t+1 verified decryption shares from distinct parties and decrypts the ciphertext
+pub fn combine(key: &ThresholdKey, ciphertext: &Ciphertext, shares: &Vec<DecryptionShare>) -> Option<Vec<u8>>{
+    if shares.len() < key.num_faults+1{
+        log::error!("Need {} decryption shares to decrypt, received {}", key.num_faults+1, shares.len());
+        return None;
+    }
+    let mut points: Vec<(Replica, GroupElement)> = Vec::new();
+    for share in shares.iter().take(key.num_faults+1){
+        if points.iter().any(|(rep, _)| *rep == share.party){
+            return None;
+        }
+        points.push((share.party, GroupElement::from_bytes(share.d.as_slice())?));
+    }
+    // pk^r = u^{f(0)}
+    let shared_key = interpolate_in_exponent(&points);
+    Some(keystream_xor(&shared_key, ciphertext.c.as_slice()))
+}
@@ -0,0 +1,157 @@
+use std::{
+    collections::HashMap,
+    net::{SocketAddr, SocketAddrV4},
+};
+
+use anyhow::{anyhow, Result};
+use config::Node;
+
+use fnv::FnvHashMap;
+use network::{
+    plaintcp::CancelHandler,
+    Acknowledgement,
+};
+use tokio::sync::{
+    mpsc::{unbounded_channel, UnboundedReceiver, Receiver, Sender},
+    oneshot,
+};
+use types::{Replica, WrapperMsg};
+use consensus::{SecretBytes, ReplicaSender};
+
+use crate::{msg::ProtMsg, keys::TsigKey, protocol::SigState};
+
+pub struct Context {
+    /// Networking context
+    pub net_send: ReplicaSender<WrapperMsg<ProtMsg>>,
+    pub net_recv: UnboundedReceiver<WrapperMsg<ProtMsg>>,
+
+    /// Data context
+    pub num_nodes: usize,
+    pub myid: usize,
+    pub num_faults: usize,
+    _byz: bool,
+
+    /// Secret Key map
+    pub sec_key_map: HashMap<Replica, SecretBytes>,
+
+    /// Cancel Handlers
+    pub cancel_handlers: HashMap<u64, Vec<CancelHandler<Acknowledgement>>>,
+    exit_rx: oneshot::Receiver<()>,
+
+    /// Share of the signing key
+    pub key: TsigKey,
+    pub sig_states: HashMap<usize, SigState>,
+
+    /// Input and output request channels
+    /// Input: (instance, message to sign). Output: (instance, message, combined signature)
+    pub sign_requests: Receiver<(usize, Vec<u8>)>,
+    pub sig_out: Sender<(usize, Vec<u8>, Vec<u8>)>,
+}
+
+impl Context {
+    pub fn spawn(config: Node,
+        key: TsigKey,
+        sign_requests: Receiver<(usize, Vec<u8>)>,
+        sig_out: Sender<(usize, Vec<u8>, Vec<u8>)>,
+        byz: bool) -> anyhow::Result<oneshot::Sender<()>> {
+        if key.myid != config.id || !key.is_consistent(){
+            return Err(anyhow!("Signing key share does not belong to party {}", config.id));
+        }
+
+        let mut consensus_addrs: FnvHashMap<Replica, SocketAddr> = FnvHashMap::default();
+        for (replica, address) in config.net_map.iter() {
+            let address: SocketAddr = address.parse().expect("Unable to parse address");
+            consensus_addrs.insert(*replica, SocketAddr::from(address.clone()));
+        }
+        let my_port = consensus_addrs.get(&config.id).unwrap();
+        let my_address = to_socket_address("0.0.0.0", my_port.port());
+
+        // Setup networking
+        let (tx_net_to_consensus, rx_net_to_consensus) = unbounded_channel();
+        let consensus_net = consensus::spawn_replica_network(my_address, consensus_addrs.clone(), tx_net_to_consensus);
+
+        let (exit_tx, exit_rx) = oneshot::channel();
+
+        tokio::spawn(async move {
+            let mut c = Context {
+                net_send: consensus_net,
+                net_recv: rx_net_to_consensus,
+
+                num_nodes: config.num_nodes,
+                sec_key_map: HashMap::default(),
+                myid: config.id,
+                _byz: byz,
+                num_faults: config.num_faults,
+                cancel_handlers: HashMap::default(),
+                exit_rx: exit_rx,
+
+                key: key,
+                sig_states: HashMap::default(),
+
+                sign_requests: sign_requests,
+                sig_out: sig_out,
+            };
+
+            // Populate secret keys from config
+            for (id, sk_data) in config.sk_map.iter() {
+                c.sec_key_map.insert(*id, SecretBytes::new(sk_data.clone()));
+            }
+
+            // Run the signing context
+            if let Err(e) = c.run().await {
+                log::error!("Threshold signing error: {}", e);
+            }
+        });
+
+        Ok(exit_tx)
+    }
+
+    pub async fn broadcast(&mut self, protmsg: ProtMsg) {
+        let replicas: Vec<Replica> = self.sec_key_map.keys().cloned().collect();
+        for replica in replicas {
+            let sec_key = self.sec_key_map.get(&replica).unwrap();
+            let wrapper_msg = WrapperMsg::new(protmsg.clone(), self.myid, &sec_key.as_slice());
+            consensus::count_sent_message(&wrapper_msg);
+            let cancel_handler: CancelHandler<Acknowledgement> = self.net_send.send(replica, wrapper_msg).await;
+            self.add_cancel_handler(cancel_handler);
+        }
+    }
+
+    pub fn add_cancel_handler(&mut self, canc: CancelHandler<Acknowledgement>) {
+        self.cancel_handlers.entry(0).or_default().push(canc);
+    }
+
+    pub async fn run(&mut self) -> Result<()>{
+        loop {
+            tokio::select! {
+                // Receive exit handlers
+                exit_val = &mut self.exit_rx => {
+                    exit_val.map_err(anyhow::Error::new)?;
+                    log::info!("Termination signal received by the server. Exiting.");
+                    break
+                },
+                msg = self.net_recv.recv() => {
+                    // Received messages are processed here
+                    log::trace!("Got a consensus message from the network: {:?}", msg);
+                    let msg = msg.ok_or_else(||
+                        anyhow!("Networking layer has closed")
+                    )?;
+                    self.process_msg(msg).await;
+                },
+                req_msg = self.sign_requests.recv() => {
+                    let req_msg = req_msg.ok_or_else(||
+                        anyhow!("Signing request channel has closed")
+                    )?;
+                    log::debug!("Received signing request for instance {}", req_msg.0);
+                    self.init_sign(req_msg.0, req_msg.1).await;
+                },
+            };
+        }
+        Ok(())
+    }
+}
+
+pub fn to_socket_address(ip_str: &str, port: u16) -> SocketAddr {
+    let addr = SocketAddrV4::new(ip_str.parse().unwrap(), port);
+    addr.into()
+}
@@ -0,0 +1,220 @@
+use std::{fmt, fs::{File, OpenOptions}, io::{BufReader, BufWriter}};
+
+use anyhow::{anyhow, Result};
+use bls12_381::{G1Affine, G1Projective, Scalar};
+use consensus::SecretBytes;
+use serde::{Serialize, Deserialize};
+use types::Replica;
+use zeroize::{Zeroize, Zeroizing};
+
+/// A party's share of the BLS signing key. The key is shared with a degree-t polynomial f over the BLS12-381 scalar field,
+/// party i holds f(i+1), and public_key_shares[j] = g1^{f(j+1)}. Keys come from the ADKG, from a key file it wrote, or from `deal` for local testing.
+#[derive(Clone)]
+pub struct TsigKey{
+    pub myid: Replica,
+    pub num_faults: usize,
+    secret_share: Scalar,
+    pub public_key: G1Affine,
+    pub public_key_shares: Vec<G1Affine>,
+}
+
+pub fn rand_scalar() -> Scalar{
+    let mut bytes = [0u8; 64];
+    bytes[..32].copy_from_slice(&rand::random::<[u8; 32]>());
+    bytes[32..].copy_from_slice(&rand::random::<[u8; 32]>());
+    Scalar::from_bytes_wide(&bytes)
+}
+
+impl TsigKey{
+    /// Builds a key from this party's share and the verification keys of all parties.
+    /// The public key is interpolated in the exponent from the first t+1 verification keys.
+    pub fn new(myid: Replica, num_faults: usize, secret_share: Scalar, public_key_shares: Vec<G1Affine>) -> TsigKey{
+        let parties: Vec<Replica> = (0..num_faults+1).collect();
+        let coefficients = lagrange_coefficients_at_zero(&parties);
+        let mut public_key = G1Projective::identity();
+        for (rep, coefficient) in parties.iter().zip(coefficients.iter()){
+            public_key += public_key_shares[*rep] * coefficient;
+        }
+        TsigKey {
+            myid: myid,
+            num_faults: num_faults,
+            secret_share: secret_share,
+            public_key: G1Affine::from(public_key),
+            public_key_shares: public_key_shares
+        }
+    }
+
+    pub fn public_key_share(secret_share: &Scalar) -> G1Affine{
+        G1Affine::from(G1Affine::generator() * secret_share)
+    }
+
+    /// Trusted dealer setup for benchmarks and local testing. Returns the key of every party.
+    pub fn deal(num_nodes: usize, num_faults: usize) -> Vec<TsigKey>{
+        let coefficients: Vec<Scalar> = (0..num_faults+1).map(|_| rand_scalar()).collect();
+        let secret_shares: Vec<Scalar> = (0..num_nodes).map(|rep| {
+            // Horner evaluation at rep+1
+            let x = Scalar::from((rep+1) as u64);
+            coefficients.iter().rev().fold(Scalar::zero(), |acc, coefficient| acc * x + coefficient)
+        }).collect();
+        let public_key_shares: Vec<G1Affine> = secret_shares.iter().map(|share| Self::public_key_share(share)).collect();
+        secret_shares.into_iter().enumerate().map(|(rep, share)| {
+            Self::new(rep, num_faults, share, public_key_shares.clone())
+        }).collect()
+    }
+
+    pub fn secret_share(&self) -> &Scalar{
+        &self.secret_share
+    }
+
+    /// Checks that this party's secret share matches its published verification key
+    pub fn is_consistent(&self) -> bool{
+        self.public_key_shares.len() > self.myid
+            && Self::public_key_share(&self.secret_share) == self.public_key_shares[self.myid]
+    }
+}
+
+impl Drop for TsigKey{
+    fn drop(&mut self) {
+        self.secret_share.zeroize();
+    }
+}
+
+impl fmt::Debug for TsigKey{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TsigKey")
+            .field("myid", &self.myid)
+            .field("num_faults", &self.num_faults)
+            .field("secret_share", &"<redacted>")
+            .field("public_key", &self.public_key)
+            .field("public_key_shares", &self.public_key_shares.len())
+            .finish()
+    }
+}
+
+/// Lagrange coefficients for evaluating at 0 the polynomial through the points (rep+1) of the given parties
+pub fn lagrange_coefficients_at_zero(parties: &Vec<Replica>) -> Vec<Scalar>{
+    let xs: Vec<Scalar> = parties.iter().map(|rep| Scalar::from((rep+1) as u64)).collect();
+    let mut coefficients = Vec::new();
+    for (i, x_i) in xs.iter().enumerate(){
+        let mut numerator = Scalar::one();
+        let mut denominator = Scalar::one();
+        for (j, x_j) in xs.iter().enumerate(){
+            if i != j{
+                numerator *= x_j;
+                denominator *= x_j - x_i;
+            }
+        }
+        coefficients.push(numerator * denominator.invert().unwrap());
+    }
+    coefficients
+}
+
+/// Version of the signing key file format
+pub const TSIG_KEY_VERSION: u32 = 1;
+
+/// On-disk format of a signing key share, written by the ADKG. Scalars are hex encoded little-endian, points in compressed form.
+/// The file holds the party's secret share and must be kept secret, so it is created readable by its owner only.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TsigKeyFile{
+    pub version: u32,
+    pub group: String,
+    pub num_nodes: usize,
+    pub num_faults: usize,
+    pub myid: usize,
+    #[serde(with = "secret_hex")]
+    pub secret_share: SecretBytes,
+    pub public_key: String,
+    pub public_key_shares: Vec<String>,
+}
+
+fn to_hex(bytes: &[u8]) -> String{
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(hex: &str) -> Result<Vec<u8>>{
+    if hex.len() % 2 != 0{
+        return Err(anyhow!("Odd length hex string"));
+    }
+    (0..hex.len()).step_by(2).map(|i| {
+        u8::from_str_radix(&hex[i..i+2], 16).map_err(anyhow::Error::new)
+    }).collect()
+}
+
+// Hex encoding of the secret share. The intermediate strings are wiped after use.
+mod secret_hex{
+    use consensus::SecretBytes;
+    use serde::{Deserialize, Deserializer, Serializer};
+    use zeroize::{Zeroize, Zeroizing};
+
+    pub fn serialize<S: Serializer>(secret: &SecretBytes, serializer: S) -> Result<S::Ok, S::Error>{
+        let hex = Zeroizing::new(super::to_hex(secret));
+        serializer.serialize_str(&hex)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<SecretBytes, D::Error>{
+        let hex = Zeroizing::new(String::deserialize(deserializer)?);
+        super::from_hex(&hex).map(SecretBytes::new).map_err(serde::de::Error::custom)
+    }
+}
+
+pub fn g1_from_bytes(bytes: &[u8]) -> Option<G1Affine>{
+    let bytes: [u8; 48] = bytes.try_into().ok()?;
+    Option::from(G1Affine::from_compressed(&bytes))
+}
+
+impl TsigKeyFile{
+    pub fn from_key(key: &TsigKey) -> TsigKeyFile{
+        TsigKeyFile {
+            version: TSIG_KEY_VERSION,
+            group: "bls12-381-g1".to_string(),
+            num_nodes: key.public_key_shares.len(),
+            num_faults: key.num_faults,
+            myid: key.myid,
+            secret_share: SecretBytes::new(key.secret_share.to_bytes().to_vec()),
+            public_key: to_hex(&key.public_key.to_compressed()),
+            public_key_shares: key.public_key_shares.iter().map(|pk_share| to_hex(&pk_share.to_compressed())).collect(),
+        }
+    }
+
+    pub fn to_key(&self) -> Result<TsigKey>{
+        if self.version != TSIG_KEY_VERSION || self.group != "bls12-381-g1"{
+            return Err(anyhow!("Unsupported signing key file version {} for group {}", self.version, self.group));
+        }
+        if self.public_key_shares.len() != self.num_nodes || self.myid >= self.num_nodes || self.num_faults >= self.num_nodes{
+            return Err(anyhow!("Inconsistent parameters in signing key file"));
+        }
+        let secret_bytes: [u8; 32] = self.secret_share.as_slice().try_into().map_err(|_| anyhow!("Invalid secret share length"))?;
+        let secret_bytes = Zeroizing::new(secret_bytes);
+        let secret_share: Scalar = Option::from(Scalar::from_bytes(&secret_bytes)).ok_or_else(|| anyhow!("Invalid secret share"))?;
+        let public_key_shares = self.public_key_shares.iter().map(|pk_share| {
+            g1_from_bytes(from_hex(pk_share)?.as_slice()).ok_or_else(|| anyhow!("Invalid public key share"))
+        }).collect::<Result<Vec<G1Affine>>>()?;
+        let key = TsigKey::new(self.myid, self.num_faults, secret_share, public_key_shares);
+        if to_hex(&key.public_key.to_compressed()) != self.public_key{
+            return Err(anyhow!("Public key does not match the public key shares"));
+        }
+        if !key.is_consistent(){
+            return Err(anyhow!("Secret share does not match the verification key of party {}", self.myid));
+        }
+        Ok(key)
+    }
+
+    /// Writes the key file. A new file is created readable and writable by its owner only, because it holds the secret share.
+    pub fn write_to_file(&self, path: &str) -> Result<()>{
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let file = options.open(path)?;
+        serde_json::to_writer_pretty(BufWriter::new(file), self)?;
+        Ok(())
+    }
+
+    pub fn read_from_file(path: &str) -> Result<TsigKeyFile>{
+        let file = File::open(path)?;
+        Ok(serde_json::from_reader(BufReader::new(file))?)
+    }
+}
@@ -0,0 +1,14 @@
+pub mod keys;
+pub use keys::*;
+
+pub mod sig;
+pub use sig::*;
+
+mod context;
+pub use context::*;
+
+mod protocol;
+
+pub mod msg;
+
+mod process;
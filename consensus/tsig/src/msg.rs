@@ -0,0 +1,9 @@
+use serde::{Serialize, Deserialize};
+
+use crate::sig::PartialSignature;
+
+#[derive(Debug,Serialize,Deserialize,Clone)]
+pub enum ProtMsg{
+    // Signing instance, message, partial signature of the sender
+    PartialSig(usize, Vec<u8>, PartialSignature),
+}
@@ -0,0 +1,4 @@
+pub mod state;
+pub use state::*;
+
+mod sign;
@@ -0,0 +1,89 @@
+use crypto::hash::do_hash;
+use types::Replica;
+
+use crate::{Context, msg::ProtMsg, sig::{PartialSignature, sign_share, verify_share, combine}};
+
+use super::SigState;
+
+/*
+    Partial signatures are verified only against the message this party was asked to sign in the instance,
+    so every party costs at most one pairing check per instance. Partial signatures that arrive before the request
+    are kept unverified, one per party, and checked once the request names the expected message.
+*/
+impl Context{
+    pub async fn init_sign(&mut self, instance: usize, message: Vec<u8>){
+        let sig_state = self.sig_states.entry(instance).or_insert_with(SigState::new);
+        if sig_state.signed{
+            log::warn!("Already signed a message in instance {}, ignoring request", instance);
+            return;
+        }
+        let msg_hash = do_hash(message.as_slice());
+        sig_state.signed = true;
+        sig_state.message_hash = Some(msg_hash);
+        let share = sign_share(&self.key, message.as_slice());
+        log::info!("Broadcasting partial signature for instance {}", instance);
+        self.broadcast(ProtMsg::PartialSig(instance, message.clone(), share)).await;
+
+        let pending: Vec<(Replica, PartialSignature)> = self.sig_states.get_mut(&instance).unwrap().pending_sigs.drain()
+            .filter(|(_, (pending_hash, _))| *pending_hash == msg_hash)
+            .map(|(sender, (_, share))| (sender, share))
+            .collect();
+        for (sender, share) in pending{
+            self.add_partial_sig(instance, &message, sender, share).await;
+        }
+    }
+
+    pub async fn process_partial_sig(&mut self, instance: usize, message: Vec<u8>, sender: Replica, share: PartialSignature){
+        if share.party != sender{
+            log::warn!("Party {} sent a partial signature on behalf of party {}", sender, share.party);
+            return;
+        }
+        let sig_state = self.sig_states.entry(instance).or_insert_with(SigState::new);
+        if sig_state.terminated{
+            return;
+        }
+        let msg_hash = do_hash(message.as_slice());
+        let expected_hash = sig_state.message_hash;
+        match expected_hash {
+            Some(expected) if expected == msg_hash => {
+                self.add_partial_sig(instance, &message, sender, share).await;
+            },
+            Some(_) => {
+                log::warn!("Party {} signed a different message in instance {}", sender, instance);
+            },
+            None => {
+                sig_state.pending_sigs.entry(sender).or_insert((msg_hash, share));
+            }
+        }
+    }
+
+    // Verifies a partial signature on the expected message of the instance and combines once t+1 are verified
+    async fn add_partial_sig(&mut self, instance: usize, message: &Vec<u8>, sender: Replica, share: PartialSignature){
+        let sig_state = self.sig_states.get_mut(&instance).unwrap();
+        if sig_state.terminated || sig_state.partial_sigs.iter().any(|partial| partial.party == sender){
+            return;
+        }
+        if !verify_share(&self.key, message.as_slice(), &share){
+            log::warn!("Partial signature of party {} for instance {} failed verification", sender, instance);
+            return;
+        }
+        sig_state.partial_sigs.push(share);
+        if sig_state.partial_sigs.len() < self.num_faults+1{
+            return;
+        }
+
+        let sig = match combine(&self.key, &sig_state.partial_sigs){
+            Some(sig) => sig,
+            None => {
+                log::error!("Unable to combine partial signatures for instance {}", instance);
+                return;
+            }
+        };
+        sig_state.terminated = true;
+        log::info!("Combined threshold signature for instance {}", instance);
+        let status = self.sig_out.send((instance, message.clone(), sig)).await;
+        if status.is_err(){
+            log::error!("Error sending signature of instance {} to the output channel", instance);
+        }
+    }
+}
@@ -0,0 +1,29 @@
+use std::collections::HashMap;
+
+use crypto::hash::Hash;
+use types::Replica;
+
+use crate::sig::PartialSignature;
+
+pub struct SigState{
+    // Hash of the message this party was asked to sign. Partial signatures are only verified against it.
+    pub message_hash: Option<Hash>,
+    // Partial signatures received before the signing request, one per party, with the hash of their message
+    pub pending_sigs: HashMap<Replica, (Hash, PartialSignature)>,
+    // Verified partial signatures on the expected message
+    pub partial_sigs: Vec<PartialSignature>,
+    pub signed: bool,
+    pub terminated: bool,
+}
+
+impl SigState{
+    pub fn new()-> SigState{
+        SigState {
+            message_hash: None,
+            pending_sigs: HashMap::default(),
+            partial_sigs: Vec::new(),
+            signed: false,
+            terminated: false,
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use bls12_381::{G1Affine, G2Affine, G2Projective, pairing, hash_to_curve::{HashToCurve, ExpandMsgXmd}};
+use serde::{Serialize, Deserialize};
+use types::Replica;
+
+use crate::keys::{TsigKey, lagrange_coefficients_at_zero};
+
+// Minimal-pubkey-size BLS: keys in G1, signatures in G2
+const DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_NUL_";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PartialSignature{
+    pub party: Replica,
+    // Compressed G2 point H(m)^{s_i}
+    pub sig: Vec<u8>,
+}
+
+pub fn hash_to_g2(message: &[u8]) -> G2Projective{
+    <G2Projective as HashToCurve<ExpandMsgXmd<sha2::Sha256>>>::hash_to_curve(message, DST)
+}
+
+pub fn g2_from_bytes(bytes: &[u8]) -> Option<G2Affine>{
+    let bytes: [u8; 96] = bytes.try_into().ok()?;
+    Option::from(G2Affine::from_compressed(&bytes))
+}
+
+pub fn sign_share(key: &TsigKey, message: &[u8]) -> PartialSignature{
+    let sig = G2Affine::from(hash_to_g2(message) * key.secret_share());
+    PartialSignature {
+        party: key.myid,
+        sig: sig.to_compressed().to_vec()
+    }
+}
+
+fn verify_with_key(public_key: &G1Affine, message: &[u8], sig: &G2Affine) -> bool{
+    // e(g1, H(m)^s) = e(g1^s, H(m))
+    pairing(&G1Affine::generator(), sig) == pairing(public_key, &G2Affine::from(hash_to_g2(message)))
+}
+
+/// Verifies a partial signature against the verification key of the party that produced it
+pub fn verify_share(key: &TsigKey, message: &[u8], share: &PartialSignature) -> bool{
+    if share.party >= key.public_key_shares.len(){
+        return false;
+    }
+    match g2_from_bytes(share.sig.as_slice()){
+        Some(sig) => verify_with_key(&key.public_key_shares[share.party], message, &sig),
+        None => false
+    }
+}
+
+/// Combines t+1 verified partial signatures from distinct parties into a signature under the public key
+pub fn combine(key: &TsigKey, shares: &Vec<PartialSignature>) -> Option<Vec<u8>>{
+    if shares.len() < key.num_faults+1{
+        return None;
+    }
+    let shares = &shares[..key.num_faults+1];
+    let mut parties: Vec<Replica> = Vec::new();
+    let mut sigs: Vec<G2Affine> = Vec::new();
+    for share in shares.iter(){
+        if parties.contains(&share.party){
+            return None;
+        }
+        parties.push(share.party);
+        sigs.push(g2_from_bytes(share.sig.as_slice())?);
+    }
+    let coefficients = lagrange_coefficients_at_zero(&parties);
+    let mut sig = G2Projective::identity();
+    for (partial, coefficient) in sigs.iter().zip(coefficients.iter()){
+        sig += partial * coefficient;
+    }
+    Some(G2Affine::from(sig).to_compressed().to_vec())
+}
+
+/// Verifies a combined signature. Anyone holding the public key can do this.
+pub fn verify(public_key: &G1Affine, message: &[u8], sig: &[u8]) -> bool{
+    match g2_from_bytes(sig){
+        Some(sig) => verify_with_key(public_key, message, &sig),
+        None => false
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    #[test]
+    fn shares_combine_into_a_signature_under_the_public_key(){
+        let keys = TsigKey::deal(4, 1);
+        let message = b"message";
+        let shares: Vec<PartialSignature> = keys.iter().map(|key| sign_share(key, message)).collect();
+        for share in shares.iter(){
+            assert!(verify_share(&keys[0], message, share));
+        }
+        let sig = combine(&keys[0], &shares[..2].to_vec()).unwrap();
+        assert!(verify(&keys[0].public_key, message, &sig));
+        assert!(!verify(&keys[0].public_key, b"other message", &sig));
+        // Any t+1 shares give the same signature
+        assert_eq!(sig, combine(&keys[0], &shares[2..].to_vec()).unwrap());
+    }
+
+    #[test]
+    fn wrong_share_fails_verification(){
+        let keys = TsigKey::deal(4, 1);
+        let message = b"message";
+        let share = sign_share(&keys[1], message);
+        assert!(!verify_share(&keys[0], b"other message", &share));
+        // A share claimed by another party is checked against that party's verification key
+        let claimed = PartialSignature { party: 2, sig: share.sig.clone() };
+        assert!(!verify_share(&keys[0], message, &claimed));
+        let out_of_range = PartialSignature { party: 4, sig: share.sig.clone() };
+        assert!(!verify_share(&keys[0], message, &out_of_range));
+        let garbage = PartialSignature { party: 1, sig: vec![0u8; 96] };
+        assert!(!verify_share(&keys[0], message, &garbage));
+    }
+
+    #[test]
+    fn combine_rejects_duplicate_parties(){
+        let keys = TsigKey::deal(4, 1);
+        let share = sign_share(&keys[1], b"message");
+        assert!(combine(&keys[0], &vec![share.clone(), share]).is_none());
+    }
+}
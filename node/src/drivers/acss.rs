@@ -0,0 +1,174 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use config::Node;
+use consensus::{AcssOutput, HashKeys, SelectedPubRecOutput};
+use crypto::{LargeField, hash::Hash, rand_field_element};
+use tokio::sync::{mpsc::{channel, Receiver, Sender}, oneshot};
+use types::Replica;
+
+use super::{Workload, collect_exit_txs, spawn_workload};
+
+/// Every node deals `batches` ACSS instances of `per_batch` random secrets. The run completes once the node holds shares of all n*batches sharings.
+pub struct AcssWorkload{
+    name: &'static str,
+    num_nodes: usize,
+    batches: usize,
+    per_batch: usize,
+    acss_req: Sender<(usize, Vec<LargeField>)>,
+    // Public reconstruction is not part of the workload, the channels are kept open for ACSS services that have it
+    _pub_rec: Option<(Sender<(usize, Replica)>, Receiver<(usize, Replica, Vec<LargeField>)>)>,
+    _pub_rec_selected: Option<(Sender<(usize, Replica, Vec<usize>)>, Receiver<SelectedPubRecOutput>)>,
+}
+
+#[async_trait]
+impl Workload for AcssWorkload{
+    type Output = AcssOutput;
+
+    fn name(&self) -> &'static str{
+        self.name
+    }
+
+    fn expected_outputs(&self) -> usize{
+        self.num_nodes*self.batches
+    }
+
+    async fn start(&mut self){
+        for instance in 1..self.batches+1{
+            let secrets: Vec<LargeField> = (0..self.per_batch).map(|_| rand_field_element()).collect();
+            let _status = self.acss_req.send((instance, secrets)).await;
+        }
+    }
+
+    async fn process_output(&mut self, output: Self::Output) -> Option<Vec<u8>>{
+        let (instance, dealer, root_comm, shares, _opening) = output;
+        if shares.is_none(){
+            log::error!("ACSS instance {} of dealer {} terminated without shares", instance, dealer);
+            return None;
+        }
+        Some(output_id(instance, dealer, &root_comm))
+    }
+}
+
+fn output_id(instance: usize, dealer: Replica, root_comm: &Hash) -> Vec<u8>{
+    let mut id = Vec::new();
+    id.extend((instance as u64).to_be_bytes());
+    id.extend((dealer as u64).to_be_bytes());
+    id.extend(root_comm);
+    id
+}
+
+pub fn spawn_acss_ske(config: Node, hash_keys: HashKeys, batches: usize, per_batch: usize, lin_or_quad: bool) -> Result<oneshot::Sender<()>>{
+    let (acss_req_send, acss_req_recv) = channel(10000);
+    let (acss_out_send, acss_out_recv) = channel(10000);
+    let (pub_rec_req_send, pub_rec_req_recv) = channel(10000);
+    let (pub_rec_out_send, pub_rec_out_recv) = channel(10000);
+    let (pub_rec_selected_req_send, pub_rec_selected_req_recv) = channel(10000);
+    let (pub_rec_selected_out_send, pub_rec_selected_out_recv) = channel(10000);
+
+    let service = acss_ske::Context::spawn(
+        config.clone(),
+        hash_keys,
+        acss_req_recv,
+        acss_out_send,
+        pub_rec_req_recv,
+        pub_rec_out_send,
+        pub_rec_selected_req_recv,
+        pub_rec_selected_out_send,
+        false,
+        lin_or_quad,
+        false
+    )?;
+    let workload = AcssWorkload{
+        name: "acss_ske",
+        num_nodes: config.num_nodes,
+        batches: batches,
+        per_batch: per_batch,
+        acss_req: acss_req_send,
+        _pub_rec: Some((pub_rec_req_send, pub_rec_out_recv)),
+        _pub_rec_selected: Some((pub_rec_selected_req_send, pub_rec_selected_out_recv)),
+    };
+    Ok(spawn_workload(&config, collect_exit_txs(service), workload, acss_out_recv))
+}
+
+pub fn spawn_g_acss(config: Node, hash_keys: HashKeys, batches: usize, per_batch: usize, lin_or_quad: bool) -> Result<oneshot::Sender<()>>{
+    let (acss_req_send, acss_req_recv) = channel(10000);
+    let (acss_out_send, acss_out_recv) = channel(10000);
+    let (pub_rec_req_send, pub_rec_req_recv) = channel(10000);
+    let (pub_rec_out_send, pub_rec_out_recv) = channel(10000);
+
+    let service = g_acss::Context::spawn(
+        config.clone(),
+        hash_keys,
+        acss_req_recv,
+        acss_out_send,
+        pub_rec_req_recv,
+        pub_rec_out_send,
+        false,
+        lin_or_quad,
+        false
+    )?;
+    let workload = AcssWorkload{
+        name: "g_acss",
+        num_nodes: config.num_nodes,
+        batches: batches,
+        per_batch: per_batch,
+        acss_req: acss_req_send,
+        _pub_rec: Some((pub_rec_req_send, pub_rec_out_recv)),
+        _pub_rec_selected: None,
+    };
+    Ok(spawn_workload(&config, collect_exit_txs(service), workload, acss_out_recv))
+}
+
+pub fn spawn_hacss(config: Node, hash_keys: HashKeys, batches: usize, per_batch: usize) -> Result<oneshot::Sender<()>>{
+    let (acss_req_send, acss_req_recv) = channel(10000);
+    let (acss_out_send, acss_out_recv) = channel(10000);
+    let (pub_rec_req_send, pub_rec_req_recv) = channel(10000);
+    let (pub_rec_out_send, pub_rec_out_recv) = channel(10000);
+
+    let service_exit_tx = hacss::Context::spawn(
+        config.clone(),
+        hash_keys,
+        acss_req_recv,
+        acss_out_send,
+        pub_rec_req_recv,
+        pub_rec_out_send,
+        false
+    )?;
+    let workload = AcssWorkload{
+        name: "hacss",
+        num_nodes: config.num_nodes,
+        batches: batches,
+        per_batch: per_batch,
+        acss_req: acss_req_send,
+        _pub_rec: Some((pub_rec_req_send, pub_rec_out_recv)),
+        _pub_rec_selected: None,
+    };
+    Ok(spawn_workload(&config, vec![service_exit_tx], workload, acss_out_recv))
+}
+
+pub fn spawn_acss_bv(config: Node, hash_keys: HashKeys, batches: usize, per_batch: usize) -> Result<oneshot::Sender<()>>{
+    let (acss_req_send, acss_req_recv) = channel(10000);
+    let (acss_out_send, acss_out_recv) = channel(10000);
+    let (pub_rec_req_send, pub_rec_req_recv) = channel(10000);
+    let (pub_rec_out_send, pub_rec_out_recv) = channel(10000);
+
+    let service_exit_tx = acss_bv::Context::spawn(
+        config.clone(),
+        hash_keys,
+        acss_req_recv,
+        acss_out_send,
+        pub_rec_req_recv,
+        pub_rec_out_send,
+        false
+    )?;
+    let workload = AcssWorkload{
+        name: "acss_bv",
+        num_nodes: config.num_nodes,
+        batches: batches,
+        per_batch: per_batch,
+        acss_req: acss_req_send,
+        _pub_rec: Some((pub_rec_req_send, pub_rec_out_recv)),
+        _pub_rec_selected: None,
+    };
+    Ok(spawn_workload(&config, vec![service_exit_tx], workload, acss_out_recv))
+}
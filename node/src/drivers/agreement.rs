@@ -0,0 +1,200 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use config::Node;
+use consensus::{HashKeys, LargeFieldSer};
+use crypto::hash::do_hash;
+use tokio::sync::{mpsc::{channel, Sender}, oneshot};
+use types::Replica;
+
+use super::{Workload, bench_coin_shares, collect_exit_txs, spawn_workload};
+
+// FIN MVBA consumes six coins per round, one for the leader election and five for the BBA of the round
+const MVBA_COINS_PER_INSTANCE: usize = 60;
+
+fn party_set_id(instance: usize, party_set: &Vec<Replica>) -> Vec<u8>{
+    let mut id = Vec::new();
+    id.extend((instance as u64).to_be_bytes());
+    for party in party_set{
+        id.extend((*party as u64).to_be_bytes());
+    }
+    id
+}
+
+/// Runs `batches` FIN MVBA instances, every node proposing a random party index
+pub struct FinMvbaWorkload{
+    myid: Replica,
+    num_nodes: usize,
+    num_faults: usize,
+    batches: usize,
+    mvba_req: Sender<(usize, usize, Vec<LargeFieldSer>)>,
+}
+
+#[async_trait]
+impl Workload for FinMvbaWorkload{
+    type Output = (usize, Vec<usize>);
+
+    fn name(&self) -> &'static str{
+        "fin_mvba"
+    }
+
+    fn expected_outputs(&self) -> usize{
+        self.batches
+    }
+
+    async fn start(&mut self){
+        for instance in 1..self.batches+1{
+            let mut seed = b"fin_mvba bench coin".to_vec();
+            seed.extend((instance as u64).to_be_bytes());
+            let coin_shares = bench_coin_shares(seed.as_slice(), self.myid, self.num_faults, self.num_nodes, MVBA_COINS_PER_INSTANCE);
+            let proposal = rand::random::<usize>() % self.num_nodes;
+            let _status = self.mvba_req.send((instance, proposal, coin_shares)).await;
+        }
+    }
+
+    async fn process_output(&mut self, output: Self::Output) -> Option<Vec<u8>>{
+        let (instance, values) = output;
+        log::info!("FIN MVBA instance {} output {:?}", instance, values);
+        Some(party_set_id(instance, &values))
+    }
+}
+
+pub fn spawn_fin_mvba(config: Node, hash_keys: HashKeys, batches: usize) -> Result<oneshot::Sender<()>>{
+    let (mvba_req_send, mvba_req_recv) = channel(10000);
+    let (mvba_out_send, mvba_out_recv) = channel(10000);
+
+    let service = fin_mvba::Context::spawn(
+        config.clone(),
+        hash_keys,
+        mvba_req_recv,
+        mvba_out_send,
+        false
+    )?;
+    let workload = FinMvbaWorkload{
+        myid: config.id,
+        num_nodes: config.num_nodes,
+        num_faults: config.num_faults,
+        batches: batches,
+        mvba_req: mvba_req_send,
+    };
+    Ok(spawn_workload(&config, collect_exit_txs(service), workload, mvba_out_recv))
+}
+
+/// Runs `batches` value-based ACS instances one after the other, every node proposing `proposal_size` random bytes
+pub struct AcsWorkload{
+    batches: usize,
+    proposal_size: usize,
+    acs_req: Sender<(usize, Vec<u8>)>,
+}
+
+impl AcsWorkload{
+    async fn propose(&mut self, instance: usize){
+        let proposal: Vec<u8> = (0..self.proposal_size).map(|_| rand::random::<u8>()).collect();
+        let _status = self.acs_req.send((instance, proposal)).await;
+    }
+}
+
+#[async_trait]
+impl Workload for AcsWorkload{
+    type Output = (usize, Vec<(Replica, Vec<u8>)>);
+
+    fn name(&self) -> &'static str{
+        "acs"
+    }
+
+    fn expected_outputs(&self) -> usize{
+        self.batches
+    }
+
+    async fn start(&mut self){
+        if self.batches > 0{
+            self.propose(1).await;
+        }
+    }
+
+    async fn process_output(&mut self, output: Self::Output) -> Option<Vec<u8>>{
+        let (instance, agreed_values) = output;
+        log::info!("ACS instance {} agreed on proposals of {:?}", instance, agreed_values.iter().map(|(rep, _)| *rep).collect::<Vec<Replica>>());
+        if instance < self.batches{
+            self.propose(instance+1).await;
+        }
+        let mut id = Vec::new();
+        id.extend((instance as u64).to_be_bytes());
+        for (rep, value) in agreed_values.iter(){
+            id.extend((*rep as u64).to_be_bytes());
+            id.extend(do_hash(value.as_slice()));
+        }
+        Some(id)
+    }
+}
+
+pub fn spawn_acs(config: Node, hash_keys: HashKeys, batches: usize, proposal_size: usize) -> Result<oneshot::Sender<()>>{
+    let (acs_req_send, acs_req_recv) = channel(10000);
+    let (acs_out_send, acs_out_recv) = channel(10000);
+
+    let service = acs::Context::spawn_with_values(
+        config.clone(),
+        hash_keys,
+        acs_req_recv,
+        acs_out_send,
+        false
+    )?;
+    let workload = AcsWorkload{
+        batches: batches,
+        proposal_size: proposal_size,
+        acs_req: acs_req_send,
+    };
+    Ok(spawn_workload(&config, collect_exit_txs(service), workload, acs_out_recv))
+}
+
+/// Runs `batches` IBFT instances. Every node reports the termination of all n sharings of an instance, so the leader proposes once n-t parties are confirmed.
+pub struct IbftWorkload{
+    num_nodes: usize,
+    batches: usize,
+    term_events: Sender<(usize, usize, Vec<LargeFieldSer>)>,
+}
+
+#[async_trait]
+impl Workload for IbftWorkload{
+    type Output = (usize, Vec<usize>);
+
+    fn name(&self) -> &'static str{
+        "ibft"
+    }
+
+    fn expected_outputs(&self) -> usize{
+        self.batches
+    }
+
+    async fn start(&mut self){
+        for instance in 1..self.batches+1{
+            for party in 0..self.num_nodes{
+                let _status = self.term_events.send((party, instance, vec![])).await;
+            }
+        }
+    }
+
+    async fn process_output(&mut self, output: Self::Output) -> Option<Vec<u8>>{
+        let (instance, party_set) = output;
+        log::info!("IBFT instance {} output {:?}", instance, party_set);
+        Some(party_set_id(instance, &party_set))
+    }
+}
+
+pub fn spawn_ibft(config: Node, batches: usize) -> Result<oneshot::Sender<()>>{
+    let (term_event_send, term_event_recv) = channel(10000);
+    let (ibft_out_send, ibft_out_recv) = channel(10000);
+
+    let service = ibft::Context::spawn(
+        config.clone(),
+        term_event_recv,
+        ibft_out_send,
+        config.num_nodes-config.num_faults,
+        false
+    )?;
+    let workload = IbftWorkload{
+        num_nodes: config.num_nodes,
+        batches: batches,
+        term_events: term_event_send,
+    };
+    Ok(spawn_workload(&config, collect_exit_txs(service), workload, ibft_out_recv))
+}
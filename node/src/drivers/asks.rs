@@ -0,0 +1,81 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use config::Node;
+use consensus::HashKeys;
+use crypto::{LargeField, hash::do_hash, rand_field_element};
+use lambdaworks_math::traits::ByteConversion;
+use tokio::sync::{mpsc::{channel, Sender}, oneshot};
+use types::Replica;
+
+use super::{Workload, spawn_workload};
+
+/// Every node shares `batches` ASKS instances of `per_batch` random secrets and reconstructs each sharing to all parties once it terminates.
+/// The run completes once the node reconstructed all n*batches sharings.
+pub struct AsksWorkload{
+    num_nodes: usize,
+    batches: usize,
+    per_batch: usize,
+    asks_req: Sender<(usize, usize, bool, bool, Option<Vec<LargeField>>, Option<usize>)>,
+}
+
+#[async_trait]
+impl Workload for AsksWorkload{
+    type Output = (usize, Replica, Option<Vec<LargeField>>);
+
+    fn name(&self) -> &'static str{
+        "asks"
+    }
+
+    fn expected_outputs(&self) -> usize{
+        self.num_nodes*self.batches
+    }
+
+    async fn start(&mut self){
+        for instance in 1..self.batches+1{
+            let secrets: Vec<LargeField> = (0..self.per_batch).map(|_| rand_field_element()).collect();
+            let _status = self.asks_req.send((instance, self.per_batch, true, false, Some(secrets), None)).await;
+        }
+    }
+
+    async fn process_output(&mut self, output: Self::Output) -> Option<Vec<u8>>{
+        let (instance, dealer, secrets) = output;
+        match secrets {
+            None => {
+                // Sharing terminated, reconstruct it publicly
+                let _status = self.asks_req.send((instance, self.per_batch, true, true, None, Some(dealer))).await;
+                None
+            },
+            Some(secrets) => {
+                let mut secrets_ser = Vec::new();
+                for secret in secrets{
+                    secrets_ser.extend(secret.to_bytes_be());
+                }
+                let mut id = Vec::new();
+                id.extend((instance as u64).to_be_bytes());
+                id.extend((dealer as u64).to_be_bytes());
+                id.extend(do_hash(secrets_ser.as_slice()));
+                Some(id)
+            }
+        }
+    }
+}
+
+pub fn spawn_asks(config: Node, hash_keys: HashKeys, batches: usize, per_batch: usize) -> Result<oneshot::Sender<()>>{
+    let (asks_req_send, asks_req_recv) = channel(10000);
+    let (asks_out_send, asks_out_recv) = channel(10000);
+
+    let service_exit_tx = asks::Context::spawn(
+        config.clone(),
+        hash_keys,
+        asks_req_recv,
+        asks_out_send,
+        false
+    )?;
+    let workload = AsksWorkload{
+        num_nodes: config.num_nodes,
+        batches: batches,
+        per_batch: per_batch,
+        asks_req: asks_req_send,
+    };
+    Ok(spawn_workload(&config, vec![service_exit_tx], workload, asks_out_recv))
+}
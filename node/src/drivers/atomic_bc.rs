@@ -0,0 +1,101 @@
+use std::sync::OnceLock;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use config::Node;
+use consensus::HashKeys;
+use crypto::hash::do_hash;
+use tokio::sync::{mpsc::{channel, Sender}, oneshot};
+use tpke::ThresholdKey;
+use types::Replica;
+
+use super::{Workload, collect_exit_txs, spawn_workload};
+
+// Milliseconds after which a node proposes whatever its queue holds
+const BATCH_TIMEOUT: u64 = 100;
+
+// Threshold decryption key of this node, written by dpss. Set once at startup.
+static THRESHOLD_KEY: OnceLock<ThresholdKey> = OnceLock::new();
+
+/// Makes atomic_bc encrypt proposals under `key` until ACS fixes them, instead of proposing in the clear
+pub fn enable_threshold_key(key: ThresholdKey) -> Result<()>{
+    THRESHOLD_KEY.set(key)
+        .map_err(|_| anyhow!("threshold key already enabled"))
+}
+
+/// Submits `batches*per_batch` random transactions and waits for `batches` epochs of the log
+pub struct AtomicBcWorkload{
+    myid: Replica,
+    batches: usize,
+    per_batch: usize,
+    tx_send: Sender<Vec<u8>>,
+}
+
+#[async_trait]
+impl Workload for AtomicBcWorkload{
+    type Output = (usize, Vec<Vec<u8>>);
+
+    fn name(&self) -> &'static str{
+        "atomic_bc"
+    }
+
+    fn expected_outputs(&self) -> usize{
+        self.batches
+    }
+
+    async fn start(&mut self){
+        for index in 0..self.batches*self.per_batch{
+            let mut tx = Vec::new();
+            tx.extend((self.myid as u64).to_be_bytes());
+            tx.extend((index as u64).to_be_bytes());
+            tx.extend(rand::random::<u64>().to_be_bytes());
+            let _status = self.tx_send.send(tx).await;
+        }
+    }
+
+    async fn process_output(&mut self, output: Self::Output) -> Option<Vec<u8>>{
+        let (epoch, txs) = output;
+        log::info!("Atomic broadcast epoch {} committed {} transactions", epoch, txs.len());
+        if epoch > self.batches{
+            return None;
+        }
+        let mut id = Vec::new();
+        id.extend((epoch as u64).to_be_bytes());
+        id.extend(do_hash(txs.concat().as_slice()));
+        Some(id)
+    }
+}
+
+pub fn spawn_atomic_bc(config: Node, hash_keys: HashKeys, batches: usize, per_batch: usize) -> Result<oneshot::Sender<()>>{
+    let (tx_send, tx_recv) = channel(10000);
+    let (log_send, log_recv) = channel(10000);
+
+    let service = match THRESHOLD_KEY.get() {
+        Some(threshold_key) => atomic_bc::Context::spawn_with_encryption(
+            config.clone(),
+            hash_keys,
+            per_batch,
+            BATCH_TIMEOUT,
+            threshold_key.clone(),
+            tx_recv,
+            log_send,
+            false
+        )?,
+        None => atomic_bc::Context::spawn(
+            config.clone(),
+            hash_keys,
+            per_batch,
+            BATCH_TIMEOUT,
+            tx_recv,
+            log_send,
+            false
+        )?
+    };
+    let workload = AtomicBcWorkload{
+        myid: config.id,
+        batches: batches,
+        per_batch: per_batch,
+        tx_send: tx_send,
+    };
+    Ok(spawn_workload(&config, collect_exit_txs(service), workload, log_recv))
+}
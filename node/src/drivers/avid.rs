@@ -0,0 +1,76 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use config::Node;
+use consensus::HashKeys;
+use tokio::sync::{mpsc::{channel, Sender}, oneshot};
+use types::Replica;
+
+use super::{Workload, spawn_workload};
+
+// AVID treats messages starting with 32 zero bytes as dummies, so blobs are at least this long
+const MIN_BLOB_SIZE: usize = 32;
+
+/// Every node disperses `batches` rounds of random blobs of `blob_size` bytes, one blob for each recipient.
+/// The run completes once the node retrieved the blobs addressed to it from all n*batches dispersals.
+pub struct AvidWorkload{
+    num_nodes: usize,
+    batches: usize,
+    blob_size: usize,
+    avid_req: Sender<Vec<(Replica, Option<Vec<u8>>)>>,
+}
+
+#[async_trait]
+impl Workload for AvidWorkload{
+    type Output = (usize, Replica, Option<Vec<u8>>);
+
+    fn name(&self) -> &'static str{
+        "avid"
+    }
+
+    fn expected_outputs(&self) -> usize{
+        self.num_nodes*self.batches
+    }
+
+    async fn start(&mut self){
+        for _ in 0..self.batches{
+            let blobs: Vec<(Replica, Option<Vec<u8>>)> = (0..self.num_nodes).map(|rep| {
+                let blob: Vec<u8> = (0..self.blob_size).map(|_| rand::random::<u8>()).collect();
+                (rep, Some(blob))
+            }).collect();
+            let _status = self.avid_req.send(blobs).await;
+        }
+    }
+
+    async fn process_output(&mut self, output: Self::Output) -> Option<Vec<u8>>{
+        let (instance, sender, blob) = output;
+        if blob.is_none(){
+            log::error!("AVID instance {} of sender {} delivered no message", instance, sender);
+            return None;
+        }
+        // Recipients get different blobs, only the instance identifies the output across nodes
+        let mut id = Vec::new();
+        id.extend((instance as u64).to_be_bytes());
+        id.extend((sender as u64).to_be_bytes());
+        Some(id)
+    }
+}
+
+pub fn spawn_avid(config: Node, hash_keys: HashKeys, batches: usize, blob_size: usize) -> Result<oneshot::Sender<()>>{
+    let (avid_req_send, avid_req_recv) = channel(10000);
+    let (avid_out_send, avid_out_recv) = channel(10000);
+
+    let service_exit_tx = avid::Context::spawn(
+        config.clone(),
+        hash_keys,
+        avid_req_recv,
+        avid_out_send,
+        false
+    )?;
+    let workload = AvidWorkload{
+        num_nodes: config.num_nodes,
+        batches: batches,
+        blob_size: std::cmp::max(blob_size, MIN_BLOB_SIZE),
+        avid_req: avid_req_send,
+    };
+    Ok(spawn_workload(&config, vec![service_exit_tx], workload, avid_out_recv))
+}
@@ -0,0 +1,103 @@
+use std::sync::OnceLock;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use config::Node;
+use consensus::{HashKeys, LargeFieldSer};
+use tokio::sync::{mpsc::{channel, Sender}, oneshot};
+use tsig::TsigKey;
+use types::Replica;
+
+use super::{Workload, bench_coin_shares, spawn_workload};
+
+// Each BBA round consumes one coin. Random inputs terminate in a few rounds with high probability.
+const COINS_PER_INSTANCE: usize = 20;
+
+// Threshold signing key of this node and the session of this run. Set once at startup.
+static COIN_KEY: OnceLock<(TsigKey, Vec<u8>)> = OnceLock::new();
+
+/// Makes binary_ba draw its coins from threshold BLS signatures under `key` instead of pre-shared coin shares.
+/// The coins are bound to `session`, which every node of a run must share and no other run under the key may reuse.
+pub fn enable_coin_key(key: TsigKey, session: &str) -> Result<()>{
+    if session.is_empty(){
+        return Err(anyhow!("coin session must not be empty"));
+    }
+    COIN_KEY.set((key, session.as_bytes().to_vec()))
+        .map_err(|_| anyhow!("coin key already enabled"))
+}
+
+/// Runs `batches` Binary BA instances on random input bits, with coins from `bench_coin_shares` or from the threshold coin key
+pub struct BbaWorkload{
+    myid: Replica,
+    num_nodes: usize,
+    num_faults: usize,
+    batches: usize,
+    bba_req: Sender<(usize, i64, Vec<LargeFieldSer>)>,
+}
+
+#[async_trait]
+impl Workload for BbaWorkload{
+    type Output = (usize, i64);
+
+    fn name(&self) -> &'static str{
+        "binary_ba"
+    }
+
+    fn expected_outputs(&self) -> usize{
+        self.batches
+    }
+
+    async fn start(&mut self){
+        for instance in 1..self.batches+1{
+            let mut seed = b"binary_ba bench coin".to_vec();
+            seed.extend((instance as u64).to_be_bytes());
+            let coin_shares = match COIN_KEY.get() {
+                Some(_) => Vec::new(),
+                None => bench_coin_shares(seed.as_slice(), self.myid, self.num_faults, self.num_nodes, COINS_PER_INSTANCE)
+            };
+            let input = rand::random::<bool>() as i64;
+            let _status = self.bba_req.send((instance, input, coin_shares)).await;
+        }
+    }
+
+    async fn process_output(&mut self, output: Self::Output) -> Option<Vec<u8>>{
+        let (instance, value) = output;
+        log::info!("Binary BA instance {} decided {}", instance, value);
+        let mut id = Vec::new();
+        id.extend((instance as u64).to_be_bytes());
+        id.extend(value.to_be_bytes());
+        Some(id)
+    }
+}
+
+pub fn spawn_binary_ba(config: Node, hash_keys: HashKeys, batches: usize) -> Result<oneshot::Sender<()>>{
+    let (bba_req_send, bba_req_recv) = channel(10000);
+    let (bba_out_send, bba_out_recv) = channel(10000);
+
+    let service_exit_tx = match COIN_KEY.get() {
+        Some((coin_key, coin_session)) => binary_ba::Context::spawn_with_threshold_coin(
+            config.clone(),
+            hash_keys,
+            bba_req_recv,
+            bba_out_send,
+            coin_key.clone(),
+            coin_session.clone(),
+            false
+        )?,
+        None => binary_ba::Context::spawn(
+            config.clone(),
+            hash_keys,
+            bba_req_recv,
+            bba_out_send,
+            false
+        )?
+    };
+    let workload = BbaWorkload{
+        myid: config.id,
+        num_nodes: config.num_nodes,
+        num_faults: config.num_faults,
+        batches: batches,
+        bba_req: bba_req_send,
+    };
+    Ok(spawn_workload(&config, vec![service_exit_tx], workload, bba_out_recv))
+}
@@ -0,0 +1,15 @@
+use consensus::{LargeFieldSSS, LargeFieldSer, pseudorandom_lf};
+use types::Replica;
+
+/// Coin shares for benchmarking BBA and MVBA without running DPSS first.
+/// Every node derives the same degree-t coin polynomials from a public seed and evaluates them at its own index,
+/// which matches the interpolation points used by binary_ba and fin_mvba. The coins are predictable and must not be used outside benchmarks.
+pub fn bench_coin_shares(seed: &[u8], myid: Replica, num_faults: usize, num_nodes: usize, num_coins: usize) -> Vec<LargeFieldSer>{
+    let large_field_ss = LargeFieldSSS::new(num_faults+1, num_nodes);
+    (0..num_coins).map(|coin| {
+        let mut coin_seed = seed.to_vec();
+        coin_seed.extend((coin as u64).to_be_bytes());
+        let coefficients = pseudorandom_lf(coin_seed.as_slice(), num_faults+1);
+        large_field_ss.mod_evaluate_at(&coefficients, myid).to_bytes_be()
+    }).collect()
+}
@@ -0,0 +1,203 @@
+use std::{net::{SocketAddr, SocketAddrV4}, time::{SystemTime, UNIX_EPOCH, Duration}};
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use config::Node;
+use crypto::hash::do_hash;
+use fnv::FnvHashMap;
+use network::{plaintcp::{CancelHandler, TcpReceiver, TcpReliableSender}, Acknowledgement};
+use tokio::{sync::{mpsc::{unbounded_channel, Receiver, UnboundedReceiver}, oneshot}, time};
+use types::{Replica, SyncMsg, SyncState};
+
+use consensus::{CompletionReport, HEARTBEAT, HEARTBEAT_INTERVAL};
+
+use crate::SyncHandler;
+
+/// A benchmark workload on top of one protocol service
+#[async_trait]
+pub trait Workload: Send + 'static {
+    type Output: Send + 'static;
+
+    /// Name used in logs and in the completion report
+    fn name(&self) -> &'static str;
+
+    /// Number of counted outputs after which the run is complete
+    fn expected_outputs(&self) -> usize;
+
+    /// Issues the requests of the run. Called once the syncer sends START.
+    async fn start(&mut self);
+
+    /// Handles an output of the protocol service. Returns the bytes that identify the output if it counts towards the run.
+    /// These bytes must be the same at every honest node, they are hashed into the completion report so the syncer can check agreement.
+    async fn process_output(&mut self, output: Self::Output) -> Option<Vec<u8>>;
+
+    /// Message reported to the syncer once all expected outputs arrived
+    fn completion_message(&self, output_ids: &mut Vec<Vec<u8>>) -> String{
+        completion_report(self.name(), output_ids)
+    }
+}
+
+/// Connection of a driver to the syncer
+pub struct SyncClient{
+    pub myid: Replica,
+    pub sync_send: TcpReliableSender<Replica, SyncMsg, Acknowledgement>,
+    pub sync_recv: UnboundedReceiver<SyncMsg>,
+    pub cancel_handlers: Vec<CancelHandler<Acknowledgement>>,
+    // Time at which the syncer started the run, reported back on completion
+    pub start_time: u128,
+}
+
+impl SyncClient{
+    pub fn spawn(config: &Node) -> SyncClient{
+        let mut syncer_map: FnvHashMap<Replica, SocketAddr> = FnvHashMap::default();
+        syncer_map.insert(0, config.client_addr);
+
+        // The server must listen to the client's messages on some port that is not being used to listen to other servers
+        let syncer_l_address = SocketAddr::V4(SocketAddrV4::new("0.0.0.0".parse().unwrap(), config.client_port));
+        let (tx_net_to_client, rx_net_from_client) = unbounded_channel();
+        TcpReceiver::<Acknowledgement, SyncMsg, _>::spawn(
+            syncer_l_address,
+            SyncHandler::new(tx_net_to_client),
+        );
+        SyncClient {
+            myid: config.id,
+            sync_send: TcpReliableSender::<Replica, SyncMsg, Acknowledgement>::with_peers(syncer_map),
+            sync_recv: rx_net_from_client,
+            cancel_handlers: Vec::new(),
+            start_time: 0,
+        }
+    }
+
+    pub async fn send_state(&mut self, state: SyncState, value: Vec<u8>){
+        let cancel_handler = self.sync_send.send(0, SyncMsg {
+            sender: self.myid,
+            state: state,
+            value: value,
+        }).await;
+        self.cancel_handlers.push(cancel_handler);
+    }
+
+    pub async fn heartbeat(&mut self){
+        self.send_state(SyncState::ALIVE, HEARTBEAT.to_vec()).await;
+    }
+
+    pub async fn completed(&mut self, msg: String){
+        let completion_report = CompletionReport::new(1, msg, self.start_time);
+        let ser_msg = bincode::serialize(&completion_report).unwrap();
+        self.send_state(SyncState::COMPLETED, ser_msg).await;
+    }
+}
+
+/// Runs a workload until the syncer sends STOP or the exit signal fires. Exit handlers of the protocol service are kept alive until then.
+pub async fn run_workload<W: Workload>(
+    mut sync: SyncClient,
+    mut exit_rx: oneshot::Receiver<()>,
+    mut service_exit_txs: Vec<oneshot::Sender<()>>,
+    mut workload: W,
+    mut outputs: Receiver<W::Output>
+) -> Result<()>{
+    sync.send_state(SyncState::ALIVE, Vec::new()).await;
+    let mut started = false;
+    let mut reported = false;
+    let mut output_ids: Vec<Vec<u8>> = Vec::new();
+    let mut heartbeat = time::interval(Duration::from_millis(HEARTBEAT_INTERVAL));
+    loop {
+        tokio::select! {
+            // Receive exit handlers
+            exit_val = &mut exit_rx => {
+                exit_val.map_err(anyhow::Error::new)?;
+                log::info!("Termination signal received by the {} driver. Exiting.", workload.name());
+                break
+            },
+            _ = heartbeat.tick() => {
+                sync.heartbeat().await;
+            },
+            sync_msg = sync.sync_recv.recv() => {
+                let sync_msg = sync_msg.ok_or_else(||
+                    anyhow!("Networking layer has closed")
+                )?;
+                match sync_msg.state {
+                    SyncState::START => {
+                        if started{
+                            continue;
+                        }
+                        started = true;
+                        sync.start_time = SystemTime::now()
+                            .duration_since(UNIX_EPOCH)
+                            .unwrap()
+                            .as_millis();
+                        log::info!("{} start time: {:?}", workload.name(), sync.start_time);
+                        workload.start().await;
+                        sync.send_state(SyncState::STARTED, Vec::new()).await;
+                    },
+                    SyncState::STOP => {
+                        log::info!("{} stop time: {:?}", workload.name(), SystemTime::now()
+                            .duration_since(UNIX_EPOCH)
+                            .unwrap()
+                            .as_millis());
+                        log::info!("Termination signal received by the {} driver. Exiting.", workload.name());
+                        break
+                    },
+                    _ => {}
+                }
+            },
+            output = outputs.recv() => {
+                let output = output.ok_or_else(||
+                    anyhow!("Protocol output channel has closed")
+                )?;
+                if let Some(output_id) = workload.process_output(output).await{
+                    output_ids.push(output_id);
+                }
+                if !reported && output_ids.len() >= workload.expected_outputs(){
+                    reported = true;
+                    let report = workload.completion_message(&mut output_ids);
+                    log::info!("All outputs received, reporting completion: {}", report);
+                    sync.completed(report).await;
+                }
+            }
+        }
+    }
+    for service_exit_tx in service_exit_txs.drain(..){
+        let _status = service_exit_tx.send(());
+    }
+    Ok(())
+}
+
+// Outputs arrive in different orders at different nodes, so the identifiers are sorted before hashing
+fn completion_report(name: &str, output_ids: &mut Vec<Vec<u8>>) -> String{
+    output_ids.sort();
+    let digest = do_hash(output_ids.concat().as_slice());
+    let digest_hex: String = digest[0..8].iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("{} completed {} outputs with digest {}", name, output_ids.len(), digest_hex)
+}
+
+/// Exit handlers of a service that spawns sub-services, in the order they should be signalled
+pub fn collect_exit_txs(service: (oneshot::Sender<()>, Vec<Result<oneshot::Sender<()>>>)) -> Vec<oneshot::Sender<()>>{
+    let (exit_tx, statuses) = service;
+    let mut exit_txs = vec![exit_tx];
+    for status in statuses{
+        match status{
+            Ok(sub_exit_tx) => exit_txs.push(sub_exit_tx),
+            Err(e) => log::error!("Error spawning sub-service: {:?}", e)
+        }
+    }
+    exit_txs
+}
+
+/// Spawns the driver loop of a workload and returns its exit handler
+pub fn spawn_workload<W: Workload>(
+    config: &Node,
+    service_exit_txs: Vec<oneshot::Sender<()>>,
+    workload: W,
+    outputs: Receiver<W::Output>
+) -> oneshot::Sender<()>{
+    let sync = SyncClient::spawn(config);
+    let (exit_tx, exit_rx) = oneshot::channel();
+    tokio::spawn(async move {
+        let name = workload.name();
+        if let Err(e) = run_workload(sync, exit_rx, service_exit_txs, workload, outputs).await {
+            log::error!("{} driver error: {}", name, e);
+        }
+    });
+    exit_tx
+}
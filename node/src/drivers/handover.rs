@@ -0,0 +1,87 @@
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use config::Node;
+use consensus::{AcssScheme, HandoverConfig, HandoverOutput, HashKeys};
+use tokio::sync::{mpsc::channel, oneshot};
+
+use super::{Workload, collect_exit_txs, spawn_workload};
+
+/// Waits for the handover at a node that only joins with the new committee. The old committee reports through dpss.
+pub struct HandoverWorkload{
+    report: Option<String>,
+}
+
+#[async_trait]
+impl Workload for HandoverWorkload{
+    type Output = HandoverOutput;
+
+    fn name(&self) -> &'static str{
+        "handover"
+    }
+
+    fn expected_outputs(&self) -> usize{
+        1
+    }
+
+    async fn start(&mut self){}
+
+    async fn process_output(&mut self, output: Self::Output) -> Option<Vec<u8>>{
+        log::info!("Received {} shares from the handover to epoch {}", output.shares.as_ref().map(|shares| shares.len()).unwrap_or(0), output.epoch);
+        let report = output.report();
+        self.report = Some(report.clone());
+        Some(report.into_bytes())
+    }
+
+    // Same message as the dpss nodes of the old committee, so the syncer sees the values agree
+    fn completion_message(&self, _output_ids: &mut Vec<Vec<u8>>) -> String{
+        self.report.clone().unwrap_or_default()
+    }
+}
+
+/// Runs dpss in the old committee and hands its output shares over to the new committee. `config` lists the nodes of both committees.
+/// Old members block in dpss until the termination signal, like a plain dpss run.
+pub fn spawn_dpss_handover(
+    config: Node,
+    hash_keys: HashKeys,
+    committees: HandoverConfig,
+    acss_scheme: AcssScheme,
+    batches: usize,
+    per_batch: usize,
+    refresh_epochs: usize,
+    opt_or_pess: bool,
+    lin_or_quad: bool,
+    ibft: bool
+) -> Result<oneshot::Sender<()>>{
+    if !committees.is_member(config.id){
+        return Err(anyhow!("Node {} is in neither committee of the handover", config.id));
+    }
+    let (handover_out_send, handover_out_recv) = channel(10000);
+    if !committees.old.contains(config.id){
+        let service = handover::Context::spawn(config.clone(), hash_keys, committees, None, handover_out_send)?;
+        let workload = HandoverWorkload{
+            report: None,
+        };
+        return Ok(spawn_workload(&config, collect_exit_txs(service), workload, handover_out_recv));
+    }
+
+    let (shares_send, shares_recv) = channel(10000);
+    let old_config = committees.old.node_config(&config).map_err(|e| anyhow!(e))?;
+    let service = handover::Context::spawn(config, hash_keys.clone(), committees, Some(shares_recv), handover_out_send)?;
+    let handover_exit_txs = collect_exit_txs(service);
+    let exit_tx = dpss::Context::spawn(old_config,
+        hash_keys,
+        acss_scheme,
+        batches,
+        per_batch,
+        refresh_epochs,
+        Some((shares_send, handover_out_recv)),
+        opt_or_pess,
+        lin_or_quad,
+        ibft,
+        false
+    );
+    for handover_exit_tx in handover_exit_txs{
+        let _status = handover_exit_tx.send(());
+    }
+    exit_tx
+}
@@ -0,0 +1,32 @@
+// Workload drivers for benchmarking the protocol crates on their own.
+// Every driver spawns the protocol service on the node's ports, waits for the syncer's START, issues its workload,
+// and reports COMPLETED once all expected outputs arrived, the same way the DPSS context does.
+pub mod driver;
+pub use driver::*;
+
+mod coin;
+pub use coin::*;
+
+pub mod acss;
+pub use acss::*;
+
+pub mod avid;
+pub use avid::*;
+
+pub mod asks;
+pub use asks::*;
+
+pub mod ra;
+pub use ra::*;
+
+pub mod bba;
+pub use bba::*;
+
+pub mod agreement;
+pub use agreement::*;
+
+pub mod atomic_bc;
+pub use self::atomic_bc::*;
+
+pub mod handover;
+pub use handover::*;
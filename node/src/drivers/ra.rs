@@ -0,0 +1,59 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use config::Node;
+use consensus::HashKeys;
+use tokio::sync::{mpsc::{channel, Sender}, oneshot};
+use types::Replica;
+
+use super::{Workload, spawn_workload};
+
+/// Runs `batches` Reliable Agreement instances. RA only terminates when the honest inputs agree, so every node inputs the parity of the instance.
+pub struct RaWorkload{
+    batches: usize,
+    ra_req: Sender<(usize, usize, usize)>,
+}
+
+#[async_trait]
+impl Workload for RaWorkload{
+    type Output = (usize, Replica, usize);
+
+    fn name(&self) -> &'static str{
+        "ra"
+    }
+
+    fn expected_outputs(&self) -> usize{
+        self.batches
+    }
+
+    async fn start(&mut self){
+        for instance in 1..self.batches+1{
+            let _status = self.ra_req.send((0, instance%2, instance)).await;
+        }
+    }
+
+    async fn process_output(&mut self, output: Self::Output) -> Option<Vec<u8>>{
+        let (_rep, instance, value) = output;
+        let mut id = Vec::new();
+        id.extend((instance as u64).to_be_bytes());
+        id.extend((value as u64).to_be_bytes());
+        Some(id)
+    }
+}
+
+pub fn spawn_ra(config: Node, hash_keys: HashKeys, batches: usize) -> Result<oneshot::Sender<()>>{
+    let (ra_req_send, ra_req_recv) = channel(10000);
+    let (ra_out_send, ra_out_recv) = channel(10000);
+
+    let service_exit_tx = ra::Context::spawn(
+        config.clone(),
+        hash_keys,
+        ra_req_recv,
+        ra_out_send,
+        false
+    )?;
+    let workload = RaWorkload{
+        batches: batches,
+        ra_req: ra_req_send,
+    };
+    Ok(spawn_workload(&config, vec![service_exit_tx], workload, ra_out_recv))
+}
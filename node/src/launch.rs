@@ -0,0 +1,121 @@
+use anyhow::{anyhow, Result};
+use config::Node;
+use consensus::{AcssScheme, HashKeys, Weights};
+use tokio::sync::oneshot;
+
+use crate::{ExperimentParams, spawn_acss_ske, spawn_g_acss, spawn_hacss, spawn_acss_bv, spawn_avid, spawn_asks, spawn_ra, spawn_binary_ba, spawn_fin_mvba, spawn_acs, spawn_ibft, spawn_atomic_bc, spawn_dpss_handover};
+
+/// Protocols a node can run, by the name used on the command line and in experiment plans
+pub const PROTOCOLS: [&str; 14] = [
+    "dpss", "g_dpss", "acss_ske", "g_acss", "hacss", "acss_bv", "avid", "asks", "ra", "binary_ba", "fin_mvba", "acs", "ibft", "atomic_bc"
+];
+
+/// Spawns the protocol named in the parameters on the ports of the given config. Under weights, the node runs one replica per unit of its weight.
+pub fn spawn_protocol(config: Node, hash_keys: HashKeys, params: &ExperimentParams) -> Result<oneshot::Sender<()>>{
+    match consensus::weights() {
+        Some(weights) => spawn_weighted(weights, config, hash_keys, params),
+        None => spawn_replica(config, hash_keys, params),
+    }
+}
+
+/// Spawns the virtual replicas of the node, each a party of the deployment with the total weight as n.
+/// Replicas start on blocking threads because some protocols, like dpss, only return on the termination signal.
+fn spawn_weighted(weights: &Weights, config: Node, hash_keys: HashKeys, params: &ExperimentParams) -> Result<oneshot::Sender<()>>{
+    let mut replicas = Vec::new();
+    for virtual_replica in weights.virtual_replicas(config.id){
+        let virtual_config = weights.virtual_config(&config, virtual_replica).map_err(|e| anyhow!(e))?;
+        let hash_keys = hash_keys.clone();
+        let params = params.clone();
+        replicas.push((virtual_replica, tokio::task::spawn_blocking(move || spawn_replica(virtual_config, hash_keys, &params))));
+    }
+    log::info!("Node {} runs virtual replicas {:?}", config.id, weights.virtual_replicas(config.id));
+
+    let (exit_tx, exit_rx) = oneshot::channel();
+    tokio::spawn(async move {
+        let mut replica_exit_txs = Vec::new();
+        for (virtual_replica, replica) in replicas{
+            match replica.await {
+                Ok(Ok(replica_exit_tx)) => replica_exit_txs.push(replica_exit_tx),
+                Ok(Err(e)) => log::error!("Virtual replica {} failed: {}", virtual_replica, e),
+                Err(e) => log::error!("Virtual replica {} panicked: {}", virtual_replica, e),
+            }
+        }
+        let _status = exit_rx.await;
+        for replica_exit_tx in replica_exit_txs{
+            let _status = replica_exit_tx.send(());
+        }
+    });
+    Ok(exit_tx)
+}
+
+// Spawns the protocol as one party of the deployment of `config`
+fn spawn_replica(config: Node, hash_keys: HashKeys, params: &ExperimentParams) -> Result<oneshot::Sender<()>>{
+    let batches = params.batches;
+    let per_batch = params.per_batch;
+    match params.protocol.as_str() {
+        "dpss" => {
+            // Under a handover, the old committee runs dpss and the new committee waits for its shares
+            if let Some(committees) = consensus::handover_config(){
+                return spawn_dpss_handover(config,
+                    hash_keys,
+                    committees.clone(),
+                    acss_scheme(params, AcssScheme::Ske)?,
+                    batches,
+                    per_batch,
+                    params.refresh,
+                    params.opt_or_pess,
+                    params.lin_or_quad,
+                    params.ibft
+                );
+            }
+            dpss::Context::spawn(config,
+                hash_keys,
+                acss_scheme(params, AcssScheme::Ske)?,
+                batches,
+                per_batch,
+                params.refresh,
+                None,
+                params.opt_or_pess,
+                params.lin_or_quad,
+                params.ibft,
+                false
+            )
+        },
+        "g_dpss" => {
+            g_dpss::Context::spawn(config,
+                hash_keys,
+                acss_scheme(params, AcssScheme::GAcss)?,
+                batches,
+                per_batch,
+                params.opt_or_pess,
+                params.lin_or_quad,
+                params.ibft,
+                false
+            )
+        },
+        "acss_ske" => spawn_acss_ske(config, hash_keys, batches, per_batch, params.lin_or_quad),
+        "g_acss" => spawn_g_acss(config, hash_keys, batches, per_batch, params.lin_or_quad),
+        "hacss" => spawn_hacss(config, hash_keys, batches, per_batch),
+        "acss_bv" => spawn_acss_bv(config, hash_keys, batches, per_batch),
+        // Blob size in bytes
+        "avid" => spawn_avid(config, hash_keys, batches, per_batch),
+        "asks" => spawn_asks(config, hash_keys, batches, per_batch),
+        "ra" => spawn_ra(config, hash_keys, batches),
+        "binary_ba" => spawn_binary_ba(config, hash_keys, batches),
+        "fin_mvba" => spawn_fin_mvba(config, hash_keys, batches),
+        // Proposal size in bytes
+        "acs" => spawn_acs(config, hash_keys, batches, per_batch),
+        "ibft" => spawn_ibft(config, batches),
+        // Epochs to wait for, transactions per node and epoch
+        "atomic_bc" => spawn_atomic_bc(config, hash_keys, batches, per_batch),
+        _ => Err(anyhow!("Unknown protocol {}", params.protocol))
+    }
+}
+
+// ACSS scheme of the run, or the protocol's own scheme if the run does not name one
+fn acss_scheme(params: &ExperimentParams, default: AcssScheme) -> Result<AcssScheme>{
+    match &params.acss {
+        Some(acss) => acss.parse::<AcssScheme>().map_err(|e| anyhow!(e)),
+        None => Ok(default),
+    }
+}
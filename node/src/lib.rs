@@ -2,4 +2,16 @@ pub mod syncer;
 pub use syncer::*;
 
 pub mod sync_handler;
-pub use sync_handler::*;
\ No newline at end of file
+pub use sync_handler::*;
+
+pub mod report;
+pub use report::*;
+
+pub mod drivers;
+pub use drivers::*;
+
+pub mod launch;
+pub use launch::*;
+
+pub mod sweep;
+pub use sweep::*;
\ No newline at end of file
@@ -2,7 +2,10 @@ use anyhow::{anyhow, Result};
 use clap::{load_yaml, App};
 use config::Node;
 use fnv::FnvHashMap;
-use node::Syncer;
+use consensus::{AcssScheme, HashKeys, HandoverConfig, TransportConfig, Weights};
+use node::{ExperimentParams, ExperimentPlan, PROTOCOLS, Syncer, SweepRunner, check_run_windows, spawn_protocol};
+use tpke::ThresholdKeyFile;
+use tsig::TsigKeyFile;
 use signal_hook::{
     consts::{SIGINT, SIGTERM},
     iterator::Signals,
@@ -45,6 +48,15 @@ async fn main() -> Result<()> {
         .value_of("ibft")
         .expect("Unable to parse per ibft")
         .parse::<bool>().unwrap();
+    // ACSS scheme under dpss and g_dpss
+    let acss = m.value_of("acss").map(|acss| {
+        acss.parse::<AcssScheme>().expect("Unable to parse ACSS scheme");
+        acss.to_string()
+    });
+    // Proactive refresh epochs under dpss
+    let refresh = m.value_of("refresh")
+        .map(|refresh| refresh.parse::<usize>().expect("Unable to parse refresh epochs"))
+        .unwrap_or(0);
 
     // let broadcast_msgs_file = m
     //     .value_of("bfile")
@@ -84,31 +96,81 @@ async fn main() -> Result<()> {
         config.update_config(util::io::file_to_ips(f.to_string()));
     }
     let config = config;
-    // Start the Reliable Broadcast protocol
+    // Commitment hash keys of the deployment, generated by genconfig next to the node configs
+    let hash_keys = match m.value_of("hashkeys") {
+        Some(path) => HashKeys::from_file(path).map_err(|err| anyhow!("Invalid hash keys: {}", err))?,
+        None if m.is_present("legacyhashkeys") => {
+            log::warn!("Using the built-in hash keys shared by all deployments");
+            HashKeys::legacy()
+        }
+        None => return Err(anyhow!("No hash keys given, pass the hash_keys.json written by genconfig with --hashkeys, or --legacyhashkeys for configs generated without keys")),
+    };
+    // Opt-in encrypted links between replicas, set up before any protocol opens its network
+    if let Some(path) = m.value_of("transport") {
+        let transport = TransportConfig::from_file(path).map_err(|err| anyhow!("Invalid transport file {}: {}", path, err))?;
+        if transport.id != config.id {
+            return Err(anyhow!("Transport file of node {} given to node {}", transport.id, config.id));
+        }
+        // Sessions are kept next to the transport file, one file per node
+        let session_file = std::path::Path::new(path).with_file_name(format!("sessions-{}.json", transport.id));
+        consensus::enable_secure_transport(&transport, &session_file.to_string_lossy())
+            .map_err(|err| anyhow!("Unable to enable the secure transport: {}", err))?;
+    }
+    // Shares written to disk for audits and recovery
+    if let Some(dir) = m.value_of("export") {
+        consensus::enable_share_export(dir).expect("Unable to enable share export");
+    }
+    // Committee handover after dpss, every node of both committees loads the same file
+    if let Some(path) = m.value_of("handover") {
+        let handover = HandoverConfig::from_file(path).map_err(|err| anyhow!("Invalid handover file {}: {}", path, err))?;
+        handover.validate_for(&config).map_err(|err| anyhow!("Handover does not match the config: {}", err))?;
+        consensus::enable_handover(handover).map_err(|err| anyhow!("Unable to enable the handover: {}", err))?;
+    }
+    // Stake weights, the syncer then expects one report per virtual replica
+    if let Some(path) = m.value_of("weights") {
+        let weights = Weights::from_file(path).map_err(|err| anyhow!("Invalid weights file {}: {}", path, err))?;
+        weights.validate_for(&config).map_err(|err| anyhow!("Weights do not match the config: {}", err))?;
+        if vss_type == "sweep" {
+            return Err(anyhow!("Sweeps do not support weights"));
+        }
+        // Rejects the encrypted transport and the handover enabled above
+        consensus::enable_weights(weights).map_err(|err| anyhow!("Unable to enable the weights: {}", err))?;
+    }
+    // Threshold coin for binary_ba from the key the ADKG wrote
+    if let Some(path) = m.value_of("coinkey") {
+        let coin_key = TsigKeyFile::read_from_file(path)
+            .and_then(|key_file| key_file.to_key())
+            .map_err(|err| anyhow!("Invalid coin key file {}: {}", path, err))?;
+        if coin_key.myid != config.id || coin_key.public_key_shares.len() != config.num_nodes {
+            return Err(anyhow!("Coin key file {} does not belong to node {} of this config", path, config.id));
+        }
+        let coin_session = m.value_of("coinsession")
+            .ok_or_else(|| anyhow!("A coin key needs a coin session shared by all nodes of the run"))?;
+        node::enable_coin_key(coin_key, coin_session)?;
+    }
+    // Threshold decryption key, written by dpss and read by atomic_bc
+    if let Some(path) = m.value_of("tpkekey") {
+        if vss_type == "sweep" || m.is_present("weights") || m.is_present("handover") {
+            return Err(anyhow!("Threshold keys are not supported with sweeps, weights or the handover"));
+        }
+        if vss_type == "atomic_bc" {
+            let threshold_key = ThresholdKeyFile::read_from_file(path)
+                .and_then(|key_file| key_file.to_key())
+                .map_err(|err| anyhow!("Invalid threshold key file {}: {}", path, err))?;
+            if threshold_key.myid != config.id || threshold_key.public_key_shares.len() != config.num_nodes {
+                return Err(anyhow!("Threshold key file {} does not belong to node {} of this config", path, config.id));
+            }
+            node::enable_threshold_key(threshold_key)?;
+        }
+        else if vss_type == "dpss" {
+            tpke::enable_threshold_key_export(path).map_err(|err| anyhow!("Unable to enable the threshold key export: {}", err))?;
+        }
+        else {
+            return Err(anyhow!("Threshold keys are written by dpss and used by atomic_bc, not by {}", vss_type));
+        }
+    }
     let exit_tx;
     match vss_type {
-        "dpss" => {
-            exit_tx = 
-                dpss::Context::spawn(config, 
-                    batches, 
-                    per_batch, 
-                    opt_pess,
-                    lin_quad,
-                    ibft,
-                    false
-                ).unwrap();
-        },
-        "g_dpss" => {
-            exit_tx = 
-                g_dpss::Context::spawn(config, 
-                    batches, 
-                    per_batch, 
-                    opt_pess,
-                    lin_quad,
-                    ibft,
-                    false
-                ).unwrap();
-        },
         "sync" => {
             let f_str = syncer_file.to_string();
             log::info!("Logging the file f {}", f_str);
@@ -121,14 +183,63 @@ async fn main() -> Result<()> {
             }
             //let client_addr = net_map.get(&(net_map.len()-1)).unwrap();
             //exit_tx = Syncer::spawn(net_map, config.client_addr.clone(),broadcast_msgs_file.to_string()).unwrap();
-            exit_tx = Syncer::spawn(net_map, config.client_addr.clone()).unwrap();
+            let params = ExperimentParams {
+                protocol: m.value_of("bench").unwrap_or("dpss").to_string(),
+                num_nodes: net_map.len(),
+                num_faults: consensus::weights().map(|weights| weights.num_faults).unwrap_or(config.num_faults),
+                batches: batches,
+                per_batch: per_batch,
+                lin_or_quad: lin_quad,
+                opt_or_pess: opt_pess,
+                ibft: ibft,
+                acss: acss.clone(),
+                refresh: refresh,
+            };
+            let report_prefix = m.value_of("report").unwrap_or("syncer-report").to_string();
+            // Experiment plan for sweeps over long-lived nodes started with the sweep protocol
+            let plan = m.value_of("plan").map(|plan_file| {
+                ExperimentPlan::read_from_file(plan_file)
+                    .expect("Unable to read experiment plan")
+                    .expand(net_map.len(), params.num_faults)
+            });
+            if let Some(runs) = &plan {
+                // The syncer's config lists the consensus addresses of every node
+                check_run_windows(&config, runs.len())
+                    .map_err(|err| anyhow!("Experiment plan does not fit the nodes' ports: {}", err))?;
+            }
+            let deadline = m.value_of("deadline")
+                .map(|deadline| deadline.parse::<u128>().expect("Unable to parse deadline"))
+                .filter(|deadline| *deadline > 0);
+            let start_quorum = m.value_of("quorum")
+                .map(|quorum| quorum.parse::<bool>().expect("Unable to parse quorum"))
+                .unwrap_or(false);
+            exit_tx = Syncer::spawn(net_map, config.client_addr.clone(), params, report_prefix, plan, deadline, start_quorum).unwrap();
+        }
+        "sweep" => {
+            // Long-lived node that runs the experiment plan of the syncer
+            exit_tx = SweepRunner::spawn(config, hash_keys).unwrap();
         }
         _ => {
-            log::error!(
-                "Matching VSS not provided {}, canceling execution",
-                vss_type
-            );
-            return Ok(());
+            if !PROTOCOLS.contains(&vss_type){
+                log::error!(
+                    "Matching VSS not provided {}, canceling execution",
+                    vss_type
+                );
+                return Ok(());
+            }
+            let params = ExperimentParams {
+                protocol: vss_type.to_string(),
+                num_nodes: config.num_nodes,
+                num_faults: config.num_faults,
+                batches: batches,
+                per_batch: per_batch,
+                lin_or_quad: lin_quad,
+                opt_or_pess: opt_pess,
+                ibft: ibft,
+                acss: acss.clone(),
+                refresh: refresh,
+            };
+            exit_tx = spawn_protocol(config, hash_keys, &params).unwrap();
         }
     }
     //let exit_tx = pedavss_cc::node::Context::spawn(config).unwrap();
@@ -0,0 +1,180 @@
+use std::{fs::File, io::{BufWriter, Write}};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use types::Replica;
+
+/// Parameters of a run, as passed to the nodes on the command line
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExperimentParams{
+    pub protocol: String,
+    pub num_nodes: usize,
+    pub num_faults: usize,
+    pub batches: usize,
+    pub per_batch: usize,
+    pub lin_or_quad: bool,
+    pub opt_or_pess: bool,
+    pub ibft: bool,
+    /// ACSS scheme under dpss and g_dpss. Without it, each uses its own scheme.
+    #[serde(default)]
+    pub acss: Option<String>,
+    /// Epochs dpss refreshes its output shares for before reporting
+    #[serde(default)]
+    pub refresh: usize,
+}
+
+/// Result of one node. Timestamps are milliseconds since the UNIX epoch.
+/// `latency` is measured by the syncer from its START to the arrival of the node's COMPLETED message.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NodeResult{
+    pub node: Replica,
+    pub start_time: u128,
+    pub completion_time: u128,
+    pub latency: u128,
+    pub messages_sent: u64,
+    pub bytes_sent: u64,
+    pub value: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct LatencyStats{
+    pub min: u128,
+    pub median: u128,
+    pub p90: u128,
+    pub max: u128,
+}
+
+impl LatencyStats{
+    pub fn from_latencies(latencies: &Vec<u128>) -> LatencyStats{
+        let mut sorted = latencies.clone();
+        sorted.sort();
+        if sorted.is_empty(){
+            return LatencyStats { min: 0, median: 0, p90: 0, max: 0 };
+        }
+        LatencyStats {
+            min: sorted[0],
+            median: percentile(&sorted, 50),
+            p90: percentile(&sorted, 90),
+            max: sorted[sorted.len()-1],
+        }
+    }
+}
+
+// Nearest-rank percentile of a sorted, non-empty vector
+fn percentile(sorted: &Vec<u128>, pct: usize) -> u128{
+    let rank = (pct*sorted.len() + 99)/100;
+    sorted[std::cmp::max(rank, 1) - 1]
+}
+
+const CSV_HEADER: &str = "run_id,protocol,n,t,batches,per_batch,lin,opt,ibft,acss,refresh,node,start_time,completion_time,latency,messages_sent,bytes_sent,median_latency,p90_latency,max_latency,values_agree,timed_out";
+
+/// Machine readable report of a run, written by the syncer once all nodes completed
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RunReport{
+    pub id: usize,
+    pub params: ExperimentParams,
+    pub start_time: u128,
+    pub latency: LatencyStats,
+    /// True if all nodes reported the same output value
+    pub values_agree: bool,
+    pub total_messages_sent: u64,
+    pub total_bytes_sent: u64,
+    pub nodes: Vec<NodeResult>,
+    /// True if the syncer stopped the run at its deadline
+    pub timed_out: bool,
+    pub never_alive: Vec<Replica>,
+    pub never_completed: Vec<Replica>,
+    /// Nodes whose last message to the syncer is older than the heartbeat timeout
+    pub unresponsive: Vec<Replica>,
+}
+
+impl RunReport{
+    pub fn new(id: usize, params: ExperimentParams, start_time: u128, mut nodes: Vec<NodeResult>) -> RunReport{
+        nodes.sort_by_key(|node| node.node);
+        let latencies: Vec<u128> = nodes.iter().map(|node| node.latency).collect();
+        let values_agree = nodes.windows(2).all(|pair| pair[0].value == pair[1].value);
+        RunReport {
+            id: id,
+            params: params,
+            start_time: start_time,
+            latency: LatencyStats::from_latencies(&latencies),
+            values_agree: values_agree,
+            total_messages_sent: nodes.iter().map(|node| node.messages_sent).sum(),
+            total_bytes_sent: nodes.iter().map(|node| node.bytes_sent).sum(),
+            nodes: nodes,
+            timed_out: false,
+            never_alive: Vec::new(),
+            never_completed: Vec::new(),
+            unresponsive: Vec::new(),
+        }
+    }
+
+    pub fn write_json(&self, path: &str) -> Result<()>{
+        let file = File::create(path)?;
+        serde_json::to_writer_pretty(BufWriter::new(file), self)?;
+        Ok(())
+    }
+
+    /// One row per node, with the run parameters and summary statistics repeated on every row
+    pub fn write_csv(&self, path: &str) -> Result<()>{
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "{}", CSV_HEADER)?;
+        self.write_csv_rows(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    pub fn write_csv_rows<W: Write>(&self, writer: &mut W) -> Result<()>{
+        for node in self.nodes.iter(){
+            writeln!(writer, "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}",
+                self.id,
+                self.params.protocol,
+                self.params.num_nodes,
+                self.params.num_faults,
+                self.params.batches,
+                self.params.per_batch,
+                self.params.lin_or_quad,
+                self.params.opt_or_pess,
+                self.params.ibft,
+                self.params.acss.as_deref().unwrap_or(""),
+                self.params.refresh,
+                node.node,
+                node.start_time,
+                node.completion_time,
+                node.latency,
+                node.messages_sent,
+                node.bytes_sent,
+                self.latency.median,
+                self.latency.p90,
+                self.latency.max,
+                self.values_agree,
+                self.timed_out
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Consolidated report of all runs of an experiment plan
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SweepReport{
+    pub runs: Vec<RunReport>,
+}
+
+impl SweepReport{
+    pub fn write_json(&self, path: &str) -> Result<()>{
+        let file = File::create(path)?;
+        serde_json::to_writer_pretty(BufWriter::new(file), self)?;
+        Ok(())
+    }
+
+    pub fn write_csv(&self, path: &str) -> Result<()>{
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "{}", CSV_HEADER)?;
+        for run in self.runs.iter(){
+            run.write_csv_rows(&mut writer)?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+}
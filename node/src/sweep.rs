@@ -0,0 +1,309 @@
+use std::{fs::File, io::BufReader, net::SocketAddr, time::Duration};
+
+use anyhow::{anyhow, Result};
+use config::Node;
+use fnv::FnvHashMap;
+use network::{plaintcp::{CancelHandler, TcpReliableSender}, Acknowledgement};
+use serde::{Deserialize, Serialize};
+use tokio::{sync::oneshot, time};
+use types::{Replica, SyncMsg, SyncState};
+
+use consensus::{AcssScheme, CompletionReport, HashKeys, HEARTBEAT, HEARTBEAT_INTERVAL};
+
+use crate::{ExperimentParams, PROTOCOLS, SyncClient, spawn_protocol};
+
+// Listeners of a finished run cannot be closed, so every run gets its own window of ports.
+// A window must hold the highest sub-protocol offset, the ACS of the dpss handover at 3300 with its own offsets up to 450,
+// plus n, which must stay below MAX_SWEEP_NODES. `check_run_windows` rejects sweeps that do not fit before the first run.
+pub const RUN_PORT_STRIDE: u16 = 4000;
+// Largest n the sub-protocol offsets of a window leave room for, exclusive
+pub const MAX_SWEEP_NODES: usize = 100;
+// Port of a run's protocol for syncer messages, relative to the node's shifted consensus port
+pub const RUN_CLIENT_PORT_OFFSET: u16 = 2900;
+
+fn default_repetitions() -> usize{
+    1
+}
+
+/// One configuration of an experiment plan
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PlanEntry{
+    pub protocol: String,
+    pub batches: usize,
+    pub per_batch: usize,
+    #[serde(default)]
+    pub lin: bool,
+    #[serde(default)]
+    pub opt: bool,
+    #[serde(default)]
+    pub ibft: bool,
+    #[serde(default)]
+    pub acss: Option<String>,
+    #[serde(default)]
+    pub refresh: usize,
+    #[serde(default = "default_repetitions")]
+    pub repetitions: usize,
+}
+
+/// Configurations the syncer runs back to back on the same set of long-lived nodes
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ExperimentPlan{
+    pub runs: Vec<PlanEntry>,
+}
+
+impl ExperimentPlan{
+    pub fn read_from_file(path: &str) -> Result<ExperimentPlan>{
+        let file = File::open(path)?;
+        let plan: ExperimentPlan = serde_json::from_reader(BufReader::new(file))?;
+        for entry in plan.runs.iter(){
+            if !PROTOCOLS.contains(&entry.protocol.as_str()){
+                return Err(anyhow!("Unknown protocol {} in experiment plan {}", entry.protocol, path));
+            }
+            if let Some(acss) = &entry.acss{
+                acss.parse::<AcssScheme>().map_err(|e| anyhow!("{} in experiment plan {}", e, path))?;
+            }
+        }
+        Ok(plan)
+    }
+
+    /// Parameters of every run in the plan, with repetitions unrolled
+    pub fn expand(&self, num_nodes: usize, num_faults: usize) -> Vec<ExperimentParams>{
+        let mut runs = Vec::new();
+        for entry in self.runs.iter(){
+            for _ in 0..entry.repetitions{
+                runs.push(ExperimentParams {
+                    protocol: entry.protocol.clone(),
+                    num_nodes: num_nodes,
+                    num_faults: num_faults,
+                    batches: entry.batches,
+                    per_batch: entry.per_batch,
+                    lin_or_quad: entry.lin,
+                    opt_or_pess: entry.opt,
+                    ibft: entry.ibft,
+                    acss: entry.acss.clone(),
+                    refresh: entry.refresh,
+                });
+            }
+        }
+        runs
+    }
+}
+
+/// Syncer commands to a runner. `SyncState` cannot carry new states, so commands travel as the value of START messages.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum SweepCommand{
+    /// Tear down the previous run and spawn the protocol of run `run`
+    Setup{run: usize, params: ExperimentParams},
+    /// Forward the value to the protocol of the current run
+    Relay(Vec<u8>),
+}
+
+/// Runner messages to the syncer. Run 0 announces that the runner is ready for the next Setup.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RunnerMsg{
+    pub run: usize,
+    pub value: Vec<u8>,
+}
+
+// Adds `shift` to a port, leaving room for the sub-protocol offsets above it
+fn shift_port(port: u16, shift: usize) -> Result<u16>{
+    let shifted = port as usize + shift;
+    if shifted + RUN_PORT_STRIDE as usize > u16::MAX as usize{
+        return Err(anyhow!("Port {} does not fit a run window at shift {}", port, shift));
+    }
+    Ok(shifted as u16)
+}
+
+/// Checks that `num_runs` runs of the nodes in `config` fit their port windows.
+/// Windows are never reused, so a sweep with more runs than windows above the nodes' ports would fail part way.
+pub fn check_run_windows(config: &Node, num_runs: usize) -> Result<()>{
+    if config.net_map.len() >= MAX_SWEEP_NODES{
+        return Err(anyhow!("Sweeps support fewer than {} nodes, not {}", MAX_SWEEP_NODES, config.net_map.len()));
+    }
+    let last_shift = num_runs.saturating_sub(1) * RUN_PORT_STRIDE as usize;
+    for address in config.net_map.values(){
+        let address: SocketAddr = address.parse()?;
+        shift_port(address.port(), last_shift)
+            .map_err(|_| anyhow!("{} runs do not fit the port windows above port {}, each run takes {} ports", num_runs, address.port(), RUN_PORT_STRIDE))?;
+    }
+    Ok(())
+}
+
+/// Config of run `run`: all consensus ports moved to the run's window, and the syncer port pointed at the runner
+pub fn run_config(config: &Node, run: usize) -> Result<Node>{
+    let shift = (run-1) * RUN_PORT_STRIDE as usize;
+    let mut run_config = config.clone();
+    for (replica, address) in config.net_map.iter(){
+        let address: SocketAddr = address.parse()?;
+        let run_address = SocketAddr::new(address.ip(), shift_port(address.port(), shift)?);
+        run_config.net_map.insert(*replica, run_address.to_string());
+    }
+    let my_address: SocketAddr = run_config.net_map.get(&config.id)
+        .ok_or_else(|| anyhow!("Node {} missing from its own net map", config.id))?
+        .parse()?;
+    run_config.client_port = my_address.port() + RUN_CLIENT_PORT_OFFSET;
+    run_config.client_addr = SocketAddr::new("127.0.0.1".parse().unwrap(), config.client_port);
+    Ok(run_config)
+}
+
+/// Long-lived node of a sweep. It stands in for the syncer towards the protocol of each run,
+/// and tags everything the protocol reports with the run number.
+pub struct SweepRunner{
+    config: Node,
+    hash_keys: HashKeys,
+    sync: SyncClient,
+    run: usize,
+    // Last run the syncer stopped
+    stopped_run: usize,
+    // Exit handler of the current run's protocol
+    run_exit_tx: Option<oneshot::Sender<()>>,
+    // Sender to the current run's protocol, kept until the next Setup so its cancel handlers stay valid
+    protocol_send: Option<TcpReliableSender<Replica, SyncMsg, Acknowledgement>>,
+    protocol_cancel_handlers: Vec<CancelHandler<Acknowledgement>>,
+    exit_rx: oneshot::Receiver<()>,
+}
+
+impl SweepRunner{
+    pub fn spawn(config: Node, hash_keys: HashKeys) -> Result<oneshot::Sender<()>>{
+        check_run_windows(&config, 1)?;
+        let (exit_tx, exit_rx) = oneshot::channel();
+        let sync = SyncClient::spawn(&config);
+        tokio::spawn(async move {
+            let mut runner = SweepRunner {
+                config: config,
+                hash_keys: hash_keys,
+                sync: sync,
+                run: 0,
+                stopped_run: 0,
+                run_exit_tx: None,
+                protocol_send: None,
+                protocol_cancel_handlers: Vec::new(),
+                exit_rx: exit_rx,
+            };
+            if let Err(e) = runner.run().await {
+                log::error!("Sweep runner error: {}", e);
+            }
+        });
+        Ok(exit_tx)
+    }
+
+    pub async fn run(&mut self) -> Result<()>{
+        self.send_to_syncer(SyncState::ALIVE, 0, Vec::new()).await;
+        // The runner outlives the protocols, so it sends the heartbeats itself
+        let mut heartbeat = time::interval(Duration::from_millis(HEARTBEAT_INTERVAL));
+        loop {
+            tokio::select! {
+                // Receive exit handlers
+                exit_val = &mut self.exit_rx => {
+                    exit_val.map_err(anyhow::Error::new)?;
+                    log::info!("Termination signal received by the sweep runner. Exiting.");
+                    break
+                },
+                _ = heartbeat.tick() => {
+                    self.sync.heartbeat().await;
+                },
+                sync_msg = self.sync.sync_recv.recv() => {
+                    let sync_msg = sync_msg.ok_or_else(||
+                        anyhow!("Networking layer has closed")
+                    )?;
+                    if sync_msg.sender == self.config.num_nodes{
+                        self.process_syncer_msg(sync_msg).await;
+                    }
+                    else if sync_msg.sender == self.config.id{
+                        // Messages of a protocol that was already told to exit are dropped
+                        let is_heartbeat = matches!(sync_msg.state, SyncState::ALIVE) && sync_msg.value == HEARTBEAT;
+                        if self.run_exit_tx.is_some() && !is_heartbeat{
+                            self.send_to_syncer(sync_msg.state, self.run, sync_msg.value).await;
+                        }
+                    }
+                }
+            }
+        }
+        self.stop_run();
+        Ok(())
+    }
+
+    async fn process_syncer_msg(&mut self, sync_msg: SyncMsg){
+        match sync_msg.state{
+            SyncState::START => {
+                let command: SweepCommand = match bincode::deserialize(&sync_msg.value){
+                    Ok(command) => command,
+                    Err(e) => {
+                        log::error!("Unable to deserialize sweep command: {}", e);
+                        return;
+                    }
+                };
+                match command{
+                    SweepCommand::Setup { run, params } => {
+                        if run <= self.run{
+                            return;
+                        }
+                        self.setup_run(run, params).await;
+                    },
+                    SweepCommand::Relay(value) => {
+                        if let Some(protocol_send) = self.protocol_send.as_mut(){
+                            let cancel_handler = protocol_send.send(0, SyncMsg {
+                                sender: self.config.num_nodes,
+                                state: SyncState::START,
+                                value: value,
+                            }).await;
+                            self.protocol_cancel_handlers.push(cancel_handler);
+                        }
+                    }
+                }
+            },
+            SyncState::STOP => {
+                // The exit signal ends the protocol the same way a relayed STOP would
+                if self.run > self.stopped_run{
+                    self.stopped_run = self.run;
+                    log::info!("Run {} stopped by the syncer", self.run);
+                    self.stop_run();
+                    self.send_to_syncer(SyncState::ALIVE, 0, Vec::new()).await;
+                }
+            },
+            _ => {}
+        }
+    }
+
+    async fn setup_run(&mut self, run: usize, params: ExperimentParams){
+        self.stop_run();
+        self.protocol_send = None;
+        self.protocol_cancel_handlers.clear();
+        self.run = run;
+        log::info!("Setting up run {} with parameters {:?}", run, params);
+
+        let spawned = run_config(&self.config, run).and_then(|run_config| {
+            let mut protocol_map: FnvHashMap<Replica, SocketAddr> = FnvHashMap::default();
+            protocol_map.insert(0, SocketAddr::new("127.0.0.1".parse().unwrap(), run_config.client_port));
+            let exit_tx = spawn_protocol(run_config, self.hash_keys.clone(), &params)?;
+            Ok((exit_tx, protocol_map))
+        });
+        match spawned{
+            Ok((exit_tx, protocol_map)) => {
+                self.run_exit_tx = Some(exit_tx);
+                self.protocol_send = Some(TcpReliableSender::<Replica, SyncMsg, Acknowledgement>::with_peers(protocol_map));
+            },
+            Err(e) => {
+                // Report the failure so the syncer can move on to the next run
+                log::error!("Unable to spawn run {}: {}", run, e);
+                let report = CompletionReport::new(1, format!("run {} failed: {}", run, e), 0);
+                self.send_to_syncer(SyncState::ALIVE, run, Vec::new()).await;
+                self.send_to_syncer(SyncState::COMPLETED, run, bincode::serialize(&report).unwrap()).await;
+            }
+        }
+    }
+
+    fn stop_run(&mut self){
+        if let Some(run_exit_tx) = self.run_exit_tx.take(){
+            let _status = run_exit_tx.send(());
+        }
+    }
+
+    async fn send_to_syncer(&mut self, state: SyncState, run: usize, value: Vec<u8>){
+        let runner_msg = RunnerMsg {
+            run: run,
+            value: value,
+        };
+        self.sync.send_state(state, bincode::serialize(&runner_msg).unwrap()).await;
+    }
+}
@@ -8,7 +8,12 @@ use tokio::{sync::{oneshot, mpsc::{unbounded_channel, UnboundedReceiver}}, time}
 use types::{Replica, SyncMsg, SyncState};
 //use std::fs::read_to_string;
 
-use crate::SyncHandler;
+// A node whose last message is older than this many milliseconds is reported as unresponsive
+const HEARTBEAT_TIMEOUT: u128 = 3*HEARTBEAT_INTERVAL as u128;
+
+use consensus::{CompletionReport, HEARTBEAT, HEARTBEAT_INTERVAL};
+
+use crate::{SyncHandler, ExperimentParams, NodeResult, RunReport, RunnerMsg, SweepCommand, SweepReport};
 
 pub struct Syncer{
     pub num_nodes: usize,
@@ -19,6 +24,26 @@ pub struct Syncer{
     pub rbc_start_times: HashMap<usize,u128>,
     pub rbc_complete_times: HashMap<usize,HashMap<Replica,u128>>,
     pub rbc_comp_values: HashMap<usize,HashSet<String>>,
+    pub rbc_results: HashMap<usize,HashMap<Replica,NodeResult>>,
+
+    // Parameters of the run and the prefix of the report files
+    pub params: ExperimentParams,
+    pub report_prefix: String,
+
+    // Runs of an experiment plan. The nodes are then long-lived runners that spawn the protocol of every run.
+    pub plan: Option<Vec<ExperimentParams>>,
+    pub ready_runners: HashSet<Replica>,
+    pub run_active: bool,
+    pub run_started: bool,
+    pub run_reports: Vec<RunReport>,
+
+    // Liveness tracking. A run that does not start or complete within `deadline` milliseconds is reported and stopped.
+    pub deadline: Option<u128>,
+    // Start once n-t nodes are alive instead of all n, and wait only for the started nodes to complete
+    pub start_quorum: bool,
+    pub wait_start: u128,
+    pub started_nodes: HashSet<Replica>,
+    pub last_seen: HashMap<Replica,u128>,
 
     //pub broadcast_msgs: Vec<String>,
     
@@ -42,6 +67,11 @@ impl Syncer{
     pub fn spawn(
         net_map: FnvHashMap<Replica,String>,
         cli_addr:SocketAddr,
+        params: ExperimentParams,
+        report_prefix: String,
+        plan: Option<Vec<ExperimentParams>>,
+        deadline: Option<u128>,
+        start_quorum: bool,
         //filename: String
     )-> anyhow::Result<oneshot::Sender<()>>{
         let (exit_tx, exit_rx) = oneshot::channel();
@@ -69,6 +99,25 @@ impl Syncer{
                 rbc_start_times: HashMap::default(),
                 rbc_complete_times: HashMap::default(),
                 rbc_comp_values:HashMap::default(),
+                rbc_results: HashMap::default(),
+
+                params: params,
+                report_prefix: report_prefix,
+
+                run_active: plan.is_none(),
+                plan: plan,
+                ready_runners: HashSet::default(),
+                run_started: false,
+                run_reports: Vec::new(),
+
+                deadline: deadline,
+                start_quorum: start_quorum,
+                wait_start: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap()
+                    .as_millis(),
+                started_nodes: HashSet::default(),
+                last_seen: HashMap::default(),
 
                 //broadcast_msgs: broadcast_msgs,
 
@@ -113,11 +162,35 @@ impl Syncer{
                     let msg = msg.ok_or_else(||
                         anyhow!("Networking layer has closed")
                     )?;
+                    self.last_seen.insert(msg.sender, SystemTime::now()
+                        .duration_since(UNIX_EPOCH)
+                        .unwrap()
+                        .as_millis());
                     match msg.state{
                         SyncState::ALIVE=>{
+                            if msg.value == HEARTBEAT{
+                                continue;
+                            }
                             log::debug!("Got ALIVE message from node {}",msg.sender);
+                            if self.plan.is_some(){
+                                let runner_msg = match self.runner_msg(&msg){
+                                    Some(runner_msg) => runner_msg,
+                                    None => continue
+                                };
+                                if runner_msg.run == 0{
+                                    // Runner finished its previous run
+                                    self.ready_runners.insert(msg.sender);
+                                    if self.ready_runners.len() >= self.start_threshold() && !self.run_active{
+                                        self.setup_next_run().await;
+                                    }
+                                    continue;
+                                }
+                                if runner_msg.run != self.rbc_id || !self.run_active{
+                                    continue;
+                                }
+                            }
                             self.alive.insert(msg.sender);
-                            if self.alive.len() == self.num_nodes{
+                            if self.alive.len() >= self.start_threshold(){
                                 self.ready_for_broadcast = true;
                             }
                         },
@@ -126,79 +199,303 @@ impl Syncer{
                         },
                         SyncState::COMPLETED=>{
                             log::info!("Got COMPLETED message from node {}",msg.sender);
+                            if !self.run_active{
+                                continue;
+                            }
                             
                             // deserialize message
-                            let rbc_msg: RBCSyncMsg = bincode::deserialize(&msg.value).expect("Unable to deserialize message received from node");
-                            
-                            let latency_map = self.rbc_complete_times.entry(rbc_msg.id).or_default();
-                            latency_map.insert(msg.sender, SystemTime::now()
-                            .duration_since(UNIX_EPOCH)
-                            .unwrap()
-                            .as_millis());
-                            
-                            let value_set = self.rbc_comp_values.entry(rbc_msg.id).or_default();
-                            value_set.insert(rbc_msg.msg.to_string());
-                            if latency_map.len() == self.num_nodes{
-
-                                let start_time = self.rbc_start_times.get(&rbc_msg.id).unwrap();
-                                // All nodes terminated protocol
-                                
-                                let mut vec_times = Vec::new();
-                                for (_rep,time) in latency_map.iter(){
-                                    vec_times.push(time.clone()-start_time);
-                                }
-                                
-                                vec_times.sort();
-                                
-                                if value_set.len() > 1{
-                                    log::info!("Received multiple values from nodes, broadcast failed, rerun test {:?}",value_set);
-                                }
-                                else{
-                                    log::info!("All n nodes completed the protocol for ID: {} with latency {:?} and value {:?}",rbc_msg.id,vec_times,value_set);
+                            let (id, rbc_msg) = if self.plan.is_some(){
+                                let runner_msg = match self.runner_msg(&msg){
+                                    Some(runner_msg) => runner_msg,
+                                    None => continue
+                                };
+                                if runner_msg.run != self.rbc_id{
+                                    continue;
                                 }
-                                self.broadcast(SyncMsg { sender: self.num_nodes, state: SyncState::STOP, value:"Terminate".to_string().into_bytes()}).await;
+                                let rbc_msg: CompletionReport = bincode::deserialize(&runner_msg.value).expect("Unable to deserialize message received from node");
+                                (runner_msg.run, rbc_msg)
+                            }
+                            else{
+                                let rbc_msg: CompletionReport = bincode::deserialize(&msg.value).expect("Unable to deserialize message received from node");
+                                (rbc_msg.id, rbc_msg)
+                            };
+                            if self.record_completion(id, msg.sender, rbc_msg){
+                                self.finish_run(id, false).await;
                             }
                         }
                         _=>{}
                     }
                 },
                 _ = interval.tick() => {
+                    self.check_deadline().await;
                     if self.ready_for_broadcast{
                         // Initiate new broadcast
-                        if self.rbc_id >= 1{
+                        if !self.run_active || self.run_started{
                             continue;
                         }
-                        self.rbc_id += 1;
-                        let sync_rbc_msg = RBCSyncMsg{
-                            id: self.rbc_id,
-                            //msg: self.broadcast_msgs.get(&self.rbc_id-1).unwrap().to_string(),
-                            msg: "Start".to_string()
-                        };
-                        let binaryfy_val = bincode::serialize(&sync_rbc_msg).expect("Failed to serialize client message");
-                        // let cancel_handler:CancelHandler<Acknowledgement> = self.net_send.send(0, SyncMsg { 
-                        //     sender: self.num_nodes, 
-                        //     state: SyncState::START,
-                        //     value:binaryfy_val
-                        // }).await;
-                        // self.add_cancel_handler(cancel_handler);
-                        
-                        self.broadcast(SyncMsg { 
-                            sender: self.num_nodes, 
-                            state: SyncState::START,
-                            value: binaryfy_val
-                        }).await;
-                        let start_time = SystemTime::now()
-                        .duration_since(UNIX_EPOCH)
-                        .unwrap()
-                        .as_millis();
-                        
-                        self.rbc_start_times.insert(self.rbc_id, start_time);
+                        self.run_started = true;
+                        if self.plan.is_none(){
+                            self.rbc_id += 1;
+                        }
+                        self.start_run().await;
                     }
                 }
             }
         }
         Ok(())
     }
+
+    async fn start_run(&mut self){
+        let sync_rbc_msg = RBCSyncMsg{
+            id: self.rbc_id,
+            //msg: self.broadcast_msgs.get(&self.rbc_id-1).unwrap().to_string(),
+            msg: "Start".to_string()
+        };
+        self.started_nodes = self.alive.clone();
+        if self.started_nodes.len() < self.num_nodes{
+            log::info!("Starting run {} without nodes {:?}", self.rbc_id, self.missing_nodes(&self.started_nodes));
+        }
+        let mut binaryfy_val = bincode::serialize(&sync_rbc_msg).expect("Failed to serialize client message");
+        if self.plan.is_some(){
+            // Runners forward the start message to the protocol of the run
+            binaryfy_val = bincode::serialize(&SweepCommand::Relay(binaryfy_val)).expect("Failed to serialize sweep command");
+        }
+        // let cancel_handler:CancelHandler<Acknowledgement> = self.net_send.send(0, SyncMsg { 
+        //     sender: self.num_nodes, 
+        //     state: SyncState::START,
+        //     value:binaryfy_val
+        // }).await;
+        // self.add_cancel_handler(cancel_handler);
+        
+        self.broadcast(SyncMsg { 
+            sender: self.num_nodes, 
+            state: SyncState::START,
+            value: binaryfy_val
+        }).await;
+        let start_time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis();
+        
+        self.rbc_start_times.insert(self.rbc_id, start_time);
+    }
+
+    // Records the completion of a node. Returns true once all nodes completed run `id`.
+    fn record_completion(&mut self, id: usize, sender: Replica, rbc_msg: CompletionReport) -> bool{
+        // Nodes that were not alive at START are not waited for
+        let expected: Vec<Replica> = match self.run_started{
+            true => self.started_nodes.iter().cloned().collect(),
+            false => (0..self.num_nodes).collect()
+        };
+        let complete_time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis();
+        let latency_map = self.rbc_complete_times.entry(id).or_default();
+        latency_map.insert(sender, complete_time);
+        
+        let start_time = self.rbc_start_times.get(&id).cloned().unwrap_or(complete_time);
+        self.rbc_results.entry(id).or_default().insert(sender, NodeResult {
+            node: sender,
+            start_time: rbc_msg.start_time,
+            completion_time: rbc_msg.completion_time,
+            latency: complete_time - start_time,
+            messages_sent: rbc_msg.messages_sent,
+            bytes_sent: rbc_msg.bytes_sent,
+            value: rbc_msg.msg.clone(),
+        });
+
+        let value_set = self.rbc_comp_values.entry(id).or_default();
+        value_set.insert(rbc_msg.msg.to_string());
+        if !expected.iter().all(|rep| latency_map.contains_key(rep)){
+            return false;
+        }
+        // All nodes terminated protocol
+        let mut vec_times = Vec::new();
+        for (_rep,time) in latency_map.iter(){
+            vec_times.push(time.clone()-start_time);
+        }
+        
+        vec_times.sort();
+        
+        if value_set.len() > 1{
+            log::info!("Received multiple values from nodes, broadcast failed, rerun test {:?}",value_set);
+        }
+        else{
+            log::info!("All started nodes completed the protocol for ID: {} with latency {:?} and value {:?}",id,vec_times,value_set);
+        }
+        true
+    }
+
+    // Writes the report of run `id` and stops the nodes
+    async fn finish_run(&mut self, id: usize, timed_out: bool){
+        self.write_report(id, timed_out);
+        self.run_active = false;
+        self.wait_start = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis();
+        self.broadcast(SyncMsg { sender: self.num_nodes, state: SyncState::STOP, value:"Terminate".to_string().into_bytes()}).await;
+    }
+
+    // Stops the current run once it has waited longer than the deadline for nodes to become alive or to complete
+    async fn check_deadline(&mut self){
+        let deadline = match self.deadline{
+            Some(deadline) => deadline,
+            None => return
+        };
+        let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis();
+        if !self.run_active{
+            // Between runs of a plan, move on without the runners that did not report ready in time
+            if self.plan.is_some() && !self.ready_runners.is_empty() && now - self.wait_start >= deadline{
+                log::error!("Runners {:?} not ready within {} ms, setting up the next run without them", self.missing_nodes(&self.ready_runners), deadline);
+                self.setup_next_run().await;
+            }
+            return;
+        }
+        let waiting_since = match self.run_started{
+            true => self.rbc_start_times.get(&self.rbc_id).cloned().unwrap_or(now),
+            false => self.wait_start
+        };
+        if now - waiting_since < deadline{
+            return;
+        }
+        if self.run_started{
+            log::error!("Run {} did not complete within {} ms", self.rbc_id, deadline);
+        }
+        else{
+            log::error!("Nodes did not become alive within {} ms, stopping without a start", deadline);
+            if self.plan.is_none(){
+                self.rbc_id += 1;
+            }
+        }
+        self.finish_run(self.rbc_id, true).await;
+    }
+
+    fn start_threshold(&self) -> usize{
+        match self.start_quorum{
+            true => self.num_nodes - self.params.num_faults,
+            false => self.num_nodes
+        }
+    }
+
+    // Nodes that are not in the given set, in ascending order
+    fn missing_nodes(&self, nodes: &HashSet<Replica>) -> Vec<Replica>{
+        (0..self.num_nodes).filter(|rep| !nodes.contains(rep)).collect()
+    }
+
+    // Nodes without a message in the last HEARTBEAT_TIMEOUT milliseconds
+    fn unresponsive_nodes(&self) -> Vec<Replica>{
+        let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis();
+        (0..self.num_nodes).filter(|rep| match self.last_seen.get(rep){
+            Some(time) => now - time > HEARTBEAT_TIMEOUT,
+            None => true
+        }).collect()
+    }
+
+    // Tells the runners to set up the next run of the plan, or writes the consolidated report after the last run
+    async fn setup_next_run(&mut self){
+        self.ready_runners.clear();
+        let plan = self.plan.as_ref().unwrap();
+        if self.rbc_id >= plan.len(){
+            if !self.run_reports.is_empty() || plan.is_empty(){
+                self.write_sweep_report();
+            }
+            return;
+        }
+        let params = plan[self.rbc_id].clone();
+        self.rbc_id += 1;
+        log::info!("Setting up run {} of {}: {:?}", self.rbc_id, plan.len(), params);
+        self.params = params.clone();
+        self.alive.clear();
+        self.started_nodes.clear();
+        self.ready_for_broadcast = false;
+        self.run_active = true;
+        self.run_started = false;
+        self.wait_start = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis();
+
+        let command = SweepCommand::Setup { run: self.rbc_id, params: params };
+        self.broadcast(SyncMsg {
+            sender: self.num_nodes,
+            state: SyncState::START,
+            value: bincode::serialize(&command).expect("Failed to serialize sweep command")
+        }).await;
+    }
+
+    fn runner_msg(&self, msg: &SyncMsg) -> Option<RunnerMsg>{
+        match bincode::deserialize::<RunnerMsg>(&msg.value){
+            Ok(runner_msg) => Some(runner_msg),
+            Err(e) => {
+                log::error!("Unable to deserialize runner message from node {}: {}", msg.sender, e);
+                None
+            }
+        }
+    }
+
+    // Writes the JSON and CSV reports of a completed run to <report_prefix>-<start time>.{json,csv}
+    pub fn write_report(&mut self, id: usize, timed_out: bool){
+        let start_time = self.rbc_start_times.get(&id).cloned().unwrap_or(0);
+        let node_results = self.rbc_results.remove(&id).unwrap_or_default();
+        let mut report = RunReport::new(id, self.params.clone(), start_time, node_results.into_values().collect());
+        let completed: HashSet<Replica> = report.nodes.iter().map(|node| node.node).collect();
+        report.timed_out = timed_out;
+        report.never_alive = self.missing_nodes(&self.alive);
+        report.never_completed = self.missing_nodes(&completed);
+        report.unresponsive = self.unresponsive_nodes();
+        if !report.never_alive.is_empty(){
+            log::error!("Run {}: nodes {:?} never became alive", id, report.never_alive);
+        }
+        if !report.never_completed.is_empty(){
+            log::error!("Run {}: nodes {:?} never completed, nodes {:?} stopped sending heartbeats", id, report.never_completed, report.unresponsive);
+        }
+        log::info!("Run {} latency: {:?}, values agree: {}, messages sent: {}, bytes sent: {}", 
+            id, report.latency, report.values_agree, report.total_messages_sent, report.total_bytes_sent);
+
+        let json_path = format!("{}-{}.json", self.report_prefix, start_time);
+        let csv_path = format!("{}-{}.csv", self.report_prefix, start_time);
+        if let Err(e) = report.write_json(&json_path){
+            log::error!("Unable to write report {}: {}", json_path, e);
+        }
+        if let Err(e) = report.write_csv(&csv_path){
+            log::error!("Unable to write report {}: {}", csv_path, e);
+        }
+        log::info!("Wrote run report to {} and {}", json_path, csv_path);
+        if self.plan.is_some(){
+            self.run_reports.push(report);
+        }
+    }
+
+    // Writes the reports of all runs of the plan to <report_prefix>-sweep-<time>.{json,csv}
+    pub fn write_sweep_report(&mut self){
+        let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis();
+        let sweep_report = SweepReport {
+            runs: self.run_reports.drain(..).collect(),
+        };
+        log::info!("Experiment plan completed with {} runs", sweep_report.runs.len());
+
+        let json_path = format!("{}-sweep-{}.json", self.report_prefix, time);
+        let csv_path = format!("{}-sweep-{}.csv", self.report_prefix, time);
+        if let Err(e) = sweep_report.write_json(&json_path){
+            log::error!("Unable to write report {}: {}", json_path, e);
+        }
+        if let Err(e) = sweep_report.write_csv(&csv_path){
+            log::error!("Unable to write report {}: {}", csv_path, e);
+        }
+        log::info!("Wrote sweep report to {} and {}", json_path, csv_path);
+    }
+
     pub fn add_cancel_handler(&mut self, canc: CancelHandler<Acknowledgement>){
         self.cancel_handlers
             .push(canc);
@@ -1,4 +1,5 @@
 # A script to test quickly
+# For any n, `cargo run --release -p cluster -- -n <n> -p <protocol>` generates configs and runs a local cluster
 
 killall {node} &> /dev/null
 rm -rf /tmp/*.db &> /dev/null
@@ -13,7 +14,10 @@ TYPE=${TYPE:="release"}
     --config $TESTDIR/nodes-0.json \
     --ip ip_file \
     --protocol sync \
+    --bench $1 \
+    --report logs/report \
     --syncer $TESTDIR/syncer \
+    --legacyhashkeys \
     --batches $2 \
     --per $3 \
     --lin $4 \
@@ -26,6 +30,7 @@ for((i=0;i<16;i++)); do
     --ip ip_file \
     --protocol $1 \
     --syncer $TESTDIR/syncer \
+    --legacyhashkeys \
     --batches $2 \
     --per $3 \
     --lin $4 \
@@ -0,0 +1,257 @@
+// A tool that runs the syncer and n nodes as local processes, waits for the
+// syncer's report and exits non-zero if the nodes disagreed or the run timed out.
+
+use clap::{load_yaml, App};
+use crypto::Algorithm;
+use genconfig::{generate, ConfigParams};
+use std::{error::Error, fs::{self, File}, net::SocketAddr, path::{Path, PathBuf}, process::{Child, Command, Stdio}, thread, time::{Duration, Instant}};
+
+const EXIT_DISAGREEMENT: i32 = 1;
+const EXIT_TIMEOUT: i32 = 2;
+const EXIT_LAUNCH_FAILURE: i32 = 3;
+
+// Extra time given to the syncer on top of its alive and completion deadlines
+const REPORT_GRACE: Duration = Duration::from_secs(10);
+
+/// Processes of the cluster. They are killed on drop, so a panic does not leave nodes running.
+struct Cluster {
+    processes: Vec<(String, Child)>,
+}
+
+impl Cluster {
+    fn spawn(&mut self, name: &str, binary: &str, args: &Vec<String>, log_path: &Path) -> Result<(), Box<dyn Error>> {
+        let log = File::create(log_path)?;
+        let child = Command::new(binary)
+            .args(args)
+            .stdout(Stdio::from(log.try_clone()?))
+            .stderr(Stdio::from(log))
+            .spawn()
+            .map_err(|e| format!("unable to start {} from {}: {}", name, binary, e))?;
+        self.processes.push((name.to_string(), child));
+        Ok(())
+    }
+
+    // Name of the first process that already exited
+    fn exited(&mut self) -> Option<String> {
+        for (name, child) in self.processes.iter_mut() {
+            if let Ok(Some(_status)) = child.try_wait() {
+                return Some(name.clone());
+            }
+        }
+        None
+    }
+
+    // Sends SIGTERM, which the nodes handle, and kills what is still running after a second
+    fn shutdown(&mut self) {
+        for (_name, child) in self.processes.iter() {
+            let _status = Command::new("kill")
+                .arg("-TERM")
+                .arg(child.id().to_string())
+                .status();
+        }
+        thread::sleep(Duration::from_secs(1));
+        for (_name, child) in self.processes.iter_mut() {
+            let _status = child.kill();
+            let _status = child.wait();
+        }
+        self.processes.clear();
+    }
+}
+
+impl Drop for Cluster {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+// Report of the syncer, once it is completely written
+fn read_report(dir: &Path) -> Option<serde_json::Value> {
+    let entries = fs::read_dir(dir).ok()?;
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with("report-") && name.ends_with(".json") {
+            let contents = fs::read_to_string(entry.path()).ok()?;
+            return serde_json::from_str(&contents).ok();
+        }
+    }
+    None
+}
+
+fn path_str(path: &PathBuf) -> String {
+    path.to_string_lossy().to_string()
+}
+
+fn run() -> Result<i32, Box<dyn Error>> {
+    let yaml = load_yaml!("cli.yml");
+    let m = App::from_yaml(yaml).get_matches();
+    let num_nodes: usize = m.value_of("num_nodes")
+        .expect("number of nodes not specified")
+        .parse::<usize>()
+        .expect("unable to convert number of nodes into a number");
+    let num_faults: usize = match m.value_of("num_faults") {
+        Some(x) => x.parse::<usize>()
+            .expect("unable to convert number of faults into a number"),
+        None => (num_nodes-1)/3,
+    };
+    let protocol = m.value_of("protocol")
+        .expect("protocol not specified");
+    let deadline: u64 = m.value_of("deadline")
+        .unwrap_or("120000")
+        .parse::<u64>()
+        .expect("unable to parse deadline into a number");
+    let base_port: u16 = m.value_of("base_port")
+        .unwrap_or("8500")
+        .parse::<u16>()
+        .expect("failed to parse base_port into a number");
+    let client_base_port: u16 = m.value_of("client_base_port")
+        .unwrap_or("7000")
+        .parse::<u16>()
+        .expect("unable to parse client_base_port into an integer");
+    let syncer_port: u16 = m.value_of("syncer_port")
+        .unwrap_or("5000")
+        .parse::<u16>()
+        .expect("unable to parse syncer_port into an integer");
+    let secure: bool = m.value_of("secure")
+        .unwrap_or("false")
+        .parse::<bool>()
+        .expect("unable to parse secure into a boolean");
+    let binary = m.value_of("binary").unwrap_or("target/release/node");
+    let dir = PathBuf::from(m.value_of("dir").unwrap_or("cluster"));
+
+    // Protocol arguments are passed through to the syncer and the nodes as given
+    let mut protocol_args = Vec::new();
+    for (arg, default) in [("batches", "1"), ("per", "1000"), ("lin", "true"), ("opt", "true"), ("ibft", "false")] {
+        protocol_args.push(format!("--{}", arg));
+        protocol_args.push(m.value_of(arg).unwrap_or(default).to_string());
+    }
+    if let Some(acss) = m.value_of("acss") {
+        protocol_args.push("--acss".to_string());
+        protocol_args.push(acss.to_string());
+    }
+    if let Some(refresh) = m.value_of("refresh"){
+        protocol_args.push("--refresh".to_string());
+        protocol_args.push(refresh.to_string());
+    }
+
+    // Fresh config, log and report directories
+    let config_dir = dir.join("config");
+    let log_dir = dir.join("logs");
+    if dir.exists() {
+        fs::remove_dir_all(&dir)?;
+    }
+    fs::create_dir_all(&config_dir)?;
+    fs::create_dir_all(&log_dir)?;
+
+    let mut configs = generate(&ConfigParams {
+        num_nodes: num_nodes,
+        num_faults: num_faults,
+        delay: 10,
+        base_port: base_port,
+        client_base_port: client_base_port,
+        block_size: 100,
+        algorithm: Algorithm::NOPKI,
+        payload: 0,
+        client_run_port: syncer_port,
+        hosts: None,
+        syncer_host: "127.0.0.1".parse().unwrap(),
+        secure_transport: secure,
+        weights: None,
+    });
+    if let Err(e) = configs.check_port_collisions() {
+        eprintln!("{}", e);
+        return Ok(EXIT_LAUNCH_FAILURE);
+    }
+    for node in configs.nodes.iter_mut() {
+        node.client_addr = SocketAddr::new("127.0.0.1".parse().unwrap(), syncer_port);
+    }
+    let ip_file = config_dir.join("ip_file");
+    let syncer_file = config_dir.join("syncer");
+    configs.write_configs(&path_str(&config_dir), "json");
+    configs.write_ip_file(&path_str(&ip_file))?;
+    configs.write_syncer_file(&path_str(&syncer_file))?;
+    println!("Wrote configs for {} nodes with {} faults to {}", num_nodes, num_faults, config_dir.display());
+
+    let mut cluster = Cluster { processes: Vec::new() };
+    let common_args = |node: usize, protocol: &str| -> Vec<String> {
+        let mut args = vec![
+            "--config".to_string(), path_str(&config_dir.join(format!("nodes-{}.json", node))),
+            "--ip".to_string(), path_str(&ip_file),
+            "--protocol".to_string(), protocol.to_string(),
+            "--syncer".to_string(), path_str(&syncer_file),
+            "--hashkeys".to_string(), path_str(&config_dir.join("hash_keys.json")),
+        ];
+        args.extend(protocol_args.iter().cloned());
+        args
+    };
+
+    let mut syncer_args = common_args(0, "sync");
+    syncer_args.extend(vec![
+        "--bench".to_string(), protocol.to_string(),
+        "--report".to_string(), path_str(&dir.join("report")),
+        "--deadline".to_string(), deadline.to_string(),
+        "--quorum".to_string(), m.value_of("quorum").unwrap_or("false").to_string(),
+    ]);
+    if let Err(e) = cluster.spawn("syncer", binary, &syncer_args, &log_dir.join("syncer.log")) {
+        eprintln!("{}", e);
+        return Ok(EXIT_LAUNCH_FAILURE);
+    }
+    for i in 0..num_nodes {
+        let mut node_args = common_args(i, protocol);
+        if secure {
+            node_args.push("--transport".to_string());
+            node_args.push(path_str(&config_dir.join(format!("transport-{}.json", i))));
+        }
+        if let Err(e) = cluster.spawn(&format!("node {}", i), binary, &node_args, &log_dir.join(format!("{}.log", i))) {
+            eprintln!("{}", e);
+            return Ok(EXIT_LAUNCH_FAILURE);
+        }
+    }
+    println!("Started the syncer and {} nodes running {}, logs in {}", num_nodes, protocol, log_dir.display());
+
+    // The syncer stops a run after waiting `deadline` for nodes to become alive and again `deadline` for them to complete
+    let timeout = Duration::from_millis(2*deadline) + REPORT_GRACE;
+    let start = Instant::now();
+    let report = loop {
+        if let Some(report) = read_report(&dir) {
+            break Some(report);
+        }
+        if let Some(name) = cluster.exited() {
+            eprintln!("The {} process exited before the syncer reported, see {}", name, log_dir.display());
+            break None;
+        }
+        if start.elapsed() > timeout {
+            eprintln!("No report from the syncer within {:?}", timeout);
+            break None;
+        }
+        thread::sleep(Duration::from_millis(200));
+    };
+    cluster.shutdown();
+
+    let report = match report {
+        Some(report) => report,
+        None => return Ok(EXIT_TIMEOUT),
+    };
+    println!("Latency (ms): {}", report["latency"]);
+    println!("Messages sent: {}, bytes sent: {}", report["total_messages_sent"], report["total_bytes_sent"]);
+    if report["timed_out"].as_bool().unwrap_or(true) {
+        eprintln!("Run timed out, nodes that never completed: {}", report["never_completed"]);
+        return Ok(EXIT_TIMEOUT);
+    }
+    if !report["values_agree"].as_bool().unwrap_or(false) {
+        eprintln!("Nodes reported different values, see the report in {}", dir.display());
+        return Ok(EXIT_DISAGREEMENT);
+    }
+    println!("All nodes completed and agree");
+    Ok(0)
+}
+
+fn main() {
+    let code = match run() {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("{}", e);
+            EXIT_LAUNCH_FAILURE
+        }
+    };
+    std::process::exit(code);
+}
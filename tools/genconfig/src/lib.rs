@@ -0,0 +1,349 @@
+// Config generation shared by the genconfig tool and the local cluster launcher
+
+use config::{Node, Client};
+use consensus::{HashKeys, TransportConfig, Weights};
+use rand::Rng;
+use types::Replica;
+use crypto::{Algorithm, SecretKey};
+use std::{error::Error, io::{BufWriter, Write}, fs::{self, File}, net::{IpAddr, SocketAddr}};
+use util::io::*;
+use fnv::FnvHashMap as HashMap;
+
+/// Offsets of the sub-protocol listeners above a node's consensus port
+pub const SUB_PROTOCOL_PORT_OFFSETS: [u16; 10] = [150, 300, 450, 600, 900, 1800, 2100, 2700, 3000, 3300];
+
+/// Address of one node in a hosts file
+#[derive(Clone, Debug)]
+pub struct HostEntry {
+    pub host: IpAddr,
+    pub port: u16,
+    pub region: Option<String>,
+}
+
+/// Reads a hosts file with one `host port [region]` line per node, in node order.
+/// Fields are separated by whitespace or commas, empty lines and lines starting with # are skipped.
+pub fn read_hosts_file(path: &str) -> Result<Vec<HostEntry>, Box<dyn Error>> {
+    let mut hosts = Vec::new();
+    for (line_no, line) in fs::read_to_string(path)?.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|field| !field.is_empty())
+            .collect();
+        if fields.len() < 2 || fields.len() > 3 {
+            return Err(format!("{}:{}: expected host, port and an optional region", path, line_no+1).into());
+        }
+        let host = fields[0].parse::<IpAddr>()
+            .map_err(|e| format!("{}:{}: invalid host {}: {}", path, line_no+1, fields[0], e))?;
+        let port = fields[1].parse::<u16>()
+            .map_err(|e| format!("{}:{}: invalid port {}: {}", path, line_no+1, fields[1], e))?;
+        hosts.push(HostEntry {
+            host: host,
+            port: port,
+            region: fields.get(2).map(|region| region.to_string()),
+        });
+    }
+    Ok(hosts)
+}
+
+/// Parameters of a deployment, as taken by the genconfig command line
+#[derive(Clone)]
+pub struct ConfigParams {
+    pub num_nodes: usize,
+    pub num_faults: usize,
+    pub delay: u64,
+    pub base_port: u16,
+    pub client_base_port: u16,
+    pub block_size: usize,
+    pub algorithm: Algorithm,
+    pub payload: usize,
+    /// Port of the syncer
+    pub client_run_port: u16,
+    /// Addresses of the nodes. Without a hosts file, node i listens on 127.0.0.1:base_port+i.
+    pub hosts: Option<Vec<HostEntry>>,
+    pub syncer_host: IpAddr,
+    /// Encrypt the links between replicas for nodes started with their transport file
+    pub secure_transport: bool,
+    /// Stake weights of the nodes. Node i then runs one replica per unit of weight, with ports one stride apart.
+    pub weights: Option<Weights>,
+}
+
+/// Configs of all nodes and the client of a deployment
+pub struct GeneratedConfigs {
+    pub nodes: Vec<Node>,
+    pub client: Client,
+    /// Consensus address of every node, and the syncer's address under id n
+    pub ips: HashMap<Replica, String>,
+    /// Commitment hash keys shared by all nodes of the deployment
+    pub hash_keys: HashKeys,
+    /// Static transport keys of every node, in node order
+    pub transport: Vec<TransportConfig>,
+    pub weights: Option<Weights>,
+}
+
+pub fn generate(params: &ConfigParams) -> GeneratedConfigs {
+    let num_nodes = params.num_nodes;
+    let t = params.algorithm.clone();
+    let mut client = Client::new();
+    client.block_size = params.block_size;
+    client.crypto_alg = t.clone();
+    client.num_nodes = num_nodes;
+    client.num_faults = params.num_faults;
+
+    let mut node:Vec<Node> = Vec::with_capacity(num_nodes);
+
+    let pk = HashMap::default();
+    let mut ip = HashMap::default();
+
+    //let (cert, privkey) = new_root_cert()?;
+    let mut sec_keys:Vec<Vec<SecretKey>> = Vec::with_capacity(num_nodes);
+    (0..num_nodes).for_each(|_i| {
+        sec_keys.push(Vec::with_capacity(num_nodes));
+    });
+    if t == Algorithm::NOPKI{
+        // Generate secret keys above and pass them to the context
+        for i in 0..num_nodes{
+            for j in i..num_nodes{
+                let skey:SecretKey = SecretKey::new();
+                sec_keys[i].push(skey.clone());
+                if j!= i{
+                    sec_keys[j].push(skey.clone());
+                }
+                //sec_keys.push(SecretKey::generate());
+            }
+        }
+    }
+    for i in 0..num_nodes {
+        node.push(Node::new());
+
+        node[i].delta = params.delay;
+        node[i].id = i as Replica;
+        node[i].num_nodes = num_nodes;
+        node[i].num_faults = params.num_faults;
+        node[i].block_size = params.block_size;
+        node[i].payload = params.payload;
+        node[i].client_port = params.client_base_port+(i as u16);
+        // generate random number for approximate consensus
+        let num = rand::thread_rng().gen_range(0, 20000000);
+        node[i].prot_payload = format!("a,{},50000,100",num);
+        node[i].crypto_alg = t.clone();
+        match t {
+            Algorithm::NOPKI =>{
+                for j in 0..num_nodes{
+                    node[i].sk_map.insert(j, sec_keys[i][j].to_vec());
+                }
+            }
+        };
+        match &params.hosts {
+            Some(hosts) => {
+                ip.insert(i as Replica,
+                SocketAddr::new(hosts[i].host, hosts[i].port).to_string()
+                );
+                client.net_map.insert(i as Replica,
+                SocketAddr::new(hosts[i].host, params.client_base_port+(i as u16)).to_string()
+                );
+                // Nodes on other hosts cannot reach the syncer through the default client address
+                node[i].client_addr = SocketAddr::new(params.syncer_host, params.client_run_port);
+            },
+            None => {
+                ip.insert(i as Replica,
+                format!("{}:{}", "127.0.0.1", params.base_port+(i as u16))
+                );
+                client.net_map.insert(i as Replica,
+                format!("127.0.0.1:{}", params.client_base_port+(i as u16))
+                );
+            }
+        }
+
+
+        //let (new_cert, new_pkey) = get_signed_cert(&cert, &privkey)?;
+
+        //node[i].root_cert = cert.to_der()?;
+        //node[i].my_cert = new_cert.to_der()?;
+        //node[i].my_cert_key = new_pkey.private_key_to_der()?;
+    }
+    ip.insert(num_nodes, SocketAddr::new(params.syncer_host, params.client_run_port).to_string());
+    //client.root_cert = cert.to_der()?;
+
+    for i in 0..num_nodes {
+        node[i].pk_map = pk.clone();
+        node[i].net_map = ip.clone();
+    }
+    client.server_pk = pk;
+    GeneratedConfigs {
+        nodes: node,
+        client: client,
+        ips: ip,
+        hash_keys: HashKeys::generate(),
+        transport: TransportConfig::generate(num_nodes, params.secure_transport),
+        weights: params.weights.clone(),
+    }
+}
+
+impl GeneratedConfigs {
+    /// Checks that no two listeners share a host and port. Every node listens on its consensus port,
+    /// the sub-protocol ports above it and its client port, and the syncer on its own port.
+    pub fn check_port_collisions(&self) -> Result<(), String> {
+        let mut listeners: HashMap<SocketAddr, String> = HashMap::default();
+        let mut collisions = Vec::new();
+        let mut add_listener = |address: SocketAddr, owner: String| {
+            if let Some(other) = listeners.insert(address, owner.clone()) {
+                collisions.push(format!("{} used by {} and {}", address, other, owner));
+            }
+        };
+        for i in 0..self.nodes.len() {
+            let address: SocketAddr = self.ips.get(&i).unwrap().parse()
+                .map_err(|e| format!("invalid address of node {}: {}", i, e))?;
+            let client_address: SocketAddr = self.client.net_map.get(&i).unwrap().parse()
+                .map_err(|e| format!("invalid client address of node {}: {}", i, e))?;
+            // Every virtual replica of a weighted node listens one port stride above the previous one
+            for (slot, slot_offset) in self.slot_offsets(i).into_iter().enumerate() {
+                let owner = match self.weights {
+                    Some(_) => format!("node {} replica {}", i, slot),
+                    None => format!("node {}", i),
+                };
+                let port = address.port() as usize + slot_offset;
+                for offset in std::iter::once(0).chain(SUB_PROTOCOL_PORT_OFFSETS) {
+                    if port + offset as usize > u16::MAX as usize {
+                        return Err(format!("port {} of {} leaves no room for the sub-protocol offset {}", port, owner, offset));
+                    }
+                    let listener_owner = match offset {
+                        0 => owner.clone(),
+                        _ => format!("{} (offset {})", owner, offset),
+                    };
+                    add_listener(SocketAddr::new(address.ip(), (port + offset as usize) as u16), listener_owner);
+                }
+                let client_port = client_address.port() as usize + slot_offset;
+                if client_port > u16::MAX as usize {
+                    return Err(format!("client port {} of {} is out of range", client_port, owner));
+                }
+                add_listener(SocketAddr::new(client_address.ip(), client_port as u16), format!("{} (client port)", owner));
+            }
+        }
+        let syncer_address: SocketAddr = self.ips.get(&self.nodes.len()).unwrap().parse()
+            .map_err(|e| format!("invalid syncer address: {}", e))?;
+        add_listener(syncer_address, "the syncer".to_string());
+        if collisions.is_empty() {
+            Ok(())
+        }
+        else {
+            Err(format!("port collisions: {}", collisions.join(", ")))
+        }
+    }
+
+    /// Writes the addresses of the nodes followed by the syncer's, one per line, in the format of the `--ip` option
+    pub fn write_ip_file(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        let file = File::create(path)?;
+        let mut writer = BufWriter::new(file);
+        for iter in 0..self.nodes.len()+1{
+            writeln!(writer,"{}",self.ips.get(&iter).unwrap())?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    // Port offsets of the virtual replicas of a node, a single zero offset without weights
+    fn slot_offsets(&self, node: usize) -> Vec<usize> {
+        match &self.weights {
+            Some(weights) => (0..weights.weight(node)).map(|slot| slot*weights.port_stride as usize).collect(),
+            None => vec![0],
+        }
+    }
+
+    /// Writes the ports the nodes listen on for the syncer, in the format of the `--syncer` option.
+    /// Under weights, there is one line per virtual replica, in the order of the virtual replica ids.
+    pub fn write_syncer_file(&self, path: &str) -> Result<(), Box<dyn Error>> {
+        let file = File::create(path)?;
+        let mut writer = BufWriter::new(file);
+        for iter in 0..self.nodes.len(){
+            let client_address: SocketAddr = self.client.net_map.get(&iter).unwrap().parse()?;
+            for slot_offset in self.slot_offsets(iter){
+                let port = client_address.port() as usize + slot_offset;
+                writeln!(writer,"{}",SocketAddr::new(client_address.ip(), port as u16))?;
+            }
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Writes the address, client address and region of every node, for the benchmark scripts
+    pub fn write_topology(&self, path: &str, hosts: &Vec<HostEntry>) {
+        let mut topology = HashMap::default();
+        for i in 0..self.nodes.len() {
+            let mut entry = HashMap::default();
+            entry.insert("address", self.ips.get(&i).unwrap().clone());
+            entry.insert("client_address", self.client.net_map.get(&i).unwrap().clone());
+            entry.insert("region", hosts[i].region.clone().unwrap_or_default());
+            topology.insert(i as Replica, entry);
+        }
+        write_json(path.to_string(), &topology);
+    }
+
+    /// Writes `nodes-<i>`, `transport-<i>.json`, `client`, `syncer.json`, `hash_keys.json` and under weights `weights.json` to the target directory
+    pub fn write_configs(&self, target: &str, out: &str) {
+        let filename = format!("{}/syncer.json",target);
+        write_json(filename, &self.client.net_map.clone());
+        self.hash_keys.validate()
+            .expect("failed to validate the hash keys");
+        let filename = format!("{}/hash_keys.json",target);
+        write_json(filename, &self.hash_keys);
+        if let Some(weights) = &self.weights {
+            let filename = format!("{}/weights.json",target);
+            weights.write_to_file(&filename)
+                .expect("failed to write the weights");
+        }
+
+        // Write all the files
+        for i in 0..self.nodes.len() {
+            match out {
+                "json" => {
+                    let filename = format!("{}/nodes-{}.json",target,i);
+                    write_json(filename, &self.nodes[i]);
+                },
+                "binary" => {
+                    let filename = format!("{}/nodes-{}.dat",target,i);
+                    write_bin(filename, &self.nodes[i]);
+                },
+                "toml" => {
+                    let filename = format!("{}/nodes-{}.toml",target,i);
+                    write_toml(filename, &self.nodes[i]);
+                },
+                "yaml" => {
+                    let filename = format!("{}/nodes-{}.yml",target,i);
+                    write_yaml(filename, &self.nodes[i]);
+                },
+                _ => (),
+            }
+            self.nodes[i].validate()
+                .expect("failed to validate node config");
+            let filename = format!("{}/transport-{}.json",target,i);
+            self.transport[i].write_to_file(&filename)
+                .expect("failed to write the transport config");
+        }
+
+        // Write the client file
+        match out {
+            "json" => {
+                let filename = format!("{}/client.json",target);
+                write_json(filename, &self.client);
+            },
+            "binary" => {
+                let filename = format!("{}/client.dat",target);
+                write_bin(filename, &self.client);
+            },
+            "toml" => {
+                let filename = format!("{}/client.toml",target);
+                write_toml(filename, &self.client);
+            },
+            "yaml" => {
+                let filename = format!("{}/client.yml",target);
+                write_yaml(filename, &self.client);
+            },
+            _ => (),
+        }
+        self.client.validate()
+            .expect("failed to validate the client config");
+    }
+}
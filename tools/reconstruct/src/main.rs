@@ -0,0 +1,138 @@
+// A tool that recovers the secrets of a batch from nodes' exported share files and
+// checks every node's shares against the dealer's commitment root and that every share lies on the sharing polynomials.
+
+use acss_ske::Context;
+use clap::{load_yaml, App};
+use consensus::{reconstruct_share_files, ExportedSharing, HashKeys, LargeField, ReconstructedSharing, ShareFile, ShareFileHeader};
+use lambdaworks_math::traits::ByteConversion;
+use std::{collections::HashMap, fs, path::Path};
+
+const EXIT_VERIFICATION_FAILURE: i32 = 1;
+const EXIT_READ_FAILURE: i32 = 2;
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+// dpss shares every batch twice, so both sharings of a dealer must open to the same secrets
+fn check_double_sharings(protocol: &str, sharings: &Vec<ReconstructedSharing>) -> Result<(), String> {
+    if protocol != "dpss" {
+        return Ok(());
+    }
+    let mut by_dealer: HashMap<usize, &ReconstructedSharing> = HashMap::new();
+    for sharing in sharings.iter() {
+        match by_dealer.get(&sharing.dealer) {
+            Some(other) if other.secrets != sharing.secrets => {
+                return Err(format!(
+                    "instances {} and {} of dealer {} open to different secrets",
+                    other.instance_id, sharing.instance_id, sharing.dealer
+                ));
+            }
+            Some(_) => {}
+            None => {
+                by_dealer.insert(sharing.dealer, sharing);
+            }
+        }
+    }
+    Ok(())
+}
+
+// Shares agree with the root only if their opening does, so a node cannot bring its own shares next to the right root.
+// Schemes other than acss_ske export no openings, their shares are only taken with --unchecked.
+fn check_opening(hash_keys: &Option<HashKeys>, header: &ShareFileHeader, sharing: &ExportedSharing) -> Result<(), String> {
+    let hash_keys = match hash_keys {
+        Some(hash_keys) => hash_keys,
+        None => return Ok(()),
+    };
+    let opening = sharing.opening.as_ref().ok_or_else(|| format!(
+        "node {} exported no opening of its shares in instance {} of dealer {}, {} shares cannot be checked against the root, pass --unchecked to reconstruct anyway",
+        header.node, sharing.instance_id, sharing.dealer, header.acss
+    ))?;
+    let shares = sharing.shares.iter()
+        .map(|share| LargeField::from_bytes_be(share.as_slice()))
+        .collect::<Result<Vec<LargeField>, _>>()
+        .map_err(|_| format!("node {} holds a malformed share in instance {} of dealer {}", header.node, sharing.instance_id, sharing.dealer))?;
+    if header.acss != "acss_ske" || !Context::verify_share_opening(&hash_keys.hash_state(), header.num_nodes, header.num_faults, shares, opening, &sharing.root) {
+        return Err(format!(
+            "shares of node {} in instance {} of dealer {} do not open the dealer's commitment",
+            header.node, sharing.instance_id, sharing.dealer
+        ));
+    }
+    Ok(())
+}
+
+fn main() {
+    let yaml = load_yaml!("cli.yml");
+    let m = App::from_yaml(yaml).get_matches();
+
+    let mut files = Vec::new();
+    for path in m.values_of("files").unwrap() {
+        match ShareFile::from_file(Path::new(path)) {
+            Ok(file) => files.push(file),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(EXIT_READ_FAILURE);
+            }
+        }
+    }
+
+    // Commitments only match under the hash keys of the deployment the shares come from
+    let hash_keys = match m.value_of("hashkeys") {
+        _ if m.is_present("unchecked") => None,
+        Some(path) => match HashKeys::from_file(path) {
+            Ok(hash_keys) => Some(hash_keys),
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(EXIT_READ_FAILURE);
+            }
+        },
+        None if m.is_present("legacyhashkeys") => Some(HashKeys::legacy()),
+        None => {
+            eprintln!("No hash keys given, pass the hash_keys.json of the deployment with --hashkeys, or --legacyhashkeys for configs generated without keys");
+            std::process::exit(EXIT_READ_FAILURE);
+        }
+    };
+    if hash_keys.is_none() {
+        eprintln!("Reconstructing without checking the shares against the commitment roots");
+    }
+
+    let sharings = match reconstruct_share_files(&files, |header, sharing| check_opening(&hash_keys, header, sharing)) {
+        Ok(sharings) => sharings,
+        Err(e) => {
+            eprintln!("Verification failed: {}", e);
+            std::process::exit(EXIT_VERIFICATION_FAILURE);
+        }
+    };
+    let header = &files[0].header;
+    if let Err(e) = check_double_sharings(&header.protocol, &sharings) {
+        eprintln!("Verification failed: {}", e);
+        std::process::exit(EXIT_VERIFICATION_FAILURE);
+    }
+
+    let report = serde_json::json!({
+        "protocol": header.protocol,
+        "acss": header.acss,
+        "num_nodes": header.num_nodes,
+        "num_faults": header.num_faults,
+        "batch": header.batch,
+        "dealers": header.dealers,
+        "nodes": files.iter().map(|file| file.header.node).collect::<Vec<usize>>(),
+        "sharings": sharings.iter().map(|sharing| serde_json::json!({
+            "dealer": sharing.dealer,
+            "instance_id": sharing.instance_id,
+            "root": to_hex(&sharing.root),
+            "secrets": sharing.secrets.iter().map(|secret| to_hex(&secret.to_bytes_be())).collect::<Vec<String>>(),
+        })).collect::<Vec<serde_json::Value>>(),
+    });
+    let report = serde_json::to_string_pretty(&report).unwrap();
+    match m.value_of("output") {
+        Some(path) => {
+            if let Err(e) = fs::write(path, report) {
+                eprintln!("Unable to write {}: {}", path, e);
+                std::process::exit(EXIT_READ_FAILURE);
+            }
+            eprintln!("Recovered {} sharings of batch {} from {} share files into {}", sharings.len(), header.batch, files.len(), path);
+        }
+        None => println!("{}", report),
+    }
+}
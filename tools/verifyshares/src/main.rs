@@ -0,0 +1,82 @@
+// A tool that reruns the share checks of acss_ske on shares a node rejected, outside of the protocol,
+// and reports which check fails. Used to look into complaints against a dealer.
+
+use acss_ske::{Context, ShareCheck, ShareEvidence};
+use clap::{load_yaml, App};
+use consensus::{HashKeys, LargeField};
+use std::path::Path;
+
+const EXIT_CHECK_FAILED: i32 = 1;
+const EXIT_READ_FAILURE: i32 = 2;
+
+fn main() {
+    let yaml = load_yaml!("cli.yml");
+    let m = App::from_yaml(yaml).get_matches();
+
+    let evidence = match ShareEvidence::from_file(Path::new(m.value_of("evidence").unwrap())) {
+        Ok(evidence) => evidence,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(EXIT_READ_FAILURE);
+        }
+    };
+    // Commitments only match under the hash keys of the deployment the shares come from
+    let hash_keys = match m.value_of("hashkeys") {
+        Some(path) => match HashKeys::from_file(path) {
+            Ok(hash_keys) => hash_keys,
+            Err(e) => {
+                eprintln!("{}", e);
+                std::process::exit(EXIT_READ_FAILURE);
+            }
+        },
+        None if m.is_present("legacyhashkeys") => HashKeys::legacy(),
+        None => {
+            eprintln!("No hash keys given, pass the hash_keys.json of the deployment with --hashkeys, or --legacyhashkeys for configs generated without keys");
+            std::process::exit(EXIT_READ_FAILURE);
+        }
+    };
+    let party = m.value_of("party")
+        .map(|party| party.parse::<usize>().expect("Unable to parse party"))
+        .unwrap_or(evidence.party);
+
+    let evaluation_points: Vec<LargeField> = if evidence.use_fft {
+        Context::gen_roots_of_unity(evidence.num_nodes)
+    } else {
+        (1..evidence.num_nodes + 1).map(|x| LargeField::from(x as u64)).collect()
+    };
+    let folding_dzk_context = Context::folding_dzk_context(evidence.num_nodes, evidence.num_faults, hash_keys.hash_state());
+    let status = Context::check_shares(
+        &folding_dzk_context,
+        &hash_keys.hash_state(),
+        evaluation_points,
+        evidence.num_faults,
+        &evidence.commitment,
+        &evidence.shares,
+        party,
+    );
+
+    println!(
+        "Shares of dealer {} for node {} in instance {} (n = {}, t = {}), rejected by the node at {}",
+        evidence.dealer, party, evidence.instance_id, evidence.num_nodes, evidence.num_faults, evidence.failed_check
+    );
+    let failed_check = status.err();
+    let mut reached_failure = false;
+    for check in ShareCheck::ALL.iter() {
+        let result = if reached_failure {
+            "not run"
+        } else if Some(*check) == failed_check {
+            reached_failure = true;
+            "FAIL"
+        } else {
+            "pass"
+        };
+        println!("  {:<22} {}", check.name(), result);
+    }
+    match failed_check {
+        Some(check) => {
+            println!("Shares rejected at {}", check);
+            std::process::exit(EXIT_CHECK_FAILED);
+        }
+        None => println!("Shares pass every check"),
+    }
+}